@@ -0,0 +1,61 @@
+//! `tflap autorun`: the bot plays a batch of games and each one goes down as a row of
+//! CSV, to show the game off or to diff before and after a change to the physics.
+//!
+//! Rows come out in seed order, so the same `--seed-range` on two builds lines up row for
+//! row, and any that differ point at the seeds that fly differently.
+
+use crate::simulate::{Batch, Outcome};
+use std::fmt::Write as _;
+use std::path::PathBuf;
+
+pub const DEFAULT_GAMES: usize = 50;
+
+/// What `tflap autorun` was asked to do.
+pub struct Autorun {
+    pub batch: Batch,
+    /// Where the CSV goes, or stdout if nowhere.
+    pub out: Option<PathBuf>,
+    /// Draw each game as it's played, at the usual speed, instead of just working it out.
+    pub render: bool,
+}
+
+/// One row per game, `labels` saying what played it.
+pub fn to_csv(labels: &[(&str, &str)], max_ticks: u32, games: &[(u64, Outcome)]) -> String {
+    let keys: Vec<&str> = labels.iter().map(|(key, _)| *key).collect();
+    let values: Vec<&str> = labels.iter().map(|(_, value)| *value).collect();
+    let mut out = format!("game,seed,{},score,ticks,capped\n", keys.join(","));
+    for (game, (seed, outcome)) in games.iter().enumerate() {
+        let _ = writeln!(
+            out,
+            "{},{},{},{},{},{}",
+            game + 1,
+            seed,
+            values.join(","),
+            outcome.score,
+            outcome.ticks,
+            u8::from(outcome.ticks >= max_ticks)
+        );
+    }
+    out
+}
+
+/// The batch in a line, like `50 games: mean score 12.40, best 41, 2 capped`.
+pub fn summary(max_ticks: u32, games: &[(u64, Outcome)]) -> String {
+    let total: u64 = games
+        .iter()
+        .map(|(_, outcome)| u64::from(outcome.score))
+        .sum();
+    let best = games.iter().map(|(_, outcome)| outcome.score).max();
+    let capped = games
+        .iter()
+        .filter(|(_, outcome)| outcome.ticks >= max_ticks)
+        .count();
+    format!(
+        "{} game{}: mean score {:.2}, best {}, {} capped",
+        games.len(),
+        if games.len() == 1 { "" } else { "s" },
+        total as f64 / games.len().max(1) as f64,
+        best.unwrap_or(0),
+        capped
+    )
+}
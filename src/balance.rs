@@ -0,0 +1,140 @@
+//! `tflap balance`: where a difficulty preset turns unfair.
+//!
+//! A reference bot flies a batch of runs at each of a spread of speeds. Every pipe it
+//! reaches is filed under the step from the gap before, in rows, and steps it crashes on
+//! far more than on pipes in general are flagged: if a bot that plans ahead can't make
+//! them, players won't. Comparing with the usual rate keeps weather that makes every pipe
+//! hard from flagging them all.
+
+use crate::simulate::{self, Batch, Outcome};
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+
+/// Share of tries crashed on that gets a step flagged, if it is also `FLAG_FACTOR` times
+/// the rate for every pipe at that speed.
+const FLAG_RATE: f64 = 0.25;
+const FLAG_FACTOR: f64 = 2.0;
+/// Tries needed before a step's crash rate counts for anything.
+const MIN_TRIES: u32 = 20;
+
+/// A pipe the bot flew at, and whether it got through.
+pub struct Encounter {
+    /// Rows from the previous gap's top to this one's, positive when this one is lower.
+    pub step: i16,
+    pub crashed: bool,
+}
+
+/// Tries and crashes for each step.
+type Tally = BTreeMap<i16, (u32, u32)>;
+
+/// Fly `batch` at each of `speeds` and report on it under `heading`. `fly` runs one seed
+/// at a speed, noting the pipes it meets.
+pub fn run(
+    batch: &Batch,
+    speeds: &[u8],
+    heading: &str,
+    fly: impl Fn(u8, u64, &mut Vec<Encounter>) -> Outcome + Sync,
+) -> String {
+    let seeds: Vec<u64> = batch.seeds.clone().collect();
+    let mut out = format!("{}\n\n", heading);
+    let _ = writeln!(
+        out,
+        "{:>6} {:>7} {:>7} {:>5} {:>7} {:>10}",
+        "speed", "mean", "median", "p90", "capped", "crash/pipe"
+    );
+    let mut flagged = Vec::new();
+
+    for &speed in speeds {
+        let flights = simulate::parallel_map(&seeds, batch.threads, &|&seed| {
+            let mut encounters = Vec::new();
+            let outcome = fly(speed, seed, &mut encounters);
+            (outcome, encounters)
+        });
+
+        let mut scores: Vec<u32> = flights.iter().map(|(outcome, _)| outcome.score).collect();
+        scores.sort_unstable();
+        let mean = scores.iter().map(|&s| f64::from(s)).sum::<f64>() / scores.len().max(1) as f64;
+        let capped = flights
+            .iter()
+            .filter(|(outcome, _)| outcome.ticks >= batch.max_ticks)
+            .count();
+
+        let mut tally = Tally::new();
+        for encounter in flights.iter().flat_map(|(_, encounters)| encounters) {
+            let (tries, crashes) = tally.entry(encounter.step).or_default();
+            *tries += 1;
+            *crashes += u32::from(encounter.crashed);
+        }
+        let (tries, crashes) = tally
+            .values()
+            .fold((0, 0), |(t, c), &(tries, crashes)| (t + tries, c + crashes));
+        let usual = f64::from(crashes) / f64::from(tries.max(1));
+        let _ = writeln!(
+            out,
+            "{:>6} {:>7.1} {:>7} {:>5} {:>7} {:>9.1}%",
+            speed_label(speed),
+            mean,
+            simulate::percentile(&scores, 50),
+            simulate::percentile(&scores, 90),
+            capped,
+            usual * 100.0
+        );
+
+        for (step, (tries, crashes)) in tally {
+            let rate = f64::from(crashes) / f64::from(tries);
+            if tries >= MIN_TRIES && rate >= FLAG_RATE.max(usual * FLAG_FACTOR) {
+                flagged.push((speed, step, tries, rate));
+            }
+        }
+    }
+
+    out.push('\n');
+    if flagged.is_empty() {
+        let _ = writeln!(
+            out,
+            "No step between gaps was crashed on {:.0}% of the time or more, and twice as often \
+             as pipes in general, at any speed.",
+            FLAG_RATE * 100.0
+        );
+        return out;
+    }
+    let _ = writeln!(
+        out,
+        "Steps between gaps crashed on {:.0}% of the time or more, and twice as often as \
+         pipes in general ({}+ tries):",
+        FLAG_RATE * 100.0,
+        MIN_TRIES
+    );
+    let _ = writeln!(
+        out,
+        "{:>6} {:>14} {:>7} {:>7}",
+        "speed", "step", "tries", "crashed"
+    );
+    for (speed, step, tries, rate) in flagged {
+        let _ = writeln!(
+            out,
+            "{:>6} {:>14} {:>7} {:>6.0}%",
+            speed_label(speed),
+            step_label(step),
+            tries,
+            rate * 100.0
+        );
+    }
+    out
+}
+
+fn speed_label(speed: u8) -> String {
+    format!("{}x", f32::from(speed) / 100.0)
+}
+
+fn step_label(step: i16) -> String {
+    match step {
+        0 => "level".to_string(),
+        _ => format!(
+            "{} row{} {}",
+            step.abs(),
+            if step.abs() == 1 { "" } else { "s" },
+            if step > 0 { "down" } else { "up" }
+        ),
+    }
+}
@@ -0,0 +1,98 @@
+//! Banking: a risk-and-reward mode where only banked points survive a crash.
+//!
+//! Every `EVERY` pipes the bank opens until the next pipe is passed. Banking keeps the
+//! score whatever happens after, but the next `BURST_PIPES` pipes come twice as wide,
+//! and until the bird is through them a hit ends the run: hearts, invincibility and the
+//! revive don't count. A run that ends any other way keeps only what was banked.
+
+/// Pipes passed between chances to bank.
+pub const EVERY: u32 = 5;
+/// Wide pipes that follow banking, and how many times wider than usual they are.
+const BURST_PIPES: u32 = 3;
+const BURST_WIDTH: u16 = 2;
+
+#[derive(Clone, Copy, Default)]
+pub struct Bank {
+    on: bool,
+    banked: u32,
+    /// Pipes to pass before the bank next opens.
+    until_open: u32,
+    open: bool,
+    /// Wide pipes yet to spawn, and pipes to pass before the bird is safe again.
+    burst: u32,
+    exposed: u32,
+    /// Points a finished run lost for not being banked.
+    lost: u32,
+}
+
+impl Bank {
+    pub fn new(on: bool) -> Self {
+        Self {
+            on,
+            until_open: EVERY,
+            ..Self::default()
+        }
+    }
+
+    pub fn is_on(&self) -> bool {
+        self.on
+    }
+
+    pub fn banked(&self) -> u32 {
+        self.banked
+    }
+
+    pub fn lost(&self) -> u32 {
+        self.lost
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.open
+    }
+
+    /// Whether a hit now ends the run whatever would otherwise save it.
+    pub fn is_exposed(&self) -> bool {
+        self.exposed > 0
+    }
+
+    /// Note a pipe passed, which opens the bank every `EVERY` pipes and closes it after.
+    pub fn passed(&mut self) {
+        self.exposed = self.exposed.saturating_sub(1);
+        self.until_open -= 1;
+        self.open = self.on && self.until_open == 0;
+        if self.until_open == 0 {
+            self.until_open = EVERY;
+        }
+    }
+
+    /// Bank `score` if the bank is open, with `ahead` pipes already out between the bird
+    /// and the burst. Returns whether it was banked.
+    pub fn bank(&mut self, score: u32, ahead: u32) -> bool {
+        if !self.open {
+            return false;
+        }
+        self.open = false;
+        self.banked = score;
+        self.burst = BURST_PIPES;
+        self.exposed = ahead + BURST_PIPES;
+        true
+    }
+
+    /// Columns wide the next pipe to spawn is, when it would otherwise be `width`.
+    pub fn pipe_width(&mut self, width: u16) -> u16 {
+        if self.burst == 0 {
+            return width;
+        }
+        self.burst -= 1;
+        width * BURST_WIDTH
+    }
+
+    /// What a run ending on `score` keeps, noting the rest as lost.
+    pub fn settle(&mut self, score: u32) -> u32 {
+        if !self.on {
+            return score;
+        }
+        self.lost = score.saturating_sub(self.banked);
+        self.banked
+    }
+}
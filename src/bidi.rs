@@ -0,0 +1,269 @@
+//! Bidirectional text for a screen laid out cell by cell.
+//!
+//! Terminals with bidi support reorder right-to-left text themselves, which scrambles a
+//! full-screen game: the HUD and boxes would be mirrored around whatever Hebrew or
+//! Arabic a rival name brings in. The game switches such terminals to explicit mode,
+//! where cells are shown as written, and puts right-to-left runs into visual order
+//! itself, one `put_str` at a time. This is a cut-down version of the Unicode
+//! Bidirectional Algorithm for a left-to-right paragraph: no embeddings or overrides,
+//! and numbers next to right-to-left text keep their digits in reading order.
+//!
+//! Text pasted into a sentence, like a name in "Next up: NAME (120)", is wrapped with
+//! `isolate` so it can't pull the neighbouring punctuation and numbers along with it.
+
+use std::borrow::Cow;
+
+/// Ask a bidi-aware terminal to show cells in the order they are written (BDSM reset),
+/// and to go back to reordering on exit. Other terminals ignore both.
+pub const EXPLICIT_MODE: &str = "\x1b[8l";
+pub const IMPLICIT_MODE: &str = "\x1b[8h";
+
+/// FIRST STRONG ISOLATE and POP DIRECTIONAL ISOLATE. They take no cell on screen.
+pub const ISOLATE: char = '\u{2068}';
+pub const POP: char = '\u{2069}';
+
+#[derive(Clone, Copy, PartialEq)]
+enum Class {
+    Left,
+    Right,
+    Number,
+    Neutral,
+}
+
+fn is_rtl(ch: char) -> bool {
+    // Hebrew, Arabic, Syriac and their neighbours, with their presentation forms
+    matches!(
+        ch as u32,
+        0x0590..=0x08FF | 0xFB1D..=0xFDFF | 0xFE70..=0xFEFF | 0x10800..=0x10FFF | 0x1E800..=0x1EFFF
+    )
+}
+
+fn class(ch: char) -> Class {
+    if ch.is_ascii_digit() || matches!(ch, '\u{0660}'..='\u{0669}' | '\u{06F0}'..='\u{06F9}') {
+        Class::Number
+    } else if is_rtl(ch) {
+        Class::Right
+    } else if ch.is_alphabetic() {
+        Class::Left
+    } else {
+        Class::Neutral
+    }
+}
+
+/// Wrap text from outside the game so it is laid out on its own. Text with nothing
+/// right-to-left in it is returned as is.
+pub fn isolate(text: &str) -> String {
+    if text.chars().any(is_rtl) {
+        format!("{}{}{}", ISOLATE, text, POP)
+    } else {
+        text.to_string()
+    }
+}
+
+/// Columns `ch` takes on screen: none for an isolate mark, two for the wide letters of
+/// East Asian scripts and for emoji, one for anything else.
+pub fn char_width(ch: char) -> usize {
+    if ch == ISOLATE || ch == POP {
+        return 0;
+    }
+    let wide = matches!(
+        ch as u32,
+        0x1100..=0x115F
+            | 0x2E80..=0x303E
+            | 0x3041..=0x33FF
+            | 0x3400..=0x4DBF
+            | 0x4E00..=0x9FFF
+            | 0xA000..=0xA4CF
+            | 0xAC00..=0xD7A3
+            | 0xF900..=0xFAFF
+            | 0xFE30..=0xFE4F
+            | 0xFF00..=0xFF60
+            | 0xFFE0..=0xFFE6
+            | 0x1F300..=0x1F64F
+            | 0x1F900..=0x1F9FF
+            | 0x20000..=0x3FFFD
+    );
+    1 + usize::from(wide)
+}
+
+/// Columns `text` takes on screen.
+pub fn width(text: &str) -> usize {
+    text.chars().map(char_width).sum()
+}
+
+/// Each letter of `text`, and whether it's in an isolated run, without the marks.
+pub fn letters(text: &str) -> Vec<(char, bool)> {
+    let mut isolated = false;
+    let mut letters = Vec::new();
+    for ch in text.chars() {
+        match ch {
+            ISOLATE => isolated = true,
+            POP => isolated = false,
+            _ => letters.push((ch, isolated)),
+        }
+    }
+    letters
+}
+
+/// `letters` back into text, each isolated run wrapped again, so a run cut short
+/// still ends with its mark.
+pub fn rejoin(letters: &[(char, bool)]) -> String {
+    let mut text = String::new();
+    let mut open = false;
+    for &(ch, isolated) in letters {
+        if isolated != open {
+            text.push(if isolated { ISOLATE } else { POP });
+            open = isolated;
+        }
+        text.push(ch);
+    }
+    if open {
+        text.push(POP);
+    }
+    text
+}
+
+/// As much of the start of `text` as fits in `columns`, without half a wide letter.
+pub fn truncate(text: &str, columns: usize) -> String {
+    let letters = letters(text);
+    let mut used = 0;
+    let fits = letters
+        .iter()
+        .take_while(|&&(ch, _)| {
+            used += char_width(ch);
+            used <= columns
+        })
+        .count();
+    rejoin(&letters[..fits])
+}
+
+fn mirror(ch: char) -> char {
+    match ch {
+        '(' => ')',
+        ')' => '(',
+        '[' => ']',
+        ']' => '[',
+        '{' => '}',
+        '}' => '{',
+        '<' => '>',
+        '>' => '<',
+        _ => ch,
+    }
+}
+
+/// One unit of the outer text: a character, or an isolated run already in visual order.
+enum Unit {
+    Char(char),
+    Isolated(Vec<char>),
+}
+
+/// `text` in the order its characters appear on screen, left to right.
+pub fn visual(text: &str) -> Cow<'_, str> {
+    if !text.chars().any(is_rtl) {
+        return Cow::Borrowed(text);
+    }
+    Cow::Owned(reorder(&mut text.chars()).into_iter().collect())
+}
+
+/// Reorder up to the end of the current isolate, or of the text.
+fn reorder(chars: &mut impl Iterator<Item = char>) -> Vec<char> {
+    let mut units = Vec::new();
+    while let Some(ch) = chars.next() {
+        match ch {
+            ISOLATE => units.push(Unit::Isolated(reorder(chars))),
+            POP => break,
+            _ => units.push(Unit::Char(ch)),
+        }
+    }
+
+    // An isolated run counts as a neutral from outside
+    let mut classes: Vec<Class> = units
+        .iter()
+        .map(|unit| match unit {
+            Unit::Char(ch) => class(*ch),
+            Unit::Isolated(_) => Class::Neutral,
+        })
+        .collect();
+    // A single separator between digits belongs to the number, as in "1,200" or "3.5"
+    for i in 1..classes.len().saturating_sub(1) {
+        let separator = matches!(units[i], Unit::Char('.' | ',' | ':' | '/'));
+        if separator && classes[i - 1] == Class::Number && classes[i + 1] == Class::Number {
+            classes[i] = Class::Number;
+        }
+    }
+
+    // Numbers follow the last strong direction before them; the paragraph starts left
+    let mut last = Class::Left;
+    let mut rtl_number = vec![false; classes.len()];
+    for (i, class) in classes.iter().enumerate() {
+        match class {
+            Class::Left | Class::Right => last = *class,
+            Class::Number => rtl_number[i] = last == Class::Right,
+            Class::Neutral => {}
+        }
+    }
+    let direction = |i: usize| match classes[i] {
+        Class::Number if rtl_number[i] => Some(Class::Right),
+        Class::Number => Some(Class::Left),
+        Class::Neutral => None,
+        class => Some(class),
+    };
+
+    let mut levels = vec![0u8; units.len()];
+    for i in 0..units.len() {
+        levels[i] = match direction(i) {
+            Some(Class::Right) if classes[i] == Class::Number => 2,
+            Some(Class::Right) => 1,
+            Some(_) => 0,
+            None => {
+                // Neutrals between two right-to-left sides read right to left
+                let before = (0..i).rev().find_map(direction).unwrap_or(Class::Left);
+                let after = (i + 1..units.len())
+                    .find_map(direction)
+                    .unwrap_or(Class::Left);
+                u8::from(before == Class::Right && after == Class::Right)
+            }
+        };
+    }
+
+    // Reverse every run at or above each level, from the highest down
+    let mut order: Vec<usize> = (0..units.len()).collect();
+    let max = levels.iter().copied().max().unwrap_or(0);
+    for level in (1..=max).rev() {
+        let mut i = 0;
+        while i < order.len() {
+            if levels[order[i]] < level {
+                i += 1;
+                continue;
+            }
+            let start = i;
+            while i < order.len() && levels[order[i]] >= level {
+                i += 1;
+            }
+            order[start..i].reverse();
+        }
+    }
+
+    let mut out = Vec::with_capacity(units.len());
+    for i in order {
+        match &units[i] {
+            Unit::Char(ch) if levels[i] % 2 == 1 => out.push(mirror(*ch)),
+            Unit::Char(ch) => out.push(*ch),
+            Unit::Isolated(run) => out.extend_from_slice(run),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn truncating_counts_columns_and_closes_the_isolate() {
+        let line = format!("Beaten: {}", isolate("אבג"));
+        assert_eq!(truncate(&line, 9), format!("Beaten: {}א{}", ISOLATE, POP));
+        assert_eq!(truncate("名前名前", 5), "名前");
+        assert_eq!(truncate("abc", 10), "abc");
+    }
+}
@@ -0,0 +1,314 @@
+//! Bots that fly the bird for the screensaver and the stress test.
+//!
+//! A bot is a `Strategy`: each tick it is shown a `View` of the bird and the pipes ahead
+//! and says whether to flap. Three come built in, one per `Tier`: a human-like flier that
+//! reacts late and sometimes fumbles, a greedy one that keeps its climbs centred on the
+//! next gap, and a planner that tries both choices a second and a half ahead. A fourth,
+//! the `Brain`, is a neural network whose weights come from `tflap train`.
+
+use crate::{BIRD_WIDTH, GRAVITY, JUMP_VELOCITY};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::collections::VecDeque;
+
+/// Most rows the bird may sink into the far edge of a roomy gap before flapping.
+const MAX_MARGIN: f32 = 2.5;
+/// Least, so a narrow gap still leaves a tick's fall to react in.
+const MIN_MARGIN: f32 = 0.8;
+/// Ticks the planner looks ahead.
+pub const HORIZON: u32 = 30;
+/// A person's reaction time, a tenth of a second or so.
+const REACTION_TICKS: usize = 2;
+/// Chance of a wanted flap going missing on a tick.
+const MISS_CHANCE: f64 = 0.05;
+/// Chance of flapping for no reason on a tick.
+const TWITCH_CHANCE: f64 = 0.003;
+
+/// One pipe's opening, as seen from the bird.
+#[derive(Clone)]
+pub struct Opening {
+    /// Columns from the bird's left edge to the pipe's, negative once the bird is inside.
+    pub ahead: f32,
+    pub width: f32,
+    /// First and last open rows.
+    pub top: f32,
+    pub bottom: f32,
+}
+
+/// What a bot sees of the game on one tick.
+#[derive(Clone)]
+pub struct View {
+    pub y: f32,
+    pub velocity: f32,
+    /// 1.0 when gravity pulls down the screen, -1.0 when a flip pipe has turned it.
+    pub gravity: f32,
+    /// Wind on each of the next `HORIZON` ticks, starting with the coming one.
+    pub wind: Vec<f32>,
+    /// Simulated ticks per real tick at the current speed.
+    pub dt: f32,
+    /// Columns the pipes move per tick.
+    pub scroll: f32,
+    /// First row the bird can fly in, and the first row of the ground below.
+    pub ceiling: f32,
+    pub floor: f32,
+    /// Pipes the bird hasn't got past yet, nearest first.
+    pub openings: Vec<Opening>,
+}
+
+pub trait Strategy {
+    /// Whether to flap this tick.
+    fn decide(&mut self, view: &View) -> bool;
+}
+
+/// How well a bot flies.
+#[derive(Clone, Copy, PartialEq, Default)]
+pub enum Tier {
+    Easy,
+    #[default]
+    Normal,
+    Hard,
+}
+
+impl Tier {
+    pub const ALL: [Tier; 3] = [Tier::Easy, Tier::Normal, Tier::Hard];
+
+    /// A tier by its key, or by the name of the bot that flies it.
+    pub fn parse(s: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|tier| tier.key() == s || tier.flier() == s)
+    }
+
+    pub fn key(self) -> &'static str {
+        match self {
+            Tier::Easy => "easy",
+            Tier::Normal => "normal",
+            Tier::Hard => "hard",
+        }
+    }
+
+    /// The name of the bot that flies the tier.
+    pub fn flier(self) -> &'static str {
+        match self {
+            Tier::Easy => "human",
+            Tier::Normal => "greedy",
+            Tier::Hard => "planner",
+        }
+    }
+
+    pub fn strategy(self) -> Box<dyn Strategy> {
+        match self {
+            Tier::Easy => Box::new(HumanLike::new(GapCenter)),
+            Tier::Normal => Box::new(GapCenter),
+            Tier::Hard => Box::new(Lookahead),
+        }
+    }
+}
+
+/// The opening the bird is heading for, or the middle third of the sky between pipes.
+fn target(view: &View) -> (f32, f32) {
+    let third = (view.floor - view.ceiling) / 3.0;
+    view.openings
+        .first()
+        .map_or((view.ceiling + third, view.floor - third), |opening| {
+            (opening.top, opening.bottom)
+        })
+}
+
+/// Greedy: flap once the bird sinks far enough that the climb after it tops out inside
+/// the next gap, and otherwise fall.
+pub struct GapCenter;
+
+impl Strategy for GapCenter {
+    fn decide(&mut self, view: &View) -> bool {
+        let (top, bottom) = target(view);
+        // The bird is in a row while its height rounds down to it, so the gap's far edge
+        // is one past its last row
+        let bottom = bottom + 1.0;
+        // Rows left before the bird reaches the side of the gap it is falling towards
+        let room = if view.gravity > 0.0 {
+            bottom - view.y
+        } else {
+            view.y - top
+        };
+        let rise = JUMP_VELOCITY * JUMP_VELOCITY / (2.0 * GRAVITY);
+        let margin = ((bottom - top - rise) / 2.0 + 0.5).clamp(MIN_MARGIN, MAX_MARGIN);
+        let falling = view.velocity * view.gravity > 0.0;
+        room < margin && falling
+    }
+}
+
+/// Tries flapping and not flapping, each followed by flying greedily, and takes whichever
+/// stays alive longer through the pipes ahead. When both make it, it does what the greedy
+/// bot would.
+pub struct Lookahead;
+
+impl Lookahead {
+    /// Ticks survived, up to `HORIZON`, after flapping or not on this one.
+    fn survives(view: &View, flap: bool) -> u32 {
+        let mut view = view.clone();
+        let mut flap = flap;
+        for tick in 0..HORIZON {
+            if flap {
+                view.velocity = JUMP_VELOCITY * view.gravity;
+            }
+            let wind = view.wind.get(tick as usize).copied().unwrap_or(0.0);
+            view.velocity += (GRAVITY * view.gravity + wind) * view.dt;
+            view.y += view.velocity * view.dt;
+            for opening in &mut view.openings {
+                opening.ahead -= view.scroll;
+            }
+            view.openings
+                .retain(|opening| opening.ahead + opening.width > 0.0);
+            if Self::crashed(&view) {
+                return tick;
+            }
+            flap = GapCenter.decide(&view);
+        }
+        HORIZON
+    }
+
+    fn crashed(view: &View) -> bool {
+        if view.y < view.ceiling || view.y >= view.floor {
+            return true;
+        }
+        let row = view.y.floor();
+        view.openings.iter().any(|opening| {
+            let overlaps = opening.ahead < BIRD_WIDTH as f32 && opening.ahead + opening.width > 0.0;
+            overlaps && (row < opening.top || row > opening.bottom)
+        })
+    }
+}
+
+impl Strategy for Lookahead {
+    fn decide(&mut self, view: &View) -> bool {
+        let flap = Self::survives(view, true);
+        let coast = Self::survives(view, false);
+        if flap == coast {
+            GapCenter.decide(view)
+        } else {
+            flap > coast
+        }
+    }
+}
+
+/// Another strategy flown with a person's reaction time and the odd fumble.
+pub struct HumanLike<S> {
+    inner: S,
+    /// Decisions made but not yet acted on, oldest first.
+    pending: VecDeque<bool>,
+    rng: StdRng,
+}
+
+impl<S: Strategy> HumanLike<S> {
+    pub fn new(inner: S) -> Self {
+        Self {
+            inner,
+            pending: VecDeque::from(vec![false; REACTION_TICKS]),
+            rng: StdRng::from_entropy(),
+        }
+    }
+}
+
+impl<S: Strategy> Strategy for HumanLike<S> {
+    fn decide(&mut self, view: &View) -> bool {
+        self.pending.push_back(self.inner.decide(view));
+        let flap = self.pending.pop_front().unwrap_or(false);
+        if flap {
+            !self.rng.gen_bool(MISS_CHANCE)
+        } else {
+            self.rng.gen_bool(TWITCH_CHANCE)
+        }
+    }
+}
+
+/// Inputs to a brain's network, counting the constant bias input.
+const INPUTS: usize = 7;
+const HIDDEN: usize = 5;
+/// Weights in a brain: input to hidden, then hidden to output with a bias. The bias input
+/// gives the hidden layer its own.
+pub const WEIGHTS: usize = INPUTS * HIDDEN + HIDDEN + 1;
+
+/// A small neural network evolved by `tflap train`. It sees where the next two gaps are,
+/// how the bird is moving and how the wind is blowing, all turned so that gravity pulls
+/// down, and flaps when its output is positive.
+#[derive(Clone)]
+pub struct Brain {
+    pub weights: Vec<f32>,
+}
+
+impl Brain {
+    fn inputs(view: &View) -> [f32; INPUTS] {
+        let (top, bottom) = target(view);
+        let down = view.gravity;
+        // Rows to the gap's far and near edges in the direction of fall
+        let (far, near) = if down > 0.0 {
+            (bottom + 1.0 - view.y, view.y - top)
+        } else {
+            (view.y - top, bottom + 1.0 - view.y)
+        };
+        let ahead = view.openings.first().map_or(1.0, |next| next.ahead / 40.0);
+        let after = view.openings.get(1).map_or(0.0, |second| {
+            ((second.top + second.bottom) / 2.0 - view.y) * down / 10.0
+        });
+        let wind = view.wind.first().copied().unwrap_or(0.0) * down * 5.0;
+        [
+            far / 10.0,
+            near / 10.0,
+            view.velocity * down,
+            ahead,
+            after,
+            wind,
+            1.0,
+        ]
+    }
+
+    /// Read a brain saved by `tflap train`.
+    pub fn parse(content: &str) -> Result<Self, String> {
+        let start = content
+            .find("\"weights\"")
+            .and_then(|at| content[at..].find('[').map(|open| at + open + 1))
+            .ok_or("no weights in brain file")?;
+        let end = content[start..]
+            .find(']')
+            .map(|close| start + close)
+            .ok_or("unfinished weights in brain file")?;
+        let weights = content[start..end]
+            .split(',')
+            .map(|weight| weight.trim().parse::<f32>())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|_| "bad weight in brain file".to_string())?;
+        if weights.len() != WEIGHTS {
+            return Err(format!(
+                "brain file has {} weights, expected {}",
+                weights.len(),
+                WEIGHTS
+            ));
+        }
+        Ok(Self { weights })
+    }
+
+    /// The brain as JSON, with how it did in training.
+    pub fn to_json(&self, generation: u32, fitness: f32) -> String {
+        let weights: Vec<String> = self.weights.iter().map(|w| format!("{:.6}", w)).collect();
+        format!(
+            "{{\n  \"generation\": {},\n  \"fitness\": {:.3},\n  \"weights\": [{}]\n}}\n",
+            generation,
+            fitness,
+            weights.join(", ")
+        )
+    }
+}
+
+impl Strategy for Brain {
+    fn decide(&mut self, view: &View) -> bool {
+        let inputs = Self::inputs(view);
+        let (hidden, output) = self.weights.split_at(INPUTS * HIDDEN);
+        let mut sum = output[HIDDEN];
+        for (weights, out) in hidden.chunks(INPUTS).zip(output) {
+            let activation: f32 = inputs.iter().zip(weights).map(|(x, w)| x * w).sum();
+            sum += activation.tanh() * out;
+        }
+        sum > 0.0
+    }
+}
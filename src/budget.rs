@@ -0,0 +1,86 @@
+//! Keeping frames within budget on slow terminals and SSH links.
+//!
+//! Each frame's draw time, including writing it out, is checked against the budget. When
+//! most frames in a window run over, the next effect on the list is turned off and a
+//! short notice says so. Effects stay off for the rest of the session, so the game never
+//! flickers between looks.
+
+use std::time::{Duration, Instant};
+
+/// Half a tick, leaving the rest for input and the simulation.
+const FRAME_BUDGET: Duration = Duration::from_millis(25);
+/// Frames looked at before deciding, and how many of them must be slow.
+const WINDOW_FRAMES: u32 = 40;
+const SLOW_FRAMES: u32 = 30;
+const NOTICE_TIME: Duration = Duration::from_secs(3);
+
+/// Effects that can go, cheapest to lose first.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Effect {
+    /// Rain, snow and fog drawn across the screen.
+    Particles,
+    /// Night shading in many shades, which takes a true-color code per cell.
+    Gradients,
+    /// Milestone confetti and the rivals' full-height marker lines.
+    Decorations,
+}
+
+impl Effect {
+    const ALL: [Effect; 3] = [Effect::Particles, Effect::Gradients, Effect::Decorations];
+
+    fn notice(self) -> &'static str {
+        match self {
+            Effect::Particles => "Slow terminal: weather effects off",
+            Effect::Gradients => "Slow terminal: night shading simplified",
+            Effect::Decorations => "Slow terminal: confetti and rival lines off",
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct FrameBudget {
+    /// How many of `Effect::ALL` are off.
+    dropped: usize,
+    frames: u32,
+    slow: u32,
+    notice: Option<(&'static str, Instant)>,
+}
+
+impl FrameBudget {
+    /// Count a frame that took `elapsed` to draw.
+    pub fn record(&mut self, elapsed: Duration) {
+        if self.dropped == Effect::ALL.len() {
+            return;
+        }
+        self.frames += 1;
+        if elapsed > FRAME_BUDGET {
+            self.slow += 1;
+        }
+        if self.frames < WINDOW_FRAMES {
+            return;
+        }
+        if self.slow >= SLOW_FRAMES {
+            let effect = Effect::ALL[self.dropped];
+            self.dropped += 1;
+            self.notice = Some((effect.notice(), Instant::now()));
+        }
+        self.frames = 0;
+        self.slow = 0;
+    }
+
+    pub fn allows(&self, effect: Effect) -> bool {
+        Effect::ALL[self.dropped..].contains(&effect)
+    }
+
+    /// Show another notice the same way, for a change made elsewhere.
+    pub fn announce(&mut self, text: &'static str) {
+        self.notice = Some((text, Instant::now()));
+    }
+
+    /// The latest notice, for a few seconds after.
+    pub fn notice(&self) -> Option<&'static str> {
+        self.notice
+            .filter(|(_, shown)| shown.elapsed() < NOTICE_TIME)
+            .map(|(text, _)| text)
+    }
+}
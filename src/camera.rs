@@ -0,0 +1,48 @@
+//! The zoom-follow camera, for low vision: the world drawn at twice the size, showing
+//! the rows around the bird and easing up and down after it.
+//!
+//! The game still plays on the whole board; only the view of the world moves. The HUD
+//! and the menus are drawn over it at their usual size.
+
+use crate::render::{Frame, Zoom};
+
+/// Terminal cells each way for every world cell in view.
+const SCALE: u16 = 2;
+/// How much of the way to the bird the view moves each tick.
+const SMOOTHING: f32 = 0.15;
+
+#[derive(Clone, Copy, Default)]
+pub struct Camera {
+    /// The world row at the top of the view.
+    top: f32,
+}
+
+impl Camera {
+    /// World rows in view on a board `height` rows tall.
+    fn rows(height: u16) -> u16 {
+        height / SCALE
+    }
+
+    /// Ease the view towards `bird_y`, keeping the board's edges in bounds.
+    pub fn follow(&mut self, bird_y: f32, height: u16) {
+        let rows = Self::rows(height) as f32;
+        let target = (bird_y - rows / 2.0).clamp(0.0, height as f32 - rows);
+        self.top += (target - self.top) * SMOOTHING;
+    }
+
+    /// The part of `world` in view, drawn at full size.
+    pub fn view(self, world: &Frame) -> Frame {
+        let (width, height) = (world.width(), world.height());
+        let seen = world
+            .crop(
+                0,
+                self.top.round() as u16,
+                width / SCALE,
+                Self::rows(height),
+            )
+            .grow(Zoom::Double);
+        let mut frame = Frame::new(width, height);
+        frame.blit(&seen, 0, 0);
+        frame
+    }
+}
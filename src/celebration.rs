@@ -0,0 +1,143 @@
+use crate::collision::Aabb;
+use crate::entity::{Body, Entities, Entity, Sprite};
+use crate::events::GameEvent;
+use crate::world::Pos;
+use crossterm::style::Color;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::cell::Cell;
+
+const BANNER_TICKS: u32 = 40;
+const FLASH_TICKS: u32 = 12;
+/// Confetti pieces thrown per tier from the third milestone on.
+const CONFETTI_PER_TIER: usize = 24;
+const CONFETTI_GRAVITY: f32 = 0.08;
+const CONFETTI_COLORS: [Color; 5] = [
+    Color::Red,
+    Color::Yellow,
+    Color::Green,
+    Color::Cyan,
+    Color::Magenta,
+];
+const CONFETTI_GLYPHS: [char; 4] = ['*', '+', '~', 'o'];
+
+/// Effects for crossing score milestones. Each milestone is one tier above the last:
+/// every tier gets a banner and a flash, the second adds a fanfare, the third confetti.
+#[derive(Clone)]
+pub struct Celebration {
+    thresholds: Vec<u32>,
+    /// How many thresholds have been celebrated this run.
+    reached: usize,
+    /// Milestone shown in the banner and ticks it has left.
+    banner: Option<(u32, u32)>,
+    flash_ticks: u32,
+    confetti: Entities,
+    /// Bells still to ring, one per drawn frame.
+    bells: Cell<u32>,
+    /// Kept apart from the game's RNG so effects never change the pipes.
+    rng: StdRng,
+}
+
+impl Celebration {
+    pub fn new(mut thresholds: Vec<u32>, seed: u64) -> Self {
+        thresholds.sort_unstable();
+        thresholds.dedup();
+        Self {
+            thresholds,
+            reached: 0,
+            banner: None,
+            flash_ticks: 0,
+            confetti: Entities::new(fall(0)),
+            bells: Cell::new(0),
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+
+    pub fn on_event(&mut self, event: &GameEvent, width: u16) {
+        let GameEvent::Scored { score } = *event else {
+            return;
+        };
+        // Several milestones can be crossed at once; celebrate the biggest, and
+        // only once even if a revive penalty drops the score back under it
+        let tier = self.thresholds.iter().filter(|&&t| score >= t).count();
+        if tier > self.reached {
+            self.reached = tier;
+            self.trigger(tier, width);
+        }
+    }
+
+    fn trigger(&mut self, tier: usize, width: u16) {
+        self.banner = Some((self.thresholds[tier - 1], BANNER_TICKS));
+        self.flash_ticks = FLASH_TICKS;
+        if tier >= 2 {
+            self.bells.set(tier as u32 - 1);
+        }
+        if tier >= 3 {
+            for _ in 0..CONFETTI_PER_TIER * (tier - 2) {
+                let pos = Pos::new(
+                    self.rng.gen_range(0.0..width as f32),
+                    self.rng.gen_range(-4.0..0.0),
+                );
+                let velocity =
+                    Pos::new(self.rng.gen_range(-0.4..0.4), self.rng.gen_range(0.0..0.5));
+                let sprite = Sprite {
+                    color: CONFETTI_COLORS[self.rng.gen_range(0..CONFETTI_COLORS.len())],
+                    glyph: CONFETTI_GLYPHS[self.rng.gen_range(0..CONFETTI_GLYPHS.len())],
+                    wide: false,
+                };
+                self.confetti.spawn(Entity {
+                    body: Body {
+                        pos,
+                        velocity,
+                        gravity: CONFETTI_GRAVITY,
+                    },
+                    sprite,
+                });
+            }
+        }
+    }
+
+    pub fn update(&mut self, height: u16) {
+        self.flash_ticks = self.flash_ticks.saturating_sub(1);
+        if let Some((_, ticks)) = &mut self.banner {
+            *ticks = ticks.saturating_sub(1);
+            if *ticks == 0 {
+                self.banner = None;
+            }
+        }
+        self.confetti.set_bounds(fall(height));
+        self.confetti.physics();
+    }
+
+    /// Whether the world should be drawn in its flash colors this tick.
+    pub fn flashing(&self) -> bool {
+        self.flash_ticks > 0 && (self.flash_ticks / 2) % 2 == 1
+    }
+
+    pub fn banner(&self) -> Option<String> {
+        self.banner
+            .map(|(milestone, _)| format!("★ {} POINTS! ★", milestone))
+    }
+
+    pub fn confetti(&self) -> &Entities {
+        &self.confetti
+    }
+
+    /// Take one pending fanfare bell, if any.
+    pub fn take_bell(&self) -> bool {
+        let bells = self.bells.get();
+        self.bells.set(bells.saturating_sub(1));
+        bells > 0
+    }
+}
+
+/// Where confetti lives: anywhere until it falls off the bottom of a screen `height`
+/// rows tall.
+fn fall(height: u16) -> Aabb {
+    Aabb::new(
+        f32::NEG_INFINITY,
+        f32::NEG_INFINITY,
+        f32::INFINITY,
+        height as f32,
+    )
+}
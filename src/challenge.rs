@@ -0,0 +1,428 @@
+//! Compact, pasteable encoding of a finished run: its seed, settings, and inputs.
+//!
+//! Byte layout before base64: a format version, the seed, the board size, packed
+//! settings, the starting speed, the mutators from version 6 on, the speed ramp from
+//! version 7 on, flags for banking from version 8 on, for double or nothing from
+//! version 9 on, for typing mode from version 10 on, for quiz gates from version 11 on
+//! and for rewind mode from version 14 on, the rhythm mode's beats a minute (0 for none)
+//! from version 12 on, the score to beat, then each input as a varint tick
+//! delta shifted left two bits with the input kind in the low bits: a flap, or a
+//! revive key, speed change, or other action followed by one byte. The other actions
+//! are a one-switch flap, small or big, banking, taking or turning down double or
+//! nothing, answering a quiz gate, and rewinding a tick. A one-byte checksum at the end
+//! catches most copy-paste damage. Version 1 codes have no speed and a one-bit kind.
+//! The version also says which rules the run was played by, since some changes to the
+//! game play a run's inputs out differently; an older code is replayed by its own rules,
+//! and a run on them is shared under that version again.
+
+use crate::base64;
+use crate::config::{Config, MAX_LIVES, MAX_SPEED, MIN_SPEED};
+use crate::difficulty::Difficulty;
+use crate::mutators::Mutators;
+use crate::ramp::Curve;
+use crate::rhythm;
+use crate::scoring::ScoringMode;
+use crate::telemetry::Input;
+use crate::weather::WeatherSetting;
+
+pub const VERSION: u8 = 14;
+/// First version to limit how far each gap sits from the one before.
+pub const STEPPED_GAPS: u8 = 3;
+/// First version with a ground and a ceiling the bird crashes into.
+pub const GROUND: u8 = 4;
+/// First version to keep the top line for the HUD, with the ceiling below it.
+pub const HUD_LINE: u8 = 5;
+/// First version that can carry mutators.
+pub const MUTATORS: u8 = 6;
+/// First version that can carry a speed ramp.
+pub const RAMP: u8 = 7;
+/// First version that can carry banking.
+pub const BANKING: u8 = 8;
+/// First version that can carry double or nothing.
+pub const WAGER: u8 = 9;
+/// First version that can carry typing mode.
+pub const TYPING: u8 = 10;
+/// First version that can carry quiz gates.
+pub const QUIZ: u8 = 11;
+/// First version that can carry rhythm mode.
+pub const RHYTHM: u8 = 12;
+/// First version to hit-test with boxes: a pipe counts as passed once the bird is clear
+/// of it, and pipes moving more than a column a tick are tested along the way.
+pub const BOXES: u8 = 13;
+/// First version that can carry rewind mode.
+pub const REWIND: u8 = 14;
+
+/// Smallest board a challenge code or file may set: room for the widest gap of any
+/// difficulty, with the three rows above and below it that gaps keep clear of.
+pub const MIN_BOARD: (u16, u16) = (40, Difficulty::WIDEST_GAP + 6);
+/// Largest, in either direction.
+const MAX_BOARD: u16 = 1000;
+
+const FLAP: u64 = 0;
+const REVIVE: u64 = 1;
+const SPEED: u64 = 2;
+const ACTION: u64 = 3;
+
+/// The byte after an `ACTION` input.
+const SMALL_FLAP: u8 = 0;
+const BIG_FLAP: u8 = 1;
+const BANK: u8 = 2;
+const TAKE_WAGER: u8 = 3;
+const DECLINE_WAGER: u8 = 4;
+/// Quiz answers, from 0 to 9.
+const ANSWER: u8 = 5;
+const LAST_ANSWER: u8 = ANSWER + 9;
+const REWIND_TICK: u8 = LAST_ANSWER + 1;
+
+/// Bits of the byte of rule flags.
+const BANKING_FLAG: u8 = 1;
+const WAGER_FLAG: u8 = 2;
+const TYPING_FLAG: u8 = 4;
+const QUIZ_FLAG: u8 = 8;
+const REWIND_FLAG: u8 = 16;
+
+pub struct Challenge {
+    pub seed: u64,
+    /// Pipe layout depends on the board size, so a challenge is always played at it.
+    pub width: u16,
+    pub height: u16,
+    pub scoring: ScoringMode,
+    pub difficulty: Difficulty,
+    pub weather: WeatherSetting,
+    pub lives: Option<u8>,
+    pub day_night: bool,
+    pub one_switch: bool,
+    pub speed: u8,
+    pub mutators: Mutators,
+    pub ramp: Curve,
+    pub banking: bool,
+    pub double_or_nothing: bool,
+    pub typing: bool,
+    pub quiz: bool,
+    pub rhythm: Option<u16>,
+    pub rewind: bool,
+    pub score: u32,
+    pub inputs: Vec<(u32, Input)>,
+    /// The version whose rules the run was played by.
+    pub rules: u8,
+}
+
+fn push_varint(out: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        out.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl Reader<'_> {
+    fn byte(&mut self) -> Option<u8> {
+        let (&first, rest) = self.bytes.split_first()?;
+        self.bytes = rest;
+        Some(first)
+    }
+
+    fn varint(&mut self) -> Option<u64> {
+        let mut value = 0u64;
+        for shift in (0..64).step_by(7) {
+            let byte = self.byte()?;
+            value |= ((byte & 0x7f) as u64) << shift;
+            if byte & 0x80 == 0 {
+                return Some(value);
+            }
+        }
+        None
+    }
+}
+
+pub fn checksum(bytes: &[u8]) -> u8 {
+    bytes
+        .iter()
+        .fold(0u8, |sum, &b| sum.rotate_left(1).wrapping_add(b))
+}
+
+impl Challenge {
+    /// A run on `seed` at the board size and with the settings that affect play from
+    /// `config`, with no score or inputs yet.
+    pub fn new(seed: u64, width: u16, height: u16, config: &Config) -> Self {
+        Self {
+            seed,
+            width,
+            height,
+            scoring: config.scoring,
+            difficulty: config.difficulty,
+            weather: config.weather,
+            lives: config.lives,
+            day_night: config.day_night,
+            one_switch: config.one_switch,
+            speed: config.speed,
+            mutators: config.mutators,
+            ramp: config.ramp,
+            banking: config.banking,
+            double_or_nothing: config.double_or_nothing,
+            typing: config.typing,
+            quiz: config.quiz,
+            rhythm: config.rhythm,
+            rewind: config.rewind,
+            score: 0,
+            inputs: Vec::new(),
+            rules: config.rules,
+        }
+    }
+
+    /// Use the challenge's settings for everything that affects the run.
+    pub fn apply(&self, config: &mut Config) {
+        config.scoring = self.scoring;
+        config.difficulty = self.difficulty;
+        config.weather = self.weather;
+        config.lives = self.lives;
+        config.day_night = self.day_night;
+        config.speed = self.speed;
+        config.one_switch = self.one_switch;
+        config.mutators = self.mutators;
+        config.ramp = self.ramp;
+        config.banking = self.banking;
+        config.double_or_nothing = self.double_or_nothing;
+        config.typing = self.typing;
+        config.quiz = self.quiz;
+        config.rhythm = self.rhythm;
+        config.rewind = self.rewind;
+        config.rules = self.rules;
+    }
+
+    pub fn encode(&self) -> String {
+        // Version 1 played by the same rules as 2, and only lacks fields
+        let mut bytes = vec![self.rules.max(2)];
+        bytes.extend_from_slice(&self.seed.to_le_bytes());
+        push_varint(&mut bytes, self.width as u64);
+        push_varint(&mut bytes, self.height as u64);
+        let weather = WeatherSetting::ALL
+            .iter()
+            .position(|&w| w == self.weather)
+            .unwrap_or(0);
+        let difficulty = Difficulty::ALL
+            .iter()
+            .position(|&d| d == self.difficulty)
+            .unwrap_or(0);
+        let settings = (self.scoring == ScoringMode::Classic) as u8
+            | (self.day_night as u8) << 1
+            | (difficulty as u8) << 2
+            | (weather as u8) << 4
+            | (self.one_switch as u8) << 7;
+        bytes.push(settings);
+        bytes.push(self.lives.unwrap_or(0));
+        bytes.push(self.speed);
+        if self.rules >= MUTATORS {
+            bytes.push(self.mutators.bits());
+        }
+        if self.rules >= RAMP {
+            let ramp = Curve::ALL.iter().position(|&c| c == self.ramp).unwrap_or(0);
+            bytes.push(ramp as u8);
+        }
+        if self.rules >= BANKING {
+            let mut flags = 0;
+            if self.banking {
+                flags |= BANKING_FLAG;
+            }
+            if self.double_or_nothing && self.rules >= WAGER {
+                flags |= WAGER_FLAG;
+            }
+            if self.typing && self.rules >= TYPING {
+                flags |= TYPING_FLAG;
+            }
+            if self.quiz && self.rules >= QUIZ {
+                flags |= QUIZ_FLAG;
+            }
+            if self.rewind && self.rules >= REWIND {
+                flags |= REWIND_FLAG;
+            }
+            bytes.push(flags);
+        }
+        if self.rules >= RHYTHM {
+            bytes.push(self.rhythm.map_or(0, |bpm| bpm as u8));
+        }
+        push_varint(&mut bytes, self.score as u64);
+
+        push_varint(&mut bytes, self.inputs.len() as u64);
+        let mut last = 0;
+        for &(tick, input) in &self.inputs {
+            let delta = (tick.saturating_sub(last) as u64) << 2;
+            last = tick;
+            match input {
+                Input::Flap => push_varint(&mut bytes, delta | FLAP),
+                Input::Revive(c) => {
+                    push_varint(&mut bytes, delta | REVIVE);
+                    bytes.push(if c.is_ascii() { c as u8 } else { b'?' });
+                }
+                Input::Speed(speed) => {
+                    push_varint(&mut bytes, delta | SPEED);
+                    bytes.push(speed);
+                }
+                Input::SmallFlap
+                | Input::BigFlap
+                | Input::Bank
+                | Input::Wager(_)
+                | Input::Answer(_)
+                | Input::Rewind => {
+                    push_varint(&mut bytes, delta | ACTION);
+                    bytes.push(match input {
+                        Input::SmallFlap => SMALL_FLAP,
+                        Input::BigFlap => BIG_FLAP,
+                        Input::Wager(true) => TAKE_WAGER,
+                        Input::Wager(false) => DECLINE_WAGER,
+                        Input::Answer(digit) => ANSWER + digit.min(9),
+                        Input::Rewind => REWIND_TICK,
+                        _ => BANK,
+                    });
+                }
+            }
+        }
+        bytes.push(checksum(&bytes));
+        base64::encode_url(&bytes)
+    }
+
+    pub fn decode(code: &str) -> Result<Self, String> {
+        let bad = || "not a valid challenge code".to_string();
+        let bytes = base64::decode_url(code.trim()).ok_or_else(bad)?;
+        let (&sum, body) = bytes.split_last().ok_or_else(bad)?;
+        if checksum(body) != sum {
+            return Err("challenge code is damaged (checksum mismatch)".to_string());
+        }
+
+        let mut r = Reader { bytes: body };
+        let version = r.byte().ok_or_else(bad)?;
+        if version == 0 || version > VERSION {
+            return Err(format!("unsupported challenge version {}", version));
+        }
+        let kind_bits = if version == 1 { 1 } else { 2 };
+        let mut seed = [0u8; 8];
+        for b in &mut seed {
+            *b = r.byte().ok_or_else(bad)?;
+        }
+        let dimension = |value: Option<u64>, least: u16| match value {
+            Some(v) if (u64::from(least)..=u64::from(MAX_BOARD)).contains(&v) => Ok(v as u16),
+            _ => Err(bad()),
+        };
+        let width = dimension(r.varint(), MIN_BOARD.0)?;
+        let height = dimension(r.varint(), MIN_BOARD.1)?;
+        let settings = r.byte().ok_or_else(bad)?;
+        let difficulty = *Difficulty::ALL
+            .get((settings >> 2 & 3) as usize)
+            .ok_or_else(bad)?;
+        let weather = *WeatherSetting::ALL
+            .get((settings >> 4 & 7) as usize)
+            .ok_or_else(bad)?;
+        let lives = match r.byte().ok_or_else(bad)? {
+            0 => None,
+            n if n <= MAX_LIVES => Some(n),
+            _ => return Err(bad()),
+        };
+        let speed = match version {
+            1 => 100,
+            _ => match r.byte().ok_or_else(bad)? {
+                s @ MIN_SPEED..=MAX_SPEED => s,
+                _ => return Err(bad()),
+            },
+        };
+        let mutators = match version {
+            v if v < MUTATORS => Mutators::default(),
+            _ => Mutators::from_bits(r.byte().ok_or_else(bad)?).ok_or_else(bad)?,
+        };
+        let ramp = match version {
+            v if v < RAMP => Curve::Off,
+            _ => *Curve::ALL
+                .get(r.byte().ok_or_else(bad)? as usize)
+                .ok_or_else(bad)?,
+        };
+        let flags = match version {
+            v if v < BANKING => 0,
+            _ => r.byte().ok_or_else(bad)?,
+        };
+        let known = match version {
+            v if v < WAGER => BANKING_FLAG,
+            v if v < TYPING => BANKING_FLAG | WAGER_FLAG,
+            v if v < QUIZ => BANKING_FLAG | WAGER_FLAG | TYPING_FLAG,
+            v if v < REWIND => BANKING_FLAG | WAGER_FLAG | TYPING_FLAG | QUIZ_FLAG,
+            _ => BANKING_FLAG | WAGER_FLAG | TYPING_FLAG | QUIZ_FLAG | REWIND_FLAG,
+        };
+        if flags & !known != 0 {
+            return Err(bad());
+        }
+        let rhythm = match version {
+            v if v < RHYTHM => None,
+            _ => match r.byte().ok_or_else(bad)? {
+                0 => None,
+                bpm if (rhythm::MIN_BPM..=rhythm::MAX_BPM).contains(&(bpm as u16)) => {
+                    Some(bpm as u16)
+                }
+                _ => return Err(bad()),
+            },
+        };
+        let score = u32::try_from(r.varint().ok_or_else(bad)?).map_err(|_| bad())?;
+
+        let count = r.varint().ok_or_else(bad)?;
+        // Every input takes at least a byte, which also bounds the allocation
+        if count > r.bytes.len() as u64 {
+            return Err(bad());
+        }
+        let mut inputs = Vec::with_capacity(count as usize);
+        let mut tick = 0u32;
+        for _ in 0..count {
+            let value = r.varint().ok_or_else(bad)?;
+            let delta = u32::try_from(value >> kind_bits).map_err(|_| bad())?;
+            tick = tick.checked_add(delta).ok_or_else(bad)?;
+            let input = match value & ((1 << kind_bits) - 1) {
+                FLAP => Input::Flap,
+                REVIVE => Input::Revive(r.byte().ok_or_else(bad)? as char),
+                SPEED => Input::Speed(r.byte().ok_or_else(bad)?),
+                ACTION => match r.byte().ok_or_else(bad)? {
+                    SMALL_FLAP => Input::SmallFlap,
+                    BIG_FLAP => Input::BigFlap,
+                    BANK => Input::Bank,
+                    TAKE_WAGER => Input::Wager(true),
+                    DECLINE_WAGER => Input::Wager(false),
+                    b @ ANSWER..=LAST_ANSWER if version >= QUIZ => Input::Answer(b - ANSWER),
+                    REWIND_TICK if version >= REWIND => Input::Rewind,
+                    _ => return Err(bad()),
+                },
+                _ => return Err(bad()),
+            };
+            inputs.push((tick, input));
+        }
+        if !r.bytes.is_empty() {
+            return Err(bad());
+        }
+
+        Ok(Self {
+            seed: u64::from_le_bytes(seed),
+            width,
+            height,
+            scoring: if settings & 1 == 1 {
+                ScoringMode::Classic
+            } else {
+                ScoringMode::Style
+            },
+            difficulty,
+            weather,
+            lives,
+            day_night: settings & 2 != 0,
+            one_switch: settings & 0x80 != 0,
+            speed,
+            mutators,
+            ramp,
+            banking: flags & BANKING_FLAG != 0,
+            double_or_nothing: flags & WAGER_FLAG != 0,
+            typing: flags & TYPING_FLAG != 0,
+            quiz: flags & QUIZ_FLAG != 0,
+            rhythm,
+            rewind: flags & REWIND_FLAG != 0,
+            score,
+            inputs,
+            rules: version,
+        })
+    }
+}
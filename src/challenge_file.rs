@@ -0,0 +1,197 @@
+//! Challenge files: a seed and settings for everyone in a community to play, say for a
+//! week, passed around as a small text file instead of through a server.
+//!
+//! The file is `key = value` lines like the config file, with `#` comments:
+//!
+//! ```text
+//! name = Week 42
+//! creator = alice
+//! seed = 4242
+//! expires = 2026-10-20
+//! size = 80x24
+//! submit = https://example.org/tflap/week42
+//! difficulty = hard
+//! mutators = heavy
+//! ```
+//!
+//! `seed` is the only key needed. The board is `DEFAULT_SIZE` unless `size` says
+//! otherwise, since the pipes depend on it, and the settings that shape a run can be
+//! given as they are in the config file; the rest are the defaults, whatever the
+//! player's own config says. The challenge runs to the end of its `expires` day, in
+//! UTC. Each player's best on each challenge is kept in `.tflap_challenges` in the data
+//! directory, under an id taken from the file's contents, so any edit makes a new
+//! challenge.
+
+use crate::challenge::{self, Challenge};
+use crate::config::Config;
+use crate::date::Date;
+use crate::players;
+use crate::sha256;
+use crate::storage;
+use std::fs;
+use std::path::Path;
+
+const DEFAULT_SIZE: (u16, u16) = (80, 24);
+/// Config keys a challenge file can set: those that change how a run plays.
+const RULE_KEYS: &[&str] = &[
+    "scoring",
+    "difficulty",
+    "weather",
+    "lives",
+    "day_night",
+    "one_switch",
+    "speed",
+    "mutators",
+    "ramp",
+    "banking",
+    "double_or_nothing",
+    "typing",
+    "quiz",
+    "rhythm",
+    "rewind",
+];
+/// Hex digits of the contents' digest kept as the id.
+const ID_LENGTH: usize = 16;
+/// The bests file's versions. Version 1 only added the header.
+const FORMAT: storage::Format = storage::Format {
+    name: "challenges",
+    migrations: &[|body| body.to_string()],
+};
+
+pub struct ChallengeFile {
+    pub name: String,
+    pub creator: Option<String>,
+    /// The last day the challenge runs.
+    pub expires: Option<Date>,
+    /// Where players send their runs' challenge codes.
+    pub submit: Option<String>,
+    pub challenge: Challenge,
+    id: String,
+    /// The player's best score on it so far.
+    pub best: u32,
+}
+
+impl ChallengeFile {
+    /// Read the file at `path`, along with `player`'s best on it.
+    pub fn load(path: &Path, player: Option<&str>) -> Result<Self, String> {
+        let content = fs::read_to_string(path)
+            .map_err(|err| format!("could not read {}: {}", path.display(), err))?;
+        let mut file =
+            Self::parse(&content).map_err(|err| format!("{}: {}", path.display(), err))?;
+        if file.name.is_empty() {
+            file.name = path
+                .file_stem()
+                .map_or_else(String::new, |stem| stem.to_string_lossy().into_owned());
+        }
+        file.best = load_bests(player)
+            .0
+            .into_iter()
+            .find(|(id, _)| *id == file.id)
+            .map_or(0, |(_, best)| best);
+        Ok(file)
+    }
+
+    pub fn parse(content: &str) -> Result<Self, String> {
+        let mut rules = Config::default();
+        let (mut name, mut creator, mut expires, mut submit) = (String::new(), None, None, None);
+        let (mut seed, mut size) = (None, DEFAULT_SIZE);
+        let mut lines = Vec::new();
+        for line in content.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (key, value) = line
+                .split_once('=')
+                .ok_or_else(|| format!("'{}' isn't a key = value line", line))?;
+            let (key, value) = (key.trim(), value.trim());
+            lines.push(format!("{}={}", key, value));
+            match key {
+                "name" => name = value.to_string(),
+                "creator" => creator = Some(value.to_string()),
+                "seed" => seed = Some(value.parse().map_err(|_| format!("bad seed '{}'", value))?),
+                "expires" => {
+                    expires = Some(Date::parse(value).ok_or_else(|| {
+                        format!("expires should be a date like 2026-01-31, got '{}'", value)
+                    })?)
+                }
+                "size" => {
+                    size = parse_size(value).ok_or_else(|| {
+                        let (width, height) = challenge::MIN_BOARD;
+                        format!(
+                            "bad size '{}', expected at least {}x{}",
+                            value, width, height
+                        )
+                    })?
+                }
+                "submit" => submit = Some(value.to_string()),
+                key if RULE_KEYS.contains(&key) => rules.set(key, value),
+                _ => return Err(format!("unknown key '{}'", key)),
+            }
+        }
+        let seed = seed.ok_or("a challenge file needs a seed")?;
+        let (width, height) = size;
+        Ok(Self {
+            name,
+            creator,
+            expires,
+            submit,
+            challenge: Challenge::new(seed, width, height, &rules),
+            id: sha256::hex_digest(lines.join("\n").as_bytes())[..ID_LENGTH].to_string(),
+            best: 0,
+        })
+    }
+
+    /// Whether the challenge has ended by `now`, in seconds since the Unix epoch.
+    pub fn is_over(&self, now: u64) -> bool {
+        self.expires.is_some_and(|last| Date::from_unix(now) > last)
+    }
+
+    /// Note a score of `player`'s, keeping it if it's their best on the challenge.
+    pub fn record(&mut self, score: u32, player: Option<&str>) {
+        if score <= self.best {
+            return;
+        }
+        self.best = score;
+        let Some(path) = players::home_file("challenges", player) else {
+            return;
+        };
+        let (mut bests, writable) = load_bests(player);
+        if !writable {
+            return;
+        }
+        bests.retain(|(id, _)| *id != self.id);
+        bests.push((self.id.clone(), score));
+        let content: String = bests
+            .iter()
+            .map(|(id, best)| format!("{} {}\n", id, best))
+            .collect();
+        let _ = storage::write_versioned(&path, &FORMAT, &content);
+    }
+}
+
+/// A board size like "80x24", no smaller than a challenge code may set.
+fn parse_size(value: &str) -> Option<(u16, u16)> {
+    let (width, height) = value.split_once('x')?;
+    let (width, height) = (width.parse().ok()?, height.parse().ok()?);
+    (width >= challenge::MIN_BOARD.0 && height >= challenge::MIN_BOARD.1).then_some((width, height))
+}
+
+/// Every challenge's id with the best score on it, and whether the file can be written
+/// back.
+fn load_bests(player: Option<&str>) -> (Vec<(String, u32)>, bool) {
+    let Some(loaded) = players::home_file("challenges", player)
+        .and_then(|path| storage::read_versioned(&path, &FORMAT))
+    else {
+        return (Vec::new(), true);
+    };
+    let bests = loaded
+        .body
+        .lines()
+        .filter_map(|line| {
+            let (id, best) = line.split_once(' ')?;
+            Some((id.to_string(), best.trim().parse().ok()?))
+        })
+        .collect();
+    (bests, loaded.writable)
+}
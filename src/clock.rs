@@ -0,0 +1,55 @@
+//! Where the game loops get the time. A game in the terminal runs on the system's clock;
+//! the tests run on a simulated one that only moves when told to, so a minute of play
+//! takes no time at all and lands on the same ticks every run.
+
+#[cfg(test)]
+use std::cell::Cell;
+use std::thread;
+use std::time::{Duration, Instant};
+
+pub trait Clock {
+    fn now(&self) -> Instant;
+
+    /// Wait for `duration` to pass.
+    fn sleep(&self, duration: Duration);
+}
+
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+
+    fn sleep(&self, duration: Duration) {
+        thread::sleep(duration);
+    }
+}
+
+/// Time that stands still between sleeps, and a sleep moves it on at once.
+#[cfg(test)]
+pub struct SimClock {
+    start: Instant,
+    elapsed: Cell<Duration>,
+}
+
+#[cfg(test)]
+impl SimClock {
+    pub fn new() -> Self {
+        Self {
+            start: Instant::now(),
+            elapsed: Cell::new(Duration::ZERO),
+        }
+    }
+}
+
+#[cfg(test)]
+impl Clock for SimClock {
+    fn now(&self) -> Instant {
+        self.start + self.elapsed.get()
+    }
+
+    fn sleep(&self, duration: Duration) {
+        self.elapsed.set(self.elapsed.get() + duration);
+    }
+}
@@ -0,0 +1,161 @@
+//! Axis-aligned boxes for hit tests, in world cells.
+//!
+//! A box takes in its left and top edges but not its right and bottom ones, so boxes
+//! side by side don't touch, and a box one cell across at column 3 covers column 3 and
+//! nothing else. Edges can be infinite, for walls that run off the board.
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Aabb {
+    pub left: f32,
+    pub top: f32,
+    pub right: f32,
+    pub bottom: f32,
+}
+
+impl Aabb {
+    pub const fn new(left: f32, top: f32, right: f32, bottom: f32) -> Self {
+        Self {
+            left,
+            top,
+            right,
+            bottom,
+        }
+    }
+
+    /// Columns `left..right` from top to bottom of the world.
+    pub const fn columns(left: f32, right: f32) -> Self {
+        Self::new(left, f32::NEG_INFINITY, right, f32::INFINITY)
+    }
+
+    /// Whether the point (`x`, `y`) is inside the box.
+    pub fn contains(self, x: f32, y: f32) -> bool {
+        x >= self.left && x < self.right && y >= self.top && y < self.bottom
+    }
+
+    pub fn is_empty(self) -> bool {
+        self.left >= self.right || self.top >= self.bottom
+    }
+
+    pub fn overlaps(self, other: Aabb) -> bool {
+        !self.is_empty()
+            && !other.is_empty()
+            && self.left < other.right
+            && other.left < self.right
+            && self.top < other.bottom
+            && other.top < self.bottom
+    }
+
+    /// Whether the box is entirely left of `other`.
+    pub fn is_left_of(self, other: Aabb) -> bool {
+        self.right <= other.left
+    }
+
+    /// The box stretched over the ground it covered moving `distance` columns left since
+    /// it was last tested, so something fast can't skip over what's in its way.
+    pub fn swept_left(self, distance: f32) -> Self {
+        Self {
+            right: self.right + distance.max(0.0),
+            ..self
+        }
+    }
+}
+
+/// Whether any of `walls`, swept over the `distance` columns they moved left, hit
+/// `target`. The walls all lie within `bounds`, which are tried first, so a target
+/// nowhere near costs one test however many walls there are.
+pub fn swept_hit(target: Aabb, bounds: Aabb, walls: &[Aabb], distance: f32) -> bool {
+    bounds.swept_left(distance).overlaps(target)
+        && walls
+            .iter()
+            .any(|wall| wall.swept_left(distance).overlaps(target))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cell(column: f32, row: f32) -> Aabb {
+        Aabb::new(column, row, column + 1.0, row + 1.0)
+    }
+
+    #[test]
+    fn boxes_side_by_side_do_not_touch() {
+        assert!(!cell(0.0, 0.0).overlaps(cell(1.0, 0.0)));
+        assert!(!cell(0.0, 0.0).overlaps(cell(0.0, 1.0)));
+        assert!(cell(0.0, 0.0).overlaps(cell(0.5, 0.5)));
+    }
+
+    #[test]
+    fn points_on_the_far_edges_are_outside() {
+        let board = Aabb::new(0.0, f32::NEG_INFINITY, 80.0, 24.0);
+        assert!(board.contains(0.0, -50.0));
+        assert!(board.contains(79.9, 23.9));
+        assert!(!board.contains(80.0, 10.0));
+        assert!(!board.contains(10.0, 24.0));
+        assert!(!board.contains(-0.1, 10.0));
+    }
+
+    #[test]
+    fn overlap_is_symmetric() {
+        let wide = Aabb::new(0.0, 0.0, 6.0, 2.0);
+        let bird = Aabb::new(5.0, 1.0, 7.0, 2.0);
+        assert!(wide.overlaps(bird));
+        assert!(bird.overlaps(wide));
+    }
+
+    #[test]
+    fn empty_boxes_hit_nothing() {
+        let empty = Aabb::new(2.0, 2.0, 2.0, 3.0);
+        assert!(empty.is_empty());
+        assert!(!empty.overlaps(Aabb::new(0.0, 0.0, 5.0, 5.0)));
+        assert!(!Aabb::new(0.0, 0.0, 5.0, 5.0).overlaps(empty));
+    }
+
+    #[test]
+    fn columns_run_the_whole_height() {
+        let column = Aabb::columns(3.0, 4.0);
+        assert!(column.overlaps(cell(3.0, -100.0)));
+        assert!(column.overlaps(cell(3.0, 100.0)));
+        assert!(!column.overlaps(cell(4.0, 0.0)));
+    }
+
+    #[test]
+    fn infinite_walls_meet_at_the_gap() {
+        let above = Aabb::new(0.0, f32::NEG_INFINITY, 6.0, 5.0);
+        let below = Aabb::new(0.0, 9.0, 6.0, f32::INFINITY);
+        assert!(above.overlaps(cell(2.0, 4.0)));
+        assert!(!above.overlaps(cell(2.0, 5.0)));
+        assert!(!below.overlaps(cell(2.0, 8.0)));
+        assert!(below.overlaps(cell(2.0, 9.0)));
+    }
+
+    #[test]
+    fn left_of_means_no_shared_column() {
+        let bird = Aabb::new(10.0, 0.0, 12.0, 1.0);
+        assert!(Aabb::columns(4.0, 10.0).is_left_of(bird));
+        assert!(!Aabb::columns(4.0, 10.5).is_left_of(bird));
+    }
+
+    #[test]
+    fn a_sweep_covers_the_columns_moved_through() {
+        let wall = Aabb::new(4.0, 0.0, 10.0, 5.0);
+        let bird = Aabb::new(10.0, 2.0, 12.0, 3.0);
+        assert!(!wall.overlaps(bird));
+        assert!(wall.swept_left(1.0).overlaps(bird));
+        assert_eq!(wall.swept_left(-3.0), wall);
+    }
+
+    #[test]
+    fn swept_hits_need_a_wall_not_just_the_bounds() {
+        let bounds = Aabb::columns(4.0, 10.0);
+        let walls = [
+            Aabb::new(4.0, f32::NEG_INFINITY, 10.0, 5.0),
+            Aabb::new(4.0, 9.0, 10.0, f32::INFINITY),
+        ];
+        let in_gap = Aabb::new(10.0, 6.0, 12.0, 7.0);
+        let in_wall = Aabb::new(10.0, 2.0, 12.0, 3.0);
+        assert!(!swept_hit(in_wall, bounds, &walls, 0.0));
+        assert!(swept_hit(in_wall, bounds, &walls, 1.0));
+        assert!(!swept_hit(in_gap, bounds, &walls, 1.0));
+    }
+}
@@ -0,0 +1,195 @@
+//! Shell completion scripts, generated from the option table in `config`.
+
+use crate::config::{Flag, COMMANDS, FLAGS};
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Shell {
+    Bash,
+    Zsh,
+    Fish,
+}
+
+impl Shell {
+    pub const ALL: [Shell; 3] = [Shell::Bash, Shell::Zsh, Shell::Fish];
+
+    pub fn key(self) -> &'static str {
+        match self {
+            Shell::Bash => "bash",
+            Shell::Zsh => "zsh",
+            Shell::Fish => "fish",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|shell| shell.key() == s)
+    }
+}
+
+pub fn script(shell: Shell) -> String {
+    match shell {
+        Shell::Bash => bash(),
+        Shell::Zsh => zsh(),
+        Shell::Fish => fish(),
+    }
+}
+
+/// The first line of an option's description.
+fn summary(help: &str) -> &str {
+    help.lines().next().unwrap_or("")
+}
+
+/// Options whose argument is required, so the next word is never another option.
+fn takes_value(flag: &Flag) -> bool {
+    flag.value.starts_with('<')
+}
+
+fn bash() -> String {
+    let mut words: Vec<String> = Vec::new();
+    for flag in FLAGS {
+        if let Some(short) = flag.short {
+            words.push(format!("-{}", short));
+        }
+        words.push(format!("--{}", flag.long));
+    }
+    let commands: Vec<&str> = COMMANDS.iter().map(|c| c.name).collect();
+
+    let mut cases = String::new();
+    for command in COMMANDS.iter().filter(|c| !c.value.is_empty()) {
+        cases.push_str(&format!(
+            "        {})\n            COMPREPLY=($(compgen -W \"{}\" -- \"$cur\"))\n            return ;;\n",
+            command.name,
+            (command.choices)().join(" ")
+        ));
+    }
+    for flag in FLAGS.iter().filter(|f| takes_value(f)) {
+        cases.push_str(&format!(
+            "        --{})\n            COMPREPLY=($(compgen -W \"{}\" -- \"$cur\"))\n            return ;;\n",
+            flag.long,
+            (flag.choices)().join(" ")
+        ));
+    }
+
+    format!(
+        "_tflap() {{
+    local cur=\"${{COMP_WORDS[COMP_CWORD]}}\"
+    local prev=\"${{COMP_WORDS[COMP_CWORD-1]}}\"
+    case \"$prev\" in
+{}    esac
+    if [[ $COMP_CWORD -eq 1 && $cur != -* ]]; then
+        COMPREPLY=($(compgen -W \"{}\" -- \"$cur\"))
+        return
+    fi
+    COMPREPLY=($(compgen -W \"{}\" -- \"$cur\"))
+}}
+complete -F _tflap tflap
+",
+        cases,
+        commands.join(" "),
+        words.join(" ")
+    )
+}
+
+/// Escape text for a zsh `_arguments` description.
+fn zsh_escape(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace('\'', "'\\''")
+        .replace('[', "\\[")
+        .replace(']', "\\]")
+        .replace(':', "\\:")
+}
+
+fn zsh() -> String {
+    let mut specs = Vec::new();
+    for flag in FLAGS {
+        let help = zsh_escape(summary(flag.help));
+        let names = match flag.short {
+            Some(short) => format!("'(-{0} --{1})'{{-{0},--{1}}}'", short, flag.long),
+            None => format!("'--{}", flag.long),
+        };
+        let value = if takes_value(flag) {
+            let name = flag.value.split(' ').next().unwrap_or("");
+            let name = zsh_escape(name.trim_matches(|c| c == '<' || c == '>'));
+            let choices = (flag.choices)();
+            if choices.is_empty() {
+                format!(":{}: ", name)
+            } else {
+                format!(":{}:({})", name, choices.join(" "))
+            }
+        } else {
+            String::new()
+        };
+        specs.push(format!("{}[{}]{}'", names, help, value));
+    }
+
+    let commands: Vec<String> = COMMANDS
+        .iter()
+        .map(|c| format!("{}\\:\"{}\"", c.name, zsh_escape(c.help)))
+        .collect();
+    specs.push(format!("'1::command:(({}))'", commands.join(" ")));
+    let mut cases = String::new();
+    for command in COMMANDS.iter().filter(|c| !c.value.is_empty()) {
+        cases.push_str(&format!(
+            "    {}) _values 'value' {}; return ;;\n",
+            command.name,
+            (command.choices)().join(" ")
+        ));
+    }
+
+    format!(
+        "#compdef tflap
+
+_tflap() {{
+  if (( CURRENT == 3 )); then
+    case $words[2] in
+{}    esac
+  fi
+  _arguments -s \\
+    {}
+}}
+
+_tflap \"$@\"
+",
+        cases,
+        specs.join(" \\\n    ")
+    )
+}
+
+fn fish_escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('\'', "\\'")
+}
+
+fn fish() -> String {
+    let mut out = String::from("complete -c tflap -f\n");
+    for command in COMMANDS {
+        out.push_str(&format!(
+            "complete -c tflap -n __fish_use_subcommand -a {} -d '{}'\n",
+            command.name,
+            fish_escape(command.help)
+        ));
+        let choices = (command.choices)();
+        if !choices.is_empty() {
+            out.push_str(&format!(
+                "complete -c tflap -n '__fish_seen_subcommand_from {}' -a '{}'\n",
+                command.name,
+                choices.join(" ")
+            ));
+        }
+    }
+    for flag in FLAGS {
+        let mut line = String::from("complete -c tflap");
+        if let Some(short) = flag.short {
+            line.push_str(&format!(" -s {}", short));
+        }
+        line.push_str(&format!(" -l {}", flag.long));
+        if takes_value(flag) {
+            line.push_str(" -x");
+            let choices = (flag.choices)();
+            if !choices.is_empty() {
+                line.push_str(&format!(" -a '{}'", choices.join(" ")));
+            }
+        }
+        line.push_str(&format!(" -d '{}'\n", fish_escape(summary(flag.help))));
+        out.push_str(&line);
+    }
+    out
+}
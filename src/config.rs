@@ -0,0 +1,1870 @@
+use crate::autorun::{self, Autorun};
+use crate::bot::{Brain, Tier};
+use crate::challenge::{self, Challenge};
+use crate::challenge_file::ChallengeFile;
+use crate::completions::Shell;
+use crate::difficulty::Difficulty;
+use crate::effects::MAX_INTENSITY;
+use crate::keymap::{self, Combo, Preset};
+use crate::music::MAX_VOLUME;
+use crate::mutators::Mutators;
+use crate::paths::{self, Paths};
+use crate::pipes::PipeStyle;
+use crate::players;
+use crate::pomodoro;
+use crate::ramp::Curve;
+use crate::render::Zoom;
+use crate::rhythm;
+use crate::rivals::{self, RivalSource};
+use crate::scoring::ScoringMode;
+use crate::simulate::{self, Batch};
+use crate::storage;
+use crate::summary::Destination;
+use crate::train::DEFAULT_GENERATIONS;
+use crate::weather::WeatherSetting;
+use crossterm::event::{KeyCode, KeyModifiers};
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+pub const DEFAULT_LIVES: u8 = 3;
+pub const MAX_LIVES: u8 = 9;
+/// Game speed limits and step, as a percentage of normal speed.
+pub const MIN_SPEED: u8 = 50;
+pub const MAX_SPEED: u8 = 150;
+pub const SPEED_STEP: u8 = 10;
+pub const MAX_STRESS_BIRDS: usize = 1000;
+/// Runs per speed for `tflap balance`, fewer than a plain batch as the planner is slow.
+const BALANCE_RUNS: usize = 200;
+pub const DEFAULT_MILESTONES: [u32; 4] = [10, 25, 50, 100];
+/// Least time between flaps, in milliseconds, by default and at most.
+const DEFAULT_FLAP_COOLDOWN: u16 = 100;
+/// What a variable's name starts with to override a config key.
+const ENV_PREFIX: &str = "TFLAP_";
+/// Where `--data-dir` can come from instead, though it isn't a config key.
+const DATA_DIR_VAR: &str = "TFLAP_DATA_DIR";
+const MAX_FLAP_COOLDOWN: u16 = 1000;
+/// Longest daily limit, in minutes: a whole day.
+const MAX_DAILY_LIMIT: u32 = 1440;
+const DEFAULT_MUSIC_VOLUME: u8 = 5;
+
+/// One command-line option. `--help`, the shell completions, and the man page are all
+/// generated from `FLAGS`, so a new option only needs adding here and to `Cli::parse`.
+pub struct Flag {
+    pub long: &'static str,
+    pub short: Option<char>,
+    /// Placeholders for the option's arguments, e.g. "<D>" or "[N]" when optional.
+    pub value: &'static str,
+    /// Values worth offering when completing the argument.
+    pub choices: fn() -> Vec<&'static str>,
+    /// Description; extra lines continue it in `--help`.
+    pub help: &'static str,
+}
+
+/// A subcommand, given instead of any options.
+pub struct Command {
+    pub name: &'static str,
+    pub value: &'static str,
+    pub choices: fn() -> Vec<&'static str>,
+    pub help: &'static str,
+}
+
+fn no_choices() -> Vec<&'static str> {
+    Vec::new()
+}
+
+fn difficulty_choices() -> Vec<&'static str> {
+    Difficulty::ALL.iter().map(|d| d.key()).collect()
+}
+
+fn weather_choices() -> Vec<&'static str> {
+    WeatherSetting::ALL.iter().map(|w| w.key()).collect()
+}
+
+fn ramp_choices() -> Vec<&'static str> {
+    Curve::ALL.iter().map(|c| c.key()).collect()
+}
+
+fn pipe_choices() -> Vec<&'static str> {
+    PipeStyle::ALL.iter().map(|s| s.key()).collect()
+}
+
+fn keymap_choices() -> Vec<&'static str> {
+    Preset::ALL.iter().map(|p| p.key()).collect()
+}
+
+fn update_choices() -> Vec<&'static str> {
+    vec!["--check-only"]
+}
+
+fn train_choices() -> Vec<&'static str> {
+    vec!["--generations", "--out"]
+}
+
+fn simulate_choices() -> Vec<&'static str> {
+    vec![
+        "--runs",
+        "--threads",
+        "--seed-range",
+        "--max-ticks",
+        "--bot",
+        "--brain",
+        "--difficulty",
+        "--weather",
+    ]
+}
+
+fn balance_choices() -> Vec<&'static str> {
+    let mut choices = vec!["--preset"];
+    choices.extend(simulate_choices());
+    choices
+}
+
+fn autorun_choices() -> Vec<&'static str> {
+    let mut choices = vec!["--games", "--out", "--render"];
+    choices.extend(simulate_choices().into_iter().filter(|&c| c != "--runs"));
+    choices
+}
+
+fn bot_choices() -> Vec<&'static str> {
+    Tier::ALL.iter().map(|t| t.key()).collect()
+}
+
+fn shell_choices() -> Vec<&'static str> {
+    Shell::ALL.iter().map(|s| s.key()).collect()
+}
+
+const fn flag(long: &'static str, value: &'static str, help: &'static str) -> Flag {
+    Flag {
+        long,
+        short: None,
+        value,
+        choices: no_choices,
+        help,
+    }
+}
+
+pub const COMMANDS: &[Command] = &[
+    Command {
+        name: "completions",
+        value: "<SHELL>",
+        choices: shell_choices,
+        help: "Print a completion script for bash, zsh, or fish",
+    },
+    Command {
+        name: "update",
+        value: "[--check-only]",
+        choices: update_choices,
+        help: "Replace a standalone binary with the latest release, or just check for one",
+    },
+    Command {
+        name: "train",
+        value: "[--generations N] [--out FILE]",
+        choices: train_choices,
+        help: "Evolve a brain for the bot over N generations (default 200) and save the best to FILE (default best.json)",
+    },
+    Command {
+        name: "simulate",
+        value: "[OPTIONS]",
+        choices: simulate_choices,
+        help: "Fly a batch of headless bot runs and print score statistics as JSON:\n\
+            --runs N (default 1000), --threads N, --seed-range A..B, --max-ticks N,\n\
+            --bot TIER, --brain FILE, --difficulty D, --weather W",
+    },
+    Command {
+        name: "balance",
+        value: "[OPTIONS]",
+        choices: balance_choices,
+        help: "Fly the hard bot through a preset at each speed and flag the steps between gaps\n\
+            it keeps crashing on: --preset D, and the options for simulate (default 200 runs)",
+    },
+    Command {
+        name: "autorun",
+        value: "[OPTIONS]",
+        choices: autorun_choices,
+        help: "Let the bot play a batch of games and write a CSV row for each to FILE or stdout:\n\
+            --games N (default 50), --out FILE, --render to watch them, and the options\n\
+            for simulate; --bot also takes human, greedy or planner",
+    },
+    Command {
+        name: "attach",
+        value: "",
+        choices: no_choices,
+        help: "Carry on with a run put away with the detach key, paused where it was left",
+    },
+    Command {
+        name: "doctor",
+        value: "",
+        choices: no_choices,
+        help: "Print terminal and setup details for a bug report and save them to a file",
+    },
+    Command {
+        name: "keytest",
+        value: "",
+        choices: no_choices,
+        help: "Show each key event the terminal sends and the action it maps to, for keys\n\
+            that don't seem to register",
+    },
+    Command {
+        name: "man",
+        value: "",
+        choices: no_choices,
+        help: "Print the man page",
+    },
+];
+
+pub const FLAGS: &[Flag] = &[
+    flag(
+        "lives",
+        "[N]",
+        "Play with N hearts instead of dying on the first hit (default 3, max 9)",
+    ),
+    Flag {
+        choices: difficulty_choices,
+        ..flag(
+            "difficulty",
+            "<D>",
+            "Pipe gaps and spacing: easy, normal, or hard (default normal)",
+        )
+    },
+    Flag {
+        choices: weather_choices,
+        ..flag(
+            "weather",
+            "<W>",
+            "Weather: clear, rain, snow, fog, or random to rotate (default clear)",
+        )
+    },
+    Flag {
+        choices: ramp_choices,
+        ..flag(
+            "ramp",
+            "<CURVE>",
+            "Speed the pipes up and close them in as the run goes on, along a curve:\n\
+             off, linear, ease-in, or ease-out (default off; ranked separately)",
+        )
+    },
+    flag(
+        "banking",
+        "",
+        "Every 5 pipes, let the score be banked at the price of a burst of wide pipes;\n\
+         a crash keeps only the banked points (ranked separately)",
+    ),
+    flag(
+        "double-or-nothing",
+        "",
+        "Once a run, offer to carry on after a crash: pass 5 more pipes to double the\n\
+         score, or crash first and keep half (ranked separately)",
+    ),
+    flag(
+        "typing",
+        "",
+        "Flap by typing the next letter of the word over the bird, with words per minute\n\
+         and accuracy in the summary (ranked separately)",
+    ),
+    flag(
+        "quiz",
+        "",
+        "Now and then a pipe's gap stays shut until the digit key answering the sum on\n\
+         it is pressed, with sums that get harder with the difficulty (ranked separately)",
+    ),
+    flag(
+        "rewind",
+        "",
+        "Hold Z to run the game back up to 3 seconds, on a meter that passing pipes\n\
+         refills (ranked separately)",
+    ),
+    flag(
+        "marathon",
+        "",
+        "One long run with a rest every 100 pipes, which can be stopped at a rest and\n\
+         carried on in a later session (ranked separately)",
+    ),
+    flag(
+        "rhythm",
+        "<BPM>",
+        "Pipes come in on the beat of BPM beats a minute (40 to 200), ticked by the bell,\n\
+         and flaps on the beat score a point (ranked separately)",
+    ),
+    flag(
+        "mutators",
+        "<LIST>",
+        "Twists that multiply the score, comma-separated: tinygap, double, invisible,\n\
+         inverted, or none (default none)",
+    ),
+    flag(
+        "classic-scoring",
+        "",
+        "Score one point per pipe with no style bonuses",
+    ),
+    flag(
+        "day-night",
+        "",
+        "Let the run fade from day to night, leaving only a lantern around the bird",
+    ),
+    flag(
+        "memory",
+        "",
+        "Fade pipes out as they near the bird, flashing only the gap's edges",
+    ),
+    flag(
+        "one-switch",
+        "",
+        "Play with Space alone: tap for a small flap, hold for a big one",
+    ),
+    Flag {
+        choices: keymap_choices,
+        ..flag(
+            "keymap",
+            "<K>",
+            "Key layout: standard, left, left-dvorak, or left-colemak (default standard)",
+        )
+    },
+    flag(
+        "boss-key",
+        "<KEY>",
+        "Key that swaps the game for a fake system monitor: a character or F1-F12 (default `)",
+    ),
+    flag(
+        "restart-key",
+        "<KEY>",
+        "Key that gives up mid-run and starts again at once: a character, backspace, or\n\
+         F1-F12 (default backspace)",
+    ),
+    flag(
+        "ascii",
+        "",
+        "Draw with plain ASCII instead of block and box-drawing characters",
+    ),
+    flag(
+        "emoji",
+        "",
+        "Draw the bird, pipes and crashes as emoji where the terminal shows them two columns wide",
+    ),
+    flag(
+        "zoom",
+        "<1x|2x1|2x2>",
+        "Draw each cell of the game across 2x1 or 2x2 terminal cells, for tiny fonts\n\
+         (default 1x)",
+    ),
+    flag(
+        "zoom-follow",
+        "",
+        "Draw the world at twice the size around the bird, the view following it up and down",
+    ),
+    Flag {
+        choices: pipe_choices,
+        ..flag(
+            "pipes",
+            "<STYLE>",
+            "How pipes are drawn: solid, capped, dashed, or gradient (default solid)",
+        )
+    },
+    flag(
+        "haptics",
+        "",
+        "Ring short bell and flash patterns on flaps, points and near misses, for devices\n\
+         that buzz on a bell",
+    ),
+    flag(
+        "low-bandwidth",
+        "",
+        "Send only the cells that change each frame, as happens by itself on a slow link",
+    ),
+    flag(
+        "status-file",
+        "[PATH]",
+        "Keep the live score in a file for a tmux status line (default ~/.tflap_status)",
+    ),
+    flag(
+        "telemetry",
+        "<FILE>",
+        "Write each finished run's position, velocity, flaps and next gap, tick by tick,\n\
+         to FILE as CSV",
+    ),
+    flag(
+        "assist",
+        "",
+        "Show an arrow pointing to the next gap (runs go on a separate leaderboard)",
+    ),
+    flag(
+        "speed",
+        "<X>",
+        "Game speed from 0.5 to 1.5 (default 1); other speeds are ranked separately\n\
+         and can be changed mid-run with -/+ while any assist is on",
+    ),
+    flag(
+        "trajectory",
+        "",
+        "Preview where the bird goes with and without a flap (separate leaderboard)",
+    ),
+    flag(
+        "no-quit-prompt",
+        "",
+        "Quit mid-run immediately instead of asking first",
+    ),
+    flag(
+        "flap-cooldown",
+        "<MS>",
+        "Least time between flaps in milliseconds, up to 1000 (default 100, 0 for none);\n\
+         the flap key's auto-repeat is ignored either way",
+    ),
+    flag(
+        "daily-limit",
+        "<MINUTES>",
+        "Minutes of play a day for each player, after which the game locks until\n\
+         tomorrow (default 0, for no limit)",
+    ),
+    flag(
+        "pomodoro",
+        "<MINUTES>",
+        "Work in focus periods of MINUTES, with the game only playable in the breaks\n\
+         between them",
+    ),
+    flag(
+        "music-volume",
+        "<0-10>",
+        "Loudness of the background music, 0 for none (default 5); only builds with\n\
+         the audio feature play it",
+    ),
+    flag(
+        "music",
+        "<FILE>",
+        "Loop the song in FILE instead of the built-in one (see the README for the format)",
+    ),
+    flag(
+        "milestones",
+        "<LIST>",
+        "Scores to celebrate, comma-separated, or off (default 10,25,50,100)",
+    ),
+    flag(
+        "effects",
+        "<PERCENT>",
+        "Strength of the milestone flash, confetti, weather particles and fanfare bells,\n\
+         0 (or off) to 100 (default 100); the flash stops below 50",
+    ),
+    flag(
+        "kiosk",
+        "",
+        "Run unattended at events: no quitting but the admin combo, a countdown to the\n\
+         next player after each run, and attract pages on the title screen",
+    ),
+    flag(
+        "kiosk-exit",
+        "<COMBO>",
+        "The admin combo that quits kiosk mode, like ctrl+alt+q (the default)",
+    ),
+    flag(
+        "no-save",
+        "",
+        "Write nothing to disk: no scores, history, settings or status file, for\n\
+         shared or read-only machines and benchmarking",
+    ),
+    flag(
+        "data-dir",
+        "<DIR>",
+        "Keep scores, history, settings and every other file in DIR instead of your\n\
+         home directory, for sandboxes, tests, or a leaderboard per project",
+    ),
+    flag(
+        "json-summary",
+        "[FILE]",
+        "On exit, write the session's runs, scores, seeds and settings as JSON to FILE,\n\
+         or without one or with - to stdout in place of the summary line",
+    ),
+    flag(
+        "quiet",
+        "",
+        "Don't print the score summary line when the game exits",
+    ),
+    flag(
+        "challenge",
+        "<CODE>",
+        "Play or watch the run behind a code from someone's game-over screen",
+    ),
+    flag(
+        "challenge-file",
+        "<PATH>",
+        "Play a shared challenge file's seed and settings, keeping your best on it",
+    ),
+    flag(
+        "ghost",
+        "",
+        "Race a challenge's original run, its bird flying beside yours as a ghost",
+    ),
+    flag(
+        "pip",
+        "",
+        "Replay your best run on the same pipes in a corner while retrying them",
+    ),
+    flag(
+        "screensaver",
+        "",
+        "Let a demo bot fly slowly across the whole screen until a key is pressed",
+    ),
+    flag("demo", "", "Same as --screensaver"),
+    flag(
+        "brain",
+        "<FILE>",
+        "Fly the screensaver with a brain saved by `tflap train` instead of --bot",
+    ),
+    Flag {
+        choices: bot_choices,
+        ..flag(
+            "bot",
+            "<TIER>",
+            "Bot for --screensaver and --stress: easy (human-like), normal, or hard (plans ahead)",
+        )
+    },
+    flag(
+        "split",
+        "[bot|player]",
+        "Play beside the bot, or a second player on Up, on the same pipes (default bot)",
+    ),
+    flag(
+        "stress",
+        "<N>",
+        "Fly N bots through close-set pipes in the rain, then print frame timings on exit",
+    ),
+    flag(
+        "add-rival",
+        "<NAME> <CODE|SCORE>",
+        "Register a rival from their challenge code, or just a score to beat",
+    ),
+    flag("remove-rival", "<NAME>", "Forget a rival"),
+    flag(
+        "player",
+        "<NAME>",
+        "Play as NAME, with your own leaderboard and history beside everyone else's\n\
+         on the family leaderboard",
+    ),
+    flag("rivals", "", "List registered rivals and exit"),
+    Flag {
+        short: Some('h'),
+        ..flag("help", "", "Print this help and exit")
+    },
+    Flag {
+        short: Some('V'),
+        ..flag("version", "", "Print version and exit")
+    },
+];
+
+/// Column where descriptions start in `--help`.
+const HELP_COLUMN: usize = 17;
+
+/// Append a `--help` entry, putting the description on its own line if the name is long.
+fn push_entry(out: &mut String, name: &str, help: &str) {
+    let mut lines = help.lines();
+    let first = lines.next().unwrap_or("");
+    if name.len() + 4 <= HELP_COLUMN {
+        out.push_str(&format!("  {:<w$}{}\n", name, first, w = HELP_COLUMN - 2));
+    } else {
+        out.push_str(&format!(
+            "  {}\n{:w$}{}\n",
+            name,
+            "",
+            first,
+            w = HELP_COLUMN
+        ));
+    }
+    for line in lines {
+        out.push_str(&format!(
+            "{:w$}{}\n",
+            "",
+            line.trim_start(),
+            w = HELP_COLUMN
+        ));
+    }
+}
+
+fn usage() -> String {
+    let mut out = String::from("Usage: tflap [OPTIONS]\n       tflap <COMMAND>\n\nCommands:\n");
+    for command in COMMANDS {
+        let name = format!("{} {}", command.name, command.value);
+        push_entry(&mut out, name.trim_end(), command.help);
+    }
+    out.push_str("\nOptions:\n");
+    for flag in FLAGS {
+        let mut name = match flag.short {
+            Some(short) => format!("-{}, --{}", short, flag.long),
+            None => format!("--{}", flag.long),
+        };
+        if !flag.value.is_empty() {
+            name.push(' ');
+            name.push_str(flag.value);
+        }
+        push_entry(&mut out, &name, flag.help);
+    }
+    out.push_str(
+        "\nDefaults are read from ~/.tflap_config, which the options screen keeps up to date.\n\
+         TFLAP_<KEY> environment variables override its keys, like TFLAP_DIFFICULTY=hard.",
+    );
+    out
+}
+
+/// A config file line a `TFLAP_*` variable changed, as the file had it and as the
+/// variable made it.
+#[derive(Clone)]
+pub struct Override {
+    file: String,
+    env: String,
+}
+
+/// Settings for a play session, assembled from the config file and the command line.
+#[derive(Clone)]
+pub struct Config {
+    /// Starting hearts when lives mode is enabled, `None` for classic one-hit runs.
+    pub lives: Option<u8>,
+    pub weather: WeatherSetting,
+    pub day_night: bool,
+    /// Pipes fade out before the bird reaches them. Ranked on its own.
+    pub memory: bool,
+    /// Point the way to the next gap. Assisted runs are ranked on their own.
+    pub assist: bool,
+    /// Single-key play where the length of a press picks the flap size.
+    pub one_switch: bool,
+    pub keymap: Preset,
+    pub boss_key: KeyCode,
+    /// Gives up on a run and starts the next one without the game-over screen.
+    pub restart_key: KeyCode,
+    /// Stick to ASCII glyphs even if the terminal seems to handle Unicode.
+    pub ascii: bool,
+    /// Emoji sprites, when the terminal lays them out correctly.
+    pub emoji: bool,
+    /// Terminal cells each game cell is drawn across.
+    pub zoom: Zoom,
+    /// A view of the world at twice the size that follows the bird, for low vision.
+    pub zoom_follow: bool,
+    pub pipe_style: PipeStyle,
+    /// Bell and flash patterns on flaps, points and near misses.
+    pub haptics: bool,
+    /// Send only what changed each frame, without waiting to find the link is slow.
+    pub low_bandwidth: bool,
+    /// Draw the predicted flight paths. Also ranked on its own.
+    pub trajectory: bool,
+    /// Simulation speed as a percentage, from `MIN_SPEED` to `MAX_SPEED`.
+    pub speed: u8,
+    pub scoring: ScoringMode,
+    pub difficulty: Difficulty,
+    /// Twists that make the run harder for more points. Mutated runs are ranked on
+    /// their own.
+    pub mutators: Mutators,
+    /// How the pipes speed up over a run. Ramped runs are ranked on their own.
+    pub ramp: Curve,
+    /// Only banked points survive a crash. Ranked on its own.
+    pub banking: bool,
+    /// Offer double or nothing on a crash. Ranked on its own.
+    pub double_or_nothing: bool,
+    /// Flap by typing words, letter by letter. Ranked on its own.
+    pub typing: bool,
+    /// Some pipes are gates that open to the answer of a sum. Ranked on its own.
+    pub quiz: bool,
+    /// Holding the rewind key runs the game back, while the meter lasts. Ranked on its own.
+    pub rewind: bool,
+    /// One long run in segments, resumable at the rests between them. Ranked on its own.
+    pub marathon: bool,
+    /// Beats a minute the pipes come in to, in rhythm mode. Ranked on its own.
+    pub rhythm: Option<u16>,
+    /// Ask before quitting a run that is worth something.
+    pub confirm_quit: bool,
+    /// Least time from one flap to the next, in milliseconds.
+    pub flap_cooldown: u16,
+    /// Minutes of play allowed each player a day, 0 for no limit.
+    pub daily_limit: u32,
+    /// Minutes in each pomodoro focus period, when the game is a break timer. Never
+    /// saved.
+    pub pomodoro: Option<u32>,
+    /// Unattended play at events. Never saved.
+    pub kiosk: bool,
+    /// What quits kiosk mode.
+    pub kiosk_exit: Combo,
+    /// Keep everything off the disk this session. Never saved.
+    pub no_save: bool,
+    /// Leave out the summary line printed on exit. Never saved.
+    pub quiet: bool,
+    /// Where to write the session as JSON on exit, if anywhere. Never saved.
+    pub json_summary: Option<Destination>,
+    /// Background music loudness, from 0 (none) to `MAX_VOLUME`.
+    pub music_volume: u8,
+    /// A song file to play instead of the built-in one.
+    pub music: Option<PathBuf>,
+    /// Scores that set off a celebration, in increasing order of fanfare.
+    pub milestones: Vec<u32>,
+    /// Strength of the effects drawn just for show, as a percentage: see `effects`.
+    pub effects: u8,
+    /// Where to keep the live score for a status bar, if anywhere.
+    pub status_file: Option<PathBuf>,
+    /// Where to write each finished run's tick-by-tick telemetry, if anywhere. Never
+    /// saved.
+    pub telemetry_file: Option<PathBuf>,
+    /// Whose leaderboard and history the runs go in, `None` for the unnamed player's.
+    /// Never saved, as the settings are shared by everyone on the machine.
+    pub player: Option<String>,
+    /// Fly the challenge's original run beside the player's as a ghost.
+    pub ghost: bool,
+    /// Show the best run on the current seed in a corner, in step with this one.
+    pub pip: bool,
+    /// How well the screensaver's and stress test's bots fly.
+    pub bot: Tier,
+    /// The challenge code version whose rules the run is played by: the latest, unless
+    /// an older code is being played. Never saved.
+    pub rules: u8,
+    /// What the environment variables changed, which saving puts back as the file had
+    /// it. Never saved.
+    pub env_layer: Vec<Override>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            lives: None,
+            weather: WeatherSetting::default(),
+            day_night: false,
+            memory: false,
+            assist: false,
+            one_switch: false,
+            keymap: Preset::default(),
+            boss_key: KeyCode::Char('`'),
+            restart_key: KeyCode::Backspace,
+            ascii: false,
+            emoji: false,
+            zoom: Zoom::default(),
+            zoom_follow: false,
+            pipe_style: PipeStyle::default(),
+            haptics: false,
+            low_bandwidth: false,
+            trajectory: false,
+            speed: 100,
+            scoring: ScoringMode::default(),
+            difficulty: Difficulty::default(),
+            mutators: Mutators::default(),
+            ramp: Curve::default(),
+            banking: false,
+            double_or_nothing: false,
+            typing: false,
+            quiz: false,
+            rewind: false,
+            marathon: false,
+            rhythm: None,
+            confirm_quit: true,
+            flap_cooldown: DEFAULT_FLAP_COOLDOWN,
+            daily_limit: 0,
+            pomodoro: None,
+            kiosk: false,
+            kiosk_exit: Combo {
+                modifiers: KeyModifiers::CONTROL | KeyModifiers::ALT,
+                code: KeyCode::Char('q'),
+            },
+            no_save: false,
+            quiet: false,
+            json_summary: None,
+            music_volume: DEFAULT_MUSIC_VOLUME,
+            music: None,
+            milestones: DEFAULT_MILESTONES.to_vec(),
+            effects: MAX_INTENSITY,
+            status_file: None,
+            telemetry_file: None,
+            player: None,
+            ghost: false,
+            pip: false,
+            bot: Tier::default(),
+            rules: challenge::VERSION,
+            env_layer: Vec::new(),
+        }
+    }
+}
+
+fn default_status_path() -> Option<PathBuf> {
+    paths::file("status")
+}
+
+fn get_config_path() -> Option<PathBuf> {
+    paths::file("config")
+}
+
+/// A comma-separated list of scores, or "off" for none.
+fn load_brain(path: &str) -> Result<Brain, String> {
+    let content =
+        fs::read_to_string(path).map_err(|err| format!("can't read brain '{}': {}", path, err))?;
+    Brain::parse(&content)
+}
+
+/// Options for `tflap simulate` and `tflap balance`, some of them settings for the runs.
+fn parse_batch<I: Iterator<Item = String>>(
+    config: &mut Config,
+    mut args: I,
+    default_runs: usize,
+) -> Result<Batch, String> {
+    let mut runs = None;
+    let mut seeds = None;
+    let mut batch = Batch {
+        seeds: 0..0,
+        threads: simulate::default_threads(),
+        max_ticks: simulate::DEFAULT_MAX_TICKS,
+        brain: None,
+    };
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or(format!("{} needs a value", arg));
+        match arg.as_str() {
+            "--runs" | "--games" => {
+                let value = value()?;
+                runs = Some(
+                    value
+                        .parse::<usize>()
+                        .ok()
+                        .filter(|&n| n > 0)
+                        .ok_or_else(|| format!("bad number of runs '{}'", value))?,
+                );
+            }
+            "--threads" => {
+                let value = value()?;
+                batch.threads = value
+                    .parse::<usize>()
+                    .ok()
+                    .filter(|&n| n > 0)
+                    .ok_or_else(|| format!("bad number of threads '{}'", value))?;
+            }
+            "--seed-range" => {
+                let value = value()?;
+                seeds = Some(
+                    value
+                        .split_once("..")
+                        .and_then(|(start, end)| Some(start.parse().ok()?..end.parse().ok()?))
+                        .filter(|range: &std::ops::Range<u64>| range.start < range.end)
+                        .ok_or_else(|| format!("bad seed range '{}', expected A..B", value))?,
+                );
+            }
+            "--max-ticks" => {
+                let value = value()?;
+                batch.max_ticks = value
+                    .parse::<u32>()
+                    .ok()
+                    .filter(|&n| n > 0)
+                    .ok_or_else(|| format!("bad tick limit '{}'", value))?;
+            }
+            "--bot" => {
+                let value = value()?;
+                config.bot =
+                    Tier::parse(&value).ok_or_else(|| format!("unknown bot tier '{}'", value))?;
+            }
+            "--brain" => batch.brain = Some(load_brain(&value()?)?),
+            "--difficulty" | "--preset" => {
+                let value = value()?;
+                config.difficulty = Difficulty::parse(&value)
+                    .ok_or_else(|| format!("unknown difficulty '{}'", value))?;
+            }
+            "--weather" => {
+                let value = value()?;
+                config.weather = WeatherSetting::parse(&value)
+                    .ok_or_else(|| format!("unknown weather '{}'", value))?;
+            }
+            other => return Err(format!("unknown simulate option '{}'", other)),
+        }
+    }
+    // A range caps the runs; without one they start at a random seed
+    batch.seeds = match (seeds, runs) {
+        (Some(range), Some(runs)) => {
+            range.start..range.end.min(range.start.saturating_add(runs as u64))
+        }
+        (Some(range), None) => range,
+        (None, runs) => {
+            let start = rand::random::<u32>() as u64;
+            start..start + runs.unwrap_or(default_runs) as u64
+        }
+    };
+    Ok(batch)
+}
+
+fn parse_milestones(value: &str) -> Option<Vec<u32>> {
+    if value == "off" {
+        return Some(Vec::new());
+    }
+    value
+        .split(',')
+        .map(|s| s.trim().parse().ok().filter(|&n| n > 0))
+        .collect()
+}
+
+fn parse_music_volume(value: &str) -> Option<u8> {
+    value.parse().ok().filter(|&v| v <= MAX_VOLUME)
+}
+
+fn parse_flap_cooldown(value: &str) -> Option<u16> {
+    let ms: u16 = value.trim_end_matches("ms").parse().ok()?;
+    (ms <= MAX_FLAP_COOLDOWN).then_some(ms)
+}
+
+/// Minutes like "30" or "30m", or "off" for no limit.
+fn parse_daily_limit(value: &str) -> Option<u32> {
+    if value == "off" {
+        return Some(0);
+    }
+    let minutes: u32 = value.trim_end_matches('m').parse().ok()?;
+    (minutes <= MAX_DAILY_LIMIT).then_some(minutes)
+}
+
+/// Beats a minute like "120" or "120bpm", or "off" for no rhythm mode.
+fn parse_rhythm(value: &str) -> Option<Option<u16>> {
+    if value == "off" {
+        return Some(None);
+    }
+    let bpm: u16 = value.trim_end_matches("bpm").parse().ok()?;
+    (rhythm::MIN_BPM..=rhythm::MAX_BPM)
+        .contains(&bpm)
+        .then_some(Some(bpm))
+}
+
+/// A percentage like "40" or "40%", or "off" for none.
+fn parse_effects(value: &str) -> Option<u8> {
+    if value == "off" {
+        return Some(0);
+    }
+    let percent: u8 = value.trim_end_matches('%').parse().ok()?;
+    (percent <= MAX_INTENSITY).then_some(percent)
+}
+
+/// A speed multiplier like "0.8", rounded to the nearest step.
+fn parse_speed(value: &str) -> Option<u8> {
+    let multiplier: f32 = value.trim_end_matches('x').parse().ok()?;
+    let steps = (multiplier * 100.0 / SPEED_STEP as f32).round();
+    let percent = steps * SPEED_STEP as f32;
+    (MIN_SPEED as f32..=MAX_SPEED as f32)
+        .contains(&percent)
+        .then_some(percent as u8)
+}
+
+fn parse_bool(value: &str) -> Option<bool> {
+    match value {
+        "true" | "yes" | "on" => Some(true),
+        "false" | "no" | "off" => Some(false),
+        _ => None,
+    }
+}
+
+impl Config {
+    /// Load `~/.tflap_config` over the defaults, then any `TFLAP_*` environment variables
+    /// over that; the command line goes over both. Unknown keys and bad values are
+    /// ignored so an old or hand-edited file never stops the game from starting.
+    pub fn load() -> Self {
+        let mut config = Config::default();
+        // A stray byte that isn't UTF-8 costs its line, not the whole file
+        if let Some(content) = get_config_path().and_then(|path| fs::read(path).ok()) {
+            config.read(&String::from_utf8_lossy(&content));
+        }
+        config.layer(Config::set_from_env);
+        config
+    }
+
+    /// Set keys over the settings so far with `set`, as a layer that saving leaves out,
+    /// so an override for one session doesn't end up in the file for good.
+    fn layer(&mut self, set: impl FnOnce(&mut Self)) {
+        let before = self.in_effect();
+        set(self);
+        self.env_layer = before
+            .lines()
+            .zip(self.in_effect().lines())
+            .filter(|(file, env)| file != env)
+            .map(|(file, env)| Override {
+                file: file.to_string(),
+                env: env.to_string(),
+            })
+            .collect();
+    }
+
+    /// Set the keys in a config file's `content`.
+    pub fn read(&mut self, content: &str) {
+        for line in content.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let Some((key, value)) = line.split_once('=') else {
+                continue;
+            };
+            self.set(key.trim(), value.trim());
+        }
+    }
+
+    /// Set the key each `TFLAP_<KEY>` variable names, like `TFLAP_FLAP_COOLDOWN=90` for
+    /// `flap_cooldown`, for containers and demos that can't easily pass flags or keep a
+    /// config file.
+    fn set_from_env(&mut self) {
+        // Variables that aren't Unicode can't be ours, and would panic `env::vars`
+        for (name, value) in env::vars_os() {
+            let (Some(name), Some(value)) = (name.to_str(), value.to_str()) else {
+                continue;
+            };
+            if let Some(key) = name.strip_prefix(ENV_PREFIX) {
+                self.set(&key.to_ascii_lowercase(), value.trim());
+            }
+        }
+    }
+
+    pub fn set(&mut self, key: &str, value: &str) {
+        match key {
+            "lives" => {
+                self.lives = match value {
+                    "off" => None,
+                    n => match n.parse::<u8>() {
+                        Ok(n) if (1..=MAX_LIVES).contains(&n) => Some(n),
+                        _ => return,
+                    },
+                }
+            }
+            "weather" => {
+                if let Some(weather) = WeatherSetting::parse(value) {
+                    self.weather = weather;
+                }
+            }
+            "mutators" => {
+                if let Some(mutators) = Mutators::parse(value) {
+                    self.mutators = mutators;
+                }
+            }
+            "ramp" => {
+                if let Some(curve) = Curve::parse(value) {
+                    self.ramp = curve;
+                }
+            }
+            "banking" => {
+                if let Some(on) = parse_bool(value) {
+                    self.banking = on;
+                }
+            }
+            "double_or_nothing" => {
+                if let Some(on) = parse_bool(value) {
+                    self.double_or_nothing = on;
+                }
+            }
+            "typing" => {
+                if let Some(on) = parse_bool(value) {
+                    self.typing = on;
+                }
+            }
+            "quiz" => {
+                if let Some(on) = parse_bool(value) {
+                    self.quiz = on;
+                }
+            }
+            "rewind" => {
+                if let Some(on) = parse_bool(value) {
+                    self.rewind = on;
+                }
+            }
+            "marathon" => {
+                if let Some(on) = parse_bool(value) {
+                    self.marathon = on;
+                }
+            }
+            "rhythm" => {
+                if let Some(bpm) = parse_rhythm(value) {
+                    self.rhythm = bpm;
+                }
+            }
+            "day_night" => {
+                if let Some(on) = parse_bool(value) {
+                    self.day_night = on;
+                }
+            }
+            "memory" => {
+                if let Some(on) = parse_bool(value) {
+                    self.memory = on;
+                }
+            }
+            "assist" => {
+                if let Some(on) = parse_bool(value) {
+                    self.assist = on;
+                }
+            }
+            "one_switch" => {
+                if let Some(on) = parse_bool(value) {
+                    self.one_switch = on;
+                }
+            }
+            "keymap" => {
+                if let Some(keymap) = Preset::parse(value) {
+                    self.keymap = keymap;
+                }
+            }
+            "boss_key" => {
+                if let Some(key) = keymap::parse_key(value) {
+                    self.boss_key = key;
+                }
+            }
+            "restart_key" => {
+                if let Some(key) = keymap::parse_key(value) {
+                    self.restart_key = key;
+                }
+            }
+            "kiosk_exit" => {
+                if let Some(combo) = Combo::parse(value) {
+                    self.kiosk_exit = combo;
+                }
+            }
+            "ascii" => {
+                if let Some(on) = parse_bool(value) {
+                    self.ascii = on;
+                }
+            }
+            "emoji" => {
+                if let Some(on) = parse_bool(value) {
+                    self.emoji = on;
+                }
+            }
+            "zoom" => {
+                if let Some(zoom) = Zoom::parse(value) {
+                    self.zoom = zoom;
+                }
+            }
+            "zoom_follow" => {
+                if let Some(on) = parse_bool(value) {
+                    self.zoom_follow = on;
+                }
+            }
+            "haptics" => {
+                if let Some(on) = parse_bool(value) {
+                    self.haptics = on;
+                }
+            }
+            "pipe_style" => {
+                if let Some(style) = PipeStyle::parse(value) {
+                    self.pipe_style = style;
+                }
+            }
+            "bot" => {
+                if let Some(tier) = Tier::parse(value) {
+                    self.bot = tier;
+                }
+            }
+            "ghost" => {
+                if let Some(on) = parse_bool(value) {
+                    self.ghost = on;
+                }
+            }
+            "pip" => {
+                if let Some(on) = parse_bool(value) {
+                    self.pip = on;
+                }
+            }
+            "low_bandwidth" => {
+                if let Some(on) = parse_bool(value) {
+                    self.low_bandwidth = on;
+                }
+            }
+            "status_file" => {
+                self.status_file = match value {
+                    "off" => None,
+                    "on" => default_status_path(),
+                    path => Some(PathBuf::from(path)),
+                };
+            }
+            "trajectory" => {
+                if let Some(on) = parse_bool(value) {
+                    self.trajectory = on;
+                }
+            }
+            "speed" => {
+                if let Some(speed) = parse_speed(value) {
+                    self.speed = speed;
+                }
+            }
+            "scoring" => {
+                if let Some(scoring) = ScoringMode::parse(value) {
+                    self.scoring = scoring;
+                }
+            }
+            "difficulty" => {
+                if let Some(difficulty) = Difficulty::parse(value) {
+                    self.difficulty = difficulty;
+                }
+            }
+            "confirm_quit" => {
+                if let Some(on) = parse_bool(value) {
+                    self.confirm_quit = on;
+                }
+            }
+            "flap_cooldown" => {
+                if let Some(ms) = parse_flap_cooldown(value) {
+                    self.flap_cooldown = ms;
+                }
+            }
+            "daily_limit" => {
+                if let Some(minutes) = parse_daily_limit(value) {
+                    self.daily_limit = minutes;
+                }
+            }
+            "music_volume" => {
+                if let Some(volume) = parse_music_volume(value) {
+                    self.music_volume = volume;
+                }
+            }
+            "music" => {
+                self.music = match value {
+                    "off" => None,
+                    path => Some(PathBuf::from(path)),
+                };
+            }
+            "milestones" => {
+                if let Some(milestones) = parse_milestones(value) {
+                    self.milestones = milestones;
+                }
+            }
+            "effects" => {
+                if let Some(effects) = parse_effects(value) {
+                    self.effects = effects;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    pub fn save(&self) {
+        if let Some(path) = get_config_path() {
+            let _ = storage::write_atomic(&path, &self.to_file());
+        }
+    }
+
+    /// The settings as a config file, for `read` to take back. Keys still as the
+    /// environment set them are written as the file had them.
+    pub fn to_file(&self) -> String {
+        let mut content = String::new();
+        for line in self.in_effect().lines() {
+            let layered = self.env_layer.iter().find(|layer| layer.env == line);
+            content.push_str(layered.map_or(line, |layer| &layer.file));
+            content.push('\n');
+        }
+        content
+    }
+
+    /// The settings in effect as a config file, environment variables and all.
+    pub fn in_effect(&self) -> String {
+        let lives = self
+            .lives
+            .map_or_else(|| "off".to_string(), |n| n.to_string());
+        let milestones = if self.milestones.is_empty() {
+            "off".to_string()
+        } else {
+            let list: Vec<String> = self.milestones.iter().map(|n| n.to_string()).collect();
+            list.join(",")
+        };
+        format!(
+            "# tflap settings\n\
+             lives = {}\n\
+             weather = {}\n\
+             day_night = {}\n\
+             memory = {}\n\
+             assist = {}\n\
+             one_switch = {}\n\
+             keymap = {}\n\
+             boss_key = {}\n\
+             restart_key = {}\n\
+             kiosk_exit = {}\n\
+             ascii = {}\n\
+             emoji = {}\n\
+             zoom = {}\n\
+             zoom_follow = {}\n\
+             pipe_style = {}\n\
+             haptics = {}\n\
+             low_bandwidth = {}\n\
+             trajectory = {}\n\
+             speed = {}\n\
+             scoring = {}\n\
+             difficulty = {}\n\
+             mutators = {}\n\
+             ramp = {}\n\
+             banking = {}\n\
+             double_or_nothing = {}\n\
+             typing = {}\n\
+             quiz = {}\n\
+             rewind = {}\n\
+             marathon = {}\n\
+             rhythm = {}\n\
+             confirm_quit = {}\n\
+             flap_cooldown = {}\n\
+             daily_limit = {}\n\
+             music_volume = {}\n\
+             music = {}\n\
+             milestones = {}\n\
+             effects = {}\n\
+             status_file = {}\n\
+             ghost = {}\n\
+             pip = {}\n\
+             bot = {}\n",
+            lives,
+            self.weather.key(),
+            self.day_night,
+            self.memory,
+            self.assist,
+            self.one_switch,
+            self.keymap.key(),
+            keymap::key_name(self.boss_key),
+            keymap::key_name(self.restart_key),
+            self.kiosk_exit.name(),
+            self.ascii,
+            self.emoji,
+            self.zoom.key(),
+            self.zoom_follow,
+            self.pipe_style.key(),
+            self.haptics,
+            self.low_bandwidth,
+            self.trajectory,
+            self.speed as f32 / 100.0,
+            self.scoring.key(),
+            self.difficulty.key(),
+            self.mutators.key(),
+            self.ramp.key(),
+            self.banking,
+            self.double_or_nothing,
+            self.typing,
+            self.quiz,
+            self.rewind,
+            self.marathon,
+            self.rhythm
+                .map_or_else(|| "off".to_string(), |bpm| bpm.to_string()),
+            self.confirm_quit,
+            self.flap_cooldown,
+            self.daily_limit,
+            self.music_volume,
+            self.music
+                .as_ref()
+                .map_or_else(|| "off".to_string(), |path| path.display().to_string()),
+            milestones,
+            self.effects,
+            self.status_file
+                .as_ref()
+                .map_or_else(|| "off".to_string(), |path| path.display().to_string()),
+            self.ghost,
+            self.pip,
+            self.bot.key()
+        )
+    }
+}
+
+/// Who flies the right-hand run in split-screen mode.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Opponent {
+    Bot,
+    Player,
+}
+
+impl Opponent {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "bot" => Some(Opponent::Bot),
+            "player" => Some(Opponent::Player),
+            _ => None,
+        }
+    }
+}
+
+pub enum Cli {
+    /// Play with these settings, optionally taking on a shared challenge run.
+    Run(Config, Option<Challenge>),
+    /// Play a challenge file, with its settings already applied.
+    Event(Config, ChallengeFile),
+    AddRival(String, RivalSource),
+    RemoveRival(String),
+    ListRivals,
+    Completions(Shell),
+    /// Report on the environment, using the settings from the config file.
+    Doctor(Config),
+    /// Show key events as they arrive, read with the config file's keymap.
+    KeyTest(Config),
+    /// Resume the detached run, with the config file's settings for everything else.
+    Attach(Config),
+    /// Run the demo bot with these settings until a key is pressed, flown by a trained
+    /// brain if one was given.
+    Screensaver(Config, Option<Brain>),
+    /// Fly a batch of headless bot runs and summarise them.
+    Simulate(Config, Batch),
+    /// Let the bot play a batch of games and write out how each went.
+    Autorun(Config, Autorun),
+    /// Look for unfair pipe patterns in a difficulty preset with a batch of bot runs.
+    Balance(Config, Batch),
+    /// Evolve a brain on the config file's settings and save the best one.
+    Train {
+        config: Config,
+        generations: u32,
+        out: PathBuf,
+    },
+    /// Load the game with this many bot-flown birds and report how it kept up.
+    Stress(Config, usize),
+    /// Two runs side by side on the same pipes, against this opponent.
+    Split(Config, Opponent),
+    Man,
+    /// Check for a newer release, and install it unless only checking.
+    Update {
+        check_only: bool,
+    },
+    Help,
+    Version,
+}
+
+/// Take `--data-dir DIR` out of `args` wherever it is, so the subcommands take it too.
+fn take_data_dir(args: &mut Vec<String>) -> Result<Option<PathBuf>, String> {
+    let Some(i) = args.iter().position(|arg| arg == "--data-dir") else {
+        return Ok(None);
+    };
+    if i + 1 == args.len() {
+        return Err("--data-dir needs a directory".to_string());
+    }
+    let dir = args.remove(i + 1);
+    args.remove(i);
+    Ok(Some(PathBuf::from(dir)))
+}
+
+impl Cli {
+    pub fn from_env() -> Result<Self, String> {
+        // The config file itself lives in the data directory, so it's settled first
+        let mut args: Vec<String> = env::args().skip(1).collect();
+        let data_dir =
+            take_data_dir(&mut args)?.or_else(|| env::var_os(DATA_DIR_VAR).map(PathBuf::from));
+        if let Some(dir) = data_dir {
+            if !dir.is_dir() {
+                return Err(format!("no data directory at {}", dir.display()));
+            }
+            Paths::set_data_dir(dir);
+        }
+        Self::parse(Config::load(), args.into_iter())
+    }
+
+    fn parse<I: Iterator<Item = String>>(mut config: Config, args: I) -> Result<Self, String> {
+        let mut args = args.peekable();
+        let mut challenge = None;
+        let mut challenge_file = None;
+        let mut screensaver = false;
+        let mut brain = None;
+        let mut stress = None;
+        let mut split = None;
+
+        match args.peek().map(String::as_str) {
+            Some("completions") => {
+                args.next();
+                let shell = args.next().ok_or("completions needs a shell")?;
+                return Shell::parse(&shell)
+                    .map(Cli::Completions)
+                    .ok_or_else(|| format!("unknown shell '{}'", shell));
+            }
+            Some("man") => return Ok(Cli::Man),
+            Some("doctor") => return Ok(Cli::Doctor(config)),
+            Some("keytest") => return Ok(Cli::KeyTest(config)),
+            Some("attach") => return Ok(Cli::Attach(config)),
+            Some("train") => {
+                args.next();
+                let mut generations = DEFAULT_GENERATIONS;
+                let mut out = PathBuf::from("best.json");
+                while let Some(arg) = args.next() {
+                    match arg.as_str() {
+                        "--generations" => {
+                            let value = args.next().ok_or("--generations needs a number")?;
+                            generations = value
+                                .parse::<u32>()
+                                .ok()
+                                .filter(|&n| n > 0)
+                                .ok_or_else(|| format!("bad number of generations '{}'", value))?;
+                        }
+                        "--out" => {
+                            out = args.next().map(PathBuf::from).ok_or("--out needs a file")?;
+                        }
+                        other => return Err(format!("unknown train option '{}'", other)),
+                    }
+                }
+                return Ok(Cli::Train {
+                    config,
+                    generations,
+                    out,
+                });
+            }
+            Some("simulate") => {
+                args.next();
+                let batch = parse_batch(&mut config, args, simulate::DEFAULT_RUNS)?;
+                return Ok(Cli::Simulate(config, batch));
+            }
+            Some("balance") => {
+                args.next();
+                // The planner is the reference, unless another bot is asked for
+                config.bot = Tier::Hard;
+                let batch = parse_batch(&mut config, args, BALANCE_RUNS)?;
+                return Ok(Cli::Balance(config, batch));
+            }
+            Some("autorun") => {
+                args.next();
+                // Everything but these two is the batch, as for simulate
+                let mut out = None;
+                let mut render = false;
+                let mut batch_args = Vec::new();
+                while let Some(arg) = args.next() {
+                    match arg.as_str() {
+                        "--out" => {
+                            out = Some(args.next().map(PathBuf::from).ok_or("--out needs a file")?);
+                        }
+                        "--render" => render = true,
+                        _ => batch_args.push(arg),
+                    }
+                }
+                let batch =
+                    parse_batch(&mut config, batch_args.into_iter(), autorun::DEFAULT_GAMES)?;
+                return Ok(Cli::Autorun(config, Autorun { batch, out, render }));
+            }
+            Some("update") => {
+                args.next();
+                return match args.next().as_deref() {
+                    None => Ok(Cli::Update { check_only: false }),
+                    Some("--check-only") => Ok(Cli::Update { check_only: true }),
+                    Some(other) => Err(format!("unknown update option '{}'", other)),
+                };
+            }
+            _ => {}
+        }
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "-h" | "--help" => return Ok(Cli::Help),
+                "-V" | "--version" => return Ok(Cli::Version),
+                "--rivals" => return Ok(Cli::ListRivals),
+                "--add-rival" => {
+                    let (Some(name), Some(value)) = (args.next(), args.next()) else {
+                        return Err("--add-rival needs a name and a challenge code or score".into());
+                    };
+                    if !rivals::valid_name(&name) {
+                        return Err(format!("rival name '{}' must be one word", name));
+                    }
+                    return Ok(Cli::AddRival(name, RivalSource::parse(&value)?));
+                }
+                "--remove-rival" => {
+                    let name = args.next().ok_or("--remove-rival needs a name")?;
+                    return Ok(Cli::RemoveRival(name));
+                }
+                "--lives" => {
+                    let lives = match args.peek().and_then(|next| next.parse::<u8>().ok()) {
+                        Some(n) => {
+                            args.next();
+                            n
+                        }
+                        None => DEFAULT_LIVES,
+                    };
+                    if lives == 0 || lives > MAX_LIVES {
+                        return Err(format!("--lives must be between 1 and {}", MAX_LIVES));
+                    }
+                    config.lives = Some(lives);
+                }
+                "--day-night" => config.day_night = true,
+                "--banking" => config.banking = true,
+                "--double-or-nothing" => config.double_or_nothing = true,
+                "--typing" => config.typing = true,
+                "--quiz" => config.quiz = true,
+                "--rewind" => config.rewind = true,
+                "--marathon" => config.marathon = true,
+                "--rhythm" => {
+                    let value = args.next().ok_or("--rhythm needs a value")?;
+                    let bpm = parse_rhythm(&value).flatten().ok_or_else(|| {
+                        format!(
+                            "--rhythm must be {} to {} bpm, got '{}'",
+                            rhythm::MIN_BPM,
+                            rhythm::MAX_BPM,
+                            value
+                        )
+                    })?;
+                    config.rhythm = Some(bpm);
+                }
+                "--memory" => config.memory = true,
+                "--assist" => config.assist = true,
+                "--ascii" => config.ascii = true,
+                "--emoji" => config.emoji = true,
+                "--zoom" => {
+                    let value = args.next().ok_or("--zoom needs a level")?;
+                    config.zoom =
+                        Zoom::parse(&value).ok_or_else(|| format!("unknown zoom '{}'", value))?;
+                }
+                "--zoom-follow" => config.zoom_follow = true,
+                "--pipes" => {
+                    let value = args.next().ok_or("--pipes needs a style")?;
+                    config.pipe_style = PipeStyle::parse(&value)
+                        .ok_or_else(|| format!("unknown pipe style '{}'", value))?;
+                }
+                "--haptics" => config.haptics = true,
+                "--low-bandwidth" => config.low_bandwidth = true,
+                "--ghost" => config.ghost = true,
+                "--pip" => config.pip = true,
+                "--one-switch" => config.one_switch = true,
+                "--trajectory" => config.trajectory = true,
+                "--classic-scoring" => config.scoring = ScoringMode::Classic,
+                "--no-quit-prompt" => config.confirm_quit = false,
+                "--screensaver" | "--demo" => screensaver = true,
+                "--brain" => {
+                    let path = args.next().ok_or("--brain needs a file")?;
+                    brain = Some(load_brain(&path)?);
+                }
+                "--difficulty" => {
+                    let value = args.next().ok_or("--difficulty needs a value")?;
+                    config.difficulty = Difficulty::parse(&value)
+                        .ok_or_else(|| format!("unknown difficulty '{}'", value))?;
+                }
+                "--status-file" => {
+                    config.status_file = match args.peek().filter(|next| !next.starts_with('-')) {
+                        Some(_) => args.next().map(PathBuf::from),
+                        None => default_status_path(),
+                    };
+                }
+                "--pomodoro" => {
+                    let value = args.next().ok_or("--pomodoro needs a value")?;
+                    let minutes = value
+                        .trim_end_matches('m')
+                        .parse()
+                        .ok()
+                        .filter(|&m| (1..=pomodoro::MAX_MINUTES).contains(&m))
+                        .ok_or_else(|| {
+                            format!(
+                                "--pomodoro must be 1 to {} minutes, got '{}'",
+                                pomodoro::MAX_MINUTES,
+                                value
+                            )
+                        })?;
+                    config.pomodoro = Some(minutes);
+                }
+                "--player" => {
+                    let name = args.next().ok_or("--player needs a name")?;
+                    if !players::valid_name(&name) {
+                        return Err(format!(
+                            "player name '{}' must be up to 16 letters, digits, - or _",
+                            name
+                        ));
+                    }
+                    config.player = Some(name);
+                }
+                "--telemetry" => {
+                    let path = args.next().ok_or("--telemetry needs a file")?;
+                    config.telemetry_file = Some(PathBuf::from(path));
+                }
+                "--milestones" => {
+                    let value = args.next().ok_or("--milestones needs a value")?;
+                    config.milestones = parse_milestones(&value)
+                        .ok_or_else(|| format!("bad milestone list '{}'", value))?;
+                }
+                "--flap-cooldown" => {
+                    let value = args.next().ok_or("--flap-cooldown needs a value")?;
+                    config.flap_cooldown = parse_flap_cooldown(&value).ok_or_else(|| {
+                        format!("--flap-cooldown must be 0 to 1000 ms, got '{}'", value)
+                    })?;
+                }
+                "--daily-limit" => {
+                    let value = args.next().ok_or("--daily-limit needs a value")?;
+                    config.daily_limit = parse_daily_limit(&value).ok_or_else(|| {
+                        format!(
+                            "--daily-limit must be 0 to {} minutes, got '{}'",
+                            MAX_DAILY_LIMIT, value
+                        )
+                    })?;
+                }
+                "--music-volume" => {
+                    let value = args.next().ok_or("--music-volume needs a value")?;
+                    config.music_volume = parse_music_volume(&value).ok_or_else(|| {
+                        format!(
+                            "--music-volume must be 0 to {}, got '{}'",
+                            MAX_VOLUME, value
+                        )
+                    })?;
+                }
+                "--music" => {
+                    let path = args.next().ok_or("--music needs a file")?;
+                    config.music = Some(PathBuf::from(path));
+                }
+                "--effects" => {
+                    let value = args.next().ok_or("--effects needs a value")?;
+                    config.effects = parse_effects(&value).ok_or_else(|| {
+                        format!("--effects must be between 0 and 100, got '{}'", value)
+                    })?;
+                }
+                "--speed" => {
+                    let value = args.next().ok_or("--speed needs a value")?;
+                    config.speed = parse_speed(&value).ok_or_else(|| {
+                        format!("--speed must be between 0.5 and 1.5, got '{}'", value)
+                    })?;
+                }
+                "--keymap" => {
+                    let value = args.next().ok_or("--keymap needs a value")?;
+                    config.keymap = Preset::parse(&value)
+                        .ok_or_else(|| format!("unknown keymap '{}'", value))?;
+                }
+                "--boss-key" => {
+                    let value = args.next().ok_or("--boss-key needs a key")?;
+                    config.boss_key = keymap::parse_key(&value)
+                        .ok_or_else(|| format!("bad boss key '{}'", value))?;
+                }
+                "--kiosk" => config.kiosk = true,
+                "--no-save" => config.no_save = true,
+                "--quiet" => config.quiet = true,
+                "--json-summary" => {
+                    config.json_summary = match args.peek().map(String::as_str) {
+                        Some("-") => {
+                            args.next();
+                            Some(Destination::Stdout)
+                        }
+                        Some(next) if !next.starts_with('-') => {
+                            args.next().map(PathBuf::from).map(Destination::File)
+                        }
+                        _ => Some(Destination::Stdout),
+                    };
+                }
+                "--kiosk-exit" => {
+                    let value = args.next().ok_or("--kiosk-exit needs a combo")?;
+                    config.kiosk_exit = Combo::parse(&value).ok_or_else(|| {
+                        format!(
+                            "--kiosk-exit needs a modifier and a key like ctrl+alt+q, got '{}'",
+                            value
+                        )
+                    })?;
+                }
+                "--restart-key" => {
+                    let value = args.next().ok_or("--restart-key needs a key")?;
+                    config.restart_key = keymap::parse_key(&value)
+                        .ok_or_else(|| format!("bad restart key '{}'", value))?;
+                }
+                "--bot" => {
+                    let value = args.next().ok_or("--bot needs a tier")?;
+                    config.bot = Tier::parse(&value)
+                        .ok_or_else(|| format!("unknown bot tier '{}'", value))?;
+                }
+                "--weather" => {
+                    let value = args.next().ok_or("--weather needs a value")?;
+                    config.weather = WeatherSetting::parse(&value)
+                        .ok_or_else(|| format!("unknown weather '{}'", value))?;
+                }
+                "--ramp" => {
+                    let value = args.next().ok_or("--ramp needs a curve")?;
+                    config.ramp = Curve::parse(&value)
+                        .ok_or_else(|| format!("unknown ramp curve '{}'", value))?;
+                }
+                "--mutators" => {
+                    let value = args.next().ok_or("--mutators needs a list")?;
+                    config.mutators = Mutators::parse(&value)
+                        .ok_or_else(|| format!("unknown mutators '{}'", value))?;
+                }
+                "--stress" => {
+                    let value = args.next().ok_or("--stress needs a number of birds")?;
+                    stress = match value.parse::<usize>() {
+                        Ok(n) if (1..=MAX_STRESS_BIRDS).contains(&n) => Some(n),
+                        _ => {
+                            return Err(format!(
+                                "--stress must be between 1 and {} birds",
+                                MAX_STRESS_BIRDS
+                            ))
+                        }
+                    };
+                }
+                "--split" => {
+                    split = match args.peek().and_then(|next| Opponent::parse(next)) {
+                        Some(opponent) => {
+                            args.next();
+                            Some(opponent)
+                        }
+                        None => Some(Opponent::Bot),
+                    };
+                }
+                "--challenge" => {
+                    let value = args.next().ok_or("--challenge needs a code")?;
+                    challenge = Some(Challenge::decode(&value)?);
+                }
+                "--challenge-file" => {
+                    let value = args.next().ok_or("--challenge-file needs a path")?;
+                    challenge_file = Some(PathBuf::from(value));
+                }
+                other => return Err(format!("unknown argument '{}'", other)),
+            }
+        }
+
+        if let Some(birds) = stress {
+            return Ok(Cli::Stress(config, birds));
+        }
+        if let Some(opponent) = split {
+            if challenge.is_some() || challenge_file.is_some() {
+                return Err("--split can't take on a challenge".into());
+            }
+            return Ok(Cli::Split(config, opponent));
+        }
+        if screensaver {
+            return Ok(Cli::Screensaver(config, brain));
+        }
+        if brain.is_some() {
+            return Err("--brain only flies the screensaver; add --demo".into());
+        }
+        // A challenge is only fair on its own settings, whatever else was asked for
+        if let Some(path) = challenge_file {
+            if challenge.is_some() {
+                return Err("--challenge and --challenge-file can't go together".into());
+            }
+            let file = ChallengeFile::load(&path, config.player.as_deref())?;
+            file.challenge.apply(&mut config);
+            return Ok(Cli::Event(config, file));
+        }
+        if let Some(challenge) = &challenge {
+            challenge.apply(&mut config);
+        }
+        Ok(Cli::Run(config, challenge))
+    }
+
+    pub fn usage() -> String {
+        usage()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    #[test]
+    fn saving_puts_back_what_the_environment_changed() {
+        let mut config = Config::default();
+        config.read("difficulty = easy\nspeed = 1.5\n");
+        config.layer(|config| {
+            config.set("difficulty", "hard");
+            config.set("flap_cooldown", "90");
+        });
+        assert!(config.difficulty == Difficulty::Hard);
+        let file = config.to_file();
+        assert!(file.contains("\ndifficulty = easy\n"), "{}", file);
+        assert!(file.contains("\nflap_cooldown = 100\n"), "{}", file);
+        assert!(file.contains("\nspeed = 1.5\n"), "{}", file);
+
+        // A key changed again since, on the options screen, is saved as it is now
+        config.difficulty = Difficulty::Normal;
+        assert!(config.to_file().contains("\ndifficulty = normal\n"));
+    }
+
+    fn json_summary(args: &[&str]) -> Option<Destination> {
+        let owned = args.iter().map(|arg| arg.to_string());
+        match Cli::parse(Config::default(), owned) {
+            Ok(Cli::Run(config, _)) => config.json_summary,
+            _ => panic!("{:?} didn't parse to a run", args),
+        }
+    }
+
+    #[test]
+    fn a_dash_sends_the_json_summary_to_stdout() {
+        let stdout = |json| matches!(json, Some(Destination::Stdout));
+        assert!(stdout(json_summary(&["--json-summary"])));
+        assert!(stdout(json_summary(&["--json-summary", "-"])));
+        assert!(stdout(json_summary(&["--json-summary", "--quiet"])));
+        assert!(matches!(
+            json_summary(&["--json-summary", "out.json"]),
+            Some(Destination::File(path)) if path.as_os_str() == "out.json"
+        ));
+    }
+
+    #[test]
+    fn the_data_dir_is_taken_out_for_every_subcommand() {
+        let mut args: Vec<String> = ["simulate", "--runs", "5", "--data-dir", "/tmp/d"]
+            .map(String::from)
+            .to_vec();
+        let dir = take_data_dir(&mut args).unwrap();
+        assert_eq!(dir.as_deref(), Some(Path::new("/tmp/d")));
+        assert!(matches!(
+            Cli::parse(Config::default(), args.into_iter()),
+            Ok(Cli::Simulate(..))
+        ));
+
+        let mut args = vec!["doctor".to_string(), "--data-dir".to_string()];
+        assert!(take_data_dir(&mut args).is_err());
+    }
+}
@@ -0,0 +1,84 @@
+/// Ticks of full daylight at the start of a run (~60s).
+const DAY_TICKS: u32 = 1200;
+/// Ticks spent fading from day to night.
+const DUSK_TICKS: u32 = 600;
+/// Light level of everything outside the lantern once night has fallen.
+const NIGHT_AMBIENT: f32 = 0.15;
+/// Radius of the lantern around the bird at full night, in columns.
+const LANTERN_RADIUS: f32 = 14.0;
+/// Terminal cells are roughly twice as tall as they are wide.
+const CELL_ASPECT: f32 = 2.0;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Phase {
+    Day,
+    Dusk,
+    Night,
+}
+
+impl Phase {
+    pub fn label(self) -> &'static str {
+        match self {
+            Phase::Day => "Day",
+            Phase::Dusk => "Dusk",
+            Phase::Night => "Night",
+        }
+    }
+}
+
+/// Day→dusk→night progression over a run. Night stays for the rest of the run.
+#[derive(Clone)]
+pub struct DayCycle {
+    enabled: bool,
+    ticks: u32,
+}
+
+impl DayCycle {
+    pub fn new(enabled: bool) -> Self {
+        Self { enabled, ticks: 0 }
+    }
+
+    pub fn reset(&mut self) {
+        self.ticks = 0;
+    }
+
+    pub fn update(&mut self) {
+        if self.enabled {
+            self.ticks = self.ticks.saturating_add(1);
+        }
+    }
+
+    pub fn phase(&self) -> Phase {
+        if !self.enabled || self.ticks < DAY_TICKS {
+            Phase::Day
+        } else if self.ticks < DAY_TICKS + DUSK_TICKS {
+            Phase::Dusk
+        } else {
+            Phase::Night
+        }
+    }
+
+    /// How far into the night we are, from 0.0 (day) to 1.0 (full night).
+    fn darkness(&self) -> f32 {
+        match self.phase() {
+            Phase::Day => 0.0,
+            Phase::Dusk => (self.ticks - DAY_TICKS) as f32 / DUSK_TICKS as f32,
+            Phase::Night => 1.0,
+        }
+    }
+
+    /// Light level of a cell given its offset from the bird.
+    pub fn brightness_at(&self, dx: i32, dy: i32) -> f32 {
+        let darkness = self.darkness();
+        if darkness == 0.0 {
+            return 1.0;
+        }
+
+        let ambient = 1.0 - darkness * (1.0 - NIGHT_AMBIENT);
+        let distance = ((dx as f32).powi(2) + (dy as f32 * CELL_ASPECT).powi(2)).sqrt();
+        // The lantern starts out covering the whole screen and closes in during dusk
+        let radius = LANTERN_RADIUS / darkness.max(0.01);
+        let lantern = (1.0 - distance / radius).max(0.0);
+        ambient.max(lantern.sqrt())
+    }
+}
@@ -0,0 +1,71 @@
+//! Putting a run away and picking it up again with `tflap attach`.
+//!
+//! A detached run is saved as its challenge code and the tick it stopped on. Runs are
+//! deterministic, so replaying the code's inputs up to that tick rebuilds it exactly,
+//! down to the bird's height and the next pipe. The file is removed once attached, so
+//! a run can only be carried on once.
+
+use crate::paths;
+use crate::storage;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+pub struct Detached {
+    /// The run so far, as a `--challenge` code.
+    pub run: String,
+    pub tick: u32,
+    /// The challenge being played, if any.
+    pub challenge: Option<String>,
+    /// Settings outside the code that decide which leaderboard the run goes on.
+    pub assist: bool,
+    pub trajectory: bool,
+}
+
+fn path() -> Option<PathBuf> {
+    paths::file("detached")
+}
+
+pub fn save(detached: &Detached) -> io::Result<()> {
+    let path = path().ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "$HOME is not set"))?;
+    let mut content = format!(
+        "run = {}\ntick = {}\nassist = {}\ntrajectory = {}\n",
+        detached.run, detached.tick, detached.assist, detached.trajectory
+    );
+    if let Some(challenge) = &detached.challenge {
+        content.push_str(&format!("challenge = {}\n", challenge));
+    }
+    storage::write_atomic(&path, &content)
+}
+
+/// Load the detached run and remove its file.
+pub fn take() -> Result<Detached, String> {
+    let path = path().ok_or("$HOME is not set")?;
+    let content = fs::read_to_string(&path).map_err(|_| "no detached run to attach")?;
+    let mut detached = Detached {
+        run: String::new(),
+        tick: 0,
+        challenge: None,
+        assist: false,
+        trajectory: false,
+    };
+    for line in content.lines() {
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+        let value = value.trim();
+        match key.trim() {
+            "run" => detached.run = value.to_string(),
+            "tick" => detached.tick = value.parse().map_err(|_| "bad tick in detached run")?,
+            "challenge" => detached.challenge = Some(value.to_string()),
+            "assist" => detached.assist = value == "true",
+            "trajectory" => detached.trajectory = value == "true",
+            _ => {}
+        }
+    }
+    if detached.run.is_empty() {
+        return Err("detached run file is damaged".to_string());
+    }
+    let _ = storage::remove(&path);
+    Ok(detached)
+}
@@ -0,0 +1,54 @@
+/// Preset pipe layout for a difficulty level.
+#[derive(Clone, Copy, PartialEq, Default)]
+pub enum Difficulty {
+    Easy,
+    #[default]
+    Normal,
+    Hard,
+}
+
+impl Difficulty {
+    pub const ALL: [Difficulty; 3] = [Difficulty::Easy, Difficulty::Normal, Difficulty::Hard];
+    /// The most rows of open space any preset gives a pipe.
+    pub const WIDEST_GAP: u16 = Difficulty::Easy.pipe_gap();
+
+    pub fn key(self) -> &'static str {
+        match self {
+            Difficulty::Easy => "easy",
+            Difficulty::Normal => "normal",
+            Difficulty::Hard => "hard",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|d| d.key() == s)
+    }
+
+    /// Rows of open space in each pipe.
+    pub const fn pipe_gap(self) -> u16 {
+        match self {
+            Difficulty::Easy => 10,
+            Difficulty::Normal => 8,
+            Difficulty::Hard => 6,
+        }
+    }
+
+    /// Farthest a gap may sit from the one before, as a share of the rows gaps can go in.
+    /// Hard asks for the longest climbs and dives.
+    pub fn gap_step(self) -> f32 {
+        match self {
+            Difficulty::Easy => 0.35,
+            Difficulty::Normal => 0.5,
+            Difficulty::Hard => 0.65,
+        }
+    }
+
+    /// Columns from one pipe to the next.
+    pub fn pipe_spacing(self) -> i32 {
+        match self {
+            Difficulty::Easy => 48,
+            Difficulty::Normal => 40,
+            Difficulty::Hard => 32,
+        }
+    }
+}
@@ -0,0 +1,237 @@
+//! The boss key's disguise: a system monitor in the style of `htop`, drawn by the game.
+//!
+//! The numbers wander on their own random generator, so putting the disguise up never
+//! disturbs the run's seeded one.
+
+use crate::render::Frame;
+use crossterm::style::Color;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::env;
+
+/// htop redraws every 1.5 seconds by default.
+const REFRESH_TICKS: u32 = 30;
+const CORES: usize = 4;
+const MEM_TOTAL_GB: f32 = 15.5;
+
+/// Fake processes: PID, user (`None` for the player), and command line.
+const PROCESSES: &[(u32, Option<&str>, &str)] = &[
+    (1, Some("root"), "/sbin/init splash"),
+    (412, Some("root"), "/usr/lib/systemd/systemd-journald"),
+    (
+        733,
+        Some("root"),
+        "/usr/bin/dockerd -H fd:// --containerd=/run/containerd.sock",
+    ),
+    (988, Some("postgres"), "postgres: 15/main: checkpointer"),
+    (991, Some("postgres"), "postgres: 15/main: walwriter"),
+    (1204, Some("redis"), "/usr/bin/redis-server 127.0.0.1:6379"),
+    (1377, Some("www-data"), "nginx: worker process"),
+    (2051, None, "/usr/lib/systemd/systemd --user"),
+    (2210, None, "sshd: deploy@pts/0"),
+    (2214, None, "-bash"),
+    (3180, None, "node /srv/app/server.js --port 3000"),
+    (3305, None, "python3 manage.py runserver 0.0.0.0:8000"),
+    (4021, None, "rust-analyzer"),
+    (4410, None, "cargo build --release"),
+    (
+        4412,
+        None,
+        "rustc --crate-name app --edition=2021 src/main.rs",
+    ),
+    (5120, None, "tail -f /var/log/app/production.log"),
+    (5533, None, "vim src/handlers/report.rs"),
+    (6001, Some("root"), "kworker/u16:2-events_unbound"),
+    (6120, None, "htop"),
+];
+
+pub struct Disguise {
+    /// The run was paused to put the disguise up, so it carries on once it comes down.
+    pub paused_run: bool,
+    rng: StdRng,
+    ticks: u32,
+    next_refresh: u32,
+    cores: [f32; CORES],
+    /// CPU use of each process, in the order of `PROCESSES`.
+    cpu: Vec<f32>,
+    /// CPU seconds used so far by each process.
+    time: Vec<f32>,
+    mem_gb: f32,
+    user: String,
+}
+
+impl Disguise {
+    pub fn new(paused_run: bool) -> Self {
+        let mut rng = StdRng::from_entropy();
+        let time = PROCESSES
+            .iter()
+            .map(|_| rng.gen_range(1.0..9000.0))
+            .collect();
+        let user = env::var("USER")
+            .ok()
+            .filter(|user| !user.is_empty())
+            .unwrap_or_else(|| "deploy".to_string());
+        let mut disguise = Self {
+            paused_run,
+            rng,
+            ticks: 0,
+            next_refresh: 0,
+            cores: [0.0; CORES],
+            cpu: vec![0.0; PROCESSES.len()],
+            time,
+            mem_gb: 6.2,
+            user,
+        };
+        disguise.refresh();
+        disguise
+    }
+
+    pub fn update(&mut self) {
+        self.ticks += 1;
+        if self.ticks >= self.next_refresh {
+            self.refresh();
+        }
+    }
+
+    fn refresh(&mut self) {
+        self.next_refresh = self.ticks + REFRESH_TICKS;
+        for (i, cpu) in self.cpu.iter_mut().enumerate() {
+            // The build and the app servers are the busy ones
+            let busy = matches!(PROCESSES[i].0, 4412 | 3180 | 3305 | 4021);
+            let peak = if busy { 90.0 } else { 3.0 };
+            *cpu = self.rng.gen_range(0.0..peak);
+            self.time[i] += *cpu / 100.0 * 1.5;
+        }
+        let total: f32 = self.cpu.iter().sum();
+        for core in &mut self.cores {
+            *core = (total / CORES as f32 * self.rng.gen_range(0.6..1.4)).min(100.0);
+        }
+        self.mem_gb = (self.mem_gb + self.rng.gen_range(-0.05..0.05)).clamp(5.8, 6.8);
+    }
+
+    pub fn render(&self, frame: &mut Frame, width: u16, height: u16) {
+        let bar_width = (width as usize / 2).saturating_sub(12).clamp(10, 40);
+        let bar = |fraction: f32| {
+            let filled = ((fraction.clamp(0.0, 1.0)) * bar_width as f32) as usize;
+            format!("{}{}", "|".repeat(filled), " ".repeat(bar_width - filled))
+        };
+        let right = bar_width as i32 + 12;
+
+        for (i, load) in self.cores.iter().enumerate() {
+            let y = i as i32;
+            frame.put_str(2, y, &format!("{:>3}", i + 1), Color::Cyan);
+            frame.put_str(6, y, "[", Color::White);
+            frame.put_str(7, y, &bar(load / 100.0), Color::Green);
+            frame.put_str(
+                7 + bar_width as i32 - 6,
+                y,
+                &format!("{:5.1}%", load),
+                Color::DarkGrey,
+            );
+            frame.put_str(7 + bar_width as i32, y, "]", Color::White);
+        }
+        let mem_y = CORES as i32;
+        frame.put_str(2, mem_y, "Mem", Color::Cyan);
+        frame.put_str(6, mem_y, "[", Color::White);
+        frame.put_str(7, mem_y, &bar(self.mem_gb / MEM_TOTAL_GB), Color::Green);
+        frame.put_str(
+            7 + bar_width as i32 - 11,
+            mem_y,
+            &format!("{:.2}G/{:.1}G", self.mem_gb, MEM_TOTAL_GB),
+            Color::DarkGrey,
+        );
+        frame.put_str(7 + bar_width as i32, mem_y, "]", Color::White);
+        frame.put_str(2, mem_y + 1, "Swp", Color::Cyan);
+        frame.put_str(6, mem_y + 1, "[", Color::White);
+        frame.put_str(
+            7 + bar_width as i32 - 8,
+            mem_y + 1,
+            "0K/2.00G",
+            Color::DarkGrey,
+        );
+        frame.put_str(7 + bar_width as i32, mem_y + 1, "]", Color::White);
+
+        let running = self.cpu.iter().filter(|&&cpu| cpu > 20.0).count().max(1);
+        let load: f32 = self.cores.iter().sum::<f32>() / 100.0;
+        let uptime = 1_100_000 + self.ticks / 20;
+        let info = [
+            format!("Tasks: 142, 311 thr; {} running", running),
+            format!(
+                "Load average: {:.2} {:.2} {:.2}",
+                load,
+                load * 0.9,
+                load * 0.8
+            ),
+            format!(
+                "Uptime: {} days, {:02}:{:02}:{:02}",
+                uptime / 86_400,
+                uptime / 3600 % 24,
+                uptime / 60 % 60,
+                uptime % 60
+            ),
+        ];
+        for (i, line) in info.iter().enumerate() {
+            frame.put_str(right, i as i32, line, Color::White);
+        }
+
+        let header_y = mem_y + 3;
+        let header = format!(
+            "{:>7} {:<9} PRI  NI  VIRT   RES   SHR S CPU% MEM%   TIME+  Command",
+            "PID", "USER"
+        );
+        frame.put_str(
+            0,
+            header_y,
+            &format!("{:<1$}", header, width as usize),
+            Color::Green,
+        );
+
+        let mut order: Vec<usize> = (0..PROCESSES.len()).collect();
+        order.sort_by(|&a, &b| self.cpu[b].total_cmp(&self.cpu[a]));
+        let rows = (height as i32 - header_y - 2).max(0) as usize;
+        for (row, &i) in order.iter().take(rows).enumerate() {
+            let (pid, user, command) = PROCESSES[i];
+            let user = user.unwrap_or(&self.user);
+            let seconds = self.time[i];
+            let line = format!(
+                "{:>7} {:<9} {:>3} {:>3} {:>5} {:>5} {:>5} {} {:>4.1} {:>4.1} {:>3}:{:05.2}  {}",
+                pid,
+                user.chars().take(9).collect::<String>(),
+                20,
+                0,
+                format!("{}M", 100 + pid % 900),
+                format!("{}M", 10 + pid % 90),
+                format!("{}M", 5 + pid % 40),
+                if self.cpu[i] > 20.0 { 'R' } else { 'S' },
+                self.cpu[i],
+                (pid % 50) as f32 / 10.0,
+                (seconds / 60.0) as u32,
+                seconds % 60.0,
+                command
+            );
+            let color = if row == 0 { Color::Cyan } else { Color::White };
+            frame.put_str(0, header_y + 1 + row as i32, &line, color);
+        }
+
+        let keys = [
+            ("F1", "Help"),
+            ("F2", "Setup"),
+            ("F3", "Search"),
+            ("F4", "Filter"),
+            ("F5", "Tree"),
+            ("F6", "SortBy"),
+            ("F7", "Nice -"),
+            ("F8", "Nice +"),
+            ("F9", "Kill"),
+            ("F10", "Quit"),
+        ];
+        let mut x = 0;
+        let y = height as i32 - 1;
+        for (key, label) in keys {
+            frame.put_str(x, y, key, Color::White);
+            x += key.len() as i32;
+            frame.put_str(x, y, &format!("{:<6}", label), Color::DarkCyan);
+            x += 6;
+        }
+    }
+}
@@ -0,0 +1,236 @@
+//! `tflap doctor` and the startup capability checks.
+//!
+//! The report gathers what usually explains a rendering or input bug: the terminal, its
+//! size and color support, the locale, the saved settings, and the last few runs. Home
+//! directory paths and the user name are masked so it can be pasted into an issue as is.
+
+use crate::config::Config;
+use crate::paths::Paths;
+use crate::render::{ColorDepth, Display};
+use crate::storage;
+use crossterm::{cursor, execute, style::Print, terminal};
+use std::env;
+use std::fs;
+use std::io::{self, Write};
+use std::path::PathBuf;
+
+/// Smallest terminal the title screen fits in comfortably.
+const MIN_WIDTH: u16 = 60;
+const MIN_HEIGHT: u16 = 24;
+/// Lines of run history included in the report.
+const HISTORY_LINES: usize = 10;
+/// Save files listed in the report, relative to the home directory.
+const SAVE_FILES: &[&str] = &[
+    ".tflap_config",
+    ".tflap_leaderboard",
+    ".tflap_history",
+    ".tflap_rivals",
+    ".tflap_scorecard.txt",
+];
+
+/// Terminals known to lack Unicode glyphs whatever the locale says.
+const PLAIN_TERMS: &[&str] = &["linux", "dumb", "vt100", "vt220", "ansi"];
+
+/// What the terminal can do, as far as the environment tells.
+pub struct Capabilities {
+    pub term: Option<String>,
+    pub size: Option<(u16, u16)>,
+    pub colors: ColorDepth,
+    pub utf8: bool,
+}
+
+impl Capabilities {
+    pub fn detect() -> Self {
+        let term = env::var("TERM").ok();
+        let true_color = env::var("COLORTERM")
+            .map(|v| v == "truecolor" || v == "24bit")
+            .unwrap_or(false)
+            || cfg!(windows);
+        let colors = if true_color {
+            ColorDepth::TrueColor
+        } else if term.as_deref().is_some_and(|t| t.contains("256color")) {
+            ColorDepth::Ansi256
+        } else {
+            ColorDepth::Ansi16
+        };
+        Self {
+            term,
+            size: terminal::size().ok(),
+            colors,
+            utf8: locale().map_or(cfg!(windows), |l| {
+                let l = l.to_ascii_lowercase();
+                l.contains("utf-8") || l.contains("utf8")
+            }),
+        }
+    }
+}
+
+/// The locale that decides the character set, in POSIX precedence order.
+fn locale() -> Option<String> {
+    ["LC_ALL", "LC_CTYPE", "LANG"]
+        .iter()
+        .filter_map(|var| env::var(var).ok())
+        .find(|value| !value.is_empty())
+}
+
+/// Print `glyph` at the top-left corner and see how many columns the cursor moves. `None`
+/// means the terminal doesn't report the cursor position at all. Needs raw mode.
+pub fn probe_width(out: &mut impl Write, glyph: char) -> Option<u16> {
+    execute!(out, cursor::MoveTo(0, 0), Print(glyph)).ok()?;
+    let (column, _) = cursor::position().ok()?;
+    execute!(out, cursor::MoveTo(0, 0), Print("    ")).ok()?;
+    Some(column)
+}
+
+/// Pick the richest output the terminal can take. Colors step down from true color to
+/// 256 to the 16 named ones; glyphs drop to ASCII when forced, when the locale or
+/// terminal type rules Unicode out, or when `probe` says a block glyph doesn't render as
+/// one column (or can't tell). Emoji are only drawn when asked for and probed at two
+/// columns, since a terminal that gets their width wrong shifts the rest of the row.
+pub fn choose_display(
+    caps: &Capabilities,
+    config: &Config,
+    mut probe: impl FnMut(char) -> Option<u16>,
+) -> Display {
+    let plain = caps
+        .term
+        .as_deref()
+        .is_some_and(|term| PLAIN_TERMS.contains(&term));
+    let ascii = config.ascii || !caps.utf8 || plain || probe('█') != Some(1);
+    let emoji = config.emoji && !ascii && probe('🐤') == Some(2);
+    Display {
+        ascii,
+        emoji,
+        colors: caps.colors,
+        zoom: config.zoom,
+    }
+}
+
+/// Problems worth pointing out before playing, short enough for the title screen.
+pub fn warnings(caps: &Capabilities, config: &Config) -> Vec<String> {
+    let mut warnings = Vec::new();
+    if caps.term.as_deref() == Some("dumb") {
+        warnings.push("TERM=dumb: screen may not draw".to_string());
+    }
+    if !caps.utf8 {
+        warnings.push("Non-UTF-8 locale: symbols may break".to_string());
+    }
+    if let Some((width, height)) = caps.size {
+        if width < MIN_WIDTH || height < MIN_HEIGHT {
+            warnings.push(format!(
+                "{}x{} is small: {}x{} plays best",
+                width, height, MIN_WIDTH, MIN_HEIGHT
+            ));
+        }
+    }
+    if config.day_night && caps.colors != ColorDepth::TrueColor {
+        warnings.push("No true color: night may look off".to_string());
+    }
+    warnings
+}
+
+/// Mask the home directory and user name.
+fn redact(text: &str) -> String {
+    let mut text = text.to_string();
+    if let Ok(home) = env::var("HOME") {
+        if !home.is_empty() {
+            text = text.replace(&home, "~");
+        }
+    }
+    for var in ["USER", "USERNAME"] {
+        if let Ok(user) = env::var(var) {
+            if user.len() > 2 {
+                text = text.replace(&user, "<user>");
+            }
+        }
+    }
+    text
+}
+
+fn home_file(name: &str) -> Option<PathBuf> {
+    Paths::get().map(|paths| paths.dir().join(name))
+}
+
+fn report(config: &Config) -> String {
+    let caps = Capabilities::detect();
+    let var = |name: &str| env::var(name).unwrap_or_else(|_| "(unset)".to_string());
+    let mut out = format!(
+        "tflap doctor report\n\n\
+         [build]\n\
+         version: {}\n\
+         platform: {}-{}\n\n\
+         [terminal]\n\
+         TERM: {}\n\
+         COLORTERM: {}\n\
+         TERM_PROGRAM: {}\n\
+         size: {}\n\
+         colors: {}\n\
+         locale: {}\n\
+         utf-8: {}\n",
+        env!("CARGO_PKG_VERSION"),
+        env::consts::OS,
+        env::consts::ARCH,
+        var("TERM"),
+        var("COLORTERM"),
+        var("TERM_PROGRAM"),
+        caps.size
+            .map_or_else(|| "unknown".to_string(), |(w, h)| format!("{}x{}", w, h)),
+        match caps.colors {
+            ColorDepth::TrueColor => "true color",
+            ColorDepth::Ansi256 => "256",
+            ColorDepth::Ansi16 => "16",
+        },
+        locale().unwrap_or_else(|| "(unset)".to_string()),
+        caps.utf8,
+    );
+    if let Ok(tmux) = env::var("TMUX") {
+        out.push_str(&format!("tmux: {}\n", !tmux.is_empty()));
+    }
+
+    out.push_str("\n[warnings]\n");
+    let warnings = warnings(&caps, config);
+    if warnings.is_empty() {
+        out.push_str("none\n");
+    }
+    for warning in warnings {
+        out.push_str(&format!("{}\n", warning));
+    }
+
+    out.push_str("\n[files]\n");
+    for name in SAVE_FILES {
+        let size = home_file(name).and_then(|path| fs::metadata(path).ok());
+        match size {
+            Some(meta) => out.push_str(&format!("~/{}: {} bytes\n", name, meta.len())),
+            None => out.push_str(&format!("~/{}: missing\n", name)),
+        }
+    }
+
+    out.push_str("\n[config]\n");
+    match home_file(".tflap_config").and_then(|path| fs::read_to_string(path).ok()) {
+        Some(content) => out.push_str(&content),
+        None => out.push_str("(defaults)\n"),
+    }
+
+    out.push_str(&format!("\n[recent runs, last {}]\n", HISTORY_LINES));
+    let history = home_file(".tflap_history")
+        .and_then(|path| fs::read_to_string(path).ok())
+        .unwrap_or_default();
+    let lines: Vec<&str> = history.lines().collect();
+    for line in &lines[lines.len().saturating_sub(HISTORY_LINES)..] {
+        out.push_str(line);
+        out.push('\n');
+    }
+    redact(&out)
+}
+
+/// Print the report and save a copy to attach to a bug report.
+pub fn run(config: &Config) -> io::Result<()> {
+    let report = report(config);
+    print!("{}", report);
+    let Some(path) = home_file(".tflap_doctor.txt") else {
+        return Ok(());
+    };
+    storage::write_atomic(&path, &report)?;
+    println!("\nSaved to {}", redact(&path.display().to_string()));
+    Ok(())
+}
@@ -0,0 +1,40 @@
+//! One dial for everything drawn or rung just for show: the milestone flash, confetti,
+//! weather particles and fanfare bells.
+//!
+//! The intensity is a percentage from the config. Effects that come in pieces thin out
+//! as it goes down; the flash, the one most likely to bother someone, stops below half;
+//! nothing is left at zero. Only drawing asks, so the game plays the same at any setting.
+//! New effects should ask here too.
+
+pub const MAX_INTENSITY: u8 = 100;
+/// Steps the options screen moves the dial in, and what it calls each.
+pub const INTENSITY_STEP: u8 = 25;
+pub const INTENSITY_LABELS: [&str; 5] = ["off", "25%", "50%", "75%", "100%"];
+/// Least intensity that still flashes the pipes at a milestone.
+const FLASH_INTENSITY: u8 = 50;
+
+#[derive(Clone, Copy)]
+pub struct Effects {
+    intensity: u8,
+}
+
+impl Effects {
+    pub fn new(intensity: u8) -> Self {
+        Self {
+            intensity: intensity.min(MAX_INTENSITY),
+        }
+    }
+
+    /// How many of `count` pieces of confetti, rain or snow to draw.
+    pub fn share(self, count: usize) -> usize {
+        count * usize::from(self.intensity) / usize::from(MAX_INTENSITY)
+    }
+
+    pub fn flash(self) -> bool {
+        self.intensity >= FLASH_INTENSITY
+    }
+
+    pub fn bells(self) -> bool {
+        self.intensity > 0
+    }
+}
@@ -0,0 +1,224 @@
+//! Components for the game's moving objects: a body to move, a sprite to draw and a
+//! collider to hit, stepped, drawn and hit-tested by the same systems whatever the
+//! object is.
+//!
+//! Weather particles and confetti are whole entities on lists. The bird is a body and
+//! a sprite with its flip on top, and a pipe hands the collision system its collider,
+//! so the pipes and the stress test's flock are tested alike. A new kind of object is a
+//! new mix of these, not new physics or hit tests in `Game::update`.
+
+use crate::collision::{self, Aabb};
+use crate::render::Frame;
+use crate::world::{Pos, View};
+use crossterm::style::Color;
+
+/// Where an entity is and how it moves each tick.
+#[derive(Clone, Copy)]
+pub struct Body {
+    pub pos: Pos,
+    pub velocity: Pos,
+    /// Added to the downward velocity each tick.
+    pub gravity: f32,
+}
+
+impl Body {
+    /// A body drifting at a steady `velocity`.
+    pub fn drifting(pos: Pos, velocity: Pos) -> Self {
+        Self {
+            pos,
+            velocity,
+            gravity: 0.0,
+        }
+    }
+
+    /// The physics system for one body: `dt` ticks of movement (1.0 at normal speed).
+    pub fn step(&mut self, dt: f32) {
+        self.velocity.y += self.gravity * dt;
+        self.pos.x += self.velocity.x * dt;
+        self.pos.y += self.velocity.y * dt;
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct Sprite {
+    pub glyph: char,
+    pub color: Color,
+    /// The glyph takes two columns.
+    pub wide: bool,
+}
+
+impl Sprite {
+    /// The render system for one sprite, at screen cell (`x`, `y`).
+    pub fn draw(self, frame: &mut Frame, x: i32, y: i32) {
+        if self.wide {
+            frame.put_wide(x, y, self.glyph, self.color);
+        } else {
+            frame.put(x, y, self.glyph, self.color);
+        }
+    }
+}
+
+/// What an entity can be hit on: its solid `walls`, all within `bounds`, in world cells.
+/// A wall it doesn't need can be left empty.
+#[derive(Clone, Copy)]
+pub struct Collider {
+    pub bounds: Aabb,
+    pub walls: [Aabb; 2],
+}
+
+impl Collider {
+    /// Whether `target` hits a wall, swept over the `sweep` columns the walls moved left
+    /// since the last test.
+    pub fn hits(&self, target: Aabb, sweep: f32) -> bool {
+        collision::swept_hit(target, self.bounds, &self.walls, sweep)
+    }
+}
+
+/// The collision system: whether `target` hits any of `colliders`.
+pub fn any_hit(target: Aabb, sweep: f32, mut colliders: impl Iterator<Item = Collider>) -> bool {
+    colliders.any(|collider| collider.hits(target, sweep))
+}
+
+#[derive(Clone, Copy)]
+pub struct Entity {
+    pub body: Body,
+    pub sprite: Sprite,
+}
+
+/// A list of entities that live within `bounds` and go once they leave them.
+#[derive(Clone)]
+pub struct Entities {
+    list: Vec<Entity>,
+    bounds: Aabb,
+}
+
+impl Entities {
+    pub fn new(bounds: Aabb) -> Self {
+        Self {
+            list: Vec::new(),
+            bounds,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.list.len()
+    }
+
+    pub fn clear(&mut self) {
+        self.list.clear();
+    }
+
+    pub fn spawn(&mut self, entity: Entity) {
+        self.list.push(entity);
+    }
+
+    /// Move the entities to new bounds, like a resized board's.
+    pub fn set_bounds(&mut self, bounds: Aabb) {
+        self.bounds = bounds;
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut Entity> {
+        self.list.iter_mut()
+    }
+
+    /// The physics system: one tick of movement, dropping whatever leaves the bounds.
+    pub fn physics(&mut self) {
+        for entity in &mut self.list {
+            entity.body.step(1.0);
+        }
+        let bounds = self.bounds;
+        self.list
+            .retain(|entity| bounds.contains(entity.body.pos.x, entity.body.pos.y));
+    }
+
+    /// The render system: draw the first `count` entities through `view`.
+    pub fn render(&self, frame: &mut Frame, view: View, count: usize) {
+        for entity in self.list.iter().take(count) {
+            let (x, y) = view.to_screen(entity.body.pos);
+            entity.sprite.draw(frame, x, y);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DOT: Sprite = Sprite {
+        glyph: '*',
+        color: Color::White,
+        wide: false,
+    };
+
+    fn entity(x: f32, y: f32, dx: f32, dy: f32) -> Entity {
+        Entity {
+            body: Body::drifting(Pos::new(x, y), Pos::new(dx, dy)),
+            sprite: DOT,
+        }
+    }
+
+    #[test]
+    fn entities_that_leave_the_bounds_go() {
+        let mut entities = Entities::new(Aabb::new(0.0, 0.0, 10.0, 10.0));
+        entities.spawn(entity(5.0, 5.0, 1.0, 0.0));
+        entities.spawn(entity(9.5, 5.0, 1.0, 0.0));
+        entities.spawn(entity(5.0, 0.5, 0.0, -1.0));
+        entities.physics();
+        assert_eq!(entities.len(), 1);
+        let body = entities.iter_mut().next().unwrap().body;
+        assert_eq!((body.pos.x, body.pos.y), (6.0, 5.0));
+    }
+
+    #[test]
+    fn gravity_builds_up_speed_tick_by_tick() {
+        let mut entities = Entities::new(Aabb::new(0.0, 0.0, 10.0, 100.0));
+        let mut falling = entity(0.0, 0.0, 0.0, 0.0);
+        falling.body.gravity = 1.0;
+        entities.spawn(falling);
+        for _ in 0..3 {
+            entities.physics();
+        }
+        let body = entities.iter_mut().next().unwrap().body;
+        assert_eq!((body.velocity.y, body.pos.y), (3.0, 6.0));
+    }
+
+    #[test]
+    fn only_the_first_few_are_drawn() {
+        let mut entities = Entities::new(Aabb::new(0.0, 0.0, 10.0, 2.0));
+        for x in 0..4 {
+            entities.spawn(entity(x as f32 * 2.0, 1.0, 0.0, 0.0));
+        }
+        let mut frame = Frame::new(10, 2);
+        entities.render(&mut frame, View::new(0.0, 1.0), 3);
+        assert_eq!(frame.to_text(), "\n* * *\n");
+    }
+
+    #[test]
+    fn a_slow_step_moves_a_fraction_of_a_tick() {
+        let mut body = Body::drifting(Pos::new(0.0, 0.0), Pos::new(2.0, 0.0));
+        body.gravity = 1.0;
+        body.step(0.5);
+        assert_eq!((body.velocity.y, body.pos.x, body.pos.y), (0.5, 1.0, 0.25));
+    }
+
+    #[test]
+    fn colliders_are_hit_on_their_walls_only() {
+        let bounds = Aabb::columns(4.0, 6.0);
+        let above = Aabb {
+            bottom: 3.0,
+            ..bounds
+        };
+        let below = Aabb { top: 7.0, ..bounds };
+        let pipe = Collider {
+            bounds,
+            walls: [above, below],
+        };
+        let cell = |column: f32, row: f32| Aabb::new(column, row, column + 1.0, row + 1.0);
+        assert!(pipe.hits(cell(4.0, 1.0), 0.0));
+        assert!(!pipe.hits(cell(4.0, 5.0), 0.0));
+        assert!(!pipe.hits(cell(6.0, 1.0), 0.0));
+        assert!(pipe.hits(cell(6.0, 1.0), 1.0));
+        assert!(any_hit(cell(5.0, 8.0), 0.0, [pipe, pipe].into_iter()));
+        assert!(!any_hit(cell(5.0, 8.0), 0.0, std::iter::empty()));
+    }
+}
@@ -0,0 +1,44 @@
+/// Something that happened during a tick that other systems may want to react to.
+#[derive(Clone)]
+// Only the music reads the speeds, and it is only built with the audio feature
+#[cfg_attr(not(feature = "audio"), allow(dead_code))]
+pub enum GameEvent {
+    /// A run began at `speed` percent.
+    Started { speed: u8 },
+    /// The bird flapped.
+    Flapped,
+    /// A pipe was passed and the score is now `score`.
+    Scored { score: u32 },
+    /// The pipe just passed was brushed without a hit.
+    NearMiss,
+    /// The game speed changed mid-run to `speed` percent.
+    SpeedChanged { speed: u8 },
+    /// The run ended in a crash.
+    Died,
+    /// A beat of the rhythm mode's tempo.
+    Beat,
+    /// The bird flew past where a rival's best run ended.
+    PassedRival { name: String },
+    /// The ghost's run ended on `score`.
+    GhostDown { score: u32 },
+    /// In a split game, the other side crashed on `score`.
+    OpponentDown { score: u32 },
+    /// In a split game, this side went ahead or fell behind on points.
+    LeadChanged { ahead: bool },
+}
+
+/// Events raised while a tick is simulated, handed out once the tick is done.
+#[derive(Default)]
+pub struct EventBus {
+    pending: Vec<GameEvent>,
+}
+
+impl EventBus {
+    pub fn emit(&mut self, event: GameEvent) {
+        self.pending.push(event);
+    }
+
+    pub fn drain(&mut self) -> Vec<GameEvent> {
+        std::mem::take(&mut self.pending)
+    }
+}
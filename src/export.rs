@@ -0,0 +1,161 @@
+//! Everything saved for sharing goes out through here: scorecards, and photo mode's
+//! pictures as ANSI text or PNG. Each gets the same footer on its way out, with the
+//! version, seed, and category of the run it came from, so anyone with tflap can play
+//! the same pipes and check the conditions matched what's shown.
+
+use crate::leaderboard::Category;
+use crate::png;
+use crate::render::Frame;
+use crate::storage;
+use crossterm::style::Color;
+use std::io;
+use std::path::Path;
+
+/// PNG pixels for each cell, which is about a terminal cell's shape.
+const CELL_WIDTH: u32 = 8;
+const CELL_HEIGHT: u32 = 16;
+/// PNG pixels for each dot of the footer's font, so a letter fills a cell.
+const FONT_SCALE: u32 = 2;
+
+/// Where a shared thing came from.
+pub struct Stamp {
+    seed: u64,
+    category: Category,
+}
+
+impl Stamp {
+    pub fn new(seed: u64, category: &Category) -> Self {
+        Self {
+            seed,
+            category: category.clone(),
+        }
+    }
+
+    /// "tflap 0.1.3 · seed 42 · Style · Hard · rain"
+    pub fn line(&self) -> String {
+        format!(
+            "tflap {} · seed {} · {}",
+            env!("CARGO_PKG_VERSION"),
+            self.seed,
+            self.category.label()
+        )
+    }
+}
+
+pub enum Export<'a> {
+    /// Lines of plain text.
+    Card(&'a str),
+    /// A picture, as text with true-color escapes...
+    Ansi(&'a Frame),
+    /// ...or as an image.
+    Png(&'a Frame),
+}
+
+/// Write `export` to `path` with `stamp` along the bottom.
+pub fn save(path: &Path, export: Export, stamp: &Stamp) -> io::Result<()> {
+    let footer = stamp.line();
+    match export {
+        Export::Card(text) => storage::write(path, format!("{}\n\n  {}\n", text, footer)),
+        Export::Ansi(frame) => {
+            let mut stamped = with_footer(frame);
+            let bottom = stamped.height() as i32 - 1;
+            stamped.put_str(0, bottom, &footer, Color::DarkGrey);
+            storage::write(path, stamped.to_ansi())
+        }
+        Export::Png(frame) => {
+            let stamped = with_footer(frame);
+            let (width, height, mut pixels) = stamped.to_pixels(CELL_WIDTH, CELL_HEIGHT);
+            let top = height - CELL_HEIGHT + (CELL_HEIGHT - 5 * FONT_SCALE) / 2;
+            write_text(&mut pixels, width, top, &footer);
+            storage::write(path, png::encode(width, height, &pixels, &footer))
+        }
+    }
+}
+
+/// `frame` with a blank row added under it for the footer.
+fn with_footer(frame: &Frame) -> Frame {
+    let mut stamped = Frame::new(frame.width(), frame.height() + 1);
+    stamped.blit(frame, 0, 0);
+    stamped
+}
+
+/// Draw `text` in grey across an RGB image `width` pixels wide, a cell a letter, with its
+/// top at pixel row `top`. What the font has no glyph for is left as a space.
+fn write_text(pixels: &mut [u8], width: u32, top: u32, text: &str) {
+    for (i, ch) in text.chars().enumerate() {
+        let Some(rows) = glyph(ch) else {
+            continue;
+        };
+        let left = i as u32 * CELL_WIDTH + (CELL_WIDTH - 3 * FONT_SCALE) / 2;
+        for (dy, bits) in rows.iter().enumerate() {
+            for dx in 0..3 {
+                if bits >> (2 - dx) & 1 == 0 {
+                    continue;
+                }
+                for py in 0..FONT_SCALE {
+                    for px in 0..FONT_SCALE {
+                        let x = left + dx * FONT_SCALE + px;
+                        let y = top + dy as u32 * FONT_SCALE + py;
+                        if x >= width {
+                            continue;
+                        }
+                        let at = (y as usize * width as usize + x as usize) * 3;
+                        if let Some(pixel) = pixels.get_mut(at..at + 3) {
+                            pixel.copy_from_slice(&[128, 128, 128]);
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// A 3 by 5 dot glyph, a row at a time with the left dot as the high bit. Letters are
+/// all capitals.
+fn glyph(ch: char) -> Option<[u8; 5]> {
+    Some(match ch.to_ascii_uppercase() {
+        'A' => [0b010, 0b101, 0b111, 0b101, 0b101],
+        'B' => [0b110, 0b101, 0b110, 0b101, 0b110],
+        'C' => [0b011, 0b100, 0b100, 0b100, 0b011],
+        'D' => [0b110, 0b101, 0b101, 0b101, 0b110],
+        'E' => [0b111, 0b100, 0b110, 0b100, 0b111],
+        'F' => [0b111, 0b100, 0b110, 0b100, 0b100],
+        'G' => [0b011, 0b100, 0b101, 0b101, 0b011],
+        'H' => [0b101, 0b101, 0b111, 0b101, 0b101],
+        'I' => [0b111, 0b010, 0b010, 0b010, 0b111],
+        'J' => [0b001, 0b001, 0b001, 0b101, 0b010],
+        'K' => [0b101, 0b101, 0b110, 0b101, 0b101],
+        'L' => [0b100, 0b100, 0b100, 0b100, 0b111],
+        'M' => [0b101, 0b111, 0b111, 0b101, 0b101],
+        'N' => [0b110, 0b101, 0b101, 0b101, 0b101],
+        'O' => [0b010, 0b101, 0b101, 0b101, 0b010],
+        'P' => [0b110, 0b101, 0b110, 0b100, 0b100],
+        'Q' => [0b010, 0b101, 0b101, 0b110, 0b011],
+        'R' => [0b110, 0b101, 0b110, 0b101, 0b101],
+        'S' => [0b011, 0b100, 0b010, 0b001, 0b110],
+        'T' => [0b111, 0b010, 0b010, 0b010, 0b010],
+        'U' => [0b101, 0b101, 0b101, 0b101, 0b111],
+        'V' => [0b101, 0b101, 0b101, 0b101, 0b010],
+        'W' => [0b101, 0b101, 0b111, 0b111, 0b101],
+        'X' => [0b101, 0b101, 0b010, 0b101, 0b101],
+        'Y' => [0b101, 0b101, 0b010, 0b010, 0b010],
+        'Z' => [0b111, 0b001, 0b010, 0b100, 0b111],
+        '0' => [0b111, 0b101, 0b101, 0b101, 0b111],
+        '1' => [0b010, 0b110, 0b010, 0b010, 0b111],
+        '2' => [0b110, 0b001, 0b010, 0b100, 0b111],
+        '3' => [0b110, 0b001, 0b010, 0b001, 0b110],
+        '4' => [0b101, 0b101, 0b111, 0b001, 0b001],
+        '5' => [0b111, 0b100, 0b110, 0b001, 0b110],
+        '6' => [0b011, 0b100, 0b111, 0b101, 0b111],
+        '7' => [0b111, 0b001, 0b010, 0b010, 0b010],
+        '8' => [0b111, 0b101, 0b111, 0b101, 0b111],
+        '9' => [0b111, 0b101, 0b111, 0b001, 0b110],
+        '.' => [0b000, 0b000, 0b000, 0b000, 0b010],
+        '·' => [0b000, 0b000, 0b010, 0b000, 0b000],
+        '-' => [0b000, 0b000, 0b111, 0b000, 0b000],
+        '+' => [0b000, 0b010, 0b111, 0b010, 0b000],
+        ':' => [0b000, 0b010, 0b000, 0b010, 0b000],
+        '/' => [0b001, 0b001, 0b010, 0b100, 0b100],
+        _ => return None,
+    })
+}
@@ -0,0 +1,202 @@
+//! Entry points for fuzzing whatever tflap reads from outside: the leaderboard file,
+//! challenge codes and files, the config file and levels. `fuzz/` runs them under cargo fuzz,
+//! which builds the game as a library with `--cfg fuzzing`; the tests here run them over
+//! mangled copies of good input, so they're exercised on every `cargo test` too.
+//!
+//! Each one panics only if a parser does, if what it read doesn't come back the same
+//! after being written out and read again, or if a game set up from a challenge it read
+//! does in its first few seconds.
+
+use crate::base64;
+use crate::challenge::{self, Challenge};
+use crate::challenge_file::ChallengeFile;
+use crate::config::Config;
+use crate::leaderboard::{self, Leaderboard};
+use crate::level::{Level, LoopingSpawner};
+use crate::paths::Paths;
+use crate::storage;
+use crate::Game;
+use std::env;
+
+/// A fixed "now" for the leaderboard's check on timestamps, so a run is repeatable.
+const NOW: u64 = 1_700_000_000;
+/// Ticks a game set up from a challenge is played for, flapping now and then.
+const TICKS: u32 = 60;
+const FLAP_EVERY: u32 = 7;
+
+/// A leaderboard file, header and all.
+pub fn leaderboard(data: &[u8]) {
+    let (_, body) = leaderboard::FORMAT.migrate(&String::from_utf8_lossy(data));
+    let mut board = Leaderboard::new(None);
+    board.read(&body, NOW);
+    let written = board.to_file();
+    let mut again = Leaderboard::new(None);
+    again.read(&written, NOW);
+    assert_eq!(again.to_file(), written);
+}
+
+/// A challenge code as typed, and the same bytes as the inside of one with a good
+/// checksum, which random bytes almost never have.
+pub fn challenge(data: &[u8]) {
+    if let Ok(code) = std::str::from_utf8(data) {
+        round_trip(code);
+    }
+    let mut bytes = data.to_vec();
+    bytes.push(challenge::checksum(data));
+    round_trip(&base64::encode_url(&bytes));
+}
+
+fn round_trip(code: &str) {
+    let Ok(challenge) = Challenge::decode(code) else {
+        return;
+    };
+    let code = challenge.encode();
+    let again = Challenge::decode(&code).expect("a re-encoded challenge decodes");
+    assert_eq!(again.encode(), code);
+    let mut config = Config::default();
+    again.apply(&mut config);
+    play(Game::with_challenge(config, again));
+}
+
+/// A challenge file, and a game on it.
+pub fn challenge_file(data: &[u8]) {
+    if let Ok(file) = ChallengeFile::parse(&String::from_utf8_lossy(data)) {
+        play(Game::with_event(Config::default(), file));
+    }
+}
+
+/// Start `game` from its title screen and fly it for a few seconds, with nothing read
+/// from or written to the real home directory.
+fn play(mut game: Game) {
+    Paths::set_data_dir(env::temp_dir().join("tflap-fuzz-empty"));
+    storage::disable_writes("saving is off while fuzzing".to_string());
+    game.start();
+    for tick in 0..TICKS {
+        if tick % FLAP_EVERY == 0 {
+            game.jump();
+        }
+        game.update();
+    }
+}
+
+/// A config file.
+pub fn config(data: &[u8]) {
+    let mut config = Config::default();
+    config.read(&String::from_utf8_lossy(data));
+    let written = config.to_file();
+    let mut again = Config::default();
+    again.read(&written);
+    assert_eq!(again.to_file(), written);
+}
+
+/// A level file, and a couple of laps of its pipes.
+pub fn level(data: &[u8]) {
+    let Ok(level) = Level::parse(&String::from_utf8_lossy(data)) else {
+        return;
+    };
+    let laps = level.pipes.len() * 2;
+    let mut spawner = LoopingSpawner::new(level);
+    for _ in 0..laps {
+        let spec = spawner.next_pipe();
+        assert!(spec.spacing > 0 && spec.gap > 0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::telemetry::Input;
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    const ROUNDS: usize = 3000;
+
+    /// `seed` with a few random bit flips, overwrites, insertions, cuts and repeats.
+    fn mangle(seed: &[u8], rng: &mut StdRng) -> Vec<u8> {
+        let mut data = seed.to_vec();
+        for _ in 0..rng.gen_range(1..6) {
+            let at = rng.gen_range(0..=data.len());
+            match rng.gen_range(0..5) {
+                0 if at < data.len() => data[at] ^= 1 << rng.gen_range(0..8),
+                1 if at < data.len() => data[at] = rng.gen(),
+                2 => data.insert(at, *b" 0-9#=\n\xff".get(rng.gen_range(0..8)).unwrap()),
+                3 => {
+                    let end = rng.gen_range(at..=data.len());
+                    data.drain(at..end);
+                }
+                _ => {
+                    let end = rng.gen_range(at..=data.len().min(at + 16));
+                    let chunk = data[at..end].to_vec();
+                    data.splice(at..at, chunk);
+                }
+            }
+        }
+        data
+    }
+
+    fn fuzz(seeds: &[Vec<u8>], target: fn(&[u8])) {
+        let mut rng = StdRng::seed_from_u64(7);
+        for seed in seeds {
+            target(seed);
+        }
+        for _ in 0..ROUNDS {
+            let seed = &seeds[rng.gen_range(0..seeds.len())];
+            target(&mangle(seed, &mut rng));
+        }
+        for _ in 0..ROUNDS / 10 {
+            let noise: Vec<u8> = (0..rng.gen_range(0..64)).map(|_| rng.gen()).collect();
+            target(&noise);
+        }
+    }
+
+    #[test]
+    fn leaderboard_files() {
+        let seeds = [
+            "# tflap leaderboard v1\nstyle/normal 12 1690000000 400\nclassic/hard/rain 3 0\n",
+            "style/easy 5 1690000000\nstyle/normal/double+ramp-linear 40 1690000000 900\n",
+            "# tflap leaderboard v9\nstyle/normal 99999 1690000000 1\n",
+        ];
+        fuzz(&seeds.map(|s| s.as_bytes().to_vec()), leaderboard);
+    }
+
+    #[test]
+    fn challenge_codes() {
+        let mut run = Challenge::new(42, 80, 24, &Config::default());
+        run.score = 7;
+        run.inputs = vec![
+            (3, Input::Flap),
+            (9, Input::Revive('k')),
+            (20, Input::Speed(80)),
+            (31, Input::Answer(4)),
+        ];
+        let code = run.encode();
+        let mut body = base64::decode_url(&code).unwrap();
+        body.pop();
+        fuzz(&[code.into_bytes(), body], challenge);
+    }
+
+    #[test]
+    fn challenge_files() {
+        let seeds = [
+            "seed = 7\n",
+            "name = Week 3\nseed = 99\nsize = 40x16\ndifficulty = easy\nlives = 3\nexpires = 2030-01-31\n",
+            "seed = 1\nsize = 200x60\nmutators = tinygap,double\nramp = ease-in\nweather = rain\n",
+        ];
+        fuzz(&seeds.map(|s| s.as_bytes().to_vec()), challenge_file);
+    }
+
+    #[test]
+    fn config_files() {
+        let seeds = [Config::default().to_file(), "lives = 3\nspeed = 1.25\nmilestones = 5,10\nmusic = /tmp/song\nkiosk_exit = ctrl+alt+q\n".to_string()];
+        fuzz(&seeds.map(String::into_bytes), config);
+    }
+
+    #[test]
+    fn levels() {
+        let seeds = [
+            "name Tight\npipe 20 0 6\npipe 18 -3 5 flip # comment\n",
+            "pipe 1 1000 1000\npipe 1000 -1000 1\n",
+        ];
+        fuzz(&seeds.map(|s| s.as_bytes().to_vec()), level);
+    }
+}
@@ -0,0 +1,104 @@
+//! Haptic-ish feedback: short bell and screen-flash patterns on flaps, points and near
+//! misses, for devices that turn the terminal bell into a buzz.
+//!
+//! Rhythm mode rings the bell on every beat as a metronome, on its own if haptics are
+//! off or along with the rest if they're on.
+//!
+//! Pulses go out at most one per `PULSE_GAP`, and a pattern that can't fit in the queue
+//! is dropped rather than saved up, so a burst of flaps never floods the terminal.
+
+use std::cell::{Cell, RefCell};
+use std::collections::VecDeque;
+use std::io::{self, Write};
+use std::time::{Duration, Instant};
+
+use crate::config::Config;
+use crate::events::GameEvent;
+
+/// Least time between two pulses.
+const PULSE_GAP: Duration = Duration::from_millis(100);
+/// Most pulses waiting to go out.
+const MAX_QUEUED: usize = 3;
+
+/// Turn reverse video on and off (DECSCNM), for the flash.
+const FLASH_ON: &str = "\x1b[?5h";
+pub const FLASH_OFF: &str = "\x1b[?5l";
+
+#[derive(Clone, Copy)]
+enum Pulse {
+    Bell,
+    /// Reverse the screen until the next frame.
+    Flash,
+}
+
+const FLAP: &[Pulse] = &[Pulse::Bell];
+const SCORE: &[Pulse] = &[Pulse::Bell, Pulse::Bell];
+const NEAR_MISS: &[Pulse] = &[Pulse::Flash, Pulse::Bell];
+const BEAT: &[Pulse] = &[Pulse::Bell];
+
+#[derive(Default)]
+pub struct Haptics {
+    queue: RefCell<VecDeque<Pulse>>,
+    last: Cell<Option<Instant>>,
+    flashing: Cell<bool>,
+    /// Only the metronome, for rhythm runs without haptics.
+    beats_only: bool,
+}
+
+impl Haptics {
+    /// Haptics as the config has them, or just the metronome for a rhythm run.
+    pub fn for_config(config: &Config) -> Option<Self> {
+        if config.haptics {
+            Some(Self::default())
+        } else {
+            config.rhythm.map(|_| Self {
+                beats_only: true,
+                ..Self::default()
+            })
+        }
+    }
+
+    pub fn on_event(&self, event: &GameEvent) {
+        let pattern = match event {
+            GameEvent::Beat => BEAT,
+            _ if self.beats_only => return,
+            GameEvent::Flapped => FLAP,
+            GameEvent::Scored { .. } => SCORE,
+            GameEvent::NearMiss => NEAR_MISS,
+            _ => return,
+        };
+        let mut queue = self.queue.borrow_mut();
+        if queue.len() + pattern.len() <= MAX_QUEUED {
+            queue.extend(pattern);
+        }
+    }
+
+    /// Send the next pulse if it is due, and end the flash a frame after it began.
+    pub fn play(&self, stdout: &mut impl Write) -> io::Result<()> {
+        let mut wrote = self.flashing.replace(false);
+        if wrote {
+            write!(stdout, "{}", FLASH_OFF)?;
+        }
+        let due = !matches!(self.last.get(), Some(last) if last.elapsed() < PULSE_GAP);
+        let pulse = if due {
+            self.queue.borrow_mut().pop_front()
+        } else {
+            None
+        };
+        if let Some(pulse) = pulse {
+            match pulse {
+                Pulse::Bell => write!(stdout, "\x07")?,
+                Pulse::Flash => {
+                    write!(stdout, "{}", FLASH_ON)?;
+                    self.flashing.set(true);
+                }
+            }
+            self.last.set(Some(Instant::now()));
+            wrote = true;
+        }
+        if wrote {
+            stdout.flush()?;
+        }
+        Ok(())
+    }
+}
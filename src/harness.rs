@@ -0,0 +1,455 @@
+//! End-to-end tests: the whole game loop, from key events to the frames it draws, driven
+//! by a script of keys on a simulated clock, so a run takes no real time and plays out
+//! the same on every machine.
+
+use crate::bot::Tier;
+use crate::challenge::{self, Challenge};
+use crate::challenge_file::ChallengeFile;
+use crate::clock::{Clock, SimClock};
+use crate::difficulty::Difficulty;
+use crate::events::GameEvent;
+use crate::input::InputSource;
+use crate::kiosk::{self, Kiosk};
+use crate::leaderboard::Leaderboard;
+use crate::marathon::Marathon;
+use crate::pacer::Pacer;
+use crate::paths::Paths;
+use crate::render::Frame;
+use crate::scoring::ScoringMode;
+use crate::storage;
+use crate::wager::Wager;
+use crate::{Config, Flow, Game, GameLoop, GameState, COMBO_MAX, QUIT_PROMPT_MIN_SCORE, TICK_RATE};
+use crossterm::event::{Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use std::collections::VecDeque;
+use std::env;
+use std::fs;
+use std::io;
+use std::rc::Rc;
+use std::time::Duration;
+
+const WIDTH: u16 = 80;
+const HEIGHT: u16 = 24;
+const SEED: u64 = 42;
+
+/// Key events to hand the loop, each at the tick it's due.
+#[derive(Default)]
+struct Script {
+    events: VecDeque<(u32, Event)>,
+    tick: u32,
+}
+
+impl InputSource for Script {
+    fn poll(&mut self) -> io::Result<Option<Event>> {
+        match self.events.front() {
+            Some(&(due, _)) if due <= self.tick => Ok(self.events.pop_front().map(|(_, e)| e)),
+            _ => Ok(None),
+        }
+    }
+}
+
+/// A game on the title screen, played a tick at a time.
+struct Harness {
+    game: Game,
+    game_loop: GameLoop,
+    script: Script,
+    clock: Rc<SimClock>,
+    pacer: Pacer,
+    tick: u32,
+    quit: bool,
+}
+
+impl Harness {
+    fn new(config: Config) -> Self {
+        isolate();
+        let clock = Rc::new(SimClock::new());
+        let mut game = Game::with_seed(WIDTH, HEIGHT, config, SEED, false);
+        game.state = GameState::Title;
+        game.clock = clock.clone();
+        if game.config.kiosk {
+            game.kiosk = Some(Kiosk::new(game.config.kiosk_exit, clock.now()));
+        }
+        let game_loop = GameLoop::new(&game, false);
+        Self {
+            game,
+            game_loop,
+            script: Script::default(),
+            pacer: Pacer::new(clock.now()),
+            clock,
+            tick: 0,
+            quit: false,
+        }
+    }
+
+    /// Press `code` at `tick`, counted from the start.
+    fn press(mut self, tick: u32, code: KeyCode) -> Self {
+        let event = Event::Key(KeyEvent::new_with_kind(
+            code,
+            KeyModifiers::NONE,
+            KeyEventKind::Press,
+        ));
+        let at = self.script.events.partition_point(|&(due, _)| due <= tick);
+        self.script.events.insert(at, (tick, event));
+        self
+    }
+
+    /// A flap every few ticks for the first couple of seconds, to keep the bird up a while.
+    fn flapping(self) -> Self {
+        (1..40).step_by(7).fold(self, |harness, tick| {
+            harness.press(tick, KeyCode::Char(' '))
+        })
+    }
+
+    /// One go round the loop at the next tick's time: its input, then the tick.
+    fn step(&mut self) {
+        self.tick += 1;
+        self.script.tick = self.tick;
+        self.clock.sleep(TICK_RATE);
+        let now = self.clock.now();
+        let flow = self
+            .game_loop
+            .handle_input(&mut self.game, &mut self.script, now)
+            .unwrap();
+        if flow == Flow::Quit || self.game.quit_requested {
+            self.quit = true;
+            return;
+        }
+        self.game_loop.advance(&mut self.game, &mut self.pacer, now);
+    }
+
+    fn run(&mut self, ticks: u32) {
+        for _ in 0..ticks {
+            if self.quit {
+                return;
+            }
+            self.step();
+        }
+    }
+
+    /// Run until `done` holds, for at most `limit` ticks, returning the tick it held at.
+    fn run_until(&mut self, limit: u32, done: impl Fn(&Game) -> bool) -> Option<u32> {
+        for _ in 0..limit {
+            self.step();
+            if done(&self.game) {
+                return Some(self.tick);
+            }
+        }
+        None
+    }
+
+    /// What's on the screen, as text.
+    fn screen(&self) -> String {
+        let mut frame = Frame::new(WIDTH, HEIGHT);
+        self.game.render(&mut frame);
+        frame.to_text()
+    }
+}
+
+/// Nothing read from the real home directory, and nothing written anywhere.
+fn isolate() {
+    Paths::set_data_dir(env::temp_dir().join("tflap-harness-empty"));
+    storage::disable_writes("saving is off in tests".to_string());
+}
+
+/// A few ticks of a game started from the title screen, which is where any trouble with
+/// its board shows.
+fn play_briefly(mut game: Game) {
+    game.start();
+    for _ in 0..100 {
+        game.update();
+    }
+}
+
+fn is_over(game: &Game) -> bool {
+    game.state == GameState::GameOver
+}
+
+#[test]
+fn space_on_the_title_screen_starts_a_run() {
+    let mut harness = Harness::new(Config::default()).press(1, KeyCode::Char(' '));
+    assert!(harness.screen().contains("Practice"));
+    harness.run(1);
+    assert!(harness.game.state == GameState::Playing);
+    assert!(!harness.screen().contains("Practice"));
+}
+
+#[test]
+fn a_bird_left_alone_crashes_and_the_game_over_box_appears() {
+    let mut harness = Harness::new(Config::default()).press(1, KeyCode::Char(' '));
+    let crashed = harness.run_until(500, is_over).expect("never crashed");
+    assert_eq!(crashed, 14);
+    let screen = harness.screen();
+    assert!(screen.contains("GAME OVER!"), "{}", screen);
+    assert!(screen.contains("Score:     0"), "{}", screen);
+}
+
+#[test]
+fn the_same_keys_crash_on_the_same_tick() {
+    let mut first = Harness::new(Config::default()).flapping();
+    let mut second = Harness::new(Config::default()).flapping();
+    let crashed = first.run_until(2000, is_over);
+    assert!(crashed.is_some());
+    assert_eq!(crashed, second.run_until(2000, is_over));
+    assert_eq!(first.screen(), second.screen());
+}
+
+#[test]
+fn a_paused_run_stands_still() {
+    let mut harness = Harness::new(Config::default())
+        .press(1, KeyCode::Char(' '))
+        .press(5, KeyCode::Char('p'));
+    harness.run(5);
+    assert!(harness.game.state == GameState::Paused);
+    let tick = harness.game.tick;
+    let screen = harness.screen();
+    assert!(screen.contains("PAUSED"), "{}", screen);
+    harness.run(50);
+    assert_eq!(harness.game.tick, tick);
+}
+
+#[test]
+fn quitting_a_run_with_nothing_to_lose_is_immediate() {
+    let mut harness = Harness::new(Config::default())
+        .press(1, KeyCode::Char(' '))
+        .press(3, KeyCode::Char('q'));
+    harness.run(10);
+    assert!(harness.quit);
+    assert_eq!(harness.tick, 3);
+}
+
+#[test]
+fn quitting_a_run_worth_keeping_asks_first() {
+    let mut harness = Harness::new(Config::default())
+        .press(1, KeyCode::Char(' '))
+        .press(3, KeyCode::Char('q'))
+        .press(6, KeyCode::Char('y'));
+    harness.run(2);
+    harness.game.score = QUIT_PROMPT_MIN_SCORE;
+    harness.run(1);
+    assert!(harness.game.state == GameState::ConfirmQuit);
+    assert!(harness.screen().contains("Quit?"));
+    harness.run(10);
+    assert!(harness.quit);
+    assert_eq!(harness.tick, 6);
+}
+
+#[test]
+fn a_flap_just_after_a_crash_does_not_start_the_next_run() {
+    let mut harness = Harness::new(Config::default()).press(1, KeyCode::Char(' '));
+    let crashed = harness.run_until(500, is_over).unwrap();
+    // Too soon after the crash to have been meant for the next run, then well after
+    let mut harness = harness
+        .press(crashed + 1, KeyCode::Char(' '))
+        .press(crashed + 40, KeyCode::Char(' '));
+    harness.run(2);
+    assert!(is_over(&harness.game));
+    harness.run(40);
+    assert!(harness.game.state == GameState::Playing);
+}
+
+#[test]
+fn taking_the_bet_after_hitting_the_ground_carries_on() {
+    let config = Config {
+        double_or_nothing: true,
+        ..Config::default()
+    };
+    let mut harness = Harness::new(config).press(1, KeyCode::Char(' '));
+    harness.run(1);
+    harness.game.score = 3;
+    let offered = harness
+        .run_until(500, |game| game.state == GameState::Wager)
+        .expect("never offered the bet");
+    let mut harness = harness.press(offered + 1, KeyCode::Char('w'));
+    harness.run(1);
+    assert!(harness.game.state == GameState::Playing);
+    harness.run(20);
+    assert!(harness.game.state == GameState::Playing);
+}
+
+#[test]
+fn a_won_bet_stays_on_the_leaderboard_when_it_is_read_back() {
+    isolate();
+    let config = Config {
+        double_or_nothing: true,
+        difficulty: Difficulty::Hard,
+        scoring: ScoringMode::Classic,
+        ..Config::default()
+    };
+    let mut game = Game::with_seed(WIDTH, HEIGHT, config, SEED, true);
+    game.pilot = Some(Tier::Hard.strategy());
+    game.start();
+    let fly_until = |game: &mut Game, done: &dyn Fn(&Game) -> bool| {
+        for _ in 0..5000 {
+            if done(game) {
+                return;
+            }
+            game.autopilot();
+            game.update();
+        }
+        panic!("never got there");
+    };
+    // Stake what the bot has scored, and end the run once the bet is won
+    fly_until(&mut game, &|game| game.score >= 20);
+    game.game_over();
+    assert!(game.state == GameState::Wager);
+    game.answer_wager(true);
+    fly_until(&mut game, &|game| {
+        matches!(game.wager, Some(Wager::Won { .. }))
+    });
+    game.game_over();
+    assert!(is_over(&game));
+
+    let score = game.score;
+    assert!(score > 40, "{}", score);
+    let mut board = Leaderboard::new(None);
+    board.read(&game.leaderboard.to_file(), storage::unix_now());
+    assert_eq!(board.set_aside(), 0);
+    assert_eq!(board.best(&game.category), score);
+}
+
+#[test]
+fn a_marathon_over_several_sessions_stays_on_its_own_leaderboard() {
+    isolate();
+    let config = Config {
+        marathon: true,
+        scoring: ScoringMode::Classic,
+        ..Config::default()
+    };
+    let mut game = Game::with_seed(WIDTH, HEIGHT, config, SEED, true);
+    // Carrying on from two sessions' worth of segments
+    let mut marathon = Marathon::default();
+    marathon.segments = 2;
+    marathon.carried = 200;
+    marathon.carried_ticks = 9000;
+    game.marathon = Some(marathon);
+    game.start();
+    game.score = 200;
+    while !is_over(&game) {
+        game.update();
+    }
+    assert!(game.category.key().ends_with("/marathon"));
+
+    let mut board = Leaderboard::new(None);
+    board.read(&game.leaderboard.to_file(), storage::unix_now());
+    assert_eq!(board.set_aside(), 0);
+    assert_eq!(board.best(&game.category), 200);
+}
+
+#[test]
+fn a_revive_after_hitting_the_ground_carries_on() {
+    let mut harness = Harness::new(Config::default()).press(1, KeyCode::Char(' '));
+    harness.run(1);
+    harness.game.streak = COMBO_MAX;
+    let crashed = harness
+        .run_until(500, |game| game.state == GameState::Revive)
+        .expect("never offered a revive");
+    let key = harness.game.reaction.as_ref().unwrap().key;
+    let mut harness = harness.press(crashed + 1, KeyCode::Char(key));
+    harness.run(1);
+    assert!(harness.game.state == GameState::Playing);
+    harness.run(20);
+    assert!(harness.game.state == GameState::Playing);
+}
+
+#[test]
+fn the_exit_line_has_the_last_run_after_another_is_started() {
+    let mut harness = Harness::new(Config::default()).press(1, KeyCode::Char(' '));
+    harness.run(1);
+    harness.game.score = 4;
+    let crashed = harness.run_until(500, is_over).unwrap();
+    let seed = harness.game.seed;
+    let mut harness = harness.press(crashed + 40, KeyCode::Char(' '));
+    harness.run(41);
+    assert!(harness.game.state == GameState::Playing);
+    assert_eq!(harness.game.score, 0);
+    let line = harness.game.exit_summary(Duration::from_secs(75));
+    assert!(
+        line.starts_with(&format!("tflap: score 4 best 4 time 1:15 seed {}", seed)),
+        "{}",
+        line
+    );
+}
+
+#[test]
+fn a_kiosk_hands_over_to_the_next_player_after_the_countdown() {
+    let config = Config {
+        kiosk: true,
+        ..Config::default()
+    };
+    let mut harness = Harness::new(config).press(1, KeyCode::Char(' '));
+    let crashed = harness.run_until(500, is_over).unwrap();
+    assert!(harness.screen().contains("Next player in 10s"));
+    let title = harness
+        .run_until(1000, |game| game.state == GameState::Title)
+        .unwrap();
+    assert_eq!(
+        title - crashed,
+        kiosk::RESTART.as_millis() as u32 / TICK_RATE.as_millis() as u32
+    );
+}
+
+#[test]
+fn the_ticker_scrolls_events_in_one_at_a_time() {
+    let mut harness = Harness::new(Config::default()).flapping();
+    let top = |harness: &Harness| {
+        harness
+            .screen()
+            .lines()
+            .next()
+            .unwrap_or_default()
+            .to_string()
+    };
+    harness.run(1);
+    harness.game.events.emit(GameEvent::Scored { score: 10 });
+    harness.game.events.emit(GameEvent::GhostDown { score: 3 });
+    harness.run(1);
+    assert!(!top(&harness).contains("10 points!"));
+    harness.run(5);
+    assert!(top(&harness).contains("10 points!"));
+    assert!(!top(&harness).contains("Ghost"));
+    harness.run(20);
+    assert!(
+        top(&harness).contains("10 points! · Ghost down at 3"),
+        "{}",
+        top(&harness)
+    );
+}
+
+#[test]
+fn a_challenge_code_for_a_board_too_short_for_its_gaps_is_turned_down() {
+    isolate();
+    let short = Challenge::new(42, 80, 10, &Config::default()).encode();
+    assert!(Challenge::decode(&short).is_err());
+
+    let config = Config {
+        difficulty: Difficulty::Easy,
+        ..Config::default()
+    };
+    let (width, height) = challenge::MIN_BOARD;
+    let smallest = Challenge::new(42, width, height, &config).encode();
+    let challenge = Challenge::decode(&smallest).expect("the smallest board decodes");
+    play_briefly(Game::with_challenge(config, challenge));
+}
+
+#[test]
+fn a_challenge_file_for_a_board_too_short_for_its_gaps_is_turned_down() {
+    isolate();
+    let dir = env::temp_dir().join(format!("tflap-harness-files-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let short = dir.join("short.tflap");
+    fs::write(&short, "seed = 1\nsize = 80x12\n").unwrap();
+    assert!(ChallengeFile::load(&short, None).is_err());
+
+    let (width, height) = challenge::MIN_BOARD;
+    let smallest = dir.join("smallest.tflap");
+    let content = format!("seed = 1\nsize = {}x{}\ndifficulty = easy\n", width, height);
+    fs::write(&smallest, content).unwrap();
+    let event = ChallengeFile::load(&smallest, None).expect("the smallest board loads");
+    let _ = fs::remove_dir_all(&dir);
+    play_briefly(Game::with_event(Config::default(), event));
+}
+
+#[test]
+fn a_board_too_short_for_its_gaps_still_plays() {
+    isolate();
+    play_briefly(Game::with_seed(80, 10, Config::default(), SEED, false));
+}
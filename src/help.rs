@@ -0,0 +1,87 @@
+//! The help overlay's text, assembled from the keymap, the mode list, and the scoring
+//! rules so it always matches what the game does.
+
+use crate::keymap::{Action, Keymap};
+use crate::modes::MODES;
+use crate::mutators::Mutator;
+use crate::scoring;
+
+/// Lay out `text` after `lead` within `width` columns, lining wrapped lines up under
+/// the start of the text.
+fn entry(lead: &str, text: &str, width: usize) -> Vec<String> {
+    let indent = lead.chars().count();
+    let limit = width.saturating_sub(indent).max(1);
+    let mut lines = Vec::new();
+    let mut line = String::new();
+    for word in text.split_whitespace() {
+        if !line.is_empty() && line.chars().count() + 1 + word.chars().count() > limit {
+            lines.push(line);
+            line = String::new();
+        }
+        if !line.is_empty() {
+            line.push(' ');
+        }
+        line.push_str(word);
+    }
+    lines.push(line);
+    lines
+        .into_iter()
+        .enumerate()
+        .map(|(i, l)| {
+            let lead = if i == 0 {
+                lead.to_string()
+            } else {
+                " ".repeat(indent)
+            };
+            lead + &l
+        })
+        .collect()
+}
+
+fn capitalize(s: &str) -> String {
+    let mut chars = s.chars();
+    chars
+        .next()
+        .map_or_else(String::new, |c| c.to_uppercase().chain(chars).collect())
+}
+
+/// Help text for `width` columns. `replay_controls` lists the replay viewer's keys.
+pub fn lines(keymap: &Keymap, replay_controls: &[(&str, &str)], width: usize) -> Vec<String> {
+    let mut lines = vec!["KEYS".to_string()];
+    for action in Action::ALL {
+        let keys = keymap.keys(action);
+        if keys.is_empty() {
+            continue;
+        }
+        let lead = format!("{:<10} ", keys.join(" "));
+        lines.extend(entry(&lead, action.about(), width));
+    }
+
+    lines.push(String::new());
+    lines.push("REPLAY".to_string());
+    for (keys, about) in replay_controls {
+        lines.extend(entry(&format!("{:<10} ", keys), &capitalize(about), width));
+    }
+
+    lines.push(String::new());
+    lines.push("MODES".to_string());
+    for mode in MODES {
+        lines.push(format!("{} ({})", mode.name, mode.flag));
+        lines.extend(entry("  ", mode.about, width));
+    }
+
+    lines.push(String::new());
+    lines.push("MUTATORS (--mutators <LIST>)".to_string());
+    for mutator in Mutator::ALL {
+        let lead = format!("{:<10} ", mutator.key());
+        let text = format!("{} Points {}.", mutator.about(), mutator.multiplier_label());
+        lines.extend(entry(&lead, &text, width));
+    }
+
+    lines.push(String::new());
+    lines.push("SCORING".to_string());
+    for rule in scoring::rules() {
+        lines.extend(entry("", &rule, width));
+    }
+    lines
+}
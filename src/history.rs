@@ -0,0 +1,179 @@
+use crate::leaderboard::Category;
+use crate::players;
+use crate::scoring::ScoringMode;
+use crate::storage;
+
+/// The history file's versions. Version 1 filled in the outcome and category columns
+/// older lines went without.
+const FORMAT: storage::Format = storage::Format {
+    name: "history",
+    migrations: &[fill_columns],
+};
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Outcome {
+    Died,
+    /// The player quit before the run was over.
+    Abandoned,
+}
+
+impl Outcome {
+    pub fn key(self) -> &'static str {
+        match self {
+            Outcome::Died => "died",
+            Outcome::Abandoned => "abandoned",
+        }
+    }
+
+    fn parse(s: &str) -> Option<Self> {
+        match s {
+            "died" => Some(Outcome::Died),
+            "abandoned" => Some(Outcome::Abandoned),
+            _ => None,
+        }
+    }
+}
+
+/// One finished run as stored in the history file.
+pub struct RunRecord {
+    /// Seconds since the Unix epoch when the run ended.
+    pub timestamp: u64,
+    pub score: u32,
+    pub ticks: u32,
+    pub scoring: ScoringMode,
+    pub outcome: Outcome,
+    /// Leaderboard category key the run counted towards.
+    pub category: String,
+}
+
+impl RunRecord {
+    pub fn new(
+        score: u32,
+        ticks: u32,
+        scoring: ScoringMode,
+        outcome: Outcome,
+        category: &Category,
+    ) -> Self {
+        Self {
+            timestamp: storage::unix_now(),
+            score,
+            ticks,
+            scoring,
+            outcome,
+            category: category.key().to_string(),
+        }
+    }
+
+    fn to_line(&self) -> String {
+        format!(
+            "{} {} {} {} {} {}",
+            self.timestamp,
+            self.score,
+            self.ticks,
+            self.scoring.key(),
+            self.outcome.key(),
+            self.category
+        )
+    }
+
+    fn parse(line: &str) -> Option<Self> {
+        let mut fields = line.split_whitespace();
+        let timestamp = fields.next()?.parse().ok()?;
+        let score = fields.next()?.parse().ok()?;
+        let ticks = fields.next()?.parse().ok()?;
+        let scoring = ScoringMode::parse(fields.next()?)?;
+        let outcome = Outcome::parse(fields.next()?)?;
+        let category = fields.next()?.to_string();
+        Some(Self {
+            timestamp,
+            score,
+            ticks,
+            scoring,
+            outcome,
+            category,
+        })
+    }
+}
+
+/// Version 0 to 1: give every line an outcome and a category. Lines that don't parse are
+/// kept as they are.
+fn fill_columns(body: &str) -> String {
+    body.lines()
+        .map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let Some(scoring) = fields.get(3).and_then(|s| ScoringMode::parse(s)) else {
+                return format!("{}\n", line);
+            };
+            let mut filled = fields.clone();
+            // Older files have no outcome column; every run in them ended in a crash...
+            if filled.len() == 4 {
+                filled.push(Outcome::Died.key());
+            }
+            // ...and no category column; they were all played on the default settings
+            let category = format!("{}/normal", scoring.key());
+            if filled.len() == 5 {
+                filled.push(&category);
+            }
+            format!("{}\n", filled.join(" "))
+        })
+        .collect()
+}
+
+/// All of `player`'s recorded runs, oldest first. Lines that fail to parse are skipped.
+pub fn load_history(player: Option<&str>) -> Vec<RunRecord> {
+    players::home_file("history", player)
+        .and_then(|path| storage::read_versioned(&path, &FORMAT))
+        .map(|loaded| loaded.body.lines().filter_map(RunRecord::parse).collect())
+        .unwrap_or_default()
+}
+
+pub fn append_run(record: &RunRecord, player: Option<&str>) {
+    if let Some(path) = players::home_file("history", player) {
+        let _ = storage::append_versioned(&path, &FORMAT, &record.to_line());
+    }
+}
+
+/// Scores of the most recent `count` runs in a category, oldest first.
+pub fn recent_scores(history: &[RunRecord], category: &Category, count: usize) -> Vec<u32> {
+    let mut scores: Vec<u32> = history
+        .iter()
+        .rev()
+        .filter(|run| run.category == category.key())
+        .take(count)
+        .map(|run| run.score)
+        .collect();
+    scores.reverse();
+    scores
+}
+
+/// Length of the rolling "this week" window, in seconds.
+pub const WEEK: u64 = 7 * 24 * 60 * 60;
+/// Length of the rolling "this month" window, in seconds.
+pub const MONTH: u64 = 30 * 24 * 60 * 60;
+
+/// Best score in a category among runs that ended within the last `window` seconds.
+pub fn best_within(history: &[RunRecord], category: &Category, window: u64, now: u64) -> u32 {
+    history
+        .iter()
+        .filter(|run| run.category == category.key() && run.timestamp + window > now)
+        .map(|run| run.score)
+        .max()
+        .unwrap_or(0)
+}
+
+/// Arrow comparing the average of the newer half of `scores` to the older half.
+pub fn trend_arrow(scores: &[u32]) -> char {
+    if scores.len() < 2 {
+        return '→';
+    }
+    let (older, newer) = scores.split_at(scores.len() / 2);
+    let mean = |s: &[u32]| s.iter().map(|&v| v as f32).sum::<f32>() / s.len() as f32;
+    let delta = mean(newer) - mean(older);
+    if delta > 0.5 {
+        '↑'
+    } else if delta < -0.5 {
+        '↓'
+    } else {
+        '→'
+    }
+}
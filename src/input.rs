@@ -0,0 +1,205 @@
+//! Timing for flap presses: keeping a held key from flapping over and over, and for
+//! one-switch play, where how long the key is held picks the flap size. Also whether a
+//! key is still held, for the rewind. And where the events come from in the first place.
+
+use crossterm::event::{self, Event};
+use std::io;
+use std::time::{Duration, Instant};
+
+/// Holding the switch at least this long turns a small flap into a big one.
+const LONG_PRESS: Duration = Duration::from_millis(250);
+/// Without release events, auto-repeat presses arrive closer together than this.
+const REPEAT_GAP: Duration = Duration::from_millis(80);
+/// Flap presses this soon after a crash were meant for the run that just ended.
+const CRASH_GRACE: Duration = Duration::from_millis(300);
+/// A flap pressed this soon before a run starts is its first flap.
+const FLAP_BUFFER: Duration = Duration::from_millis(150);
+/// Without release events, a fresh press counts as held this long, to bridge the wait
+/// before auto-repeat starts...
+const HOLD_START: Duration = Duration::from_millis(500);
+/// ...and each auto-repeat this long more.
+const HOLD_REPEAT: Duration = Duration::from_millis(100);
+
+/// Lets a press flap only if it is a fresh press, not the terminal's auto-repeat, and
+/// the last flap was at least the cooldown ago.
+pub struct FlapGate {
+    cooldown: Duration,
+    last_press: Option<Instant>,
+    last_flap: Option<Instant>,
+}
+
+impl FlapGate {
+    pub fn new(cooldown: Duration) -> Self {
+        Self {
+            cooldown,
+            last_press: None,
+            last_flap: None,
+        }
+    }
+
+    /// Whether a press at `now` flaps. `repeat` is set when the terminal reports the press
+    /// is an auto-repeat; others find auto-repeats by how closely they follow each other.
+    pub fn press(&mut self, now: Instant, repeat: bool) -> bool {
+        let previous = self.last_press.replace(now);
+        let auto_repeat = repeat || previous.is_some_and(|at| now.duration_since(at) < REPEAT_GAP);
+        let cooling = self
+            .last_flap
+            .is_some_and(|at| now.duration_since(at) < self.cooldown);
+        if auto_repeat || cooling {
+            return false;
+        }
+        self.last_flap = Some(now);
+        true
+    }
+}
+
+/// Flap presses around a restart. Those just after a crash are ignored rather than
+/// restarting, so a player still mashing the flap key doesn't throw the next run away,
+/// and the latest is kept in case a restart follows straight after.
+#[derive(Default)]
+pub struct RestartGate {
+    crashed_at: Option<Instant>,
+    flap_at: Option<Instant>,
+}
+
+impl RestartGate {
+    pub fn crashed(&mut self, now: Instant) {
+        self.crashed_at = Some(now);
+        self.flap_at = None;
+    }
+
+    /// Whether a flap press at `now` on the game-over screen may restart.
+    pub fn flap(&mut self, now: Instant) -> bool {
+        self.flap_at = Some(now);
+        match self.crashed_at {
+            Some(at) => now.duration_since(at) >= CRASH_GRACE,
+            None => true,
+        }
+    }
+
+    /// Whether a run starting at `now` another way begins with a flap pressed just before.
+    pub fn take_flap(&mut self, now: Instant) -> bool {
+        self.flap_at
+            .take()
+            .is_some_and(|at| now.duration_since(at) < FLAP_BUFFER)
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Tap {
+    /// The switch went down.
+    Short,
+    /// The switch has been held down long enough to count as a long press.
+    Long,
+}
+
+/// Measures how long the switch is held. Terminals that report key releases give
+/// exact timings; elsewhere a hold is spotted by the burst of auto-repeat presses.
+pub struct SwitchInput {
+    release_events: bool,
+    /// When the current press started, while the switch is down.
+    held_since: Option<Instant>,
+    last_press: Option<Instant>,
+    long_sent: bool,
+}
+
+impl SwitchInput {
+    pub fn new(release_events: bool) -> Self {
+        Self {
+            release_events,
+            held_since: None,
+            last_press: None,
+            long_sent: false,
+        }
+    }
+
+    /// A press of the switch, including auto-repeats.
+    pub fn press(&mut self, now: Instant, repeat: bool) -> Option<Tap> {
+        let previous = self.last_press.replace(now);
+        let auto_repeat = if self.release_events {
+            repeat || self.held_since.is_some()
+        } else {
+            previous.is_some_and(|at| now.duration_since(at) < REPEAT_GAP)
+        };
+
+        if !auto_repeat {
+            self.held_since = Some(now);
+            self.long_sent = false;
+            return Some(Tap::Short);
+        }
+        if !self.release_events && !self.long_sent {
+            self.long_sent = true;
+            return Some(Tap::Long);
+        }
+        None
+    }
+
+    pub fn release(&mut self) {
+        self.held_since = None;
+    }
+
+    /// Report a long press once the switch has been held past the threshold.
+    pub fn poll(&mut self, now: Instant) -> Option<Tap> {
+        let held_since = self.held_since?;
+        if self.release_events && !self.long_sent && now.duration_since(held_since) >= LONG_PRESS {
+            self.long_sent = true;
+            return Some(Tap::Long);
+        }
+        None
+    }
+}
+
+/// Where the game loop's events come from: the terminal, or a script in tests.
+pub trait InputSource {
+    /// The next event waiting, or None if there isn't one yet. Never blocks.
+    fn poll(&mut self) -> io::Result<Option<Event>>;
+}
+
+pub struct Terminal;
+
+impl InputSource for Terminal {
+    fn poll(&mut self) -> io::Result<Option<Event>> {
+        if event::poll(Duration::ZERO)? {
+            event::read().map(Some)
+        } else {
+            Ok(None)
+        }
+    }
+}
+
+/// Whether a key is down, for actions that last as long as it's held. Terminals that
+/// report releases say exactly; elsewhere the key counts as down for a while after each
+/// press, so a quick tap holds it for `HOLD_START`.
+pub struct Hold {
+    release_events: bool,
+    down_until: Option<Instant>,
+    last_press: Option<Instant>,
+}
+
+impl Hold {
+    pub fn new(release_events: bool) -> Self {
+        Self {
+            release_events,
+            down_until: None,
+            last_press: None,
+        }
+    }
+
+    /// A press of the key, including auto-repeats.
+    pub fn press(&mut self, now: Instant, repeat: bool) {
+        let previous = self.last_press.replace(now);
+        let auto_repeat = repeat || previous.is_some_and(|at| now.duration_since(at) < REPEAT_GAP);
+        let hold = if auto_repeat { HOLD_REPEAT } else { HOLD_START };
+        self.down_until = Some(now + hold);
+    }
+
+    pub fn release(&mut self) {
+        self.down_until = None;
+    }
+
+    pub fn is_held(&self, now: Instant) -> bool {
+        // With releases to go by, a press holds until one comes
+        self.down_until
+            .is_some_and(|until| self.release_events || now < until)
+    }
+}
@@ -0,0 +1,404 @@
+//! Key bindings. Every key the game reacts to goes through a `Keymap`, built from one of
+//! a few presets. The left-hand presets put everything on the left half of the keyboard,
+//! with movement on the WASD cluster; the Dvorak and Colemak variants use the same
+//! physical keys as the QWERTY one, so muscle memory carries over between layouts.
+
+use crossterm::event::{KeyCode, KeyModifiers};
+
+/// Keys the revive prompt picks from, as the game sees them. Presets may ask for other
+/// keys, which are translated back so recorded runs replay the same under any keymap.
+pub const REVIVE_KEYS: [char; 7] = ['A', 'S', 'D', 'F', 'J', 'K', 'L'];
+
+/// Revive keys on the left hand, written as QWERTY positions.
+const LEFT_REVIVE_KEYS: [char; 7] = ['a', 's', 'd', 'f', 'g', 'r', 't'];
+
+/// Letter rows of each layout, in the same physical order.
+const QWERTY: &str = "qwertyuiopasdfghjkl;zxcvbnm,./";
+const DVORAK: &str = "',.pyfgcrlaoeuidhtns;qjkxbmwvz";
+const COLEMAK: &str = "qwfpgjluy;arstdhneiozxcvbkm,./";
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Action {
+    Flap,
+    Pause,
+    /// Keep the score safe while the bank is open, in banking mode.
+    Bank,
+    /// Take double or nothing when it's offered.
+    Wager,
+    /// Run the game back while held, in rewind mode.
+    Rewind,
+    SpeedDown,
+    SpeedUp,
+    Retry,
+    RetrySameSeed,
+    /// Give up on the run and start another straight away.
+    QuickRestart,
+    Share,
+    Replay,
+    /// Put the run away in a file and exit, to carry on with `tflap attach`.
+    Detach,
+    /// Swap the screen for a harmless-looking one, and back.
+    Boss,
+    Yes,
+    No,
+    Quit,
+    Up,
+    Down,
+    Left,
+    Right,
+    Select,
+    /// Leave a menu, decline the quit prompt, or quit when neither is showing.
+    Back,
+    Help,
+}
+
+impl Action {
+    pub const ALL: [Action; 24] = [
+        Action::Flap,
+        Action::Pause,
+        Action::Bank,
+        Action::Wager,
+        Action::Rewind,
+        Action::SpeedDown,
+        Action::SpeedUp,
+        Action::Retry,
+        Action::RetrySameSeed,
+        Action::QuickRestart,
+        Action::Share,
+        Action::Replay,
+        Action::Detach,
+        Action::Boss,
+        Action::Quit,
+        Action::Yes,
+        Action::No,
+        Action::Up,
+        Action::Down,
+        Action::Left,
+        Action::Right,
+        Action::Select,
+        Action::Back,
+        Action::Help,
+    ];
+
+    /// What the action does, for the help screen.
+    pub fn about(self) -> &'static str {
+        match self {
+            Action::Flap => "Flap (and start a run from the title or game-over screen)",
+            Action::Pause => "Pause",
+            Action::Bank => "Bank the score while the bank is open (banking mode)",
+            Action::Wager => "Go double or nothing when it's offered (any other key stops)",
+            Action::Rewind => "Hold to rewind (rewind mode)",
+            Action::SpeedDown => "Slow down (while an assist is on)",
+            Action::SpeedUp => "Speed up (while an assist is on)",
+            Action::Retry => "Retry with new pipes",
+            Action::RetrySameSeed => "Retry on the same pipes",
+            Action::QuickRestart => "Give up mid-run and start again on new pipes",
+            Action::Share => "Save a scorecard",
+            Action::Replay => "Watch the replay",
+            Action::Detach => "Hide the run and exit; resume it with tflap attach",
+            Action::Boss => "Boss key: show a system monitor instead, and back",
+            Action::Quit => "Quit",
+            Action::Yes => "Confirm quitting",
+            Action::No => "Keep playing",
+            Action::Up => "Menu up",
+            Action::Down => "Menu down",
+            Action::Left => "Previous setting value",
+            Action::Right => "Next setting value",
+            Action::Select => "Choose menu item",
+            Action::Back => "Back out of a menu",
+            Action::Help => "This help",
+        }
+    }
+
+    /// The key menus understand for this action, if it means anything to them.
+    pub fn menu_key(self) -> Option<KeyCode> {
+        match self {
+            Action::Up => Some(KeyCode::Up),
+            Action::Down => Some(KeyCode::Down),
+            Action::Left => Some(KeyCode::Left),
+            Action::Right => Some(KeyCode::Right),
+            Action::Select => Some(KeyCode::Enter),
+            Action::Flap => Some(KeyCode::Char(' ')),
+            Action::Back => Some(KeyCode::Esc),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Default)]
+pub enum Preset {
+    #[default]
+    Standard,
+    Left,
+    LeftDvorak,
+    LeftColemak,
+}
+
+impl Preset {
+    pub const ALL: [Preset; 4] = [
+        Preset::Standard,
+        Preset::Left,
+        Preset::LeftDvorak,
+        Preset::LeftColemak,
+    ];
+
+    pub fn key(self) -> &'static str {
+        match self {
+            Preset::Standard => "standard",
+            Preset::Left => "left",
+            Preset::LeftDvorak => "left-dvorak",
+            Preset::LeftColemak => "left-colemak",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|p| p.key() == s)
+    }
+
+    /// The layout whose letters the left-hand positions are translated to.
+    fn layout(self) -> &'static str {
+        match self {
+            Preset::LeftDvorak => DVORAK,
+            Preset::LeftColemak => COLEMAK,
+            _ => QWERTY,
+        }
+    }
+
+    /// The key at the same place as `c` on a QWERTY keyboard.
+    fn translate(self, c: char) -> char {
+        QWERTY
+            .chars()
+            .position(|q| q == c)
+            .and_then(|i| self.layout().chars().nth(i))
+            .unwrap_or(c)
+    }
+}
+
+pub struct Keymap {
+    bindings: Vec<(KeyCode, Action)>,
+    revive_keys: [char; 7],
+}
+
+impl Keymap {
+    /// The preset's bindings, with `boss` and `restart` ahead of them all so they work
+    /// whatever they overlap.
+    pub fn new(preset: Preset, boss: KeyCode, restart: KeyCode) -> Self {
+        let common = [
+            (KeyCode::Char(' '), Action::Flap),
+            (KeyCode::Esc, Action::Back),
+            (KeyCode::Up, Action::Up),
+            (KeyCode::Down, Action::Down),
+            (KeyCode::Left, Action::Left),
+            (KeyCode::Right, Action::Right),
+            (KeyCode::Tab, Action::Down),
+            (KeyCode::Enter, Action::Select),
+            (KeyCode::Char('?'), Action::Help),
+            (KeyCode::F(1), Action::Help),
+        ];
+        let (letters, revive_keys): (&[(char, Action)], _) = match preset {
+            Preset::Standard => (
+                &[
+                    ('p', Action::Pause),
+                    ('b', Action::Bank),
+                    ('w', Action::Wager),
+                    ('z', Action::Rewind),
+                    ('-', Action::SpeedDown),
+                    ('+', Action::SpeedUp),
+                    ('=', Action::SpeedUp),
+                    ('r', Action::Retry),
+                    ('s', Action::RetrySameSeed),
+                    ('c', Action::Share),
+                    ('v', Action::Replay),
+                    ('d', Action::Detach),
+                    ('y', Action::Yes),
+                    ('n', Action::No),
+                    ('q', Action::Quit),
+                ],
+                REVIVE_KEYS,
+            ),
+            _ => (
+                &[
+                    ('w', Action::Up),
+                    ('a', Action::Left),
+                    ('s', Action::Down),
+                    ('d', Action::Right),
+                    ('e', Action::Select),
+                    ('f', Action::Pause),
+                    ('t', Action::Bank),
+                    ('2', Action::Wager),
+                    ('1', Action::Rewind),
+                    ('z', Action::SpeedDown),
+                    ('x', Action::SpeedUp),
+                    ('r', Action::Retry),
+                    ('g', Action::RetrySameSeed),
+                    ('c', Action::Share),
+                    ('v', Action::Replay),
+                    ('b', Action::Detach),
+                    ('q', Action::Quit),
+                ],
+                LEFT_REVIVE_KEYS.map(|c| preset.translate(c).to_ascii_uppercase()),
+            ),
+        };
+
+        let mut bindings = vec![(boss, Action::Boss), (restart, Action::QuickRestart)];
+        bindings.extend_from_slice(&common);
+        for &(c, action) in letters {
+            bindings.push((KeyCode::Char(preset.translate(c)), action));
+        }
+        Self {
+            bindings,
+            revive_keys,
+        }
+    }
+
+    /// What a key press means, ignoring case.
+    pub fn action(&self, code: KeyCode) -> Option<Action> {
+        let code = match code {
+            KeyCode::Char(c) => KeyCode::Char(c.to_ascii_lowercase()),
+            other => other,
+        };
+        self.bindings
+            .iter()
+            .find(|&&(key, _)| key == code)
+            .map(|&(_, action)| action)
+    }
+
+    /// The first key bound to an action, for on-screen hints.
+    pub fn label(&self, action: Action) -> Option<String> {
+        self.keys(action).into_iter().next()
+    }
+
+    /// Every key bound to an action.
+    pub fn keys(&self, action: Action) -> Vec<String> {
+        self.bindings
+            .iter()
+            .filter(|&&(_, a)| a == action)
+            .map(|&(code, _)| key_label(code))
+            .collect()
+    }
+
+    /// The key to ask for when the game picked `key` from `REVIVE_KEYS`.
+    pub fn revive_prompt(&self, key: char) -> char {
+        REVIVE_KEYS
+            .iter()
+            .position(|&k| k == key)
+            .map_or(key, |i| self.revive_keys[i])
+    }
+
+    /// Map a key typed at the revive prompt back to the `REVIVE_KEYS` it stands for.
+    pub fn revive_answer(&self, typed: char) -> char {
+        let typed = typed.to_ascii_uppercase();
+        self.revive_keys
+            .iter()
+            .position(|&k| k == typed)
+            .map_or(typed, |i| REVIVE_KEYS[i])
+    }
+}
+
+/// The key a layout or input method meant, for the characters some of them type in
+/// place of plain ones: other spaces for Space, full-width letters and digits from a CJK
+/// input method left in full-width mode, and the lone accent a dead key gives when
+/// Space follows it. The ASCII accents are left alone, as keys of their own.
+pub fn logical(code: KeyCode) -> KeyCode {
+    let KeyCode::Char(c) = code else {
+        return code;
+    };
+    let c = match c {
+        '\u{a0}' | '\u{2007}' | '\u{202f}' | '\u{3000}' => ' ',
+        '\u{ff01}'..='\u{ff5e}' => char::from_u32(c as u32 - 0xfee0).unwrap_or(c),
+        '\u{a8}' | '\u{b4}' | '\u{b8}' | '\u{2d8}'..='\u{2dd}' | '\u{2c7}' => ' ',
+        _ => c,
+    };
+    KeyCode::Char(c)
+}
+
+/// A key written in the config file or on the command line: one character, Backspace,
+/// or F1 to F12.
+pub fn parse_key(s: &str) -> Option<KeyCode> {
+    let mut chars = s.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) if !c.is_whitespace() => Some(KeyCode::Char(c.to_ascii_lowercase())),
+        _ if s.eq_ignore_ascii_case("backspace") => Some(KeyCode::Backspace),
+        _ => {
+            let n: u8 = s.strip_prefix(|c| c == 'f' || c == 'F')?.parse().ok()?;
+            (1..=12).contains(&n).then_some(KeyCode::F(n))
+        }
+    }
+}
+
+/// The config file spelling of a key `parse_key` understands.
+pub fn key_name(code: KeyCode) -> String {
+    match code {
+        KeyCode::F(n) => format!("f{}", n),
+        KeyCode::Char(c) => c.to_string(),
+        KeyCode::Backspace => "backspace".to_string(),
+        _ => String::new(),
+    }
+}
+
+/// A key pressed with modifiers held, like "ctrl+alt+q". There is always at least one
+/// modifier, so it's never hit by accident.
+#[derive(Clone, Copy, PartialEq)]
+pub struct Combo {
+    pub modifiers: KeyModifiers,
+    pub code: KeyCode,
+}
+
+impl Combo {
+    pub fn parse(s: &str) -> Option<Self> {
+        let mut parts: Vec<&str> = s.split('+').collect();
+        let code = parse_key(parts.pop()?)?;
+        let mut modifiers = KeyModifiers::NONE;
+        for part in parts {
+            modifiers |= match part.to_ascii_lowercase().as_str() {
+                "ctrl" => KeyModifiers::CONTROL,
+                "alt" => KeyModifiers::ALT,
+                "shift" => KeyModifiers::SHIFT,
+                _ => return None,
+            };
+        }
+        (!modifiers.is_empty()).then_some(Self { modifiers, code })
+    }
+
+    /// The config file spelling.
+    pub fn name(self) -> String {
+        let mut parts = Vec::new();
+        for (modifier, name) in [
+            (KeyModifiers::CONTROL, "ctrl"),
+            (KeyModifiers::ALT, "alt"),
+            (KeyModifiers::SHIFT, "shift"),
+        ] {
+            if self.modifiers.contains(modifier) {
+                parts.push(name.to_string());
+            }
+        }
+        parts.push(key_name(self.code));
+        parts.join("+")
+    }
+
+    /// Whether a key press is this combo, ignoring case and any extra modifiers.
+    pub fn matches(self, code: KeyCode, modifiers: KeyModifiers) -> bool {
+        let code = match code {
+            KeyCode::Char(c) => KeyCode::Char(c.to_ascii_lowercase()),
+            code => code,
+        };
+        code == self.code && modifiers.contains(self.modifiers)
+    }
+}
+
+pub fn key_label(code: KeyCode) -> String {
+    match code {
+        KeyCode::Char(' ') => "Space".to_string(),
+        KeyCode::Char(c) => c.to_ascii_uppercase().to_string(),
+        KeyCode::F(n) => format!("F{}", n),
+        KeyCode::Esc => "Esc".to_string(),
+        KeyCode::Enter => "Enter".to_string(),
+        KeyCode::Tab => "Tab".to_string(),
+        KeyCode::Backspace => "Backspace".to_string(),
+        KeyCode::Up => "↑".to_string(),
+        KeyCode::Down => "↓".to_string(),
+        KeyCode::Left => "←".to_string(),
+        KeyCode::Right => "→".to_string(),
+        _ => "?".to_string(),
+    }
+}
@@ -0,0 +1,73 @@
+//! `tflap keytest`: every event the terminal sends, as it arrives, and what the game makes
+//! of it.
+//!
+//! For reports of a flap or other key not registering. Each key shows the raw event,
+//! including its kind where the terminal reports presses and releases apart, then the
+//! key after `keymap::logical` and the action it is bound to under the saved keymap.
+
+use crate::config::Config;
+use crate::keymap::{self, Keymap};
+use crossterm::event::{
+    self, Event, KeyCode, KeyEvent, KeyModifiers, KeyboardEnhancementFlags,
+    PopKeyboardEnhancementFlags, PushKeyboardEnhancementFlags,
+};
+use crossterm::{execute, style::Print, terminal};
+use std::io::{self, Write};
+
+pub fn run(config: &Config) -> io::Result<()> {
+    let keymap = Keymap::new(config.keymap, config.boss_key, config.restart_key);
+    let mut stdout = io::stdout();
+    terminal::enable_raw_mode()?;
+    let enhanced = terminal::supports_keyboard_enhancement().unwrap_or(false);
+    if enhanced {
+        execute!(
+            stdout,
+            PushKeyboardEnhancementFlags(KeyboardEnhancementFlags::REPORT_EVENT_TYPES)
+        )?;
+    }
+    let result = show_events(&mut stdout, &keymap, enhanced);
+    if enhanced {
+        execute!(stdout, PopKeyboardEnhancementFlags)?;
+    }
+    terminal::disable_raw_mode()?;
+    result
+}
+
+fn show_events(stdout: &mut io::Stdout, keymap: &Keymap, enhanced: bool) -> io::Result<()> {
+    let releases = if enhanced {
+        "this terminal reports key releases"
+    } else {
+        "this terminal doesn't report key releases"
+    };
+    execute!(
+        stdout,
+        Print(format!(
+            "Press keys to see what tflap receives ({}). Ctrl-C to leave.\r\n\r\n",
+            releases
+        ))
+    )?;
+    loop {
+        let event = event::read()?;
+        let line = match &event {
+            Event::Key(KeyEvent {
+                code: KeyCode::Char('c'),
+                modifiers,
+                ..
+            }) if modifiers.contains(KeyModifiers::CONTROL) => return Ok(()),
+            Event::Key(key) => {
+                let logical = keymap::logical(key.code);
+                let action = keymap
+                    .action(logical)
+                    .map_or("nothing", |action| action.about());
+                let label = match keymap::key_label(logical).as_str() {
+                    "?" => format!("{:?}", logical),
+                    label => label.to_string(),
+                };
+                format!("{:?}\r\n    as {}: {}\r\n", key, label, action)
+            }
+            other => format!("{:?}\r\n", other),
+        };
+        execute!(stdout, Print(line))?;
+        stdout.flush()?;
+    }
+}
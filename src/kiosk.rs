@@ -0,0 +1,77 @@
+//! Kiosk mode, for leaving the game running unattended on a demo terminal.
+//!
+//! Nothing quits but the admin combo from the settings (and SIGTERM). A finished run
+//! counts down `RESTART` on its game-over screen and then goes back to the title, which
+//! turns through the attract pages every `PAGE` until someone presses a key.
+
+use std::time::{Duration, Instant};
+
+use crossterm::event::{KeyCode, KeyModifiers};
+
+use crate::keymap::Combo;
+
+/// How long the game-over screen stays up before the next player's turn.
+pub const RESTART: Duration = Duration::from_secs(10);
+/// How long each attract page shows.
+const PAGE: Duration = Duration::from_secs(8);
+
+/// What the title screen shows while nobody is playing.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Page {
+    Title,
+    HowToPlay,
+    Leaderboard,
+}
+
+const PAGES: [Page; 3] = [Page::Title, Page::HowToPlay, Page::Leaderboard];
+
+pub struct Kiosk {
+    exit: Combo,
+    /// When the title screen was last touched, or the game-over screen came up, on the
+    /// game's clock.
+    since: Instant,
+    /// The attract page on screen.
+    pub shown: Page,
+}
+
+impl Kiosk {
+    pub fn new(exit: Combo, now: Instant) -> Self {
+        Self {
+            exit,
+            since: now,
+            shown: Page::Title,
+        }
+    }
+
+    pub fn is_exit(&self, code: KeyCode, modifiers: KeyModifiers) -> bool {
+        self.exit.matches(code, modifiers)
+    }
+
+    /// Start the attract pages over from the title, or the countdown from the top.
+    pub fn restart_clock(&mut self, now: Instant) {
+        self.since = now;
+        self.shown = Page::Title;
+    }
+
+    /// Move on to the attract page due now, returning it if it's a new one.
+    pub fn turn(&mut self, now: Instant) -> Option<Page> {
+        let pages = (now.duration_since(self.since).as_secs() / PAGE.as_secs()) as usize;
+        let page = PAGES[pages % PAGES.len()];
+        (page != self.shown).then(|| {
+            self.shown = page;
+            page
+        })
+    }
+
+    /// Whole seconds left of the game-over countdown, rounded up.
+    pub fn countdown(&self, now: Instant) -> u64 {
+        RESTART
+            .saturating_sub(now.duration_since(self.since))
+            .as_secs_f32()
+            .ceil() as u64
+    }
+
+    pub fn countdown_over(&self, now: Instant) -> bool {
+        now.duration_since(self.since) >= RESTART
+    }
+}
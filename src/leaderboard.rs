@@ -0,0 +1,462 @@
+use crate::config::{Config, MAX_SPEED};
+use crate::difficulty::Difficulty;
+use crate::history::{self, RunRecord};
+use crate::mutators::{Mutator, Mutators};
+use crate::paths::{self, Paths};
+use crate::players;
+use crate::ramp::{self, Curve};
+use crate::scoring::ScoringMode;
+use crate::storage;
+use crate::weather::WeatherSetting;
+use std::fs;
+use std::path::PathBuf;
+
+/// Entries kept per category.
+const TOP_ENTRIES: usize = 10;
+/// How far a timestamp may be in the future before it can't be real, for clocks that
+/// disagree a little.
+const CLOCK_SLACK_SECS: u64 = 24 * 60 * 60;
+
+/// Which scores are comparable: same scoring rules, difficulty, and modifiers.
+#[derive(Clone, PartialEq)]
+pub struct Category {
+    key: String,
+}
+
+impl Category {
+    pub fn for_config(config: &Config) -> Self {
+        let mut modifiers = Vec::new();
+        if let Some(lives) = config.lives {
+            modifiers.push(format!("lives{}", lives));
+        }
+        if config.weather != WeatherSetting::default() {
+            modifiers.push(config.weather.key().to_string());
+        }
+        if config.day_night {
+            modifiers.push("daynight".to_string());
+        }
+        if config.memory {
+            modifiers.push("memory".to_string());
+        }
+        if config.speed != 100 {
+            modifiers.push(format!("speed{}", config.speed));
+        }
+        if config.one_switch {
+            modifiers.push("oneswitch".to_string());
+        }
+        if config.assist {
+            modifiers.push("assist".to_string());
+        }
+        if config.trajectory {
+            modifiers.push("trajectory".to_string());
+        }
+        modifiers.extend(config.mutators.iter().map(|m| m.key().to_string()));
+        if config.ramp != Curve::Off {
+            modifiers.push(format!("ramp-{}", config.ramp.key()));
+        }
+        if config.banking {
+            modifiers.push("banking".to_string());
+        }
+        if config.double_or_nothing {
+            modifiers.push("wager".to_string());
+        }
+        if config.typing && !config.one_switch {
+            modifiers.push("typing".to_string());
+        }
+        if config.quiz {
+            modifiers.push("quiz".to_string());
+        }
+        if config.rewind {
+            modifiers.push("rewind".to_string());
+        }
+        if let Some(bpm) = config.rhythm {
+            modifiers.push(format!("rhythm{}", bpm));
+        }
+        // A marathon's total is from many sessions, so it's only up against other marathons
+        if config.marathon {
+            modifiers.push("marathon".to_string());
+        }
+
+        let mut key = format!("{}/{}", config.scoring.key(), config.difficulty.key());
+        if !modifiers.is_empty() {
+            key.push('/');
+            key.push_str(&modifiers.join("+"));
+        }
+        Self { key }
+    }
+
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// Human-readable name, e.g. "Style · Normal · lives3 rain".
+    pub fn label(&self) -> String {
+        let mut parts = self.key.split('/');
+        let capitalize = |s: Option<&str>| {
+            let s = s.unwrap_or("");
+            let mut chars = s.chars();
+            chars
+                .next()
+                .map(|c| c.to_uppercase().chain(chars).collect::<String>())
+                .unwrap_or_default()
+        };
+        let scoring = capitalize(parts.next());
+        let difficulty = capitalize(parts.next());
+        match parts.next() {
+            Some(modifiers) => format!(
+                "{} · {} · {}",
+                scoring,
+                difficulty,
+                modifiers.replace('+', " ")
+            ),
+            None => format!("{} · {}", scoring, difficulty),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct Entry {
+    pub score: u32,
+    /// Seconds since the Unix epoch.
+    pub timestamp: u64,
+    /// How long the run lasted. Runs recorded before this was kept have none.
+    pub ticks: Option<u32>,
+}
+
+impl Entry {
+    /// Whether the game could have produced this entry in `key`'s category: timed
+    /// runs can't score faster than pipes arrive at the top speed and closest spacing
+    /// (the top of the speed ramp, in a ramped category) with every bonus on each, times
+    /// the category's mutators and doubled by a won bet in a wager category, and nothing
+    /// was scored in the future.
+    fn is_plausible(&self, key: &str, now: u64) -> bool {
+        if self.timestamp > now + CLOCK_SLACK_SECS {
+            return false;
+        }
+        let Some(ticks) = self.ticks else {
+            return true;
+        };
+        let mut spacing = Difficulty::ALL
+            .iter()
+            .map(|difficulty| difficulty.pipe_spacing())
+            .min()
+            .unwrap_or(1) as u64;
+        let mut pipe_speed = 1.0;
+        let mut parts = key.split('/');
+        let scoring = parts
+            .next()
+            .and_then(ScoringMode::parse)
+            .unwrap_or_default();
+        let mut mutators = Mutators::default();
+        // A won bet pays the stake again, and the stake is at most the points so far
+        let mut winnings = 1;
+        for modifier in parts.nth(1).unwrap_or("").split('+') {
+            if modifier == "wager" {
+                winnings = 2;
+            }
+            if let Some(mutator) = Mutator::parse(modifier) {
+                mutators.set(mutator, true);
+            }
+            if modifier.starts_with("ramp-") {
+                pipe_speed = ramp::MAX_PIPE_SPEED;
+                spacing = ((spacing as f32 * ramp::MIN_SPACING) as u64).max(1);
+            }
+        }
+        let columns = (u64::from(ticks) * u64::from(MAX_SPEED) / 100
+            * mutators.speed_factor() as u64) as f32
+            * pipe_speed;
+        let columns = columns.ceil() as u64;
+        // The first pipe can already be at the bird when the run starts
+        let pipes = columns / spacing + 1;
+        let points = pipes * u64::from(scoring.max_pipe_points());
+        u64::from(self.score) <= points * u64::from(mutators.multiplier()) / 100 * winnings
+    }
+}
+
+/// One player's best scores per category, highest first.
+pub struct Leaderboard {
+    /// Whose scores these are, `None` for the unnamed player.
+    player: Option<String>,
+    categories: Vec<(String, Vec<Entry>)>,
+    /// Lines from the file that no real run could have scored, kept out of the board and
+    /// moved to the quarantine file on the next save.
+    quarantined: Vec<String>,
+    /// How many were found on loading, for the leaderboard screen.
+    set_aside: usize,
+    /// False if the file is from a newer tflap, so scores go on the board but not to disk.
+    writable: bool,
+}
+
+/// The leaderboard file's versions. Version 1 only added the header.
+pub const FORMAT: storage::Format = storage::Format {
+    name: "leaderboard",
+    migrations: &[|body| body.to_string()],
+};
+
+fn get_quarantine_path() -> Option<PathBuf> {
+    paths::file("leaderboard_quarantine")
+}
+
+/// The single-number high score files used before categories existed.
+fn legacy_highscores() -> Vec<(&'static str, u32)> {
+    let Some(paths) = Paths::get() else {
+        return Vec::new();
+    };
+    [
+        (ScoringMode::Classic, "highscore"),
+        (ScoringMode::Style, "highscore_style"),
+    ]
+    .into_iter()
+    .filter_map(|(mode, file)| {
+        let score = fs::read_to_string(paths.file(file))
+            .ok()?
+            .trim()
+            .parse()
+            .ok()?;
+        let key = match mode {
+            ScoringMode::Classic => "classic/normal",
+            ScoringMode::Style => "style/normal",
+        };
+        Some((key, score))
+    })
+    .collect()
+}
+
+impl Leaderboard {
+    /// An empty board for `player`.
+    pub fn new(player: Option<&str>) -> Self {
+        Self {
+            player: player.map(str::to_string),
+            categories: Vec::new(),
+            quarantined: Vec::new(),
+            set_aside: 0,
+            writable: true,
+        }
+    }
+
+    /// Load `player`'s leaderboard, importing the old high score files into the unnamed
+    /// player's the first time.
+    pub fn load(player: Option<&str>) -> Self {
+        let mut board = Self::new(player);
+        let path = players::home_file("leaderboard", player);
+        match path
+            .as_ref()
+            .and_then(|p| storage::read_versioned(p, &FORMAT))
+        {
+            Some(loaded) => {
+                board.writable = loaded.writable;
+                board.read(&loaded.body, storage::unix_now());
+            }
+            None if player.is_none() => {
+                for (key, score) in legacy_highscores() {
+                    board.insert(
+                        key,
+                        Entry {
+                            score,
+                            timestamp: 0,
+                            ticks: None,
+                        },
+                    );
+                }
+            }
+            None => {}
+        }
+        board
+    }
+
+    /// Take in the entries in a leaderboard file's `body`, setting aside any that
+    /// couldn't have been played by `now`.
+    pub fn read(&mut self, body: &str, now: u64) {
+        for line in body.lines() {
+            let mut fields = line.split_whitespace();
+            let (Some(key), Some(score), Some(timestamp)) =
+                (fields.next(), fields.next(), fields.next())
+            else {
+                continue;
+            };
+            let (Ok(score), Ok(timestamp)) = (score.parse(), timestamp.parse()) else {
+                continue;
+            };
+            let ticks = fields.next().and_then(|ticks| ticks.parse().ok());
+            let entry = Entry {
+                score,
+                timestamp,
+                ticks,
+            };
+            if entry.is_plausible(key, now) {
+                self.insert(key, entry);
+            } else {
+                self.quarantined.push(line.to_string());
+            }
+        }
+        self.set_aside = self.quarantined.len();
+    }
+
+    fn insert(&mut self, key: &str, entry: Entry) {
+        let entries = match self.categories.iter_mut().find(|(k, _)| k == key) {
+            Some((_, entries)) => entries,
+            None => {
+                self.categories.push((key.to_string(), Vec::new()));
+                &mut self.categories.last_mut().unwrap().1
+            }
+        };
+        let at = entries
+            .iter()
+            .position(|e| e.score < entry.score)
+            .unwrap_or(entries.len());
+        entries.insert(at, entry);
+        entries.truncate(TOP_ENTRIES);
+    }
+
+    pub fn entries(&self, category: &Category) -> &[Entry] {
+        self.categories
+            .iter()
+            .find(|(k, _)| k == category.key())
+            .map(|(_, entries)| entries.as_slice())
+            .unwrap_or(&[])
+    }
+
+    pub fn best(&self, category: &Category) -> u32 {
+        self.entries(category).first().map_or(0, |e| e.score)
+    }
+
+    /// Every category with at least one score.
+    pub fn categories(&self) -> Vec<Category> {
+        self.categories
+            .iter()
+            .map(|(key, _)| Category { key: key.clone() })
+            .collect()
+    }
+
+    /// Scores found on loading that no real run could have made.
+    pub fn set_aside(&self) -> usize {
+        self.set_aside
+    }
+
+    /// Add a finished run that lasted `ticks` and write the leaderboard back to disk.
+    pub fn submit(&mut self, category: &Category, score: u32, timestamp: u64, ticks: u32) {
+        let entry = Entry {
+            score,
+            timestamp,
+            ticks: Some(ticks),
+        };
+        self.insert(category.key(), entry);
+        self.save();
+    }
+
+    /// The entries as a leaderboard file's body, for `read` to take back.
+    pub fn to_file(&self) -> String {
+        let mut content = String::new();
+        for (key, entries) in &self.categories {
+            for entry in entries {
+                content.push_str(&format!("{} {} {}", key, entry.score, entry.timestamp));
+                if let Some(ticks) = entry.ticks {
+                    content.push_str(&format!(" {}", ticks));
+                }
+                content.push('\n');
+            }
+        }
+        content
+    }
+
+    fn save(&mut self) {
+        if !self.writable {
+            return;
+        }
+        let mut content = self.to_file();
+        // Move what was set aside to the quarantine file, or leave it where it was if
+        // that can't be written
+        if !self.quarantined.is_empty() {
+            let mut lines = String::new();
+            for line in &self.quarantined {
+                lines.push_str(line);
+                lines.push('\n');
+            }
+            let moved = get_quarantine_path().is_some_and(|path| {
+                let kept = fs::read_to_string(&path).unwrap_or_default();
+                storage::write_atomic(&path, &(kept + &lines)).is_ok()
+            });
+            if moved {
+                self.quarantined.clear();
+            } else {
+                content.push_str(&lines);
+            }
+        }
+        if let Some(path) = players::home_file("leaderboard", self.player.as_deref()) {
+            let _ = storage::write_versioned(&path, &FORMAT, &content);
+        }
+    }
+}
+
+/// A score on the family leaderboard, and whose it is.
+pub struct FamilyEntry {
+    pub player: Option<String>,
+    pub score: u32,
+    pub timestamp: u64,
+}
+
+/// Every player's leaderboard together, for everyone sharing the machine, with their
+/// histories for who did best lately.
+pub struct Family {
+    boards: Vec<Leaderboard>,
+    histories: Vec<Vec<RunRecord>>,
+}
+
+impl Family {
+    pub fn load() -> Self {
+        let players = players::all();
+        Self {
+            boards: players
+                .iter()
+                .map(|player| Leaderboard::load(player.as_deref()))
+                .collect(),
+            histories: players
+                .iter()
+                .map(|player| history::load_history(player.as_deref()))
+                .collect(),
+        }
+    }
+
+    /// `first`, then every other category anyone has scored in.
+    pub fn categories(&self, first: &Category) -> Vec<Category> {
+        let mut categories = vec![first.clone()];
+        for category in self.boards.iter().flat_map(Leaderboard::categories) {
+            if !categories.contains(&category) {
+                categories.push(category);
+            }
+        }
+        categories
+    }
+
+    /// The best scores in a category across everyone, highest first.
+    pub fn entries(&self, category: &Category) -> Vec<FamilyEntry> {
+        let mut entries: Vec<FamilyEntry> = self
+            .boards
+            .iter()
+            .flat_map(|board| {
+                board.entries(category).iter().map(|entry| FamilyEntry {
+                    player: board.player.clone(),
+                    score: entry.score,
+                    timestamp: entry.timestamp,
+                })
+            })
+            .collect();
+        entries.sort_by_key(|entry| std::cmp::Reverse(entry.score));
+        entries.truncate(TOP_ENTRIES);
+        entries
+    }
+
+    /// Whoever scored best in a category within the last `window` seconds, and their
+    /// score. Nobody has until someone scores.
+    pub fn winner(&self, category: &Category, window: u64, now: u64) -> Option<(&str, u32)> {
+        self.boards
+            .iter()
+            .zip(&self.histories)
+            .map(|(board, runs)| {
+                let best = history::best_within(runs, category, window, now);
+                (players::label(board.player.as_deref()), best)
+            })
+            .filter(|&(_, best)| best > 0)
+            .max_by_key(|&(_, best)| best)
+    }
+}
@@ -0,0 +1,120 @@
+//! Hand-made pipe layouts, used by the practice drills.
+//!
+//! A level is a text file with one command per line; `#` starts a comment.
+//!
+//! ```text
+//! name <title...>
+//! pipe <spacing> <offset> <gap> [flip]
+//! ```
+//!
+//! `spacing` is the number of columns since the previous pipe (ignored for the first),
+//! `offset` is how many rows the middle of the gap sits below the middle of the screen
+//! (negative is above), and `gap` is the height of the opening. Passing a `flip` pipe
+//! turns gravity upside down. None of them goes past `LIMIT` either way.
+
+/// The most cells any pipe field can be, far past any board, so sums on them can't
+/// overflow.
+const LIMIT: i32 = 1000;
+
+const DRILLS: [&str; 3] = [
+    include_str!("../levels/tight_gap.lvl"),
+    include_str!("../levels/double_pipes.lvl"),
+    include_str!("../levels/gravity_flip.lvl"),
+];
+
+#[derive(Clone)]
+pub struct PipeSpec {
+    pub spacing: i32,
+    pub offset: i32,
+    pub gap: u16,
+    pub flip: bool,
+}
+
+#[derive(Clone)]
+pub struct Level {
+    pub name: String,
+    pub pipes: Vec<PipeSpec>,
+}
+
+impl Level {
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut name = String::new();
+        let mut pipes = Vec::new();
+        for (number, line) in text.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or("").trim();
+            let mut words = line.split_whitespace();
+            let bad = |what: &str| format!("line {}: {}", number + 1, what);
+            match words.next() {
+                None => {}
+                Some("name") => name = words.collect::<Vec<_>>().join(" "),
+                Some("pipe") => {
+                    let mut number = |field: &str| {
+                        words
+                            .next()
+                            .and_then(|w| w.parse::<i32>().ok())
+                            .ok_or_else(|| bad(&format!("pipe needs a numeric {}", field)))
+                    };
+                    let spacing = number("spacing")?;
+                    let offset = number("offset")?;
+                    let gap = number("gap")?;
+                    if spacing < 1 || gap < 1 {
+                        return Err(bad("spacing and gap must be positive"));
+                    }
+                    if spacing > LIMIT || gap > LIMIT || !(-LIMIT..=LIMIT).contains(&offset) {
+                        return Err(bad(&format!("pipe fields can't go past {}", LIMIT)));
+                    }
+                    let flip = match words.next() {
+                        None => false,
+                        Some("flip") => true,
+                        Some(other) => return Err(bad(&format!("unknown pipe flag '{}'", other))),
+                    };
+                    pipes.push(PipeSpec {
+                        spacing,
+                        offset,
+                        gap: gap as u16,
+                        flip,
+                    });
+                }
+                Some(other) => return Err(bad(&format!("unknown command '{}'", other))),
+            }
+        }
+        if pipes.is_empty() {
+            return Err("level has no pipes".to_string());
+        }
+        Ok(Self { name, pipes })
+    }
+}
+
+/// The built-in practice drills.
+pub fn drills() -> Vec<Level> {
+    DRILLS
+        .iter()
+        .map(|text| Level::parse(text).expect("built-in drill levels are valid"))
+        .collect()
+}
+
+/// Hands out a level's pipes in order, starting over after the last one.
+pub struct LoopingSpawner {
+    level: Level,
+    next: usize,
+}
+
+impl LoopingSpawner {
+    pub fn new(level: Level) -> Self {
+        Self { level, next: 0 }
+    }
+
+    pub fn level(&self) -> &Level {
+        &self.level
+    }
+
+    pub fn rewind(&mut self) {
+        self.next = 0;
+    }
+
+    pub fn next_pipe(&mut self) -> PipeSpec {
+        let spec = self.level.pipes[self.next].clone();
+        self.next = (self.next + 1) % self.level.pipes.len();
+        spec
+    }
+}
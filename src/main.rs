@@ -1,461 +1,5833 @@
+// Built as a library for cargo fuzz, most of the game goes unused
+#![cfg_attr(fuzzing, allow(dead_code))]
+
+mod autorun;
+mod balance;
+mod banking;
+mod base64;
+mod bidi;
+mod bot;
+mod budget;
+mod camera;
+mod celebration;
+mod challenge;
+mod challenge_file;
+mod clock;
+mod collision;
+mod completions;
+mod config;
+mod date;
+mod daycycle;
+mod detach;
+mod difficulty;
+mod disguise;
+mod doctor;
+mod effects;
+mod entity;
+mod events;
+mod export;
+#[cfg(any(test, fuzzing))]
+pub mod fuzz;
+mod haptics;
+#[cfg(test)]
+mod harness;
+mod help;
+mod history;
+mod input;
+mod keymap;
+mod keytest;
+mod kiosk;
+mod leaderboard;
+mod level;
+mod manpage;
+mod marathon;
+mod menu;
+mod modes;
+mod music;
+mod mutators;
+mod pacer;
+mod paths;
+mod photo;
+mod pipes;
+mod players;
+mod png;
+mod pomodoro;
+mod profile;
+mod quiz;
+mod ramp;
+mod render;
+mod rewind;
+mod rhythm;
+mod rivals;
+mod scoring;
+mod sha256;
+mod signals;
+mod simulate;
+mod status;
+mod storage;
+mod stress;
+mod summary;
+mod telemetry;
+mod ticker;
+mod timelimit;
+mod train;
+mod typing;
+mod update;
+mod wager;
+mod weather;
+mod world;
+
+use autorun::Autorun;
+use balance::Encounter;
+use banking::Bank;
+use bot::{Brain, Strategy};
+use budget::{Effect, FrameBudget};
+use camera::Camera;
+use celebration::Celebration;
+use challenge::Challenge;
+use challenge_file::ChallengeFile;
+use clock::{Clock, SystemClock};
+use collision::Aabb;
+use config::{Cli, Config, Opponent, DEFAULT_LIVES, MAX_LIVES, MAX_SPEED, MIN_SPEED, SPEED_STEP};
 use crossterm::{
-    cursor::{Hide, MoveTo, Show},
-    event::{self, Event, KeyCode, KeyEvent, KeyModifiers},
+    cursor::{Hide, Show},
+    event::{
+        self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEvent, KeyEventKind,
+        KeyModifiers, KeyboardEnhancementFlags, MouseEvent, MouseEventKind,
+        PopKeyboardEnhancementFlags, PushKeyboardEnhancementFlags,
+    },
     execute,
-    style::{Color, Print, ResetColor, SetForegroundColor},
-    terminal::{self, Clear, ClearType, EnterAlternateScreen, LeaveAlternateScreen},
+    style::{Color, Print},
+    terminal::{self, EnterAlternateScreen, LeaveAlternateScreen},
 };
-use rand::Rng;
+use date::Date;
+use daycycle::{DayCycle, Phase};
+use detach::Detached;
+use difficulty::Difficulty;
+use disguise::Disguise;
+use effects::{Effects, INTENSITY_LABELS, INTENSITY_STEP};
+use entity::{Body, Collider};
+use events::{EventBus, GameEvent};
+use export::{Export, Stamp};
+use haptics::Haptics;
+use history::{Outcome, RunRecord};
+use input::{FlapGate, Hold, InputSource, RestartGate, SwitchInput, Tap, Terminal};
+use keymap::{Action, Keymap, Preset, REVIVE_KEYS};
+use kiosk::{Kiosk, Page};
+use leaderboard::{Category, Family, Leaderboard};
+use level::{LoopingSpawner, PipeSpec};
+use marathon::{Marathon, Mark};
+use menu::{Menu, MenuEvent, MenuItem};
+use music::Music;
+use mutators::{Mutator, Mutators};
+use pacer::{Jitter, Pacer};
+use photo::Photo;
+use pipes::PipeStyle;
+use pomodoro::{Period, Pomodoro};
+use quiz::{Gate, Quiz, Tally};
+use ramp::{Curve, SpeedRamp};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use render::{boxed, sparkline, Display, Frame, Screen};
+use rewind::Rewind;
+use rhythm::BeatClock;
+use rivals::{Rival, RivalSource};
+use scoring::{PipePass, ScoreBreakdown, ScoringMode};
+use simulate::Batch;
+use status::StatusExporter;
 use std::env;
-use std::fs;
 use std::io::{self, Write};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::time::{Duration, Instant};
+use stress::Stats;
+use summary::{Destination, Summary};
+use telemetry::{Hit, Input, PassRecord, RunTelemetry, Sample};
+use ticker::Ticker;
+use timelimit::TimeLimit;
+use typing::Typing;
+use wager::Wager;
+use weather::{Weather, WeatherSetting, WeatherSystem};
+use world::{Pos, View};
 
 const BIRD_X: u16 = 10;
+/// Columns the bird is hit-tested across, which is also how wide the emoji bird is drawn.
+const BIRD_WIDTH: i32 = 2;
 const GRAVITY: f32 = 0.3;
 const JUMP_VELOCITY: f32 = -1.5;
+/// Flap strengths for a tap and a long press in one-switch mode.
+const SMALL_JUMP_VELOCITY: f32 = -1.0;
+const BIG_JUMP_VELOCITY: f32 = -2.0;
 const PIPE_WIDTH: u16 = 6;
-const PIPE_GAP: u16 = 8;
-const PIPE_SPEED: u16 = 1;
+/// Degrees the pipes' and ground's hue turns, and how much wider the view ahead of the
+/// bird gets, at the top of the speed ramp.
+const RAMP_HUE: f32 = 110.0;
+const RAMP_STRETCH: f32 = 0.12;
+/// Rows along the top and bottom of the board the bird crashes into.
+const CEILING_ROWS: u16 = 1;
+const GROUND_ROWS: u16 = 1;
+/// Rows above the ceiling and below the ground kept for the HUD, which nothing in the
+/// world is drawn over or flies through.
+const HUD_ROWS: u16 = 1;
+/// Ticks between flaps for a fast tapper, about seven a second, used to judge how far a
+/// climb the pipes may ask for.
+const FLAP_TICKS: u32 = 3;
+/// Share of that climb a new gap may ask for, leaving the rest for gusts and slow fingers.
+const REACH_MARGIN: f32 = 0.8;
+/// Unreachable gaps thrown away before settling for the highest reachable one.
+const REACH_ATTEMPTS: u32 = 8;
+/// Least a gap may move between pipes, however short the board or close the pipes.
+const MIN_GAP_STEP: u16 = 2;
 const TICK_RATE: Duration = Duration::from_millis(50);
+/// Longest the loops sleep between frames, so keys and the next tick are never missed
+/// by much.
+const FRAME_SLEEP: Duration = Duration::from_millis(5);
+/// How long the screensaver shows a crash before the bot starts over.
+const SCREENSAVER_WRECK_TICKS: u32 = 40;
+/// Board size for `tflap train` and `tflap simulate`, a common terminal.
+const HEADLESS_WIDTH: u16 = 80;
+const HEADLESS_HEIGHT: u16 = 24;
+const INVINCIBLE_TICKS: u32 = 40;
+const COMBO_MAX: u32 = 10;
+const REVIVE_TICKS: u32 = 60;
+const REVIVE_PENALTY: u32 = 3;
+const POPUP_TICKS: u32 = 20;
+const RIVAL_POPUP_TICKS: u32 = 40;
+/// Labels for each speed step from `MIN_SPEED` to `MAX_SPEED`.
+const SPEED_LABELS: [&str; 11] = [
+    "0.5x", "0.6x", "0.7x", "0.8x", "0.9x", "1.0x", "1.1x", "1.2x", "1.3x", "1.4x", "1.5x",
+];
+/// In memory mode, columns ahead of the bird where a pipe starts to fade and where it is
+/// gone, and how long its gap's edges flash once it is.
+const MEMORY_FADE_START: i32 = 40;
+const MEMORY_FADE_END: i32 = 16;
+const MEMORY_FLASH_TICKS: u32 = 6;
+/// How many times smaller than the board the best-run inset is drawn.
+const PIP_SCALE: u16 = 3;
+/// Ticks ahead the trajectory preview looks.
+const PREVIEW_TICKS: u32 = 24;
+const SUMMARY_WIDTH: usize = 40;
+const HELP_WIDTH: usize = 56;
+const HISTORY_SPARKLINE_RUNS: usize = 30;
+const MENU_WIDTH: usize = 32;
+/// Runs below this score quit without asking first.
+const QUIT_PROMPT_MIN_SCORE: u32 = 5;
+const LOGO: [&str; 2] = ["▀█▀ █▀▀ █   ▄▀█ █▀█", " █  █▀  █▄▄ █▀█ █▀▀"];
 
-#[derive(PartialEq)]
+#[derive(Clone, Copy, PartialEq)]
 enum GameState {
+    Title,
     Playing,
+    Paused,
+    Revive,
+    /// Double or nothing is on offer after a crash.
+    Wager,
+    /// A rest stop between marathon segments.
+    Intermission,
+    ConfirmQuit,
     GameOver,
 }
 
+#[derive(Clone)]
 struct Bird {
-    y: f32,
-    velocity: f32,
+    /// Its column and height, stepped by the entity physics under gravity and the wind.
+    body: Body,
+    /// Gravity pulls upwards, and flaps push down.
+    flipped: bool,
 }
 
 impl Bird {
-    fn new(y: f32) -> Self {
-        Self { y, velocity: 0.0 }
+    fn new(column: u16, y: f32) -> Self {
+        Self {
+            body: Body::drifting(Pos::new(column as f32, y), Pos::new(0.0, 0.0)),
+            flipped: false,
+        }
+    }
+
+    fn direction(&self) -> f32 {
+        if self.flipped {
+            -1.0
+        } else {
+            1.0
+        }
     }
 
     fn jump(&mut self) {
-        self.velocity = JUMP_VELOCITY;
+        self.jump_with(JUMP_VELOCITY);
     }
 
-    fn update(&mut self) {
-        self.velocity += GRAVITY;
-        self.y += self.velocity;
+    fn jump_with(&mut self, velocity: f32) {
+        self.body.velocity.y = velocity * self.direction();
+    }
+
+    /// Step the physics forward by `dt` ticks (1.0 at normal speed).
+    fn update(&mut self, wind: f32, dt: f32) {
+        self.body.gravity = GRAVITY * self.direction() + wind;
+        self.body.step(dt);
     }
 
     fn reset(&mut self, y: f32) {
-        self.y = y;
-        self.velocity = 0.0;
+        self.body.pos.y = y;
+        self.body.velocity.y = 0.0;
+    }
+
+    /// What gets hit-tested: the cell the bird is in and the next one to the right,
+    /// whether or not its glyph is that wide, so every display plays alike.
+    fn hitbox(&self) -> Aabb {
+        let (column, row) = self.body.pos.cell();
+        Aabb::new(
+            column as f32,
+            row as f32,
+            (column + BIRD_WIDTH) as f32,
+            (row + 1) as f32,
+        )
+    }
+}
+
+/// One of the extra birds in a stress test, flown by the bot at its own column.
+struct FlockBird {
+    bird: Bird,
+    pilot: Box<dyn Strategy>,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum TitleItem {
+    Start,
+    Watch,
+    Practice,
+    Mutators,
+    Leaderboard,
+    Family,
+    Options,
+    Quit,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum PracticeItem {
+    Drill(usize),
+    Back,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum PauseItem {
+    Resume,
+    Restart,
+    Options,
+    Photo,
+    Detach,
+    Quit,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum MutatorItem {
+    Toggle(Mutator),
+    Back,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum OptionItem {
+    Lives,
+    Hearts,
+    Difficulty,
+    Weather,
+    Ramp,
+    Banking,
+    DoubleOrNothing,
+    Typing,
+    Quiz,
+    Rewind,
+    DayNight,
+    Memory,
+    OneSwitch,
+    Keymap,
+    Assist,
+    Trajectory,
+    Pip,
+    ZoomFollow,
+    Haptics,
+    Pipes,
+    MusicVolume,
+    Speed,
+    Effects,
+    ClassicScoring,
+    ConfirmQuit,
+    Back,
+}
+
+/// The title menu, with the family leaderboard once more than one player has played
+/// here.
+fn title_menu(challenge: Option<&Challenge>, kiosk: bool) -> Menu<TitleItem> {
+    let start = if challenge.is_some() {
+        "Play challenge"
+    } else {
+        "Start"
+    };
+    let mut items = vec![MenuItem::action(TitleItem::Start, start)];
+    // A challenge file has only its seed, with no run to watch
+    if challenge.is_some_and(|c| !c.inputs.is_empty()) {
+        items.push(MenuItem::action(TitleItem::Watch, "Watch challenge"));
+    }
+    items.push(MenuItem::action(TitleItem::Practice, "Practice"));
+    // A challenge brings its own mutators
+    if challenge.is_none() {
+        items.push(MenuItem::action(TitleItem::Mutators, "Mutators"));
+    }
+    items.extend([MenuItem::action(TitleItem::Leaderboard, "Leaderboard")]);
+    if players::all().len() > 1 {
+        items.push(MenuItem::action(TitleItem::Family, "Family leaderboard"));
+    }
+    // A kiosk's settings are the organiser's, and it never quits
+    if !kiosk {
+        items.extend([
+            MenuItem::action(TitleItem::Options, "Options"),
+            MenuItem::action(TitleItem::Quit, "Quit"),
+        ]);
     }
+    Menu::new(items, MENU_WIDTH)
 }
 
+fn pause_menu() -> Menu<PauseItem> {
+    Menu::new(
+        vec![
+            MenuItem::action(PauseItem::Resume, "Resume"),
+            MenuItem::action(PauseItem::Restart, "Restart"),
+            MenuItem::action(PauseItem::Options, "Options"),
+            MenuItem::action(PauseItem::Photo, "Photo mode"),
+            MenuItem::action(PauseItem::Detach, "Detach"),
+            MenuItem::action(PauseItem::Quit, "Quit"),
+        ],
+        MENU_WIDTH,
+    )
+}
+
+fn practice_menu() -> Menu<PracticeItem> {
+    let mut items: Vec<_> = level::drills()
+        .into_iter()
+        .enumerate()
+        .map(|(i, drill)| MenuItem::action(PracticeItem::Drill(i), drill.name))
+        .collect();
+    items.push(MenuItem::action(PracticeItem::Back, "Back"));
+    Menu::new(items, MENU_WIDTH)
+}
+
+fn mutators_menu(mutators: Mutators) -> Menu<MutatorItem> {
+    let mut items: Vec<_> = Mutator::ALL
+        .into_iter()
+        .map(|mutator| {
+            let label = format!("{:<16}{}", mutator.name(), mutator.multiplier_label());
+            MenuItem::toggle(MutatorItem::Toggle(mutator), label, mutators.has(mutator))
+        })
+        .collect();
+    items.push(MenuItem::action(MutatorItem::Back, "Back"));
+    Menu::new(items, MENU_WIDTH)
+}
+
+fn options_menu(config: &Config) -> Menu<OptionItem> {
+    let weather_index = WeatherSetting::ALL
+        .iter()
+        .position(|&setting| setting == config.weather)
+        .unwrap_or(0);
+    let difficulty_index = Difficulty::ALL
+        .iter()
+        .position(|&d| d == config.difficulty)
+        .unwrap_or(0);
+    let keymap_index = Preset::ALL
+        .iter()
+        .position(|&p| p == config.keymap)
+        .unwrap_or(0);
+    let ramp_index = Curve::ALL
+        .iter()
+        .position(|&c| c == config.ramp)
+        .unwrap_or(0);
+    let pipes_index = PipeStyle::ALL
+        .iter()
+        .position(|&s| s == config.pipe_style)
+        .unwrap_or(0);
+    Menu::new(
+        vec![
+            MenuItem::toggle(OptionItem::Lives, "Lives mode", config.lives.is_some()),
+            MenuItem::slider(
+                OptionItem::Hearts,
+                "Hearts",
+                config.lives.unwrap_or(DEFAULT_LIVES) as i32,
+                1,
+                MAX_LIVES as i32,
+            ),
+            MenuItem::choice(
+                OptionItem::Difficulty,
+                "Difficulty",
+                Difficulty::ALL.iter().map(|d| d.key()).collect(),
+                difficulty_index,
+            ),
+            MenuItem::choice(
+                OptionItem::Weather,
+                "Weather",
+                WeatherSetting::ALL.iter().map(|s| s.key()).collect(),
+                weather_index,
+            ),
+            MenuItem::choice(
+                OptionItem::Ramp,
+                "Speed ramp",
+                Curve::ALL.iter().map(|c| c.key()).collect(),
+                ramp_index,
+            ),
+            MenuItem::toggle(OptionItem::Banking, "Banking", config.banking),
+            MenuItem::toggle(
+                OptionItem::DoubleOrNothing,
+                "Double or nothing",
+                config.double_or_nothing,
+            ),
+            MenuItem::toggle(OptionItem::Typing, "Typing mode", config.typing),
+            MenuItem::toggle(OptionItem::Quiz, "Quiz gates", config.quiz),
+            MenuItem::toggle(OptionItem::Rewind, "Rewind", config.rewind),
+            MenuItem::toggle(OptionItem::DayNight, "Day/night cycle", config.day_night),
+            MenuItem::toggle(OptionItem::Memory, "Memory mode", config.memory),
+            MenuItem::toggle(OptionItem::OneSwitch, "One-switch mode", config.one_switch),
+            MenuItem::choice(
+                OptionItem::Keymap,
+                "Keys",
+                Preset::ALL.iter().map(|p| p.key()).collect(),
+                keymap_index,
+            ),
+            MenuItem::toggle(OptionItem::Assist, "Gap assist arrow", config.assist),
+            MenuItem::toggle(
+                OptionItem::Trajectory,
+                "Trajectory preview",
+                config.trajectory,
+            ),
+            MenuItem::toggle(OptionItem::Pip, "Best run inset", config.pip),
+            MenuItem::toggle(
+                OptionItem::ZoomFollow,
+                "Zoom-follow camera",
+                config.zoom_follow,
+            ),
+            MenuItem::toggle(OptionItem::Haptics, "Haptic bells", config.haptics),
+            MenuItem::choice(
+                OptionItem::Pipes,
+                "Pipes",
+                PipeStyle::ALL.iter().map(|s| s.key()).collect(),
+                pipes_index,
+            ),
+            MenuItem::slider(
+                OptionItem::MusicVolume,
+                "Music volume",
+                config.music_volume as i32,
+                0,
+                music::MAX_VOLUME as i32,
+            ),
+            MenuItem::choice(
+                OptionItem::Speed,
+                "Game speed",
+                SPEED_LABELS.to_vec(),
+                ((config.speed - MIN_SPEED) / SPEED_STEP) as usize,
+            ),
+            MenuItem::choice(
+                OptionItem::Effects,
+                "Effects",
+                INTENSITY_LABELS.to_vec(),
+                ((config.effects + INTENSITY_STEP / 2) / INTENSITY_STEP) as usize,
+            ),
+            MenuItem::toggle(
+                OptionItem::ClassicScoring,
+                "Classic scoring",
+                config.scoring == ScoringMode::Classic,
+            ),
+            MenuItem::toggle(
+                OptionItem::ConfirmQuit,
+                "Confirm quit mid-run",
+                config.confirm_quit,
+            ),
+            MenuItem::action(OptionItem::Back, "Back"),
+        ],
+        MENU_WIDTH,
+    )
+}
+
+/// Read the options screen back into a config.
+fn config_from_options(menu: &Menu<OptionItem>, base: &Config) -> Config {
+    let mut config = base.clone();
+    config.lives = menu
+        .toggle(OptionItem::Lives)
+        .then_some(menu.slider(OptionItem::Hearts) as u8);
+    config.difficulty = Difficulty::ALL[menu.choice(OptionItem::Difficulty)];
+    config.weather = WeatherSetting::ALL[menu.choice(OptionItem::Weather)];
+    config.ramp = Curve::ALL[menu.choice(OptionItem::Ramp)];
+    config.banking = menu.toggle(OptionItem::Banking);
+    config.double_or_nothing = menu.toggle(OptionItem::DoubleOrNothing);
+    config.typing = menu.toggle(OptionItem::Typing);
+    config.quiz = menu.toggle(OptionItem::Quiz);
+    config.rewind = menu.toggle(OptionItem::Rewind);
+    config.day_night = menu.toggle(OptionItem::DayNight);
+    config.memory = menu.toggle(OptionItem::Memory);
+    config.one_switch = menu.toggle(OptionItem::OneSwitch);
+    config.keymap = Preset::ALL[menu.choice(OptionItem::Keymap)];
+    config.assist = menu.toggle(OptionItem::Assist);
+    config.trajectory = menu.toggle(OptionItem::Trajectory);
+    config.pip = menu.toggle(OptionItem::Pip);
+    config.zoom_follow = menu.toggle(OptionItem::ZoomFollow);
+    config.haptics = menu.toggle(OptionItem::Haptics);
+    config.pipe_style = PipeStyle::ALL[menu.choice(OptionItem::Pipes)];
+    config.music_volume = menu.slider(OptionItem::MusicVolume) as u8;
+    config.speed = MIN_SPEED + menu.choice(OptionItem::Speed) as u8 * SPEED_STEP;
+    config.effects = menu.choice(OptionItem::Effects) as u8 * INTENSITY_STEP;
+    config.confirm_quit = menu.toggle(OptionItem::ConfirmQuit);
+    config.scoring = if menu.toggle(OptionItem::ClassicScoring) {
+        ScoringMode::Classic
+    } else {
+        ScoringMode::Style
+    };
+    config
+}
+
+/// Countdown for the revive mini-game: the player must press `key` before it expires.
+#[derive(Clone)]
+struct ReactionTimer {
+    key: char,
+    remaining: u32,
+    total: u32,
+}
+
+impl ReactionTimer {
+    fn new(key: char, ticks: u32) -> Self {
+        Self {
+            key,
+            remaining: ticks,
+            total: ticks,
+        }
+    }
+
+    /// Advance one tick. Returns true once the time is up.
+    fn tick(&mut self) -> bool {
+        self.remaining = self.remaining.saturating_sub(1);
+        self.remaining == 0
+    }
+
+    fn matches(&self, c: char) -> bool {
+        c.eq_ignore_ascii_case(&self.key)
+    }
+
+    /// Fraction of the window still left, from 1.0 down to 0.0.
+    fn fraction_left(&self) -> f32 {
+        self.remaining as f32 / self.total as f32
+    }
+}
+
+/// A finished run's seed, score and inputs, which is all it takes to replay it.
+struct BestRun {
+    seed: u64,
+    score: u32,
+    inputs: Vec<(u32, Input)>,
+}
+
+#[derive(Clone)]
 struct Pipe {
     x: i32,
+    /// Columns across, `PIPE_WIDTH` but for a banking burst.
+    width: u16,
     gap_y: u16,
+    /// Rows of open space starting at `gap_y`.
+    gap: u16,
     passed: bool,
+    /// Closest the bird has come to a gap edge while inside this pipe.
+    min_clearance: u16,
+    flapped: bool,
+    clipped: bool,
+    /// Passing this pipe turns gravity upside down.
+    flip: bool,
+    /// How much of the pipe is drawn in memory mode, from 1.0 down to 0.0 once it has
+    /// faded out, and ticks since it did.
+    alpha: f32,
+    faded_ticks: u32,
+    /// The sum that opens the gap, on a quiz gate.
+    quiz: Option<Quiz>,
 }
 
 impl Pipe {
-    fn new(x: i32, gap_y: u16) -> Self {
+    fn new(x: i32, gap_y: u16, gap: u16) -> Self {
         Self {
             x,
+            width: PIPE_WIDTH,
             gap_y,
+            gap,
             passed: false,
+            min_clearance: u16::MAX,
+            flapped: false,
+            clipped: false,
+            flip: false,
+            alpha: 1.0,
+            faded_ticks: 0,
+            quiz: None,
         }
     }
 
+    /// Scroll one column to the left.
     fn update(&mut self) {
-        self.x -= PIPE_SPEED as i32;
+        self.x -= 1;
     }
 
     fn is_offscreen(&self) -> bool {
-        self.x + PIPE_WIDTH as i32 <= 0
+        self.x + self.width as i32 <= 0
     }
 
-    fn collides_with(&self, bird_x: u16, bird_y: u16) -> bool {
-        let bird_x = bird_x as i32;
-        if bird_x + 2 > self.x
-            && bird_x < self.x + PIPE_WIDTH as i32
-            && (bird_y < self.gap_y || bird_y >= self.gap_y + PIPE_GAP)
-        {
-            return true;
+    /// What the bird can hit: the pipe's walls.
+    fn collider(&self) -> Collider {
+        Collider {
+            bounds: self.bounds(),
+            walls: self.walls(),
         }
-        false
     }
 
-    fn has_bird_passed(&self, bird_x: u16) -> bool {
-        bird_x as i32 > self.x + PIPE_WIDTH as i32
+    /// The pipe's columns, all the way up and down.
+    fn bounds(&self) -> Aabb {
+        Aabb::columns(self.x as f32, (self.x + self.width as i32) as f32)
     }
-}
 
-fn get_highscore_path() -> Option<PathBuf> {
-    env::var("HOME").ok().map(|home| {
-        let mut path = PathBuf::from(home);
-        path.push(".tflap_highscore");
-        path
-    })
-}
+    /// The pipe above the gap and below it. A shut quiz gate walls the gap off too.
+    fn walls(&self) -> [Aabb; 2] {
+        let bounds = self.bounds();
+        let gap_top = if self.is_shut() {
+            f32::INFINITY
+        } else {
+            self.gap_y as f32
+        };
+        [
+            Aabb {
+                bottom: gap_top,
+                ..bounds
+            },
+            Aabb {
+                top: (self.gap_y + self.gap) as f32,
+                ..bounds
+            },
+        ]
+    }
+
+    /// A quiz gate not yet answered right, which nothing gets through.
+    fn is_shut(&self) -> bool {
+        self.quiz.as_ref().is_some_and(|quiz| !quiz.is_open())
+    }
+
+    fn contains_column(&self, bird: Aabb) -> bool {
+        self.bounds().overlaps(bird)
+    }
 
-fn load_highscore() -> u32 {
-    if let Some(path) = get_highscore_path() {
-        if let Ok(content) = fs::read_to_string(&path) {
-            return content.trim().parse().unwrap_or(0);
+    /// Rows between the bird and the nearest gap edge, `None` if it is outside the gap.
+    fn clearance(&self, bird: Pos) -> Option<u16> {
+        let row = bird.row();
+        let top = self.gap_y as i32;
+        let bottom = (self.gap_y + self.gap) as i32 - 1;
+        if row < top || row > bottom {
+            return None;
         }
+        Some((row - top).min(bottom - row) as u16)
+    }
+
+    fn pass_summary(&self, streak: u32) -> PipePass {
+        PipePass {
+            min_clearance: self.min_clearance,
+            center_clearance: (self.gap - 1) / 2,
+            flapped: self.flapped,
+            clipped: self.clipped,
+            streak,
+        }
+    }
+
+    /// Whether `bird` is past the pipe. By the rules of codes from before boxes, that
+    /// takes a column more.
+    fn has_bird_passed(&self, bird: Aabb, rules: u8) -> bool {
+        if rules < challenge::BOXES {
+            return bird.left > self.bounds().right;
+        }
+        self.bounds().is_left_of(bird)
     }
-    0
 }
 
-fn save_highscore(score: u32) {
-    if let Some(path) = get_highscore_path() {
-        let _ = fs::write(&path, score.to_string());
+/// Rows a player can climb in `ticks`, flapping as fast as they can tap.
+fn max_climb(ticks: i32) -> f32 {
+    let mut velocity = 0.0;
+    let mut climbed = 0.0;
+    let mut since_flap = FLAP_TICKS;
+    for _ in 0..ticks {
+        if since_flap == FLAP_TICKS {
+            velocity = JUMP_VELOCITY;
+            since_flap = 0;
+        }
+        since_flap += 1;
+        velocity += GRAVITY;
+        climbed -= velocity;
     }
+    climbed
+}
+
+/// The highest `gap_y`, as the smallest row, that a bird leaving `previous` by its top row
+/// can climb into before reaching a pipe at `x`, with some room left for gusts. Falling
+/// is never the problem, as a flap stops any fall at once. The pipes scroll
+/// `pipe_speed` columns a tick on the way.
+fn highest_reachable_gap(previous: &Pipe, x: i32, gap: u16, pipe_speed: f32) -> u16 {
+    let ticks = ((x - previous.x - previous.width as i32 - BIRD_WIDTH) as f32 / pipe_speed) as i32;
+    let climb = max_climb(ticks) * REACH_MARGIN;
+    // The next gap's bottom row must come within that climb of the previous gap's top
+    let bottom = previous.gap_y as f32 - climb;
+    (bottom.ceil() as i32 - gap as i32 + 1).max(0) as u16
+}
+
+fn get_scorecard_path() -> Option<PathBuf> {
+    paths::file("scorecard.txt")
 }
 
 struct Game {
     bird: Bird,
     pipes: Vec<Pipe>,
     score: u32,
+    /// Best score in the current leaderboard category.
     high_score: u32,
     is_new_record: bool,
     state: GameState,
     width: u16,
     height: u16,
+    config: Config,
+    /// Follows the options screen right away, unlike the rest of the config.
+    keymap: Keymap,
+    lives: u8,
+    invincible_ticks: u32,
+    /// Pipes passed since the last hit.
+    streak: u32,
+    breakdown: ScoreBreakdown,
+    bonus_popup: Option<(&'static str, u32)>,
+    reaction: Option<ReactionTimer>,
+    weather: WeatherSystem,
+    day_cycle: DayCycle,
+    seed: u64,
+    rng: StdRng,
+    events: EventBus,
+    celebration: Celebration,
+    /// Recent events along the top of the HUD.
+    ticker: Ticker,
+    /// Current speed as a percentage; starts each run at the configured speed.
+    speed: u8,
+    /// How fast the pipes come and how close together, for this run.
+    ramp: SpeedRamp,
+    /// Points banked this run, in banking mode.
+    bank: Bank,
+    /// This run's go at double or nothing, once it has been offered.
+    wager: Option<Wager>,
+    /// The word being typed and how the typing has gone, in typing mode.
+    typing: Option<Typing>,
+    /// Quiz gates answered this run.
+    quiz: Tally,
+    /// The last few ticks of the run to go back through, and the meter, in rewind mode.
+    rewind: Rewind<Snapshot>,
+    /// Whether to go back a tick instead of simulating the next one.
+    rewinding: bool,
+    /// The tempo pipes come in to, in rhythm mode.
+    rhythm: Option<BeatClock>,
+    /// The marathon so far, in marathon mode.
+    marathon: Option<Marathon>,
+    /// Fraction of a column the pipes have yet to scroll.
+    scroll: f32,
+    /// Whole columns scrolled this run, for moving the ground along.
+    travelled: u32,
+    /// Ticks simulated so far this run.
+    tick: u32,
+    telemetry: RunTelemetry,
+    /// Best score before this run started, for the summary comparison.
+    previous_best: u32,
+    /// Replays re-simulate a finished run and must not touch the save files.
+    persist: bool,
+    status: Option<String>,
+    /// Keeps the live score in a file for a status bar, when asked to.
+    status_file: Option<StatusExporter>,
+    /// Background music, in the player's own sessions.
+    music: Option<Music>,
+    /// Bell patterns for game events, when asked for.
+    haptics: Option<Haptics>,
+    /// Terminal problems found at startup, shown on the title screen.
+    warnings: Vec<String>,
+    /// Every run finished this session, for `--json-summary`.
+    runs: Vec<summary::Run>,
+    /// Glyphs and colors the terminal can take.
+    display: Display,
+    /// Recent scores in the current scoring mode, oldest first.
+    recent_scores: Vec<u32>,
+    /// Best scores in the current category over the rolling week and month.
+    week_best: u32,
+    month_best: u32,
+    is_new_week_best: bool,
+    /// Today's play time against the daily limit, when there is one.
+    time_limit: Option<TimeLimit>,
+    /// The focus and break periods, when the game is a pomodoro break timer.
+    pomodoro: Option<Pomodoro>,
+    title_menu: Menu<TitleItem>,
+    pause_menu: Menu<PauseItem>,
+    /// Photo mode's camera and filter, while it's open over the pause menu.
+    photo: Option<Photo>,
+    options_menu: Option<Menu<OptionItem>>,
+    practice_menu: Option<Menu<PracticeItem>>,
+    mutators_menu: Option<Menu<MutatorItem>>,
+    /// The practice drill being looped, if any. Drills never touch scores or history.
+    drill: Option<LoopingSpawner>,
+    drill_attempts: u32,
+    /// Options changed mid-run, applied when the next run starts.
+    pending_config: Option<Config>,
+    quit_requested: bool,
+    /// The run was put away for `tflap attach`, so leaving doesn't end it.
+    detached: bool,
+    /// Flown by the demo bot with nothing drawn but the world.
+    screensaver: bool,
+    /// Extra birds for a stress test, which also packs the pipes closer together.
+    flock: Vec<FlockBird>,
+    flock_crashes: u32,
+    /// The bot flying the player's bird, in the screensaver and the stress test.
+    pilot: Option<Box<dyn Strategy>>,
+    /// Draw the profiler's summary, toggled with F3 in profiling builds.
+    show_profile: bool,
+    /// Turns effects off when frames take too long to draw.
+    budget: FrameBudget,
+    /// Where the loop and the kiosk's timers get the time.
+    clock: Rc<dyn Clock>,
+    /// How strongly to draw effects, from the settings.
+    effects: Effects,
+    /// The shared run this session is trying to beat.
+    challenge: Option<Challenge>,
+    /// Unattended play, in kiosk mode.
+    kiosk: Option<Kiosk>,
+    /// The challenge file being played, whose challenge has the player's best on it as
+    /// the score to beat and no run to watch.
+    event: Option<ChallengeFile>,
+    /// Asks the main loop to open the challenge replay.
+    watch_requested: bool,
+    rivals: Vec<Rival>,
+    /// "Passed <rival>" message and ticks it has left.
+    rival_popup: Option<(String, u32)>,
+    /// The challenge's own run, replayed in step with this one when racing its ghost.
+    ghost: Option<Box<ReplayViewer>>,
+    /// The best run this session on the current seed, and its replay in the corner when
+    /// the inset is on.
+    best_run: Option<BestRun>,
+    pip: Option<Box<ReplayViewer>>,
+    /// The view of the world with zoom-follow on.
+    camera: Camera,
+    /// State to go back to if the quit prompt is declined.
+    resume_state: GameState,
+    leaderboard: Leaderboard,
+    category: Category,
+    /// Index into the leaderboard's categories while the leaderboard screen is open.
+    leaderboard_view: Option<usize>,
+    /// Everyone's leaderboards and the category shown, while the family leaderboard is
+    /// open.
+    family_view: Option<(Family, usize)>,
+    /// Scroll position of the help overlay while it is open.
+    help_scroll: Option<usize>,
 }
 
 impl Game {
-    fn new(width: u16, height: u16) -> Self {
+    fn new(width: u16, height: u16, config: Config) -> Self {
+        let mut game = Self::with_seed(width, height, config, rand::random(), true);
+        game.load_recent_scores();
+        game.rivals = rivals::load_rivals();
+        game.state = GameState::Title;
+        game
+    }
+
+    /// Start on the title screen with a shared run's seed and board size.
+    fn with_challenge(config: Config, challenge: Challenge) -> Self {
+        let mut game = Self::with_seed(
+            challenge.width,
+            challenge.height,
+            config,
+            challenge.seed,
+            true,
+        );
+        game.load_recent_scores();
+        game.rivals = rivals::load_rivals();
+        game.state = GameState::Title;
+        game.title_menu = title_menu(Some(&challenge), false);
+        game.challenge = Some(challenge);
+        game.start_ghost();
+        game
+    }
+
+    /// Start on the title screen with a challenge file's seed and board size.
+    fn with_event(config: Config, event: ChallengeFile) -> Self {
+        let challenge = Challenge {
+            score: event.best,
+            ..Challenge::new(
+                event.challenge.seed,
+                event.challenge.width,
+                event.challenge.height,
+                &config,
+            )
+        };
+        let mut game = Self::with_challenge(config, challenge);
+        game.event = Some(event);
+        game
+    }
+
+    /// Rebuild a detached run by playing its inputs up to where it was left, and pause it
+    /// there.
+    fn resume(mut config: Config, detached: &Detached) -> io::Result<Self> {
+        let invalid = |err: String| io::Error::new(io::ErrorKind::InvalidData, err);
+        let run = Challenge::decode(&detached.run).map_err(invalid)?;
+        let challenge = match &detached.challenge {
+            Some(code) => Some(Challenge::decode(code).map_err(invalid)?),
+            None => None,
+        };
+        run.apply(&mut config);
+        config.assist = detached.assist;
+        config.trajectory = detached.trajectory;
+        let mut game = Self::with_seed(run.width, run.height, config, run.seed, true);
+        game.load_recent_scores();
+        game.rivals = rivals::load_rivals();
+        if let Some(challenge) = challenge {
+            game.title_menu = title_menu(Some(&challenge), false);
+            game.challenge = Some(challenge);
+            game.start_ghost();
+        }
+
+        let mut inputs = run.inputs.iter().peekable();
+        while game.tick < detached.tick && game.state != GameState::GameOver {
+            while let Some(&(_, input)) = inputs.next_if(|&&(tick, _)| tick <= game.tick) {
+                game.apply_input(input);
+            }
+            game.update();
+        }
+        // Inputs on the last tick came in after it was simulated
+        while let Some(&(_, input)) = inputs.next_if(|&&(tick, _)| tick <= game.tick) {
+            game.apply_input(input);
+        }
+        game.pause();
+        Ok(game)
+    }
+
+    /// A run for the demo bot to fly: slow, unranked, and without anything to celebrate.
+    fn screensaver(width: u16, height: u16, mut config: Config) -> Self {
+        config.speed = MIN_SPEED;
+        config.scoring = ScoringMode::Classic;
+        config.lives = None;
+        config.double_or_nothing = false;
+        config.typing = false;
+        config.quiz = false;
+        config.rewind = false;
+        config.rhythm = None;
+        config.marathon = false;
+        config.assist = false;
+        config.trajectory = false;
+        config.milestones = Vec::new();
+        config.day_night = true;
+        let mut game = Self::with_seed(width, height, config, rand::random(), false);
+        game.screensaver = true;
+        game.pilot = Some(game.config.bot.strategy());
+        game
+    }
+
+    /// A run for a stress test: `birds` bots spread across the screen, with pipes at a
+    /// third of the usual spacing and rain falling. The player's bird flies too.
+    fn stress(width: u16, height: u16, mut config: Config, birds: usize) -> Self {
+        config.scoring = ScoringMode::Classic;
+        config.lives = None;
+        config.double_or_nothing = false;
+        config.typing = false;
+        config.quiz = false;
+        config.rewind = false;
+        config.rhythm = None;
+        config.marathon = false;
+        config.weather = WeatherSetting::Fixed(Weather::Rain);
+        config.milestones = Vec::new();
+        let mut game = Self::with_seed(width, height, config, rand::random(), false);
+        game.pilot = Some(game.config.bot.strategy());
+        // Keep clear of the player's bird and of the right edge where pipes come in
+        let span = (width as usize * 2 / 3).max(1);
+        let rows = (height as usize / 2).max(1);
+        game.flock = (0..birds)
+            .map(|i| FlockBird {
+                bird: Bird::new(
+                    (2 + i * span / birds) as u16,
+                    (height as usize / 4 + i * 7 % rows) as f32,
+                ),
+                pilot: game.config.bot.strategy(),
+            })
+            .collect();
+        game.pipes.clear();
+        game.spawn_initial_pipes();
+        game
+    }
+
+    fn with_seed(width: u16, height: u16, config: Config, seed: u64, persist: bool) -> Self {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let leaderboard = Leaderboard::load(config.player.as_deref());
+        let category = Category::for_config(&config);
+        let high_score = leaderboard.best(&category);
+        let effects = Effects::new(config.effects);
+        let bird = Bird {
+            flipped: config.mutators.has(Mutator::Inverted),
+            ..Bird::new(BIRD_X, (height / 2) as f32)
+        };
+        let marathon = start_marathon(&config, persist);
         let mut game = Self {
-            bird: Bird::new((height / 2) as f32),
+            bird,
             pipes: Vec::new(),
-            score: 0,
-            high_score: load_highscore(),
+            score: marathon.as_ref().map_or(0, |m| m.carried),
+            high_score,
             is_new_record: false,
             state: GameState::Playing,
             width,
             height,
+            weather: WeatherSystem::new(config.weather, &mut rng),
+            day_cycle: DayCycle::new(config.day_night),
+            celebration: Celebration::new(config.milestones.clone(), seed),
+            ticker: Ticker::new(config.milestones.clone()),
+            speed: config.speed,
+            ramp: SpeedRamp::new(config.ramp),
+            bank: Bank::new(config.banking),
+            wager: None,
+            typing: (config.typing && !config.one_switch).then(|| Typing::new(seed)),
+            quiz: Tally::default(),
+            rewind: Rewind::default(),
+            rewinding: false,
+            rhythm: config.rhythm.map(beat_clock),
+            marathon,
+            lives: config.lives.unwrap_or(1),
+            keymap: Keymap::new(config.keymap, config.boss_key, config.restart_key),
+            config,
+            invincible_ticks: 0,
+            streak: 0,
+            breakdown: ScoreBreakdown::default(),
+            bonus_popup: None,
+            reaction: None,
+            seed,
+            rng,
+            events: EventBus::default(),
+            scroll: 0.0,
+            travelled: 0,
+            tick: 0,
+            telemetry: RunTelemetry::default(),
+            previous_best: high_score,
+            persist,
+            status: None,
+            recent_scores: Vec::new(),
+            week_best: 0,
+            month_best: 0,
+            is_new_week_best: false,
+            time_limit: None,
+            pomodoro: None,
+            title_menu: title_menu(None, false),
+            pause_menu: pause_menu(),
+            photo: None,
+            options_menu: None,
+            practice_menu: None,
+            mutators_menu: None,
+            drill: None,
+            drill_attempts: 0,
+            pending_config: None,
+            quit_requested: false,
+            detached: false,
+            screensaver: false,
+            flock: Vec::new(),
+            flock_crashes: 0,
+            pilot: None,
+            show_profile: false,
+            budget: FrameBudget::default(),
+            clock: Rc::new(SystemClock),
+            effects,
+            challenge: None,
+            event: None,
+            kiosk: None,
+            watch_requested: false,
+            rivals: Vec::new(),
+            rival_popup: None,
+            ghost: None,
+            best_run: None,
+            pip: None,
+            camera: Camera::default(),
+            resume_state: GameState::Playing,
+            leaderboard,
+            category,
+            leaderboard_view: None,
+            family_view: None,
+            help_scroll: None,
+            status_file: None,
+            music: None,
+            haptics: None,
+            warnings: Vec::new(),
+            runs: Vec::new(),
+            display: Display::default(),
         };
+        game.spawn_initial_pipes();
+        game
+    }
 
-        // Spawn initial pipes spread across the screen
-        let mut rng = rand::thread_rng();
-        for i in 0..4 {
-            let min_gap_y = 3;
-            let max_gap_y = game.height.saturating_sub(PIPE_GAP + 3);
-            let gap_y = rng.gen_range(min_gap_y..=max_gap_y);
-            let x = width as i32 / 2 + (i * 40);
-            game.pipes.push(Pipe::new(x, gap_y));
+    fn load_recent_scores(&mut self) {
+        let runs = history::load_history(self.config.player.as_deref());
+        let now = storage::unix_now();
+        self.recent_scores = history::recent_scores(&runs, &self.category, HISTORY_SPARKLINE_RUNS);
+        self.week_best = history::best_within(&runs, &self.category, history::WEEK, now);
+        self.month_best = history::best_within(&runs, &self.category, history::MONTH, now);
+        let ticks_per_minute = 60_000 / TICK_RATE.as_millis() as u32;
+        self.time_limit = (self.persist && self.config.daily_limit > 0)
+            .then(|| TimeLimit::new(self.config.daily_limit, ticks_per_minute, &runs, now));
+    }
+
+    /// Switch to a new config: right away on the title screen, otherwise from the next run.
+    fn apply_config(&mut self, config: Config) {
+        self.keymap = Keymap::new(config.keymap, config.boss_key, config.restart_key);
+        self.effects = Effects::new(config.effects);
+        if self.persist && config.haptics != self.settings().haptics {
+            self.haptics = Haptics::for_config(&config);
+        }
+        if self.persist && config.music_volume != self.settings().music_volume {
+            match &self.music {
+                Some(music) => music.set_volume(config.music_volume),
+                None => match Music::start(&config) {
+                    Ok(music) => self.music = music,
+                    Err(e) => self.status = Some(e),
+                },
+            }
         }
+        self.pending_config = Some(config);
+        if self.state == GameState::Title {
+            self.reset();
+            self.state = GameState::Title;
+        }
+    }
 
-        game
+    fn switch_config(&mut self, config: Config) {
+        self.weather = WeatherSystem::new(config.weather, &mut self.rng);
+        self.day_cycle = DayCycle::new(config.day_night);
+        self.config = config;
+        let category = Category::for_config(&self.config);
+        if category != self.category {
+            self.high_score = self.leaderboard.best(&category);
+            self.category = category;
+            self.load_recent_scores();
+        }
     }
 
-    fn spawn_pipe(&mut self) {
-        let mut rng = rand::thread_rng();
-        let min_gap_y = 3;
-        let max_gap_y = self.height.saturating_sub(PIPE_GAP + 3);
-        let gap_y = rng.gen_range(min_gap_y..=max_gap_y);
+    fn pause(&mut self) {
+        // Nobody may come back to a kiosk's paused run
+        if self.state == GameState::Playing && self.kiosk.is_none() {
+            self.pause_menu = pause_menu();
+            self.state = GameState::Paused;
+        }
+    }
 
-        // Calculate next pipe position - always 40 pixels after the last pipe
-        let new_x = if let Some(last_pipe) = self.pipes.last() {
-            last_pipe.x + 40
-        } else {
-            self.width as i32
-        };
+    /// The config the player last chose, even if it only applies from the next run.
+    fn settings(&self) -> &Config {
+        self.pending_config.as_ref().unwrap_or(&self.config)
+    }
 
-        self.pipes.push(Pipe::new(new_x, gap_y));
+    fn open_options(&mut self) {
+        self.options_menu = Some(options_menu(self.settings()));
     }
 
-    fn update(&mut self) {
-        if self.state != GameState::Playing {
+    /// Handle Q/Esc: quit right away, or ask first if a run worth keeping is underway.
+    /// Drills go back to the title screen instead.
+    fn request_quit(&mut self) {
+        if self.drill.is_some() {
+            self.drill = None;
+            self.reset();
+            self.state = GameState::Title;
             return;
         }
+        if self.kiosk.is_some() {
+            return;
+        }
+        let in_run = matches!(
+            self.state,
+            GameState::Playing | GameState::Revive | GameState::Wager
+        );
+        if in_run && self.settings().confirm_quit && self.score >= QUIT_PROMPT_MIN_SCORE {
+            self.resume_state = self.state;
+            self.state = GameState::ConfirmQuit;
+        } else {
+            self.quit_requested = true;
+        }
+    }
 
-        self.bird.update();
-
-        // Check boundary collision
-        if self.bird.y < 0.0 || self.bird.y as u16 >= self.height {
-            self.state = GameState::GameOver;
-            self.check_and_save_highscore();
+    /// Save the run for `tflap attach` and leave without ending it.
+    fn detach(&mut self) {
+        if !matches!(self.state, GameState::Playing | GameState::Paused)
+            || self.drill.is_some()
+            || !self.persist
+            || self.kiosk.is_some()
+        {
             return;
         }
+        let detached = Detached {
+            run: self.challenge_code(),
+            tick: self.tick,
+            challenge: self.challenge.as_ref().map(Challenge::encode),
+            assist: self.config.assist,
+            trajectory: self.config.trajectory,
+        };
+        match detach::save(&detached) {
+            Ok(()) => {
+                self.detached = true;
+                self.quit_requested = true;
+            }
+            Err(err) => {
+                self.pause();
+                self.status = Some(format!("Could not detach: {}", err));
+            }
+        }
+    }
 
-        // Update pipes and check for scoring
-        let bird_y = self.bird.y as u16;
-        for pipe in &mut self.pipes {
-            pipe.update();
+    fn answer_quit(&mut self, quit: bool) {
+        if quit {
+            self.quit_requested = true;
+        } else {
+            self.state = self.resume_state;
+        }
+    }
 
-            // Check if bird passed this pipe
-            if !pipe.passed && pipe.has_bird_passed(BIRD_X) {
-                pipe.passed = true;
-                self.score += 1;
+    /// Open the help overlay, pausing a run first.
+    fn open_help(&mut self) {
+        self.pause();
+        self.help_scroll = Some(0);
+    }
+
+    /// Scroll or close the help overlay. Returns false if it isn't open.
+    fn handle_help_key(&mut self, action: Option<Action>, code: KeyCode) -> bool {
+        let Some(scroll) = self.help_scroll else {
+            return false;
+        };
+        let page = self.help_page();
+        let last = self.help_lines().len().saturating_sub(page);
+        let scroll = match (action, code) {
+            (_, KeyCode::PageUp) | (Some(Action::Left), _) => scroll.saturating_sub(page),
+            (_, KeyCode::PageDown) | (Some(Action::Right), _) => scroll + page,
+            (_, KeyCode::Home) => 0,
+            (_, KeyCode::End) => last,
+            (Some(Action::Up), _) => scroll.saturating_sub(1),
+            (Some(Action::Down), _) => scroll + 1,
+            (Some(Action::Back | Action::Help | Action::Select | Action::Quit), _) => {
+                self.help_scroll = None;
+                return true;
             }
+            _ => scroll,
+        };
+        self.help_scroll = Some(scroll.min(last));
+        true
+    }
+
+    /// Feed a key to whichever menu is on screen. Returns false if no menu wanted it.
+    fn handle_menu_key(&mut self, code: KeyCode) -> bool {
+        if !matches!(
+            code,
+            KeyCode::Up
+                | KeyCode::Down
+                | KeyCode::Left
+                | KeyCode::Right
+                | KeyCode::Tab
+                | KeyCode::Enter
+                | KeyCode::Char(' ')
+                | KeyCode::Esc
+        ) {
+            return false;
         }
 
-        // Check pipe collision
-        for pipe in &self.pipes {
-            if pipe.collides_with(BIRD_X, bird_y) {
-                self.state = GameState::GameOver;
-                self.check_and_save_highscore();
-                return;
+        if let Some(menu) = &mut self.options_menu {
+            let event = menu.handle_key(code);
+            self.on_options_event(event);
+            return true;
+        }
+        if let Some(menu) = &mut self.practice_menu {
+            let event = menu.handle_key(code);
+            self.on_practice_event(event);
+            return true;
+        }
+        if let Some(menu) = &mut self.mutators_menu {
+            let event = menu.handle_key(code);
+            self.on_mutators_event(event);
+            return true;
+        }
+        if let Some(index) = self.leaderboard_view {
+            let count = self.leaderboard_categories().len();
+            match code {
+                KeyCode::Left | KeyCode::Up => {
+                    self.leaderboard_view = Some((index + count - 1) % count)
+                }
+                KeyCode::Right | KeyCode::Down | KeyCode::Tab => {
+                    self.leaderboard_view = Some((index + 1) % count)
+                }
+                KeyCode::Esc | KeyCode::Enter | KeyCode::Char(' ') => self.leaderboard_view = None,
+                _ => {}
             }
+            return true;
         }
-
-        // Remove offscreen pipes
-        self.pipes.retain(|pipe| !pipe.is_offscreen());
-
-        // Spawn new pipe if the rightmost pipe has moved into view
-        if let Some(last_pipe) = self.pipes.last() {
-            if last_pipe.x < self.width as i32 - 20 {
-                self.spawn_pipe();
+        if let Some((family, index)) = &mut self.family_view {
+            let count = family.categories(&self.category).len();
+            match code {
+                KeyCode::Left | KeyCode::Up => *index = (*index + count - 1) % count,
+                KeyCode::Right | KeyCode::Down | KeyCode::Tab => *index = (*index + 1) % count,
+                KeyCode::Esc | KeyCode::Enter | KeyCode::Char(' ') => self.family_view = None,
+                _ => {}
             }
-        } else {
-            // If no pipes, spawn one at the right edge
-            let mut rng = rand::thread_rng();
-            let min_gap_y = 3;
-            let max_gap_y = self.height.saturating_sub(PIPE_GAP + 3);
-            let gap_y = rng.gen_range(min_gap_y..=max_gap_y);
-            self.pipes.push(Pipe::new(self.width as i32, gap_y));
+            return true;
+        }
+        match self.state {
+            GameState::Title => {
+                let event = self.title_menu.handle_key(code);
+                self.on_title_event(event);
+                true
+            }
+            GameState::Paused => {
+                let event = self.pause_menu.handle_key(code);
+                self.on_pause_event(event);
+                true
+            }
+            _ => false,
         }
     }
 
-    fn jump(&mut self) {
-        if self.state == GameState::Playing {
-            self.bird.jump();
+    fn handle_mouse(&mut self, event: MouseEvent) {
+        if self.leaderboard_view.is_some()
+            || self.family_view.is_some()
+            || self.photo.is_some()
+            || self.locked_out()
+        {
+            return;
+        }
+        if let Some(menu) = &mut self.options_menu {
+            let event = menu.handle_mouse(event);
+            self.on_options_event(event);
+            return;
+        }
+        if let Some(menu) = &mut self.practice_menu {
+            let event = menu.handle_mouse(event);
+            self.on_practice_event(event);
+            return;
+        }
+        if let Some(menu) = &mut self.mutators_menu {
+            let event = menu.handle_mouse(event);
+            self.on_mutators_event(event);
+            return;
+        }
+        match self.state {
+            GameState::Title => {
+                let event = self.title_menu.handle_mouse(event);
+                self.on_title_event(event);
+            }
+            GameState::Paused => {
+                let event = self.pause_menu.handle_mouse(event);
+                self.on_pause_event(event);
+            }
+            _ => {}
         }
     }
 
-    fn check_and_save_highscore(&mut self) {
-        if self.score > self.high_score {
-            self.high_score = self.score;
-            self.is_new_record = true;
-            save_highscore(self.high_score);
+    fn on_title_event(&mut self, event: Option<MenuEvent<TitleItem>>) {
+        match event {
+            Some(MenuEvent::Activated(TitleItem::Start)) => self.start(),
+            Some(MenuEvent::Activated(TitleItem::Watch)) => self.watch_requested = true,
+            Some(MenuEvent::Activated(TitleItem::Practice)) => {
+                self.practice_menu = Some(practice_menu())
+            }
+            Some(MenuEvent::Activated(TitleItem::Mutators)) => {
+                self.mutators_menu = Some(mutators_menu(self.settings().mutators))
+            }
+            Some(MenuEvent::Activated(TitleItem::Leaderboard)) => self.leaderboard_view = Some(0),
+            Some(MenuEvent::Activated(TitleItem::Family)) => {
+                self.family_view = Some((Family::load(), 0))
+            }
+            Some(MenuEvent::Activated(TitleItem::Options)) => self.open_options(),
+            Some(MenuEvent::Activated(TitleItem::Quit)) | Some(MenuEvent::Back) => {
+                self.quit_requested = self.kiosk.is_none();
+            }
+            _ => {}
         }
     }
 
-    fn reset(&mut self) {
-        self.bird.reset((self.height / 2) as f32);
-        self.pipes.clear();
-        self.score = 0;
-        self.is_new_record = false;
-        self.state = GameState::Playing;
+    /// Categories to page through on the leaderboard screen, current one first.
+    fn leaderboard_categories(&self) -> Vec<Category> {
+        let mut categories = vec![self.category.clone()];
+        categories.extend(
+            self.leaderboard
+                .categories()
+                .into_iter()
+                .filter(|c| *c != self.category),
+        );
+        categories
+    }
 
-        // Spawn initial pipes spread across the screen
-        let mut rng = rand::thread_rng();
-        for i in 0..4 {
-            let min_gap_y = 3;
-            let max_gap_y = self.height.saturating_sub(PIPE_GAP + 3);
-            let gap_y = rng.gen_range(min_gap_y..=max_gap_y);
-            let x = self.width as i32 / 2 + (i * 40);
-            self.pipes.push(Pipe::new(x, gap_y));
+    /// Move the photo mode camera, change the picture, or save it. Esc goes back to the
+    /// pause menu.
+    fn handle_photo_key(&mut self, code: KeyCode) {
+        let (width, height) = (self.width, self.height);
+        let Some(photo) = &mut self.photo else {
+            return;
+        };
+        let pan = match code {
+            KeyCode::Left => Some((-1, 0)),
+            KeyCode::Right => Some((1, 0)),
+            KeyCode::Up => Some((0, -1)),
+            KeyCode::Down => Some((0, 1)),
+            _ => None,
+        };
+        if let Some((dx, dy)) = pan {
+            photo.pan(dx, dy, width, height);
+            return;
+        }
+        match code {
+            KeyCode::Esc | KeyCode::Backspace => self.photo = None,
+            KeyCode::Char('z') | KeyCode::Char('Z') => photo.zoomed = !photo.zoomed,
+            KeyCode::Char('h') | KeyCode::Char('H') => photo.hud = !photo.hud,
+            KeyCode::Char('f') | KeyCode::Char('F') => photo.filter = photo.filter.next(),
+            KeyCode::Char(c @ ('t' | 'T' | 'p' | 'P')) => {
+                let png = c.eq_ignore_ascii_case(&'p');
+                let picture = self.photograph();
+                let stamp = Stamp::new(self.seed, &self.category);
+                let Some(photo) = &mut self.photo else {
+                    return;
+                };
+                photo.status = Some(match photo::save(&picture, png, &stamp) {
+                    Ok(path) => format!("Saved {}", path.display()),
+                    Err(err) => format!("Could not save the photo: {}", err),
+                });
+            }
+            _ => {}
         }
     }
 
-    fn draw(&self, stdout: &mut io::Stdout) -> io::Result<()> {
-        execute!(stdout, Clear(ClearType::All))?;
+    fn on_pause_event(&mut self, event: Option<MenuEvent<PauseItem>>) {
+        match event {
+            Some(MenuEvent::Activated(PauseItem::Resume)) | Some(MenuEvent::Back) => {
+                self.state = GameState::Playing;
+            }
+            Some(MenuEvent::Activated(PauseItem::Restart)) => self.retry(),
+            Some(MenuEvent::Activated(PauseItem::Options)) => self.open_options(),
+            Some(MenuEvent::Activated(PauseItem::Photo)) => {
+                let bird = self.view().to_screen(self.bird_pos());
+                self.photo = Some(Photo::new(bird, self.width, self.height));
+            }
+            Some(MenuEvent::Activated(PauseItem::Detach)) => self.detach(),
+            Some(MenuEvent::Activated(PauseItem::Quit)) => self.quit_requested = true,
+            _ => {}
+        }
+    }
 
-        // Draw pipes
-        execute!(stdout, SetForegroundColor(Color::Green))?;
-        for pipe in &self.pipes {
-            // Skip drawing if pipe is completely off screen
-            if pipe.x + PIPE_WIDTH as i32 <= 0 || pipe.x >= self.width as i32 {
-                continue;
+    fn on_practice_event(&mut self, event: Option<MenuEvent<PracticeItem>>) {
+        match event {
+            Some(MenuEvent::Activated(PracticeItem::Drill(index))) => self.start_drill(index),
+            Some(MenuEvent::Activated(PracticeItem::Back)) | Some(MenuEvent::Back) => {
+                self.practice_menu = None;
             }
+            _ => {}
+        }
+    }
 
-            // Only draw if x is positive
-            if pipe.x >= 0 {
-                let pipe_x = pipe.x as u16;
-                // Draw top pipe
-                for y in 0..pipe.gap_y {
-                    execute!(
-                        stdout,
-                        MoveTo(pipe_x, y),
-                        Print("█".repeat(PIPE_WIDTH as usize))
-                    )?;
-                }
-                // Draw bottom pipe
-                for y in (pipe.gap_y + PIPE_GAP)..self.height {
-                    execute!(
-                        stdout,
-                        MoveTo(pipe_x, y),
-                        Print("█".repeat(PIPE_WIDTH as usize))
-                    )?;
+    fn on_mutators_event(&mut self, event: Option<MenuEvent<MutatorItem>>) {
+        let Some(menu) = &self.mutators_menu else {
+            return;
+        };
+        match event {
+            Some(MenuEvent::Changed(_)) => {
+                let mut config = self.settings().clone();
+                for mutator in Mutator::ALL {
+                    config
+                        .mutators
+                        .set(mutator, menu.toggle(MutatorItem::Toggle(mutator)));
                 }
+                self.apply_config(config);
+            }
+            Some(MenuEvent::Activated(MutatorItem::Back)) | Some(MenuEvent::Back) => {
+                self.settings().save();
+                self.mutators_menu = None;
             }
+            _ => {}
         }
+    }
+
+    fn start_drill(&mut self, index: usize) {
+        let Some(drill) = level::drills().into_iter().nth(index) else {
+            return;
+        };
+        self.practice_menu = None;
+        self.drill = Some(LoopingSpawner::new(drill));
+        self.drill_attempts = 1;
+        self.reset();
+        self.start();
+    }
 
-        // Draw bird
-        execute!(stdout, SetForegroundColor(Color::Yellow))?;
-        let bird_y = self.bird.y as u16;
-        if bird_y < self.height {
-            execute!(stdout, MoveTo(BIRD_X, bird_y), Print("@"))?;
+    fn on_options_event(&mut self, event: Option<MenuEvent<OptionItem>>) {
+        let Some(menu) = &self.options_menu else {
+            return;
+        };
+        match event {
+            Some(MenuEvent::Changed(_)) => {
+                let base = self.pending_config.as_ref().unwrap_or(&self.config);
+                let config = config_from_options(menu, base);
+                self.apply_config(config);
+            }
+            Some(MenuEvent::Activated(OptionItem::Back)) | Some(MenuEvent::Back) => {
+                self.settings().save();
+                self.options_menu = None;
+            }
+            _ => {}
         }
+    }
 
-        // Draw score
-        execute!(stdout, SetForegroundColor(Color::Cyan))?;
-        execute!(
-            stdout,
-            MoveTo(2, self.height - 1),
-            Print(format!(
-                "Score: {}  High Score: {}",
-                self.score, self.high_score
-            ))
-        )?;
+    /// A gap for a pipe at `x`, within the difficulty's step of the one before and never
+    /// further up than the bird can climb to. The step is a share of the rows gaps can go
+    /// in, so short boards keep their moves small, and shrinks with closer pipes.
+    fn random_gap_y(&mut self, x: i32) -> u16 {
+        let difficulty = self.config.difficulty;
+        let gap = self.pipe_gap();
+        let min_gap_y = 3;
+        // A board too short for the gap gets it as high as it goes, rather than no room
+        let max_gap_y = self.height.saturating_sub(gap + 3).max(min_gap_y);
+        let Some(previous) = self.pipes.last() else {
+            return self.rng.gen_range(min_gap_y..=max_gap_y);
+        };
+        // The bird gets to this pipe after passing the ones still ahead of it
+        let ahead = self.pipes.iter().filter(|p| !p.passed).count() as u32;
+        let pipe_speed = self.ramp.pipe_speed_after(ahead);
+        let highest =
+            highest_reachable_gap(previous, x, gap, pipe_speed).clamp(min_gap_y, max_gap_y);
+        if self.config.rules < challenge::STEPPED_GAPS {
+            return self.free_gap_y(min_gap_y, max_gap_y, highest);
+        }
 
-        // Draw game over screen
-        if self.state == GameState::GameOver {
-            let msg_y = self.height / 2;
-            let msg_x = self.width / 2 - 12;
-
-            if self.is_new_record {
-                execute!(stdout, SetForegroundColor(Color::Yellow))?;
-                execute!(
-                    stdout,
-                    MoveTo(msg_x, msg_y - 1),
-                    Print("╔══════════════════════════╗")
-                )?;
-                execute!(
-                    stdout,
-                    MoveTo(msg_x, msg_y),
-                    Print("║   *** NEW RECORD! ***    ║")
-                )?;
-                execute!(
-                    stdout,
-                    MoveTo(msg_x, msg_y + 1),
-                    Print(format!("║   Score: {:5}            ║", self.score))
-                )?;
-                execute!(
-                    stdout,
-                    MoveTo(msg_x, msg_y + 2),
-                    Print("║                          ║")
-                )?;
-                execute!(
-                    stdout,
-                    MoveTo(msg_x, msg_y + 3),
-                    Print("║   R: Retry               ║")
-                )?;
-                execute!(
-                    stdout,
-                    MoveTo(msg_x, msg_y + 4),
-                    Print("║   Q: Quit                ║")
-                )?;
-                execute!(
-                    stdout,
-                    MoveTo(msg_x, msg_y + 5),
-                    Print("╚══════════════════════════╝")
-                )?;
-            } else {
-                execute!(stdout, SetForegroundColor(Color::Red))?;
-                execute!(
-                    stdout,
-                    MoveTo(msg_x, msg_y - 1),
-                    Print("╔══════════════════════════╗")
-                )?;
-                execute!(
-                    stdout,
-                    MoveTo(msg_x, msg_y),
-                    Print("║   GAME OVER!             ║")
-                )?;
-                execute!(
-                    stdout,
-                    MoveTo(msg_x, msg_y + 1),
-                    Print(format!("║   Score: {:5}            ║", self.score))
-                )?;
-                execute!(
-                    stdout,
-                    MoveTo(msg_x, msg_y + 2),
-                    Print(format!("║   Best:  {:5}            ║", self.high_score))
-                )?;
-                execute!(
-                    stdout,
-                    MoveTo(msg_x, msg_y + 3),
-                    Print("║                          ║")
-                )?;
-                execute!(
-                    stdout,
-                    MoveTo(msg_x, msg_y + 4),
-                    Print("║   R: Retry               ║")
-                )?;
-                execute!(
-                    stdout,
-                    MoveTo(msg_x, msg_y + 5),
-                    Print("║   Q: Quit                ║")
-                )?;
-                execute!(
-                    stdout,
-                    MoveTo(msg_x, msg_y + 6),
-                    Print("╚══════════════════════════╝")
-                )?;
-            }
-        }
-
-        execute!(stdout, ResetColor)?;
-        stdout.flush()?;
-        Ok(())
+        let closeness = ((x - previous.x) as f32 / difficulty.pipe_spacing() as f32).min(1.0);
+        let range = f32::from(max_gap_y.saturating_sub(min_gap_y));
+        let step = ((range * difficulty.gap_step() * closeness).round() as u16).max(MIN_GAP_STEP);
+        let top = previous
+            .gap_y
+            .saturating_sub(step)
+            .max(highest)
+            .min(max_gap_y);
+        let bottom = (previous.gap_y + step).clamp(top, max_gap_y.max(top));
+        self.rng.gen_range(top..=bottom)
     }
-}
 
-fn main() -> io::Result<()> {
-    let mut stdout = io::stdout();
+    /// A gap anywhere from `min_gap_y` to `max_gap_y`, as runs were laid out before
+    /// `challenge::STEPPED_GAPS`.
+    /// Draws above `highest` are out of reach and thrown away, and if that keeps happening
+    /// `highest` is used.
+    fn free_gap_y(&mut self, min_gap_y: u16, max_gap_y: u16, highest: u16) -> u16 {
+        for _ in 0..REACH_ATTEMPTS {
+            let gap_y = self.rng.gen_range(min_gap_y..=max_gap_y);
+            if gap_y >= highest {
+                return gap_y;
+            }
+        }
+        highest
+    }
 
-    // Setup terminal
-    terminal::enable_raw_mode()?;
-    execute!(stdout, EnterAlternateScreen, Hide)?;
+    fn new_pipe(&mut self, x: i32) -> Pipe {
+        let gap_y = self.random_gap_y(x);
+        Pipe {
+            width: self.bank.pipe_width(PIPE_WIDTH),
+            ..Pipe::new(x, gap_y, self.pipe_gap())
+        }
+    }
+
+    /// Rows of open space in each random pipe.
+    fn pipe_gap(&self) -> u16 {
+        self.config
+            .mutators
+            .pipe_gap(self.config.difficulty.pipe_gap())
+    }
+
+    fn level_pipe(&self, x: i32, spec: &PipeSpec) -> Pipe {
+        let top = self.height as i32 / 2 + spec.offset - spec.gap as i32 / 2;
+        let min_top = self.ceiling().max(1) as i32;
+        let max_top = (self.ground() as i32 - spec.gap as i32).max(min_top);
+        let mut pipe = Pipe::new(x, top.clamp(min_top, max_top) as u16, spec.gap);
+        pipe.flip = spec.flip;
+        pipe
+    }
+
+    /// Spawn initial pipes spread across the screen
+    fn spawn_initial_pipes(&mut self) {
+        if let Some(drill) = &mut self.drill {
+            drill.rewind();
+            let first = drill.next_pipe();
+            let pipe = self.level_pipe(self.width as i32 / 2, &first);
+            self.pipes.push(pipe);
+            while self.pipes.last().is_some_and(|p| p.x < self.width as i32) {
+                self.spawn_pipe();
+            }
+            return;
+        }
+
+        if let Some((x, beats_apart)) = self.beat_spacing() {
+            // As if they had come in on the beats before the run, the last just now
+            let spacing = self.beat_columns(beats_apart);
+            let mut xs: Vec<i32> = (0..)
+                .map(|i| x - i * spacing)
+                .take_while(|&x| x >= self.width as i32 / 2)
+                .collect();
+            xs.reverse();
+            for x in xs {
+                let pipe = self.new_pipe(x);
+                self.pipes.push(pipe);
+            }
+            return;
+        }
+
+        let spacing = self.pipe_spacing();
+        for i in 0..4 {
+            let pipe = self.new_pipe(self.width as i32 / 2 + i * spacing);
+            self.pipes.push(pipe);
+        }
+    }
+
+    /// In rhythm mode, where a pipe comes in on a beat so as to reach the bird on a later
+    /// one, and how many beats apart the pipes come: one beat is too close to fly, so it's
+    /// as many as keep them their usual spacing apart.
+    fn beat_spacing(&self) -> Option<(i32, u32)> {
+        let clock = self.rhythm.as_ref().filter(|_| self.drill.is_none())?;
+        let beat = clock.ticks_per_beat() * self.ramp.pipe_speed();
+        let beats_apart = (self.pipe_spacing() as f32 / beat).ceil().max(1.0) as u32;
+        let beats_in = (self.width.saturating_sub(BIRD_X) as f32 / beat).ceil();
+        Some((
+            BIRD_X as i32 + (beats_in * beat).round() as i32,
+            beats_apart,
+        ))
+    }
+
+    /// Columns the pipes scroll in `beats` of rhythm mode.
+    fn beat_columns(&self, beats: u32) -> i32 {
+        self.rhythm.as_ref().map_or(0, |clock| {
+            (beats as f32 * clock.ticks_per_beat() * self.ramp.pipe_speed()).round() as i32
+        })
+    }
+
+    /// On a beat in rhythm mode, bring in a pipe if enough beats have gone since the last.
+    fn spawn_on_beat(&mut self) {
+        let Some((x, beats_apart)) = self.beat_spacing() else {
+            return;
+        };
+        if self
+            .rhythm
+            .as_mut()
+            .is_some_and(|clock| clock.pipe_due(beats_apart))
+        {
+            self.push_pipe(x);
+        }
+    }
+
+    fn pipe_spacing(&self) -> i32 {
+        let spacing = self.config.difficulty.pipe_spacing();
+        if self.flock.is_empty() {
+            self.ramp.spacing(spacing)
+        } else {
+            (spacing / 3).max(PIPE_WIDTH as i32 + 4)
+        }
+    }
+
+    fn spawn_pipe(&mut self) {
+        if let Some(drill) = &mut self.drill {
+            let spec = drill.next_pipe();
+            let x = self.pipes.last().map_or(self.width as i32, |p| p.x) + spec.spacing;
+            let pipe = self.level_pipe(x, &spec);
+            self.pipes.push(pipe);
+            return;
+        }
+
+        // Calculate next pipe position - always one pipe spacing after the last pipe,
+        // with a wide one's extra width on top
+        let new_x = if let Some(last_pipe) = self.pipes.last() {
+            last_pipe.x + (last_pipe.width - PIPE_WIDTH) as i32 + self.pipe_spacing()
+        } else {
+            self.width as i32
+        };
+
+        self.push_pipe(new_x);
+    }
+
+    /// Add a random pipe at `x`, now and then a quiz gate.
+    fn push_pipe(&mut self, x: i32) {
+        let mut pipe = self.new_pipe(x);
+        // Gates only come in from the edge, leaving time to work the sum out
+        if self.config.quiz && self.rng.gen_range(0..quiz::ONE_IN) == 0 {
+            pipe.quiz = Some(Quiz::new(&mut self.rng, self.config.difficulty));
+        }
+        self.pipes.push(pipe);
+    }
+
+    fn update(&mut self) {
+        let _span = profile::span("update");
+        if let Some(limit) = &mut self.time_limit {
+            limit.refresh(storage::unix_now());
+        }
+        self.step();
+        self.camera.follow(self.bird_pos().y, self.height);
+        for event in self.events.drain() {
+            self.celebration.on_event(&event, self.width);
+            self.ticker.on_event(&event);
+            if let Some(music) = &self.music {
+                music.on_event(&event);
+            }
+            if let Some(haptics) = &self.haptics {
+                haptics.on_event(&event);
+            }
+        }
+        if self.status_file.is_some() {
+            let line = self.status_line();
+            if let Some(status) = &mut self.status_file {
+                status.publish(line);
+            }
+        }
+        if self.state != GameState::Paused {
+            self.celebration.update(self.height);
+            self.ticker.update();
+        }
+        if let Some(music) = &self.music {
+            music.set_playing(self.state == GameState::Playing);
+        }
+        self.update_kiosk();
+    }
+
+    /// Hand a kiosk over to the next player once the countdown is done, and turn the
+    /// attract pages over while nobody is playing.
+    fn update_kiosk(&mut self) {
+        let Some(kiosk) = &mut self.kiosk else {
+            return;
+        };
+        match self.state {
+            GameState::GameOver if kiosk.countdown_over(self.clock.now()) => {
+                kiosk.restart_clock(self.clock.now());
+                self.retry();
+                self.state = GameState::Title;
+            }
+            GameState::Title => {
+                if let Some(page) = kiosk.turn(self.clock.now()) {
+                    self.leaderboard_view = (page == Page::Leaderboard).then_some(0);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Advance the simulation by one tick.
+    fn step(&mut self) {
+        if matches!(
+            self.state,
+            GameState::Playing | GameState::Revive | GameState::Wager
+        ) {
+            self.tick += 1;
+            if self.locked_out() {
+                self.time_up();
+                return;
+            }
+            let sample = self.sample();
+            self.telemetry.samples.push(sample);
+            if let Some(rival) = self.rivals.iter().find(|r| r.ticks == self.tick) {
+                self.rival_popup = Some((
+                    format!("Passed {}!", bidi::isolate(&rival.name)),
+                    RIVAL_POPUP_TICKS,
+                ));
+                self.events.emit(GameEvent::PassedRival {
+                    name: rival.name.clone(),
+                });
+            }
+            if let Some((_, ticks)) = &mut self.rival_popup {
+                *ticks = ticks.saturating_sub(1);
+                if *ticks == 0 {
+                    self.rival_popup = None;
+                }
+            }
+            if let Some(pip) = &mut self.pip {
+                pip.advance();
+            }
+            if let Some(ghost) = &mut self.ghost {
+                ghost.advance();
+                if ghost.is_finished() {
+                    let score = ghost.game.score;
+                    self.rival_popup =
+                        Some((format!("Ghost down at {}", score), RIVAL_POPUP_TICKS));
+                    self.events.emit(GameEvent::GhostDown { score });
+                    self.ghost = None;
+                }
+            }
+        }
+
+        if self.state == GameState::Revive {
+            let expired = match self.reaction.as_mut() {
+                Some(timer) => timer.tick(),
+                None => true,
+            };
+            if expired {
+                self.game_over();
+            }
+            return;
+        }
+        if self.state == GameState::Wager {
+            let expired = match self.wager.as_mut() {
+                Some(wager) => wager.tick(),
+                None => true,
+            };
+            if expired {
+                self.wager = Some(Wager::Declined);
+                self.game_over();
+            }
+            return;
+        }
+        if self.state != GameState::Playing {
+            return;
+        }
+        if self.config.rewind {
+            if std::mem::take(&mut self.rewinding) {
+                if let Some(moment) = self.rewind.step_back() {
+                    self.go_back(moment);
+                }
+                return;
+            }
+            let moment = self.moment();
+            self.rewind.record(moment);
+        }
+
+        {
+            let _span = profile::span("weather");
+            self.weather.update(&mut self.rng, self.width, self.height);
+        }
+        let dt = self.dt();
+        self.bird.update(self.weather.wind(), dt);
+        self.day_cycle.update();
+        let beat = self.rhythm.as_mut().is_some_and(|clock| clock.advance(dt));
+        if beat {
+            self.events.emit(GameEvent::Beat);
+        }
+        self.invincible_ticks = self.invincible_ticks.saturating_sub(1);
+        if let Some((_, ticks)) = &mut self.bonus_popup {
+            *ticks = ticks.saturating_sub(1);
+            if *ticks == 0 {
+                self.bonus_popup = None;
+            }
+        }
+
+        // Check boundary collision
+        let (ceiling, ground) = (self.ceiling(), self.ground());
+        let bird = self.bird_pos();
+        if bird.y < ceiling as f32 || bird.row() >= ground as i32 {
+            let hit = if bird.y < ceiling as f32 {
+                Hit::Ceiling
+            } else {
+                Hit::Ground
+            };
+            if self.config.lives.is_none() && self.invincible_ticks == 0 {
+                self.telemetry.hits.push(hit);
+                self.crash();
+                return;
+            }
+
+            // In lives mode, and just after a revive or a taken bet, the bird bounces
+            // off the edges instead of dying
+            if hit == Hit::Ceiling {
+                self.bird.reset(ceiling as f32);
+            } else {
+                self.bird.reset((ground - 1) as f32);
+                self.bird.jump();
+            }
+            if self.config.lives.is_some() && self.take_hit(hit) {
+                return;
+            }
+        }
+
+        // Update pipes and check for scoring. Pipes move whole columns, so slow motion
+        // builds up fractions of a column until there is a full one to scroll
+        self.scroll += dt * self.ramp.pipe_speed();
+        let columns = self.scroll as u32;
+        self.scroll -= columns as f32;
+        self.travelled += columns;
+        let (bird, hitbox) = (self.bird_pos(), self.bird.hitbox());
+        // Pipes that moved more than a column have to be tested along the way
+        let rules = self.config.rules;
+        let sweep = if rules >= challenge::BOXES {
+            columns.saturating_sub(1) as f32
+        } else {
+            0.0
+        };
+        for pipe in &mut self.pipes {
+            for _ in 0..columns {
+                pipe.update();
+            }
+            if self.config.memory {
+                let ahead = pipe.x - BIRD_X as i32 - MEMORY_FADE_END;
+                pipe.alpha =
+                    (ahead as f32 / (MEMORY_FADE_START - MEMORY_FADE_END) as f32).clamp(0.0, 1.0);
+                if pipe.alpha == 0.0 {
+                    pipe.faded_ticks += 1;
+                }
+            }
+
+            // Track how the bird is going through this pipe for style points
+            if pipe.contains_column(hitbox) {
+                match pipe.clearance(bird) {
+                    Some(clearance) => pipe.min_clearance = pipe.min_clearance.min(clearance),
+                    None => pipe.clipped = true,
+                }
+            }
+
+            // Check if bird passed this pipe
+            if !pipe.passed && pipe.has_bird_passed(hitbox, rules) {
+                pipe.passed = true;
+                if pipe.flip {
+                    self.bird.flipped = !self.bird.flipped;
+                }
+                self.streak += 1;
+                self.ramp.passed();
+                self.bank.passed();
+                self.rewind.refill();
+                if self.wager.as_mut().is_some_and(Wager::passed) {
+                    self.bonus_popup = Some(("DOUBLED!", POPUP_TICKS));
+                }
+                let pass = pipe.pass_summary(self.streak);
+                self.telemetry.passes.push(PassRecord {
+                    min_clearance: pass.min_clearance,
+                    center_clearance: pass.center_clearance,
+                });
+                if let Some(bonus) = self.breakdown.record_pass(self.config.scoring, &pass) {
+                    self.bonus_popup = Some((bonus, POPUP_TICKS));
+                }
+                // `points()` would borrow the pipes being looped over
+                self.score = self.config.mutators.apply(self.breakdown.total())
+                    + self.wager.as_ref().map_or(0, Wager::bonus)
+                    + self.marathon.as_ref().map_or(0, |m| m.carried);
+                self.events.emit(GameEvent::Scored { score: self.score });
+                if pass.min_clearance == 0 {
+                    self.events.emit(GameEvent::NearMiss);
+                }
+            }
+        }
+
+        // Check pipe collision
+        let hit = {
+            let _span = profile::span("collision");
+            entity::any_hit(hitbox, sweep, self.pipes.iter().map(Pipe::collider))
+        };
+        if hit && self.take_hit(Hit::Pipe) {
+            return;
+        }
+        if self.drill.is_none()
+            && self
+                .marathon
+                .as_ref()
+                .is_some_and(|m| m.to_go(self.breakdown.pipes) == 0)
+        {
+            self.rest();
+            return;
+        }
+
+        self.step_flock(dt, sweep);
+
+        let _span = profile::span("spawn");
+        // Remove offscreen pipes
+        self.pipes.retain(|pipe| !pipe.is_offscreen());
+
+        // Spawn new pipe if the rightmost pipe has moved into view, or on the beat
+        if self.beat_spacing().is_some() {
+            if beat {
+                self.spawn_on_beat();
+            }
+        } else if let Some(last_pipe) = self.pipes.last() {
+            if last_pipe.x < self.width as i32 - self.pipe_spacing().min(20) {
+                self.spawn_pipe();
+            }
+        } else {
+            // If no pipes, spawn one at the right edge
+            let pipe = self.new_pipe(self.width as i32);
+            self.pipes.push(pipe);
+        }
+    }
+
+    /// Fly the stress test's extra birds. One that crashes starts again in the middle of
+    /// the next gap, so the screen stays full.
+    fn step_flock(&mut self, dt: f32, sweep: f32) {
+        let wind = self.weather.wind();
+        let mut flock = std::mem::take(&mut self.flock);
+        for member in &mut flock {
+            let view = self.bot_view(&member.bird);
+            if member.pilot.decide(&view) {
+                member.bird.jump();
+            }
+            member.bird.update(wind, dt);
+
+            let pos = member.bird.body.pos;
+            let crashed = pos.y < self.ceiling() as f32
+                || pos.row() >= self.ground() as i32
+                || entity::any_hit(
+                    member.bird.hitbox(),
+                    sweep,
+                    self.pipes.iter().map(Pipe::collider),
+                );
+            if crashed {
+                self.flock_crashes += 1;
+                let middle = view
+                    .openings
+                    .first()
+                    .map_or(self.height as f32 / 2.0, |next| {
+                        (next.top + next.bottom) / 2.0
+                    });
+                member.bird.reset(middle);
+            }
+        }
+        self.flock = flock;
+    }
+
+    /// What a bot flying `bird` gets to see.
+    fn bot_view(&self, bird: &Bird) -> bot::View {
+        let dt = self.dt();
+        let pos = bird.body.pos;
+        bot::View {
+            y: pos.y,
+            velocity: bird.body.velocity.y,
+            gravity: bird.direction(),
+            wind: (1..=bot::HORIZON)
+                .map(|ticks| self.weather.wind_after(ticks))
+                .collect(),
+            dt,
+            scroll: dt * self.ramp.pipe_speed(),
+            ceiling: self.ceiling() as f32,
+            floor: self.ground() as f32,
+            openings: self
+                .pipes
+                .iter()
+                .filter(|pipe| !pipe.has_bird_passed(bird.hitbox(), self.config.rules))
+                .map(|pipe| bot::Opening {
+                    ahead: pipe.x as f32 - pos.x,
+                    width: pipe.width as f32,
+                    top: pipe.gap_y as f32,
+                    bottom: (pipe.gap_y + pipe.gap - 1) as f32,
+                })
+                .collect(),
+        }
+    }
+
+    /// Consume a heart for crashing into `hit`. Returns true when the run is over.
+    fn take_hit(&mut self, hit: Hit) -> bool {
+        let exposed = self.bank.is_exposed();
+        if self.invincible_ticks > 0 && !exposed {
+            return false;
+        }
+        self.telemetry.hits.push(hit);
+
+        self.lives = self.lives.saturating_sub(1);
+        if self.lives == 0 || exposed {
+            self.crash();
+            return true;
+        }
+
+        self.streak = 0;
+        self.invincible_ticks = INVINCIBLE_TICKS;
+        false
+    }
+
+    /// Whether the run has a ground and ceiling, which runs from older challenge codes
+    /// don't: their birds fly right to the edges of the board.
+    fn has_ground(&self) -> bool {
+        self.config.rules >= challenge::GROUND
+    }
+
+    /// First row of the ceiling: below the conditions line, except by the rules of
+    /// `challenge::GROUND`, whose ceiling took the top row with the conditions over it.
+    fn ceiling_top(&self) -> u16 {
+        if self.config.rules >= challenge::HUD_LINE {
+            HUD_ROWS
+        } else {
+            0
+        }
+    }
+
+    /// First row the bird can fly in, below the ceiling.
+    fn ceiling(&self) -> u16 {
+        if self.has_ground() {
+            self.ceiling_top() + CEILING_ROWS
+        } else {
+            0
+        }
+    }
+
+    /// First row of the ground, above the score line.
+    fn ground(&self) -> u16 {
+        if self.has_ground() {
+            self.height.saturating_sub(GROUND_ROWS + HUD_ROWS)
+        } else {
+            self.height
+        }
+    }
+
+    /// Simulated ticks that pass per real tick at the current speed.
+    fn dt(&self) -> f32 {
+        self.speed as f32 / 100.0 * self.config.mutators.speed_factor()
+    }
+
+    /// Slow-motion is an assist, so it can only be adjusted mid-run when assists are on.
+    fn is_assisted(&self) -> bool {
+        self.config.assist || self.config.trajectory || self.config.speed != 100
+    }
+
+    /// Nudge the game speed by one step in assisted runs.
+    fn change_speed(&mut self, faster: bool) {
+        if self.state != GameState::Playing || !self.is_assisted() {
+            return;
+        }
+        let speed = if faster {
+            self.speed.saturating_add(SPEED_STEP).min(MAX_SPEED)
+        } else {
+            self.speed.saturating_sub(SPEED_STEP).max(MIN_SPEED)
+        };
+        if speed != self.speed {
+            self.telemetry.inputs.push((self.tick, Input::Speed(speed)));
+            self.speed = speed;
+            self.events.emit(GameEvent::SpeedChanged { speed });
+        }
+    }
+
+    /// Where the bird will be over the next few ticks, its column a whole one, stepped
+    /// with the same physics as the real bird under the current wind and speed.
+    fn predict(&self, flap: bool, wind: f32) -> Vec<Pos> {
+        let mut bird = self.bird.clone();
+        if flap {
+            bird.jump();
+        }
+        let dt = self.dt();
+        (1..=PREVIEW_TICKS)
+            .map(|i| {
+                bird.update(wind, dt);
+                let columns = i as f32 * dt * self.ramp.pipe_speed();
+                Pos::new(bird.body.pos.x + columns.round(), bird.body.pos.y)
+            })
+            .take_while(|pos| pos.y >= self.ceiling() as f32 && pos.y < self.ground() as f32)
+            .collect()
+    }
+
+    /// The next pipe the bird hasn't got past.
+    fn next_pipe(&self) -> Option<&Pipe> {
+        self.pipes.iter().find(|pipe| {
+            !pipe.passed && !pipe.has_bird_passed(self.bird.hitbox(), self.config.rules)
+        })
+    }
+
+    /// Where the bird is in the world.
+    fn bird_pos(&self) -> Pos {
+        self.bird.body.pos
+    }
+
+    /// The bird and the next gap as the tick starts, for the telemetry.
+    fn sample(&self) -> Sample {
+        // Inputs are keyed by the ticks simulated before them, so this tick's are one back
+        let flapped = self
+            .telemetry
+            .inputs
+            .iter()
+            .rev()
+            .take_while(|&&(tick, _)| tick + 1 == self.tick)
+            .any(|(_, input)| matches!(input, Input::Flap | Input::SmallFlap | Input::BigFlap));
+        Sample {
+            score: self.score,
+            y: self.bird.body.pos.y,
+            velocity: self.bird.body.velocity.y,
+            flapped,
+            gap: self
+                .next_pipe()
+                .map(|pipe| (pipe.gap_y, pipe.gap_y + pipe.gap - 1)),
+        }
+    }
+
+    /// Which way the next gap is from the bird: up, down, or level with it.
+    fn assist_arrow(&self) -> Option<char> {
+        let next = self.next_pipe()?;
+        let row = self.bird_pos().row();
+        Some(if row < next.gap_y as i32 {
+            '▼'
+        } else if row >= (next.gap_y + next.gap) as i32 {
+            '▲'
+        } else {
+            '►'
+        })
+    }
+
+    /// Pipes in a row, capped at the size of the combo meter.
+    fn combo(&self) -> u32 {
+        self.streak.min(COMBO_MAX)
+    }
+
+    /// The run would end here; a full combo meter earns a shot at the revive mini-game.
+    /// Drills restart on the spot instead.
+    fn crash(&mut self) {
+        if self.drill.is_some() {
+            self.drill_attempts += 1;
+            self.reset();
+            return;
+        }
+        // The revive mini-game needs letter keys, which one-switch players don't have.
+        // A bird exposed after banking gets no revive either
+        if self.combo() < COMBO_MAX || self.config.one_switch || self.bank.is_exposed() {
+            self.game_over();
+            return;
+        }
+
+        let key = REVIVE_KEYS[self.rng.gen_range(0..REVIVE_KEYS.len())];
+        self.reaction = Some(ReactionTimer::new(key, REVIVE_TICKS));
+        self.state = GameState::Revive;
+    }
+
+    fn game_over(&mut self) {
+        self.reaction = None;
+        if self.offers_wager() {
+            self.wager = Some(Wager::offer());
+            self.state = GameState::Wager;
+            return;
+        }
+        self.state = GameState::GameOver;
+        self.events.emit(GameEvent::Died);
+        self.finish_run(Outcome::Died);
+        let beaten = match &self.best_run {
+            Some(best) => best.seed != self.seed || self.score > best.score,
+            None => true,
+        };
+        if beaten && self.drill.is_none() {
+            self.best_run = Some(BestRun {
+                seed: self.seed,
+                score: self.score,
+                inputs: self.telemetry.inputs.clone(),
+            });
+        }
+    }
+
+    /// Stop for a rest at the end of a marathon segment, saving the marathon so far.
+    fn rest(&mut self) {
+        let now = Mark {
+            pipes: self.breakdown.pipes,
+            score: self.score,
+            tick: self.tick,
+            flaps: self.telemetry.flaps() as u32,
+        };
+        let player = self.config.player.as_deref();
+        if let Some(marathon) = &mut self.marathon {
+            marathon.finish_segment(now, self.persist, player);
+        }
+        self.state = GameState::Intermission;
+    }
+
+    /// Set off on the next marathon segment.
+    fn next_segment(&mut self) {
+        if self.state != GameState::Intermission {
+            return;
+        }
+        if let Some(marathon) = &mut self.marathon {
+            marathon.last = None;
+        }
+        self.state = GameState::Playing;
+    }
+
+    /// Whether a crash now gets the double-or-nothing offer: once a run, with something
+    /// to stake, and for players with a key to take it with.
+    fn offers_wager(&self) -> bool {
+        self.config.double_or_nothing
+            && self.wager.is_none()
+            && self.score > 0
+            && !self.config.one_switch
+            && self.drill.is_none()
+    }
+
+    /// Record the run's score as a high score and in the history.
+    fn finish_run(&mut self, outcome: Outcome) {
+        if let Some(kiosk) = &mut self.kiosk {
+            kiosk.restart_clock(self.clock.now());
+        }
+        if let Some(limit) = &mut self.time_limit {
+            limit.ran(self.tick);
+        }
+        if let Some(wager) = &mut self.wager {
+            self.score = wager.settle(self.score);
+        }
+        self.score = self.bank.settle(self.score);
+        self.check_and_save_highscore();
+        if let Some(event) = self.event.as_mut().filter(|_| self.persist) {
+            if !event.is_over(storage::unix_now()) {
+                event.record(self.score, self.config.player.as_deref());
+            }
+        }
+        if self.marathon.is_some() && self.persist {
+            marathon::clear(self.config.player.as_deref());
+        }
+        if let Some(path) = self.config.telemetry_file.as_ref().filter(|_| self.persist) {
+            if let Err(err) = storage::write_atomic(path, &self.telemetry.to_csv()) {
+                self.status = Some(format!("Could not save telemetry: {}", err));
+            }
+        }
+        self.runs.push(summary::Run {
+            score: self.score,
+            seed: self.seed,
+            ticks: self.tick,
+            outcome,
+            category: self.category.key().to_string(),
+        });
+        if self.persist {
+            history::append_run(
+                &RunRecord::new(
+                    self.score,
+                    self.tick,
+                    self.config.scoring,
+                    outcome,
+                    &self.category,
+                ),
+                self.config.player.as_deref(),
+            );
+            self.is_new_week_best = self.score > self.week_best;
+            self.week_best = self.week_best.max(self.score);
+            self.month_best = self.month_best.max(self.score);
+            self.recent_scores.push(self.score);
+            if self.recent_scores.len() > HISTORY_SPARKLINE_RUNS {
+                self.recent_scores.remove(0);
+            }
+        }
+    }
+
+    /// Whether a run is under way, paused or not, and not yet recorded.
+    fn in_run(&self) -> bool {
+        matches!(
+            self.state,
+            GameState::Playing
+                | GameState::Revive
+                | GameState::Wager
+                | GameState::Paused
+                | GameState::ConfirmQuit
+        )
+    }
+
+    /// Ticks of the run in progress, which the daily limit has yet to count.
+    fn unrecorded_ticks(&self) -> u32 {
+        if self.in_run() {
+            self.tick
+        } else {
+            0
+        }
+    }
+
+    /// Whether play is shut off: the daily limit is used up, counting the run in
+    /// progress, or it's a pomodoro focus period.
+    fn locked_out(&self) -> bool {
+        let ticks = self.unrecorded_ticks();
+        self.time_limit
+            .as_ref()
+            .is_some_and(|limit| limit.is_over(ticks))
+            || self.focusing()
+    }
+
+    fn focusing(&self) -> bool {
+        self.pomodoro
+            .as_ref()
+            .is_some_and(|pomodoro| pomodoro.period(storage::unix_now()).0 == Period::Focus)
+    }
+
+    /// End the run in progress once the day's time is spent or a break is over. Nothing
+    /// more can be played until the lockout lifts.
+    fn time_up(&mut self) {
+        if self.drill.is_some() {
+            self.drill = None;
+            self.reset();
+            self.state = GameState::Title;
+            return;
+        }
+        self.reaction = None;
+        self.state = GameState::GameOver;
+        self.finish_run(Outcome::Abandoned);
+    }
+
+    /// Called on the way out of the game so a run in progress is kept as abandoned.
+    fn shutdown(&mut self) {
+        if self.in_run() && self.tick > 0 && self.drill.is_none() && !self.detached {
+            self.state = GameState::GameOver;
+            self.finish_run(Outcome::Abandoned);
+        }
+        if let Some(status) = self.status_file.take() {
+            status.finish();
+        }
+    }
+
+    /// The line printed after leaving the game, for scripts wrapped around it: the last
+    /// run's score, the best in its category, how long the game was open, and the seed,
+    /// like `tflap: score 12 best 40 time 3:05 seed 1234`.
+    fn exit_summary(&self, open: Duration) -> String {
+        let seconds = open.as_secs();
+        // A retry or the title screen has already set the score back to nothing
+        let (score, seed) = self
+            .runs
+            .last()
+            .map_or((self.score, self.seed), |run| (run.score, run.seed));
+        format!(
+            "tflap: score {} best {} time {}:{:02} seed {}",
+            score,
+            self.high_score,
+            seconds / 60,
+            seconds % 60,
+            seed
+        )
+    }
+
+    /// Every run of the session and the settings they were played with, as JSON.
+    fn json_summary(&self, open: Duration, jitter: Jitter) -> String {
+        Summary {
+            player: self.config.player.as_deref(),
+            open,
+            jitter,
+            best: self.high_score,
+            runs: &self.runs,
+            settings: self.settings().in_effect(),
+        }
+        .to_json()
+    }
+
+    /// The run as one short line for a status bar.
+    fn status_line(&self) -> String {
+        let state = match self.state {
+            GameState::Title => return "tflap".to_string(),
+            GameState::Playing | GameState::ConfirmQuit => String::new(),
+            GameState::Paused => " paused".to_string(),
+            GameState::Revive => " revive!".to_string(),
+            GameState::Wager => " double or nothing?".to_string(),
+            GameState::Intermission => " resting".to_string(),
+            GameState::GameOver => " game over".to_string(),
+        };
+        format!(
+            "tflap {}{} (best {})",
+            self.score,
+            state,
+            self.high_score.max(self.score)
+        )
+    }
+
+    /// Leave the title screen with a first flap.
+    fn start(&mut self) {
+        self.state = GameState::Playing;
+        self.opening_flap();
+    }
+
+    /// The flap a run starts with when the key that started it was a flap.
+    fn opening_flap(&mut self) {
+        if self.config.one_switch {
+            self.flap(Input::SmallFlap);
+        } else {
+            self.jump();
+        }
+    }
+
+    /// Answer the revive prompt. The right key resumes the run at the crash site
+    /// with a score penalty and an empty combo meter; anything else ends it.
+    fn attempt_revive(&mut self, c: char) {
+        let Some(timer) = self.reaction.take() else {
+            return;
+        };
+        self.telemetry.inputs.push((self.tick, Input::Revive(c)));
+        if !timer.matches(c) {
+            self.game_over();
+            return;
+        }
+
+        self.breakdown.penalty += REVIVE_PENALTY.min(self.score);
+        self.score = self.points();
+        self.carry_on();
+    }
+
+    /// Go back a tick on the next step instead of simulating one, in rewind mode, if
+    /// there's meter and run left to go back through.
+    fn rewind(&mut self) {
+        if self.state != GameState::Playing || !self.config.rewind || self.rewinding {
+            return;
+        }
+        if !self.rewind.can_rewind() {
+            return;
+        }
+        self.telemetry.inputs.push((self.tick, Input::Rewind));
+        self.rewinding = true;
+    }
+
+    /// Put the run back to `moment`, with the clock carrying on and the meter as it is.
+    fn go_back(&mut self, moment: Snapshot) {
+        let tick = self.tick;
+        let rewind = std::mem::take(&mut self.rewind);
+        self.restore(&moment);
+        self.tick = tick;
+        self.rewind = rewind;
+    }
+
+    /// Answer the double-or-nothing offer, carrying on with the score at stake or
+    /// ending the run.
+    fn answer_wager(&mut self, take: bool) {
+        if self.state != GameState::Wager {
+            return;
+        }
+        self.telemetry.inputs.push((self.tick, Input::Wager(take)));
+        if !take {
+            self.wager = Some(Wager::Declined);
+            self.game_over();
+            return;
+        }
+        self.wager = Some(Wager::take(self.score));
+        self.carry_on();
+    }
+
+    /// Put the bird back in the air where it crashed, briefly invincible.
+    fn carry_on(&mut self) {
+        let max_y = (self.ground() - 1) as f32;
+        self.bird
+            .reset(self.bird_pos().y.clamp(self.ceiling() as f32, max_y));
+        self.streak = 0;
+        self.lives = self.lives.max(1);
+        self.invincible_ticks = INVINCIBLE_TICKS;
+        self.state = GameState::Playing;
+    }
+
+    /// The run's points: the pipes' with the mutators applied, a won bet's stake, and
+    /// what earlier sessions of a marathon scored.
+    fn points(&self) -> u32 {
+        self.config.mutators.apply(self.breakdown.total())
+            + self.wager.as_ref().map_or(0, Wager::bonus)
+            + self.carried()
+    }
+
+    /// The score a resumed marathon started from.
+    fn carried(&self) -> u32 {
+        self.marathon.as_ref().map_or(0, |m| m.carried)
+    }
+
+    fn jump(&mut self) {
+        self.flap(Input::Flap);
+    }
+
+    /// Type a letter in typing mode, flapping if it was the next one in the word.
+    fn type_letter(&mut self, c: char) {
+        if self.state != GameState::Playing {
+            return;
+        }
+        let Some(typing) = &mut self.typing else {
+            return;
+        };
+        if typing.press(c) {
+            self.jump();
+        }
+    }
+
+    /// Flap with the strength `input` calls for and record it for the replay.
+    fn flap(&mut self, input: Input) {
+        if self.state == GameState::Playing {
+            self.telemetry.inputs.push((self.tick, input));
+            self.bird.jump_with(match input {
+                Input::SmallFlap => SMALL_JUMP_VELOCITY,
+                Input::BigFlap => BIG_JUMP_VELOCITY,
+                _ => JUMP_VELOCITY,
+            });
+            let bird = self.bird.hitbox();
+            for pipe in &mut self.pipes {
+                if pipe.contains_column(bird) {
+                    pipe.flapped = true;
+                }
+            }
+            let on_beat = self.rhythm.as_mut().is_some_and(BeatClock::on_beat);
+            if on_beat && self.config.scoring == ScoringMode::Style {
+                self.breakdown.beat += rhythm::BEAT_BONUS;
+                self.score = self.points();
+            }
+            self.events.emit(GameEvent::Flapped);
+        }
+    }
+
+    /// Bank the score while the bank is open, bringing on the burst of wide pipes.
+    fn bank(&mut self) {
+        if self.state != GameState::Playing {
+            return;
+        }
+        let ahead = self.pipes.iter().filter(|p| !p.passed).count() as u32;
+        if self.bank.bank(self.score, ahead) {
+            self.telemetry.inputs.push((self.tick, Input::Bank));
+            self.bonus_popup = Some(("Banked!", POPUP_TICKS));
+        }
+    }
+
+    /// Answer the nearest quiz gate still shut ahead of the bird with a digit key.
+    fn answer_quiz(&mut self, digit: u8) {
+        if self.state != GameState::Playing {
+            return;
+        }
+        let Some(quiz) = self
+            .pipes
+            .iter_mut()
+            .filter(|pipe| pipe.x + pipe.width as i32 > BIRD_X as i32)
+            .find_map(|pipe| pipe.quiz.as_mut().filter(|quiz| quiz.gate == Gate::Shut))
+        else {
+            return;
+        };
+        self.telemetry
+            .inputs
+            .push((self.tick, Input::Answer(digit)));
+        if quiz.answer(digit) {
+            self.quiz.right += 1;
+            self.bonus_popup = Some(("Open!", POPUP_TICKS));
+        } else {
+            self.quiz.wrong += 1;
+            self.bonus_popup = Some(("Wrong!", POPUP_TICKS));
+        }
+    }
+
+    fn check_and_save_highscore(&mut self) {
+        if self.score > self.high_score {
+            self.high_score = self.score;
+            self.is_new_record = true;
+        }
+        if self.persist {
+            // A marathon's score is from its earlier sessions too, and so is its length
+            let ticks = self.tick + self.marathon.as_ref().map_or(0, |m| m.carried_ticks);
+            self.leaderboard
+                .submit(&self.category, self.score, storage::unix_now(), ticks);
+        }
+    }
+
+    fn apply_input(&mut self, input: Input) {
+        match input {
+            Input::Flap | Input::SmallFlap | Input::BigFlap => self.flap(input),
+            Input::Revive(c) => self.attempt_revive(c),
+            Input::Bank => self.bank(),
+            Input::Wager(take) => self.answer_wager(take),
+            Input::Answer(digit) => self.answer_quiz(digit),
+            Input::Rewind => self.rewind(),
+            Input::Speed(_) if self.state != GameState::Playing => {}
+            Input::Speed(speed) => {
+                self.telemetry.inputs.push((self.tick, input));
+                self.speed = speed.clamp(MIN_SPEED, MAX_SPEED);
+                self.events
+                    .emit(GameEvent::SpeedChanged { speed: self.speed });
+            }
+        }
+    }
+
+    /// Let the bot play this tick: flap when it wants to, and always make the revive.
+    fn autopilot(&mut self) {
+        if let Some(key) = self.reaction.as_ref().map(|timer| timer.key) {
+            self.attempt_revive(key);
+            return;
+        }
+        let view = self.bot_view(&self.bird);
+        let Some(pilot) = &mut self.pilot else {
+            return;
+        };
+        if pilot.decide(&view) {
+            self.jump();
+        }
+    }
+
+    /// Start over on a fresh random seed, or the same one when playing a challenge.
+    fn retry(&mut self) {
+        if self.challenge.is_none() {
+            self.seed = rand::random();
+        }
+        self.reset();
+    }
+
+    /// This run packed into a code others can pass to `--challenge`.
+    fn challenge_code(&self) -> String {
+        Challenge {
+            // The inputs only replay this session of a marathon
+            score: self.score.saturating_sub(self.carried()),
+            inputs: self.telemetry.inputs.clone(),
+            ..Challenge::new(self.seed, self.width, self.height, &self.config)
+        }
+        .encode()
+    }
+
+    /// Start over on the same pipes and weather as the last run.
+    fn retry_same_seed(&mut self) {
+        self.reset();
+    }
+
+    /// Give up on the run in progress and start the next one without the game-over
+    /// screen. The run is still kept in the history, as abandoned.
+    fn quick_restart(&mut self) {
+        if self.drill.is_some() {
+            self.drill_attempts += 1;
+            self.reset();
+            return;
+        }
+        if self.tick > 0 {
+            self.reaction = None;
+            self.state = GameState::GameOver;
+            self.finish_run(Outcome::Abandoned);
+        }
+        self.retry();
+    }
+
+    /// Where runs on the challenge file are sent in, while it's still on.
+    fn submission_url(&self) -> Option<&str> {
+        let event = self.event.as_ref()?;
+        if event.is_over(storage::unix_now()) {
+            return None;
+        }
+        event.submit.as_deref()
+    }
+
+    /// Write a plain-text scorecard for the finished run and report where it went.
+    fn share(&mut self) {
+        let mut card = format!(
+            "{}\n\n  Try it: tflap --challenge {}",
+            self.summary_lines().join("\n"),
+            self.challenge_code()
+        );
+        if let Some(url) = self.submission_url() {
+            card.push_str(&format!("\n  Send it in at {}", url));
+        }
+        let stamp = Stamp::new(self.seed, &self.category);
+        self.status = Some(match get_scorecard_path() {
+            Some(path) => match export::save(&path, Export::Card(&card), &stamp) {
+                Ok(()) => format!("Saved scorecard to {}", path.display()),
+                Err(err) => format!("Could not save scorecard: {}", err),
+            },
+            None => "Could not save scorecard: $HOME is not set".to_string(),
+        });
+    }
+
+    fn reset(&mut self) {
+        // Drills never finish a run, so their time counts as each attempt ends
+        if let (Some(limit), Some(_)) = (&mut self.time_limit, &self.drill) {
+            limit.ran(self.tick);
+        }
+        if let Some(config) = self.pending_config.take() {
+            self.switch_config(config);
+        }
+        self.bird.reset((self.height / 2) as f32);
+        self.bird.flipped = self.config.mutators.has(Mutator::Inverted);
+        self.pipes.clear();
+        self.marathon = start_marathon(&self.config, self.persist);
+        self.score = self.carried();
+        if let (Some(challenge), Some(event)) = (&mut self.challenge, &self.event) {
+            challenge.score = event.best;
+        }
+        self.is_new_record = false;
+        self.is_new_week_best = false;
+        self.state = GameState::Playing;
+        self.lives = self.config.lives.unwrap_or(1);
+        self.invincible_ticks = 0;
+        self.streak = 0;
+        self.breakdown = ScoreBreakdown::default();
+        self.bonus_popup = None;
+        self.rival_popup = None;
+        self.reaction = None;
+        self.rng = StdRng::seed_from_u64(self.seed);
+        self.tick = 0;
+        self.telemetry = RunTelemetry::default();
+        self.previous_best = self.high_score;
+        self.status = None;
+        self.weather.reset(&mut self.rng);
+        self.day_cycle.reset();
+        self.events = EventBus::default();
+        self.speed = self.config.speed;
+        self.ramp = SpeedRamp::new(self.config.ramp);
+        self.bank = Bank::new(self.config.banking);
+        self.wager = None;
+        // One-switch players have no letter keys to type with
+        self.typing =
+            (self.config.typing && !self.config.one_switch).then(|| Typing::new(self.seed));
+        self.quiz = Tally::default();
+        self.rewind = Rewind::default();
+        self.rewinding = false;
+        self.rhythm = self.config.rhythm.map(beat_clock);
+
+        self.events.emit(GameEvent::Started { speed: self.speed });
+        self.scroll = 0.0;
+        self.travelled = 0;
+        self.celebration = Celebration::new(self.config.milestones.clone(), self.seed);
+        self.ticker = Ticker::new(self.config.milestones.clone());
+        self.spawn_initial_pipes();
+        self.start_ghost();
+        self.start_pip();
+    }
+
+    /// Line the challenge's run up at its first tick as a ghost to race, if asked to.
+    fn start_ghost(&mut self) {
+        self.ghost = match &self.challenge {
+            Some(challenge) if self.config.ghost && !challenge.inputs.is_empty() => Some(Box::new(
+                ReplayViewer::for_challenge(challenge, &self.config),
+            )),
+            _ => None,
+        };
+    }
+
+    /// Line the best run on this seed up at its first tick in the corner, if it has one
+    /// and the inset is on.
+    fn start_pip(&mut self) {
+        self.pip = match &self.best_run {
+            Some(best) if self.config.pip && best.seed == self.seed => {
+                let mut viewer = ReplayViewer::for_run(self, best.inputs.clone());
+                // The inset has no room for a HUD
+                viewer.game.screensaver = true;
+                Some(Box::new(viewer))
+            }
+            _ => None,
+        };
+    }
+
+    /// The run summary shown after game over, without the surrounding box.
+    fn summary_lines(&self) -> Vec<String> {
+        let t = &self.telemetry;
+        let seconds = t.ticks() as u64 * TICK_RATE.as_millis() as u64 / 1000;
+        let title = if self.is_new_record {
+            "*** NEW RECORD! ***"
+        } else if self.is_new_week_best {
+            "** WEEKLY BEST! **"
+        } else {
+            "GAME OVER!"
+        };
+        let comparison = if self.is_new_record {
+            format!("Beat your best by {}", self.score - self.previous_best)
+        } else if self.score == self.previous_best {
+            "Tied your best!".to_string()
+        } else {
+            format!("{} short of your best", self.previous_best - self.score)
+        };
+
+        let mut lines = vec![
+            format!("  {:<22}Score: {:5}", title, self.score),
+            format!("  Best: {:<5} {}", self.high_score, comparison),
+            format!("  Week: {:<5} Month: {}", self.week_best, self.month_best),
+            format!("  {}", self.category.label()),
+            String::new(),
+            format!("  {}", sparkline(&t.scores(), SUMMARY_WIDTH - 4)),
+            String::new(),
+            format!("  Flaps  {:5}      Accuracy {:4}%", t.flaps(), t.accuracy()),
+            format!(
+                "  Grazes {:5}      Time   {:3}:{:02}",
+                t.grazes(),
+                seconds / 60,
+                seconds % 60
+            ),
+        ];
+        if let Some(hit) = t.hits.last() {
+            let mut crash = format!("  Crashed into {}", hit.label());
+            if t.hits.len() > 1 {
+                crash.push_str(&format!(
+                    " ({} pipe, {} ground)",
+                    t.hits_on(Hit::Pipe),
+                    t.hits_on(Hit::Ground) + t.hits_on(Hit::Ceiling)
+                ));
+            }
+            lines.push(crash);
+        }
+        if self.config.scoring == ScoringMode::Style {
+            let b = &self.breakdown;
+            lines.push(String::new());
+            lines.push(format!("  Pipes  {:5}      Center {:6}", b.pipes, b.center));
+            lines.push(format!("  Glide  {:5}      Graze  {:6}", b.glide, b.graze));
+            lines.push(format!(
+                "  Streak {:5}      Revive {:6}",
+                b.streak,
+                -(b.penalty as i64)
+            ));
+            if self.rhythm.is_some() {
+                lines.push(format!("  Beat   {:5}", b.beat));
+            }
+        }
+        match self.wager {
+            Some(Wager::Won { stake }) => {
+                lines.push(format!("  Double or nothing: won, +{}", stake));
+            }
+            Some(Wager::Lost { stake }) => lines.push(format!(
+                "  Double or nothing: lost, {} cut to {}",
+                stake,
+                stake / 2
+            )),
+            _ => {}
+        }
+        if let Some(typing) = &self.typing {
+            let seconds = self.tick as f32 * TICK_RATE.as_secs_f32();
+            let accuracy = match typing.accuracy() {
+                Some(percent) => format!("{:.0}% right", percent),
+                None => "nothing typed".to_string(),
+            };
+            lines.push(format!(
+                "  Typing {:.0} wpm, {}",
+                typing.wpm(seconds),
+                accuracy
+            ));
+        }
+        if let Some(marathon) = self.marathon.as_ref().filter(|_| self.drill.is_none()) {
+            let total = (marathon.carried_ticks + self.tick) * TICK_RATE.as_millis() as u32 / 1000;
+            lines.push(format!(
+                "  Marathon: {} segments in {}:{:02}",
+                marathon.segments,
+                total / 60,
+                total % 60
+            ));
+        }
+        if self.config.quiz {
+            lines.push(format!(
+                "  Quiz gates: {} right, {} wrong",
+                self.quiz.right, self.quiz.wrong
+            ));
+        }
+        if self.bank.is_on() {
+            lines.push(format!(
+                "  Banked {:5}      Lost   {:6}",
+                self.bank.banked(),
+                -(self.bank.lost() as i64)
+            ));
+        }
+        if !self.config.mutators.is_empty() {
+            let names: Vec<&str> = self.config.mutators.iter().map(Mutator::name).collect();
+            lines.push(format!(
+                "  Mutators {}: {}",
+                self.config.mutators.multiplier_label(),
+                names.join(", ")
+            ));
+        }
+        lines.push(format!("  Seed   {:016x}", self.seed));
+        if !self.rivals.is_empty() {
+            let beaten: Vec<String> = self
+                .rivals
+                .iter()
+                .filter(|r| self.score > r.score)
+                .map(|r| bidi::isolate(&r.name))
+                .collect();
+            let beaten = if beaten.is_empty() {
+                "none".to_string()
+            } else {
+                beaten.join(", ")
+            };
+            let mut line = format!("  Rivals beaten: {}", beaten);
+            if bidi::width(&line) > SUMMARY_WIDTH {
+                line = bidi::truncate(&line, SUMMARY_WIDTH - 3) + "...";
+            }
+            lines.push(line);
+            // The closest rival still ahead
+            if let Some(next) = self
+                .rivals
+                .iter()
+                .filter(|r| r.score >= self.score)
+                .min_by_key(|r| r.score)
+            {
+                lines.push(format!(
+                    "  Next up: {} ({})",
+                    bidi::isolate(&next.name),
+                    next.score
+                ));
+            }
+        }
+        if let Some(challenge) = &self.challenge {
+            let verdict = if self.score > challenge.score {
+                "beaten!"
+            } else {
+                "not beaten yet"
+            };
+            let label = if self.event.is_some() {
+                "Your best here"
+            } else {
+                "Challenge"
+            };
+            lines.push(format!("  {} {} {}", label, challenge.score, verdict));
+        }
+        lines
+    }
+
+    fn draw(&self, stdout: &mut io::Stdout, screen: &mut Screen) -> io::Result<()> {
+        let _span = profile::span("draw");
+        let mut frame = Frame::new(self.width, self.height);
+        {
+            let _span = profile::span("render");
+            self.render(&mut frame);
+            self.render_budget_notice(&mut frame);
+            self.render_profile(&mut frame);
+        }
+        {
+            let _span = profile::span("flush");
+            screen.present(frame, stdout, self.display)?;
+        }
+        // Milestone fanfare, one bell per frame
+        if self.celebration.take_bell() && self.effects.bells() {
+            write!(stdout, "\x07")?;
+            stdout.flush()?;
+        }
+        if let Some(haptics) = &self.haptics {
+            haptics.play(stdout)?;
+        }
+        Ok(())
+    }
+
+    fn render(&self, frame: &mut Frame) {
+        if let Some(photo) = &self.photo {
+            *frame = self.photograph();
+            self.render_photo_hints(frame, photo);
+            return;
+        }
+        self.render_world(frame);
+        if self.focusing() {
+            self.render_focus(frame);
+            return;
+        }
+        if self.locked_out() {
+            self.render_lockout(frame);
+            return;
+        }
+        self.render_pip(frame);
+        self.render_title(frame);
+        self.render_pause(frame);
+        self.render_intermission(frame);
+        self.render_quit_prompt(frame);
+        self.render_summary(frame);
+        self.render_options(frame);
+        self.render_practice(frame);
+        self.render_mutators(frame);
+        self.render_leaderboard(frame);
+        self.render_family(frame);
+        self.render_help(frame);
+    }
+
+    /// The best run on this seed shrunk into the top right corner, in a border labelled
+    /// with its score.
+    fn render_pip(&self, frame: &mut Frame) {
+        let Some(pip) = &self.pip else {
+            return;
+        };
+        if !matches!(self.state, GameState::Playing | GameState::Paused) {
+            return;
+        }
+        let mut full = Frame::new(pip.game.width, pip.game.height);
+        pip.game.render_world(&mut full);
+        let small = full.shrink(PIP_SCALE);
+        let (width, height) = (small.width() as i32, small.height() as i32);
+        let x = self.width as i32 - width - 2;
+        let y = self.ceiling() as i32 + 1;
+        let border = "─".repeat(width as usize);
+        frame.put_str(x - 1, y - 1, &format!("┌{}┐", border), Color::DarkGrey);
+        frame.put_str(x - 1, y + height, &format!("└{}┘", border), Color::DarkGrey);
+        for row in y..y + height {
+            frame.put(x - 1, row, '│', Color::DarkGrey);
+            frame.put(x + width, row, '│', Color::DarkGrey);
+        }
+        let label = match self.best_run.as_ref() {
+            Some(best) => format!(" BEST {} ", best.score),
+            None => String::new(),
+        };
+        frame.put_str(x + 1, y - 1, &label, Color::DarkGrey);
+        frame.blit(&small, x, y);
+        // Shrinking can lose the bird among the pipes, so it goes on top
+        let sprites = self.display.sprites();
+        let (bird_x, bird_y) = pip.game.view().to_screen(pip.game.bird_pos());
+        let (bird_x, bird_y) = (x + bird_x / PIP_SCALE as i32, y + bird_y / PIP_SCALE as i32);
+        if bird_y >= y && bird_y < y + height {
+            sprites
+                .sprite(sprites.bird, Color::Yellow)
+                .draw(frame, bird_x, bird_y);
+        }
+    }
+
+    fn render_budget_notice(&self, frame: &mut Frame) {
+        if let Some(notice) = self.budget.notice() {
+            let x = (self.width as i32 - bidi::width(notice) as i32) / 2;
+            frame.put_str(x, 0, notice, Color::White);
+        }
+    }
+
+    /// The profiler's summary in the top right corner, when built with it and asked for.
+    fn render_profile(&self, frame: &mut Frame) {
+        if !self.show_profile {
+            return;
+        }
+        let lines = profile::summary();
+        if lines.is_empty() {
+            return;
+        }
+        let inner = lines
+            .iter()
+            .map(|line| bidi::width(line))
+            .max()
+            .unwrap_or(0);
+        let x = self.width as i32 - inner as i32 - 3;
+        for (row, line) in boxed(&lines, inner).iter().enumerate() {
+            frame.put_str(x, row as i32, line, Color::White);
+        }
+    }
+
+    fn render_title(&self, frame: &mut Frame) {
+        if self.state != GameState::Title {
+            return;
+        }
+        if self
+            .kiosk
+            .as_ref()
+            .is_some_and(|kiosk| kiosk.shown == Page::HowToPlay)
+        {
+            self.render_how_to_play(frame);
+            return;
+        }
+
+        let history = if self.recent_scores.is_empty() {
+            "  No runs yet".to_string()
+        } else {
+            format!(
+                "  Last {:<2} {} {}",
+                self.recent_scores.len(),
+                sparkline(&self.recent_scores, HISTORY_SPARKLINE_RUNS),
+                history::trend_arrow(&self.recent_scores)
+            )
+        };
+        let mut lines = vec![String::new()];
+        lines.extend(LOGO.iter().map(|row| format!("          {}", row)));
+        lines.extend([
+            String::new(),
+            format!("  Best: {:<5} {}", self.high_score, self.category.label()),
+            history,
+            String::new(),
+        ]);
+        if let Some(event) = &self.event {
+            lines.push(format!("  CHALLENGE: {}", bidi::isolate(&event.name)));
+            if let Some(creator) = &event.creator {
+                lines.push(format!("  Set by {}", bidi::isolate(creator)));
+            }
+            let ends = match event.expires {
+                Some(last) if event.is_over(storage::unix_now()) => format!("  Ended {}", last),
+                Some(last) => format!("  Runs to the end of {}", last),
+                None => "  Never ends".to_string(),
+            };
+            lines.push(ends);
+            lines.push(format!("  Your best: {}", event.best));
+            lines.push(String::new());
+        } else if let Some(challenge) = &self.challenge {
+            lines.push(format!("  CHALLENGE: score to beat {}", challenge.score));
+            lines.push(String::new());
+        }
+        if !self.warnings.is_empty() {
+            lines.extend(self.warnings.iter().map(|w| format!("  ! {}", w)));
+            lines.push(String::new());
+        }
+        if self.kiosk.is_some() {
+            lines.push("  Step right up and have a go!".to_string());
+            lines.push(String::new());
+        }
+        self.render_menu_panel(frame, lines, &self.title_menu, Color::Cyan);
+    }
+
+    /// The kiosk's attract page on how to play.
+    fn render_how_to_play(&self, frame: &mut Frame) {
+        let flap = self.keymap.label(Action::Flap).unwrap_or_default();
+        let lines = vec![
+            String::new(),
+            "  HOW TO PLAY".to_string(),
+            String::new(),
+            format!("  {}: flap", flap),
+            String::new(),
+            "  Fly the bird through the gaps".to_string(),
+            "  between the pipes. Touching a pipe".to_string(),
+            "  or the ground ends the run.".to_string(),
+            String::new(),
+            "  Every pipe passed scores a point,".to_string(),
+            "  with bonus points for style.".to_string(),
+            String::new(),
+            format!("  Press {} to play!", flap),
+            String::new(),
+        ];
+        let panel = boxed(&lines, SUMMARY_WIDTH);
+        let x = (self.width as i32 - SUMMARY_WIDTH as i32 - 2) / 2;
+        let y = (self.height as i32 - panel.len() as i32) / 2;
+        for (i, line) in panel.iter().enumerate() {
+            frame.put_str(x, y + i as i32, line, Color::Green);
+        }
+    }
+
+    fn render_pause(&self, frame: &mut Frame) {
+        if self.state != GameState::Paused {
+            return;
+        }
+        let mut lines = vec![String::new(), "  PAUSED".to_string(), String::new()];
+        if let Some(status) = &self.status {
+            lines.push(format!("  {}", status));
+            lines.push(String::new());
+        }
+        self.render_menu_panel(frame, lines, &self.pause_menu, Color::White);
+    }
+
+    fn render_quit_prompt(&self, frame: &mut Frame) {
+        if self.state != GameState::ConfirmQuit {
+            return;
+        }
+        // Presets without yes/no keys confirm with the quit key and cancel with back
+        let yes = self
+            .keymap
+            .label(Action::Yes)
+            .or_else(|| self.keymap.label(Action::Quit));
+        let no = self
+            .keymap
+            .label(Action::No)
+            .or_else(|| self.keymap.label(Action::Back));
+        let lines = vec![
+            String::new(),
+            format!(
+                "  Quit? The run ends here ({}/{})",
+                yes.unwrap_or_default().to_lowercase(),
+                no.unwrap_or_default().to_lowercase()
+            ),
+            String::new(),
+        ];
+        let panel = boxed(&lines, SUMMARY_WIDTH);
+        let x = (self.width as i32 - SUMMARY_WIDTH as i32 - 2) / 2;
+        let y = (self.height as i32 - panel.len() as i32) / 2;
+        for (i, line) in panel.iter().enumerate() {
+            frame.put_str(x, y + i as i32, line, Color::Yellow);
+        }
+    }
+
+    /// What's left of the game once the day's time is spent.
+    fn render_lockout(&self, frame: &mut Frame) {
+        let minutes = self.time_limit.as_ref().map_or(0, TimeLimit::minutes);
+        let quit = self.keymap.label(Action::Quit).unwrap_or_default();
+        let lines = vec![
+            String::new(),
+            "  TIME'S UP FOR TODAY".to_string(),
+            String::new(),
+            format!("  Today's {} min of play are used up.", minutes),
+            "  Come back tomorrow!".to_string(),
+            String::new(),
+            format!("  {}: quit", quit),
+            String::new(),
+        ];
+        let panel = boxed(&lines, SUMMARY_WIDTH);
+        let x = (self.width as i32 - SUMMARY_WIDTH as i32 - 2) / 2;
+        let y = (self.height as i32 - panel.len() as i32) / 2;
+        for (i, line) in panel.iter().enumerate() {
+            frame.put_str(x, y + i as i32, line, Color::Yellow);
+        }
+    }
+
+    /// The focus timer, in place of the game while a pomodoro focus period runs.
+    fn render_focus(&self, frame: &mut Frame) {
+        let Some(pomodoro) = &self.pomodoro else {
+            return;
+        };
+        let (_, round, left) = pomodoro.period(storage::unix_now());
+        let quit = self.keymap.label(Action::Quit).unwrap_or_default();
+        let lines = vec![
+            String::new(),
+            format!("  FOCUS  {}", pomodoro::clock(left)),
+            String::new(),
+            format!("  Round {} of {}", round, pomodoro::ROUNDS),
+            format!(
+                "  Then a {} min break to play.",
+                pomodoro::break_after(round) / 60
+            ),
+            String::new(),
+            format!("  {}: quit", quit),
+            String::new(),
+        ];
+        let panel = boxed(&lines, SUMMARY_WIDTH);
+        let x = (self.width as i32 - SUMMARY_WIDTH as i32 - 2) / 2;
+        let y = (self.height as i32 - panel.len() as i32) / 2;
+        for (i, line) in panel.iter().enumerate() {
+            frame.put_str(x, y + i as i32, line, Color::Red);
+        }
+    }
+
+    fn render_intermission(&self, frame: &mut Frame) {
+        if self.state != GameState::Intermission {
+            return;
+        }
+        let Some(marathon) = &self.marathon else {
+            return;
+        };
+        let Some(segment) = marathon.last else {
+            return;
+        };
+        let flap = self.keymap.label(Action::Flap).unwrap_or_default();
+        let quit = self.keymap.label(Action::Quit).unwrap_or_default();
+        let seconds = segment.ticks * TICK_RATE.as_millis() as u32 / 1000;
+        let total = (marathon.carried_ticks + self.tick) * TICK_RATE.as_millis() as u32 / 1000;
+        let lines = vec![
+            String::new(),
+            format!("  REST STOP  Segment {} done", marathon.segments),
+            String::new(),
+            format!(
+                "  Points {:5}      Time   {:3}:{:02}",
+                segment.points,
+                seconds / 60,
+                seconds % 60
+            ),
+            format!(
+                "  Flaps  {:5}      Pipes  {:6}",
+                segment.flaps,
+                marathon::SEGMENT
+            ),
+            format!(
+                "  Total  {:5}      In all {:3}:{:02}",
+                self.score,
+                total / 60,
+                total % 60
+            ),
+            String::new(),
+            "  Stretch:".to_string(),
+            format!("  {}", marathon.stretch()),
+            String::new(),
+            format!("  {}: next segment", flap),
+            format!("  {}: stop here, carry on later", quit),
+            String::new(),
+        ];
+        let panel = boxed(&lines, SUMMARY_WIDTH);
+        let x = (self.width as i32 - SUMMARY_WIDTH as i32 - 2) / 2;
+        let y = (self.height as i32 - panel.len() as i32) / 2;
+        for (i, line) in panel.iter().enumerate() {
+            frame.put_str(x, y + i as i32, line, Color::Green);
+        }
+    }
+
+    fn render_options(&self, frame: &mut Frame) {
+        let Some(menu) = &self.options_menu else {
+            return;
+        };
+        let note = if self.state == GameState::Title {
+            ""
+        } else {
+            "  Changes apply from the next run"
+        };
+        let lines = vec![
+            String::new(),
+            "  OPTIONS".to_string(),
+            "  Arrows/mouse to change, Esc to go back".to_string(),
+            note.to_string(),
+        ];
+        self.render_menu_panel(frame, lines, menu, Color::Cyan);
+    }
+
+    fn render_practice(&self, frame: &mut Frame) {
+        let Some(menu) = &self.practice_menu else {
+            return;
+        };
+        let lines = vec![
+            String::new(),
+            "  PRACTICE".to_string(),
+            "  Drills loop forever; a crash restarts".to_string(),
+            "  them instantly. Scores are not kept.".to_string(),
+        ];
+        self.render_menu_panel(frame, lines, menu, Color::Cyan);
+    }
+
+    fn render_mutators(&self, frame: &mut Frame) {
+        let Some(menu) = &self.mutators_menu else {
+            return;
+        };
+        let lines = vec![
+            String::new(),
+            "  MUTATORS".to_string(),
+            "  Harder runs for more points. Scores".to_string(),
+            format!(
+                "  are ranked on their own. Now: {}",
+                self.settings().mutators.multiplier_label()
+            ),
+        ];
+        self.render_menu_panel(frame, lines, menu, Color::Magenta);
+    }
+
+    fn render_leaderboard(&self, frame: &mut Frame) {
+        let Some(index) = self.leaderboard_view else {
+            return;
+        };
+        let categories = self.leaderboard_categories();
+        let category = &categories[index];
+        let entries = self.leaderboard.entries(category);
+
+        let mut lines = vec![
+            String::new(),
+            "  LEADERBOARD".to_string(),
+            format!("  < {} >", category.label()),
+            String::new(),
+        ];
+        if entries.is_empty() {
+            lines.push("  No scores yet".to_string());
+        }
+        for (rank, entry) in entries.iter().enumerate() {
+            // Scores imported from the old high score files have no date
+            let date = if entry.timestamp == 0 {
+                String::new()
+            } else {
+                Date::from_unix(entry.timestamp).to_string()
+            };
+            lines.push(format!("  {:>2}. {:>6}    {}", rank + 1, entry.score, date));
+        }
+        let set_aside = self.leaderboard.set_aside();
+        if set_aside > 0 {
+            lines.push(String::new());
+            lines.push(format!(
+                "  {} impossible score{} set aside",
+                set_aside,
+                if set_aside == 1 { "" } else { "s" }
+            ));
+        }
+        lines.push(String::new());
+        lines.push(format!(
+            "  {}/{}  Arrows: category  Esc: back",
+            index + 1,
+            categories.len()
+        ));
+
+        let panel = boxed(&lines, SUMMARY_WIDTH);
+        let x = (self.width as i32 - SUMMARY_WIDTH as i32 - 2) / 2;
+        let y = (self.height as i32 - panel.len() as i32) / 2;
+        for (i, line) in panel.iter().enumerate() {
+            frame.put_str(x, y + i as i32, line, Color::Cyan);
+        }
+    }
+
+    /// Everyone's best scores together, each in their own color, under whoever did best
+    /// this week.
+    fn render_family(&self, frame: &mut Frame) {
+        let Some((family, index)) = &self.family_view else {
+            return;
+        };
+        let categories = family.categories(&self.category);
+        let category = &categories[*index];
+        let entries = family.entries(category);
+        let banner = match family.winner(category, history::WEEK, storage::unix_now()) {
+            Some((name, score)) => format!("  ★ This week: {} with {} ★", name, score),
+            None => "  No scores this week yet".to_string(),
+        };
+
+        let mut lines = vec![
+            String::new(),
+            "  FAMILY LEADERBOARD".to_string(),
+            format!("  < {} >", category.label()),
+            String::new(),
+        ];
+        let banner_line = lines.len();
+        lines.extend([banner, String::new()]);
+        let first_entry = lines.len();
+        if entries.is_empty() {
+            lines.push("  No scores yet".to_string());
+        }
+        for (rank, entry) in entries.iter().enumerate() {
+            let date = if entry.timestamp == 0 {
+                String::new()
+            } else {
+                Date::from_unix(entry.timestamp).to_string()
+            };
+            lines.push(format!(
+                "  {:>2}. {:<16} {:>5}  {}",
+                rank + 1,
+                players::label(entry.player.as_deref()),
+                entry.score,
+                date
+            ));
+        }
+        lines.push(String::new());
+        lines.push(format!(
+            "  {}/{}  Arrows: category  Esc: back",
+            index + 1,
+            categories.len()
+        ));
+
+        let panel = boxed(&lines, SUMMARY_WIDTH);
+        let x = (self.width as i32 - SUMMARY_WIDTH as i32 - 2) / 2;
+        let y = (self.height as i32 - panel.len() as i32) / 2;
+        for (i, line) in panel.iter().enumerate() {
+            // The border comes first, so each line is one row down in the panel
+            let color = match i.checked_sub(1 + first_entry) {
+                Some(rank) if rank < entries.len() => {
+                    players::color(entries[rank].player.as_deref())
+                }
+                _ if i == 1 + banner_line => Color::Yellow,
+                _ => Color::Cyan,
+            };
+            frame.put_str(x, y + i as i32, line, color);
+        }
+    }
+
+    fn help_lines(&self) -> Vec<String> {
+        let width = HELP_WIDTH
+            .min((self.width as usize).saturating_sub(2))
+            .saturating_sub(4);
+        help::lines(&self.keymap, &REPLAY_CONTROLS, width)
+    }
+
+    /// Help lines that fit on screen at once.
+    fn help_page(&self) -> usize {
+        (self.height as usize).saturating_sub(8).max(1)
+    }
+
+    fn render_help(&self, frame: &mut Frame) {
+        let Some(scroll) = self.help_scroll else {
+            return;
+        };
+        let help = self.help_lines();
+        let page = self.help_page();
+        let end = (scroll + page).min(help.len());
+
+        let mut lines = vec![String::new(), "  HELP".to_string()];
+        lines.extend(help[scroll..end].iter().map(|line| format!("  {}", line)));
+        lines.push(String::new());
+        lines.push(format!(
+            "  {}-{} of {}  ↑/↓: scroll  Esc: close",
+            scroll + 1,
+            end,
+            help.len()
+        ));
+
+        let width = HELP_WIDTH.min((self.width as usize).saturating_sub(2));
+        let panel = boxed(&lines, width);
+        let x = (self.width as i32 - width as i32 - 2) / 2;
+        let y = (self.height as i32 - panel.len() as i32) / 2;
+        for (i, line) in panel.iter().enumerate() {
+            frame.put_str(x, y + i as i32, line, Color::White);
+        }
+    }
+
+    /// Draw a centered panel with `header` lines above a menu.
+    fn render_menu_panel<T: Copy + PartialEq>(
+        &self,
+        frame: &mut Frame,
+        mut header: Vec<String>,
+        menu: &Menu<T>,
+        color: Color,
+    ) {
+        let menu_row = header.len();
+        for _ in 0..=menu.height() {
+            header.push(String::new());
+        }
+
+        let panel = boxed(&header, SUMMARY_WIDTH);
+        let x = (self.width as i32 - SUMMARY_WIDTH as i32 - 2) / 2;
+        let y = (self.height as i32 - panel.len() as i32) / 2;
+        for (i, line) in panel.iter().enumerate() {
+            frame.put_str(x, y + i as i32, line, color);
+        }
+        menu.render(frame, x + 3, y + 1 + menu_row as i32);
+    }
+
+    /// `color` warmed towards red as the speed ramp climbs.
+    fn heat(&self, color: Color) -> Color {
+        let level = self.ramp.level();
+        if level > 0.0 {
+            render::shift_hue(color, -RAMP_HUE * level)
+        } else {
+            color
+        }
+    }
+
+    /// How the world is drawn: widening a little ahead of the bird up the speed ramp.
+    fn view(&self) -> View {
+        View::new(BIRD_X as f32, 1.0 + RAMP_STRETCH * self.ramp.level())
+    }
+
+    /// Everything on the board but the HUD.
+    fn render_scene(&self, frame: &mut Frame) {
+        let sprites = self.display.sprites();
+        let view = self.view();
+        // Draw weather behind everything else
+        if self.budget.allows(Effect::Particles) {
+            let particles = self.weather.particles();
+            particles.render(frame, view, self.effects.share(particles.len()));
+        }
+
+        // Draw pipes
+        let visible_until = self
+            .weather
+            .visibility()
+            .map(|distance| BIRD_X as i32 + distance);
+        // Pipes flash gold when a milestone is reached
+        let pipe_color = if self.celebration.flashing() && self.effects.flash() {
+            Color::Yellow
+        } else {
+            self.heat(Color::Green)
+        };
+        // Invisible pipes only show in flashes while the run is on
+        let hidden =
+            self.state == GameState::Playing && !self.config.mutators.pipes_shown(self.tick);
+        for pipe in &self.pipes {
+            let pipe_color = if pipe.flip {
+                Color::Magenta
+            } else {
+                pipe_color
+            };
+            // Skip drawing if pipe is completely off screen
+            if pipe.is_offscreen() || pipe.x >= self.width as i32 {
+                continue;
+            }
+
+            // Fog hides pipes beyond the visibility distance
+            if visible_until.is_some_and(|limit| pipe.x > limit) {
+                continue;
+            }
+
+            if hidden {
+                continue;
+            }
+
+            // Memory mode fades the pipe out, then blinks its gap's edges for a moment
+            let x = view.column(pipe.x as f32);
+            if pipe.alpha == 0.0 {
+                if pipe.faded_ticks <= MEMORY_FLASH_TICKS && pipe.faded_ticks % 2 == 1 {
+                    let edge = "━".repeat(pipe.width as usize);
+                    for y in [pipe.gap_y.saturating_sub(1), pipe.gap_y + pipe.gap] {
+                        frame.put_str(x, y as i32, &edge, Color::Yellow);
+                    }
+                }
+                continue;
+            }
+
+            // Draw top pipe, then bottom pipe
+            let rows = (self.ceiling()..pipe.gap_y).chain((pipe.gap_y + pipe.gap)..self.ground());
+            for y in rows {
+                if sprites.wide {
+                    for column in (0..pipe.width as i32).step_by(2) {
+                        sprites
+                            .sprite(sprites.pipe, pipe_color)
+                            .draw(frame, x + column, y as i32);
+                    }
+                } else {
+                    let cap = y + 1 == pipe.gap_y || y == pipe.gap_y + pipe.gap;
+                    let row = self
+                        .config
+                        .pipe_style
+                        .row(y as i32, pipe.width, cap, sprites.pipe);
+                    frame.put_str(x, y as i32, &row, pipe_color);
+                }
+                if pipe.alpha < 1.0 {
+                    frame.dim(x, y as i32, pipe.width as i32, pipe.alpha);
+                }
+            }
+
+            // A shut quiz gate fills its gap, with the sum across the middle
+            if let Some(quiz) = pipe.quiz.as_ref().filter(|quiz| !quiz.is_open()) {
+                let color = if quiz.gate == Gate::Wrong {
+                    Color::Red
+                } else {
+                    Color::Yellow
+                };
+                let bars = "▒".repeat(pipe.width as usize);
+                let middle = pipe.gap_y + pipe.gap / 2;
+                for y in pipe.gap_y..pipe.gap_y + pipe.gap {
+                    frame.put_str(x, y as i32, &bars, color);
+                }
+                let question = format!("{}=", quiz.question);
+                let offset = (pipe.width as i32 - question.len() as i32) / 2;
+                frame.put_str(x + offset, middle as i32, &question, Color::White);
+            }
+        }
+
+        if self.has_ground() {
+            let strips = [
+                (
+                    self.ground(),
+                    GROUND_ROWS,
+                    sprites.ground,
+                    self.heat(Color::DarkGreen),
+                ),
+                (
+                    self.ceiling_top(),
+                    CEILING_ROWS,
+                    sprites.ceiling,
+                    Color::DarkGrey,
+                ),
+            ];
+            for (top, rows, pattern, color) in strips {
+                let skip = self.travelled as usize % pattern.chars().count();
+                let strip: String = pattern
+                    .chars()
+                    .cycle()
+                    .skip(skip)
+                    .take(self.width as usize)
+                    .collect();
+                for y in top..top + rows {
+                    frame.put_str(0, y as i32, &strip, color);
+                }
+            }
+        }
+
+        // Mark where each rival's best run ended
+        for rival in &self.rivals {
+            if rival.ticks <= self.tick {
+                continue;
+            }
+            let ahead = (rival.ticks - self.tick) as f32 * self.ramp.pipe_speed();
+            let x = view.column(BIRD_X as f32 + ahead);
+            if x >= self.width as i32 {
+                continue;
+            }
+            // The name goes just under the ceiling, clear of the conditions line
+            let name_y = self.ceiling().max(1) as i32;
+            if self.budget.allows(Effect::Decorations) {
+                for y in name_y + 1..self.ground() as i32 {
+                    frame.put(x, y, sprites.rival, Color::DarkYellow);
+                }
+            }
+            frame.put_str(x, name_y, &rival.name, Color::DarkYellow);
+        }
+
+        // Night falls on the world but not on the bird or the HUD
+        let (bird_x, bird_y) = view.to_screen(self.bird_pos());
+        let gradients = self.budget.allows(Effect::Gradients);
+        frame.apply_lighting(|x, y| {
+            let light = self.day_cycle.brightness_at(x - bird_x, y - bird_y);
+            // Without shades a cell is either lit or dark, so none needs a true-color code
+            match (gradients, light >= 0.5) {
+                (true, _) => light,
+                (false, true) => 1.0,
+                (false, false) => 0.0,
+            }
+        });
+
+        // Draw the predicted paths, flapping now and not flapping
+        if self.config.trajectory && self.state == GameState::Playing {
+            let wind = self.weather.wind();
+            for (flap, glyph, color) in [
+                (false, sprites.coast, Color::DarkGrey),
+                (true, sprites.flap, Color::DarkCyan),
+            ] {
+                for pos in self.predict(flap, wind) {
+                    let (x, y) = view.to_screen(pos);
+                    frame.put(x, y, glyph, color);
+                }
+            }
+        }
+
+        if let Some(ghost) = &self.ghost {
+            let (x, y) = view.to_screen(ghost.game.bird_pos());
+            sprites
+                .sprite(sprites.bird, Color::DarkGrey)
+                .draw(frame, x, y);
+        }
+
+        for member in &self.flock {
+            let (x, y) = view.to_screen(member.bird.body.pos);
+            sprites
+                .sprite(sprites.bird, Color::DarkYellow)
+                .draw(frame, x, y);
+        }
+
+        // Draw bird over the paths, blinking while invincible
+        let blink_off = self.invincible_ticks > 0 && (self.invincible_ticks / 2) % 2 == 1;
+        if !blink_off {
+            let glyph = if self.state == GameState::GameOver {
+                sprites.crash
+            } else {
+                sprites.bird
+            };
+            sprites
+                .sprite(glyph, Color::Yellow)
+                .draw(frame, bird_x, bird_y);
+        }
+
+        // Draw the assist arrow at the right edge, level with the bird
+        if self.config.assist {
+            if let Some(arrow) = self.assist_arrow() {
+                frame.put(self.width as i32 - 2, bird_y, arrow, Color::White);
+            }
+        }
+
+        // Draw the latest style bonus above the bird
+        if let Some((bonus, _)) = self.bonus_popup {
+            frame.put_str(bird_x - 2, bird_y - 2, bonus, Color::White);
+        }
+
+        if let Some((message, _)) = &self.rival_popup {
+            let x = (self.width as i32 - bidi::width(message) as i32) / 2;
+            frame.put_str(x, 3, message, Color::DarkYellow);
+        }
+
+        // Draw milestone confetti and banner
+        if self.budget.allows(Effect::Decorations) {
+            // Confetti is thrown across the screen rather than the world
+            let confetti = self.celebration.confetti();
+            confetti.render(
+                frame,
+                View::new(0.0, 1.0),
+                self.effects.share(confetti.len()),
+            );
+        }
+        if let Some(banner) = self.celebration.banner() {
+            let x = (self.width as i32 - bidi::width(&banner) as i32) / 2;
+            frame.put_str(x, 2, &banner, Color::Yellow);
+        }
+
+        if self.config.zoom_follow && !self.screensaver {
+            *frame = self.camera.view(frame);
+        }
+    }
+
+    fn render_world(&self, frame: &mut Frame) {
+        self.render_scene(frame);
+        if !self.screensaver {
+            self.render_hud(frame);
+        }
+    }
+
+    /// The paused run as photo mode's camera sees it, with the HUD if it's showing and
+    /// the filter over it all.
+    fn photograph(&self) -> Frame {
+        let mut scene = Frame::new(self.width, self.height);
+        self.render_scene(&mut scene);
+        let Some(photo) = &self.photo else {
+            return scene;
+        };
+        let mut frame = photo.frame(scene);
+        if photo.hud {
+            self.render_hud(&mut frame);
+        }
+        photo.develop(&mut frame);
+        frame
+    }
+
+    /// Photo mode's keys along the bottom, kept out of the saved picture.
+    fn render_photo_hints(&self, frame: &mut Frame, photo: &Photo) {
+        let hints = format!(
+            "←→↑↓ move  Z {}  H {}  F {}  T save text  P save PNG  Esc back",
+            if photo.zoomed { "zoom out" } else { "zoom in" },
+            if photo.hud { "hide HUD" } else { "show HUD" },
+            photo.filter.label(),
+        );
+        let bottom = self.height as i32 - 1;
+        let x = (self.width as i32 - bidi::width(&hints) as i32).max(0) / 2;
+        frame.put_str(0, bottom, &" ".repeat(self.width as usize), Color::Reset);
+        frame.put_str(x, bottom, &hints, Color::White);
+        if let Some(status) = &photo.status {
+            let x = (self.width as i32 - bidi::width(status) as i32).max(0) / 2;
+            frame.put_str(
+                0,
+                bottom - 1,
+                &" ".repeat(self.width as usize),
+                Color::Reset,
+            );
+            frame.put_str(x, bottom - 1, status, Color::Yellow);
+        }
+    }
+
+    /// Score, conditions, and hearts around the edges, the word to type, and the revive
+    /// prompt.
+    fn render_hud(&self, frame: &mut Frame) {
+        // Draw score
+        let hud_y = self.height as i32 - 1;
+        let hud = match &self.drill {
+            Some(drill) => {
+                let level = drill.level();
+                format!(
+                    "{}  Attempt {}  Loops {}",
+                    level.name,
+                    self.drill_attempts,
+                    self.breakdown.pipes as usize / level.pipes.len()
+                )
+            }
+            None if self.bank.is_on() => format!(
+                "Banked: {}  At risk: {}  Best: {}",
+                self.bank.banked(),
+                self.score.saturating_sub(self.bank.banked()),
+                self.high_score
+            ),
+            None => format!(
+                "Score: {}  Week: {}  Best: {}",
+                self.score, self.week_best, self.high_score
+            ),
+        };
+        frame.put_str(2, hud_y, &hud, Color::Cyan);
+
+        // Draw the open bank or the danger of having just banked, a bet riding, the
+        // rewind meter, the day's last minutes and the break's, from the right
+        let mut notices = Vec::new();
+        if self.bank.is_open() {
+            let key = self.keymap.label(Action::Bank).unwrap_or_default();
+            notices.push((format!("[{}] Bank {}", key, self.score), Color::Yellow));
+        } else if self.bank.is_exposed() {
+            notices.push(("Exposed!".to_string(), Color::Red));
+        }
+        if let Some(Wager::Riding { stake, pipes_left }) = self.wager {
+            notices.push((
+                format!("x2 or /2: {} pipes for {}", pipes_left, stake * 2),
+                Color::Magenta,
+            ));
+        }
+        if self.config.rewind {
+            let key = self.keymap.label(Action::Rewind).unwrap_or_default();
+            let filled = (self.rewind.fraction() * 10.0).round() as usize;
+            let color = if filled == 0 {
+                Color::DarkGrey
+            } else {
+                Color::Blue
+            };
+            notices.push((
+                format!(
+                    "[{}] ◀◀ {}{}",
+                    key,
+                    "▮".repeat(filled),
+                    "▯".repeat(10 - filled)
+                ),
+                color,
+            ));
+        }
+        let ticks = self.unrecorded_ticks();
+        if let Some(minutes) = self.time_limit.as_ref().and_then(|l| l.warning(ticks)) {
+            let color = if minutes > 1 {
+                Color::Yellow
+            } else {
+                Color::Red
+            };
+            notices.push((format!("{} min left today", minutes), color));
+        }
+        if let Some(pomodoro) = &self.pomodoro {
+            let (_, _, left) = pomodoro.period(storage::unix_now());
+            notices.push((format!("Break {}", pomodoro::clock(left)), Color::Green));
+        }
+        if let Some(marathon) = self.marathon.as_ref().filter(|_| self.drill.is_none()) {
+            notices.push((
+                format!(
+                    "Segment {}: {} to rest",
+                    marathon.segments + 1,
+                    marathon.to_go(self.breakdown.pipes)
+                ),
+                Color::Cyan,
+            ));
+        }
+        let mut right = self.width as i32 - 2;
+        for (notice, color) in notices {
+            right -= bidi::width(&notice) as i32;
+            frame.put_str(right, 0, &notice, color);
+            right -= 2;
+        }
+
+        // Draw combo meter
+        frame.put_str(
+            40,
+            hud_y,
+            &format!(
+                "Combo [{}{}]",
+                "#".repeat(self.combo() as usize),
+                ".".repeat((COMBO_MAX - self.combo()) as usize)
+            ),
+            Color::Magenta,
+        );
+
+        // Draw the metronome: a dot for each beat between pipes, lit around the beat
+        if let Some(clock) = &self.rhythm {
+            let (beat, beats) = clock.bar();
+            let dots: String = (0..beats)
+                .map(|i| if i == beat { '●' } else { '○' })
+                .collect();
+            let color = if clock.is_near_beat() {
+                Color::Yellow
+            } else {
+                Color::DarkGrey
+            };
+            frame.put_str(60, hud_y, &format!("♪ {}", dots), color);
+        }
+
+        // Draw weather and time of day
+        let mut conditions = Vec::new();
+        if self.weather.current() != weather::Weather::Clear {
+            conditions.push(self.weather.current().label());
+        }
+        if self.day_cycle.phase() != Phase::Day {
+            conditions.push(self.day_cycle.phase().label());
+        }
+        if self.speed != 100 {
+            conditions.push(SPEED_LABELS[((self.speed - MIN_SPEED) / SPEED_STEP) as usize]);
+        }
+        let conditions = conditions.join("  ");
+        frame.put_str(2, 0, &conditions, Color::White);
+
+        // Draw the ticker in whatever room the conditions and notices leave on the top row
+        let left = 2 + bidi::width(&conditions) as i32 + 2;
+        let line = self.ticker.line((right + 1 - left).max(0) as usize);
+        frame.put_str(
+            right + 1 - bidi::width(&line) as i32,
+            0,
+            &line,
+            Color::DarkYellow,
+        );
+
+        // Draw hearts
+        if let Some(max_lives) = self.config.lives {
+            let hearts = format!(
+                "{}{}",
+                "♥".repeat(self.lives as usize),
+                "♡".repeat(max_lives.saturating_sub(self.lives) as usize)
+            );
+            let hearts_x = self.width.saturating_sub(max_lives as u16 + 2);
+            frame.put_str(hearts_x as i32, hud_y, &hearts, Color::Red);
+        }
+
+        // Draw the word to type with the next letter over the bird, or under it near the
+        // ceiling
+        if let Some(typing) = self
+            .typing
+            .as_ref()
+            .filter(|_| self.state == GameState::Playing)
+        {
+            let (typed, rest) = typing.word();
+            let (x, bird_y) = self.view().to_screen(self.bird_pos());
+            let y = if bird_y - 2 > self.ceiling() as i32 {
+                bird_y - 2
+            } else {
+                bird_y + 2
+            };
+            frame.put_str(x - typed.len() as i32, y, typed, Color::DarkGrey);
+            let mut rest = rest.chars();
+            if let Some(next) = rest.next() {
+                frame.put(x, y, next, Color::Yellow);
+            }
+            frame.put_str(x + 1, y, rest.as_str(), Color::White);
+        }
+
+        let msg_y = (self.height / 2) as i32;
+        let msg_x = (self.width / 2) as i32 - 12;
+
+        // Draw revive prompt
+        if let Some(timer) = &self.reaction {
+            let filled = (timer.fraction_left() * 20.0).ceil() as usize;
+            let lines = [
+                "╔══════════════════════════╗".to_string(),
+                "║   REVIVE!                ║".to_string(),
+                format!(
+                    "║   Press [{}] now!         ║",
+                    self.keymap.revive_prompt(timer.key)
+                ),
+                format!("║   {}{} ║", "█".repeat(filled), " ".repeat(22 - filled)),
+                format!("║   Penalty: -{} points     ║", REVIVE_PENALTY),
+                "╚══════════════════════════╝".to_string(),
+            ];
+            for (i, line) in lines.iter().enumerate() {
+                frame.put_str(msg_x, msg_y - 1 + i as i32, line, Color::Magenta);
+            }
+        }
+
+        // Draw the double-or-nothing offer
+        if let Some(wager @ Wager::Offered { .. }) = self.wager {
+            let filled = (wager.fraction_left() * 20.0).ceil() as usize;
+            let key = self.keymap.label(Action::Wager).unwrap_or_default();
+            let lines = [
+                "   DOUBLE OR NOTHING?".to_string(),
+                format!("   Pass {} more pipes: {}", wager::PIPES, self.score * 2),
+                format!("   Crash first: {}", self.score / 2),
+                format!("   [{}] Go, other keys: stop", key),
+                format!("   {}", "█".repeat(filled)),
+            ];
+            for (i, line) in boxed(&lines, 26).iter().enumerate() {
+                frame.put_str(msg_x, msg_y - 1 + i as i32, line, Color::Magenta);
+            }
+        }
+    }
+
+    fn render_summary(&self, frame: &mut Frame) {
+        if self.state == GameState::GameOver {
+            let mut lines = self.summary_lines();
+            lines.push(String::new());
+            let key = |action| self.keymap.label(action).unwrap_or_default();
+            let retry = format!("{}/{}: Retry", key(Action::Retry), key(Action::Flap));
+            let share = format!("{}: Share", key(Action::Share));
+            lines.push(format!(
+                "  {:<16}{}: Same seed",
+                retry,
+                key(Action::RetrySameSeed)
+            ));
+            lines.push(format!(
+                "  {:<16}{}: View replay",
+                share,
+                key(Action::Replay)
+            ));
+            match &self.kiosk {
+                Some(kiosk) => lines.push(format!(
+                    "  Next player in {}s",
+                    kiosk.countdown(self.clock.now())
+                )),
+                None => lines.push(format!("  {}: Quit", key(Action::Quit))),
+            }
+
+            let color = if self.is_new_record {
+                Color::Yellow
+            } else {
+                Color::Red
+            };
+            let panel = boxed(&lines, SUMMARY_WIDTH);
+            let x = (self.width as i32 - SUMMARY_WIDTH as i32 - 2) / 2;
+            let y = (self.height as i32 - panel.len() as i32) / 2;
+            for (i, line) in panel.iter().enumerate() {
+                frame.put_str(x, y + i as i32, line, color);
+            }
+            if let Some(status) = &self.status {
+                frame.put_str(x, y + panel.len() as i32, status, Color::White);
+            }
+
+            // The challenge code is long, so it gets the full width below the panel
+            let mut texts = vec![format!(
+                "Challenge code: tflap --challenge {}",
+                self.challenge_code()
+            )];
+            if let Some(url) = self.submission_url() {
+                texts.push(format!("Send the code in at {}", url));
+            }
+            let columns = (self.width as usize).saturating_sub(4).max(1);
+            let rows: Vec<String> = texts
+                .iter()
+                .flat_map(|text| {
+                    let chars: Vec<char> = text.chars().collect();
+                    chars
+                        .chunks(columns)
+                        .map(|row| row.iter().collect())
+                        .collect::<Vec<String>>()
+                })
+                .collect();
+            for (i, row) in rows.iter().enumerate() {
+                frame.put_str(
+                    2,
+                    y + panel.len() as i32 + 1 + i as i32,
+                    row,
+                    Color::DarkGrey,
+                );
+            }
+        }
+    }
+}
+
+/// Everything the simulation needs to carry on from a given tick.
+#[derive(Clone)]
+struct Snapshot {
+    bird: Bird,
+    pipes: Vec<Pipe>,
+    score: u32,
+    state: GameState,
+    lives: u8,
+    invincible_ticks: u32,
+    streak: u32,
+    breakdown: ScoreBreakdown,
+    bonus_popup: Option<(&'static str, u32)>,
+    reaction: Option<ReactionTimer>,
+    weather: WeatherSystem,
+    day_cycle: DayCycle,
+    rng: StdRng,
+    celebration: Celebration,
+    speed: u8,
+    ramp: SpeedRamp,
+    bank: Bank,
+    wager: Option<Wager>,
+    typing: Option<Typing>,
+    quiz: Tally,
+    rewind: Rewind<Snapshot>,
+    rewinding: bool,
+    rhythm: Option<BeatClock>,
+    marathon: Option<Marathon>,
+    scroll: f32,
+    travelled: u32,
+    tick: u32,
+}
+
+impl Game {
+    fn snapshot(&self) -> Snapshot {
+        self.capture(self.rewind.clone())
+    }
+
+    /// A state to rewind to, without the rewind's own history inside it.
+    fn moment(&self) -> Snapshot {
+        self.capture(Rewind::default())
+    }
+
+    fn capture(&self, rewind: Rewind<Snapshot>) -> Snapshot {
+        Snapshot {
+            bird: self.bird.clone(),
+            pipes: self.pipes.clone(),
+            score: self.score,
+            state: self.state,
+            lives: self.lives,
+            invincible_ticks: self.invincible_ticks,
+            streak: self.streak,
+            breakdown: self.breakdown,
+            bonus_popup: self.bonus_popup,
+            reaction: self.reaction.clone(),
+            weather: self.weather.clone(),
+            day_cycle: self.day_cycle.clone(),
+            rng: self.rng.clone(),
+            celebration: self.celebration.clone(),
+            speed: self.speed,
+            ramp: self.ramp,
+            bank: self.bank,
+            wager: self.wager,
+            typing: self.typing.clone(),
+            quiz: self.quiz,
+            rewind,
+            rewinding: self.rewinding,
+            rhythm: self.rhythm.clone(),
+            marathon: self.marathon.clone(),
+            scroll: self.scroll,
+            travelled: self.travelled,
+            tick: self.tick,
+        }
+    }
+
+    fn restore(&mut self, snapshot: &Snapshot) {
+        let s = snapshot.clone();
+        self.bird = s.bird;
+        self.pipes = s.pipes;
+        self.score = s.score;
+        self.state = s.state;
+        self.lives = s.lives;
+        self.invincible_ticks = s.invincible_ticks;
+        self.streak = s.streak;
+        self.breakdown = s.breakdown;
+        self.bonus_popup = s.bonus_popup;
+        self.reaction = s.reaction;
+        self.weather = s.weather;
+        self.day_cycle = s.day_cycle;
+        self.rng = s.rng;
+        self.celebration = s.celebration;
+        self.speed = s.speed;
+        self.ramp = s.ramp;
+        self.bank = s.bank;
+        self.wager = s.wager;
+        self.typing = s.typing;
+        self.quiz = s.quiz;
+        self.rewind = s.rewind;
+        self.rewinding = s.rewinding;
+        self.rhythm = s.rhythm;
+        self.marathon = s.marathon;
+        self.scroll = s.scroll;
+        self.travelled = s.travelled;
+        self.tick = s.tick;
+    }
+}
+
+/// Ticks between saved snapshots of a replay, so a seek only re-simulates a short stretch.
+const KEYFRAME_INTERVAL: u32 = 100;
+const REPLAY_TAIL_TICKS: u32 = 5000;
+/// Playback speeds as simulated half-ticks per real tick, with their labels.
+const REPLAY_SPEEDS: [(u32, &str); 3] = [(1, "0.5x"), (2, "1x"), (4, "2x")];
+
+/// The replay viewer's keys, for its hint line and the help screen.
+const REPLAY_CONTROLS: [(&str, &str); 6] = [
+    ("Space", "pause"),
+    ("←/→", "step"),
+    ("-/+", "speed"),
+    ("[/]", "pipe"),
+    ("N Enter", "pipe N"),
+    ("Esc", "back"),
+];
+
+/// Plays a finished run back by re-simulating its seed and recorded inputs.
+struct ReplayViewer {
+    game: Game,
+    inputs: Vec<(u32, Input)>,
+    next_input: usize,
+    /// `keyframes[i]` is the state at tick `i * KEYFRAME_INTERVAL`.
+    keyframes: Vec<Snapshot>,
+    /// Tick at which each pipe was passed, for seeking by pipe number.
+    pipe_ticks: Vec<u32>,
+    /// Ticks in the whole run.
+    length: u32,
+    final_score: u32,
+    /// Telemetry for the whole run, as replayed.
+    samples: Vec<Sample>,
+    /// Whether the replay went the same way as the run it came from, tick for tick.
+    in_step: bool,
+    paused: bool,
+    speed: usize,
+    /// Half-ticks owed to the simulation at the current speed.
+    budget: u32,
+    /// Pipe number being typed for a seek.
+    seek_entry: String,
+}
+
+impl ReplayViewer {
+    fn new(source: &Game) -> Self {
+        let mut viewer = Self::play(
+            Game::with_seed(
+                source.width,
+                source.height,
+                source.config.clone(),
+                source.seed,
+                false,
+            ),
+            source.telemetry.inputs.clone(),
+        );
+        viewer.game.display = source.display;
+        viewer.in_step = viewer.samples == source.telemetry.samples;
+        viewer
+    }
+
+    /// Another run on `source`'s board and seed, from its inputs.
+    fn for_run(source: &Game, inputs: Vec<(u32, Input)>) -> Self {
+        let mut viewer = Self::play(
+            Game::with_seed(
+                source.width,
+                source.height,
+                source.config.clone(),
+                source.seed,
+                false,
+            ),
+            inputs,
+        );
+        viewer.game.display = source.display;
+        viewer
+    }
+
+    fn for_challenge(challenge: &Challenge, config: &Config) -> Self {
+        Self::play(
+            Game::with_seed(
+                challenge.width,
+                challenge.height,
+                config.clone(),
+                challenge.seed,
+                false,
+            ),
+            challenge.inputs.clone(),
+        )
+    }
+
+    fn play(game: Game, inputs: Vec<(u32, Input)>) -> Self {
+        // A run ends once the bird runs out of hearts after its last input; this is a
+        // backstop in case a bad challenge code never ends
+        let limit = inputs.last().map_or(0, |&(tick, _)| tick) + REPLAY_TAIL_TICKS;
+        let mut viewer = Self {
+            game,
+            inputs,
+            next_input: 0,
+            keyframes: Vec::new(),
+            pipe_ticks: Vec::new(),
+            length: 0,
+            final_score: 0,
+            samples: Vec::new(),
+            in_step: true,
+            paused: false,
+            speed: 1,
+            budget: 0,
+            seek_entry: String::new(),
+        };
+
+        // Play the whole run once up front to lay out the timeline
+        loop {
+            let tick = viewer.game.tick;
+            if viewer.keyframes.len() as u32 * KEYFRAME_INTERVAL == tick {
+                viewer.keyframes.push(viewer.game.snapshot());
+            }
+            if viewer.is_finished() || tick > limit {
+                break;
+            }
+            let pipes = viewer.game.breakdown.pipes;
+            viewer.advance();
+            if viewer.game.breakdown.pipes > pipes {
+                viewer.pipe_ticks.push(viewer.game.tick);
+            }
+        }
+        viewer.length = viewer.game.tick;
+        viewer.final_score = viewer.game.score;
+        viewer.samples = std::mem::take(&mut viewer.game.telemetry.samples);
+        viewer.seek(0);
+        viewer
+    }
+
+    fn is_finished(&self) -> bool {
+        self.game.state == GameState::GameOver
+    }
+
+    /// Simulate one tick.
+    fn advance(&mut self) {
+        if self.is_finished() {
+            return;
+        }
+        while let Some(&(tick, input)) = self.inputs.get(self.next_input) {
+            if tick > self.game.tick {
+                break;
+            }
+            self.game.apply_input(input);
+            self.next_input += 1;
+        }
+        self.game.update();
+    }
+
+    /// Jump to `tick` from the nearest keyframe before it.
+    fn seek(&mut self, tick: u32) {
+        let tick = tick.min(self.length);
+        let index = ((tick / KEYFRAME_INTERVAL) as usize).min(self.keyframes.len() - 1);
+        self.game.restore(&self.keyframes[index]);
+        let start = self.game.tick;
+        self.next_input = self.inputs.partition_point(|&(t, _)| t < start);
+        while self.game.tick < tick && !self.is_finished() {
+            self.advance();
+        }
+    }
+
+    /// Jump to shortly before pipe `number` (counting from 1) is passed.
+    fn seek_pipe(&mut self, number: usize) {
+        let Some(&tick) = number.checked_sub(1).and_then(|i| self.pipe_ticks.get(i)) else {
+            return;
+        };
+        self.seek(tick.saturating_sub(PIPE_WIDTH as u32 * 4));
+    }
+
+    /// Number of pipes passed by the current tick.
+    fn pipes_passed(&self) -> usize {
+        self.pipe_ticks.partition_point(|&t| t <= self.game.tick)
+    }
+
+    fn step_frame(&mut self, forward: bool) {
+        self.paused = true;
+        if forward {
+            self.advance();
+        } else {
+            self.seek(self.game.tick.saturating_sub(1));
+        }
+    }
+
+    fn handle_key(&mut self, code: KeyCode) {
+        match code {
+            KeyCode::Char(' ') | KeyCode::Char('p') | KeyCode::Char('P') => {
+                self.paused = !self.paused;
+            }
+            KeyCode::Right | KeyCode::Char('.') => self.step_frame(true),
+            KeyCode::Left | KeyCode::Char(',') => self.step_frame(false),
+            KeyCode::Char('-') => self.speed = self.speed.saturating_sub(1),
+            KeyCode::Char('+') | KeyCode::Char('=') => {
+                self.speed = (self.speed + 1).min(REPLAY_SPEEDS.len() - 1);
+            }
+            KeyCode::Char(']') => self.seek_pipe(self.pipes_passed() + 1),
+            KeyCode::Char('[') => match self.pipes_passed() {
+                0 => self.seek(0),
+                n => self.seek_pipe(n),
+            },
+            KeyCode::Home => self.seek(0),
+            KeyCode::End => self.seek(self.length),
+            KeyCode::Char(c) if c.is_ascii_digit() && self.seek_entry.len() < 4 => {
+                self.seek_entry.push(c);
+            }
+            KeyCode::Backspace => {
+                self.seek_entry.pop();
+            }
+            KeyCode::Enter => {
+                if let Ok(number) = self.seek_entry.parse() {
+                    self.seek_pipe(number);
+                }
+                self.seek_entry.clear();
+            }
+            _ => {}
+        }
+    }
+
+    fn update(&mut self) {
+        if self.paused {
+            return;
+        }
+        self.budget += REPLAY_SPEEDS[self.speed].0;
+        while self.budget >= 2 {
+            self.budget -= 2;
+            self.advance();
+        }
+    }
+
+    fn draw(&self, stdout: &mut io::Stdout, screen: &mut Screen) -> io::Result<()> {
+        // Render only the world so the final moment stays visible instead of the summary
+        let mut frame = Frame::new(self.game.width, self.game.height);
+        self.game.render_world(&mut frame);
+        let label = if !self.in_step {
+            "REPLAY - out of step with the run"
+        } else if self.is_finished() {
+            "REPLAY - finished"
+        } else if self.paused {
+            "REPLAY - paused"
+        } else {
+            "REPLAY"
+        };
+        frame.put_str(2, 0, label, Color::White);
+        frame.put_str(
+            2,
+            1,
+            &REPLAY_CONTROLS
+                .iter()
+                .map(|(keys, about)| format!("{}: {}", keys, about))
+                .collect::<Vec<_>>()
+                .join("  "),
+            Color::DarkGrey,
+        );
+        self.draw_timeline(&mut frame);
+        screen.present(frame, stdout, self.game.display)
+    }
+
+    /// Progress bar across the bottom row with a tick mark for every pipe passed.
+    fn draw_timeline(&self, frame: &mut Frame) {
+        let y = self.game.height as i32 - 1;
+        let mut status = format!(
+            " {} pipe {}/{}",
+            REPLAY_SPEEDS[self.speed].1,
+            self.pipes_passed(),
+            self.pipe_ticks.len()
+        );
+        if !self.seek_entry.is_empty() {
+            status.push_str(&format!("  go to {}_", self.seek_entry));
+        }
+        let width = (self.game.width as usize).saturating_sub(status.chars().count() + 6);
+        if width == 0 {
+            return;
+        }
+
+        let column = |tick: u32| tick as usize * (width - 1) / self.length.max(1) as usize;
+        let mut bar = vec!['─'; width];
+        for &tick in &self.pipe_ticks {
+            bar[column(tick)] = '┼';
+        }
+        let head = column(self.game.tick);
+        for cell in &mut bar[..head] {
+            if *cell == '─' {
+                *cell = '━';
+            }
+        }
+        bar[head] = '●';
+
+        let line: String = bar.into_iter().collect();
+        frame.put_str(0, y, &" ".repeat(self.game.width as usize), Color::Reset);
+        frame.put_str(2, y, "[", Color::White);
+        frame.put_str(3, y, &line, Color::Cyan);
+        frame.put_str(3 + width as i32, y, "]", Color::White);
+        frame.put_str(4 + width as i32, y, &status, Color::White);
+    }
+}
+
+/// What the game was started to do.
+enum Session {
+    Play(Option<Challenge>),
+    Event(ChallengeFile),
+    Attach(Detached),
+    Screensaver(Option<Brain>),
+    Stress(usize),
+    Split(Opponent),
+}
+
+impl Session {
+    /// The board a shared run is played on, whatever size the terminal is.
+    fn board(&self) -> Option<(u16, u16)> {
+        match self {
+            Session::Play(Some(challenge)) => Some((challenge.width, challenge.height)),
+            Session::Event(event) => Some((event.challenge.width, event.challenge.height)),
+            _ => None,
+        }
+    }
+}
+
+fn main() -> io::Result<()> {
+    let (config, session) = match Cli::from_env() {
+        Ok(Cli::Run(config, challenge)) => (config, Session::Play(challenge)),
+        Ok(Cli::Event(config, event)) => (config, Session::Event(event)),
+        Ok(Cli::Screensaver(config, brain)) => (config, Session::Screensaver(brain)),
+        Ok(Cli::Stress(config, birds)) => (config, Session::Stress(birds)),
+        // Split screen keeps to the shared keys, so nothing is typed or answered there,
+        // and one player's marathon rest would stop the other
+        Ok(Cli::Split(mut config, opponent)) => {
+            config.typing = false;
+            config.quiz = false;
+            config.rewind = false;
+            config.marathon = false;
+            (config, Session::Split(opponent))
+        }
+        Ok(Cli::Attach(config)) => match detach::take() {
+            Ok(detached) => (config, Session::Attach(detached)),
+            Err(err) => {
+                eprintln!("tflap: {}", err);
+                std::process::exit(1);
+            }
+        },
+        Ok(Cli::Help) => {
+            println!("{}", Cli::usage());
+            return Ok(());
+        }
+        Ok(Cli::Version) => {
+            println!("tflap {}", env!("CARGO_PKG_VERSION"));
+            return Ok(());
+        }
+        Ok(Cli::AddRival(name, source)) => return add_rival(name, source),
+        Ok(Cli::RemoveRival(name)) => return remove_rival(&name),
+        Ok(Cli::ListRivals) => {
+            list_rivals();
+            return Ok(());
+        }
+        Ok(Cli::Completions(shell)) => {
+            print!("{}", completions::script(shell));
+            return Ok(());
+        }
+        Ok(Cli::Doctor(config)) => return doctor::run(&config),
+        Ok(Cli::KeyTest(config)) => return keytest::run(&config),
+        Ok(Cli::Train {
+            config,
+            generations,
+            out,
+        }) => return train_brain(config, generations, &out),
+        Ok(Cli::Simulate(config, batch)) => return simulate_batch(config, &batch),
+        Ok(Cli::Balance(config, batch)) => return balance_report(config, &batch),
+        Ok(Cli::Autorun(config, run)) => return autorun_batch(config, &run),
+        Ok(Cli::Man) => {
+            print!("{}", manpage::render());
+            return Ok(());
+        }
+        Ok(Cli::Update { check_only }) => {
+            if let Err(err) = update::run(check_only) {
+                eprintln!("tflap: update failed: {}", err);
+                std::process::exit(1);
+            }
+            return Ok(());
+        }
+        Err(err) => {
+            eprintln!("tflap: {}\n\n{}", err, Cli::usage());
+            std::process::exit(2);
+        }
+    };
+    // A challenge is only the same run on its own board, so one that doesn't fit can't
+    // be played here
+    if let Some((width, height)) = session.board() {
+        let (columns, rows) = terminal::size()?;
+        let (columns, rows) = config.zoom.logical(columns, rows);
+        if width > columns || height > rows {
+            eprintln!(
+                "tflap: this challenge's board is {}x{}, but the terminal only has room \
+                 for {}x{}{}; make the window bigger and try again",
+                width,
+                height,
+                columns,
+                rows,
+                if config.zoom.cells() == (1, 1) {
+                    ""
+                } else {
+                    " at this zoom"
+                }
+            );
+            std::process::exit(1);
+        }
+    }
+
+    // Without a place to save, scores still go on the board for the session, and the
+    // title screen says once where they could go instead
+    let mut unsaved = None;
+    if config.no_save {
+        storage::disable_writes("saving is off (--no-save)".to_string());
+    } else if let Err(why) = storage::probe() {
+        storage::disable_writes(format!("saving is off: {}", why));
+        unsaved = Some(why);
+    }
+
+    let stop = signals::install()?;
+    let mut stdout = io::stdout();
 
-    let (width, height) = terminal::size()?;
-    let mut game = Game::new(width, height);
-    let mut last_tick = Instant::now();
+    // Setup terminal
+    terminal::enable_raw_mode()?;
+    execute!(
+        stdout,
+        EnterAlternateScreen,
+        Hide,
+        EnableMouseCapture,
+        Print(bidi::EXPLICIT_MODE)
+    )?;
+    // Event kinds tell a held key's auto-repeats from fresh presses, and releases let
+    // one-switch mode time holds exactly
+    let enhanced = terminal::supports_keyboard_enhancement().unwrap_or(false);
+    if enhanced {
+        execute!(
+            stdout,
+            PushKeyboardEnhancementFlags(KeyboardEnhancementFlags::REPORT_EVENT_TYPES)
+        )?;
+    }
+    // Windows consoles always report releases
+    let release_events = enhanced || cfg!(windows);
+
+    let caps = doctor::Capabilities::detect();
+    let warnings = doctor::warnings(&caps, &config);
+    let display = doctor::choose_display(&caps, &config, |glyph| {
+        doctor::probe_width(&mut stdout, glyph)
+    });
+    // The game plays on the board the zoom leaves room for
+    let (columns, rows) = terminal::size()?;
+    let (width, height) = display.zoom.logical(columns, rows);
+    let split = match session {
+        Session::Split(opponent) => Some(opponent),
+        _ => None,
+    };
+    let (screensaver, stress) = match session {
+        Session::Screensaver(_) => (true, None),
+        Session::Stress(birds) => (false, Some(Stats::new(birds))),
+        _ => (false, None),
+    };
+    let mut game = match session {
+        Session::Attach(detached) => Game::resume(config, &detached)?,
+        Session::Play(Some(challenge)) => Game::with_challenge(config, challenge),
+        Session::Play(None) => Game::new(width, height, config),
+        Session::Event(event) => Game::with_event(config, event),
+        Session::Screensaver(brain) => {
+            let mut game = Game::screensaver(width, height, config);
+            if let Some(brain) = brain {
+                game.pilot = Some(Box::new(brain));
+            }
+            game
+        }
+        Session::Stress(birds) => Game::stress(width, height, config, birds),
+        // Each run gets half the screen, less a column between them
+        Session::Split(_) => Game::with_seed(
+            width.saturating_sub(1) / 2,
+            height,
+            config,
+            rand::random(),
+            false,
+        ),
+    };
+    game.warnings = warnings;
+    if let Some(why) = unsaved {
+        game.warnings.extend([
+            format!("Not saving: {}", why),
+            "Keep scores with --data-dir <DIR>".to_string(),
+        ]);
+    }
+    if !screensaver && stress.is_none() && split.is_none() {
+        game.status_file = game.config.status_file.clone().map(StatusExporter::start);
+        match Music::start(&game.config) {
+            Ok(music) => game.music = music,
+            Err(e) => game.warnings.push(e),
+        }
+        game.haptics = Haptics::for_config(&game.config);
+        game.pomodoro = game
+            .config
+            .pomodoro
+            .map(|minutes| Pomodoro::resume(minutes, storage::unix_now()));
+        if game.config.kiosk {
+            game.kiosk = Some(Kiosk::new(game.config.kiosk_exit, game.clock.now()));
+            game.title_menu = title_menu(game.challenge.as_ref(), true);
+        }
+    }
+    if game.config.emoji && !display.emoji {
+        game.warnings
+            .push("No emoji widths: drawing plain".to_string());
+    }
+    game.display = display;
+    let started = game.clock.now();
+    let mut pacer = Pacer::new(started);
 
-    let result = run_game(&mut stdout, &mut game, &mut last_tick);
+    let mut stress = stress;
+    let result = if let Some(stats) = &mut stress {
+        run_stress(&mut stdout, &mut game, &stop, stats)
+    } else if screensaver {
+        run_screensaver(&mut stdout, &mut game, &stop)
+    } else if let Some(opponent) = split {
+        run_split(&mut stdout, &mut game, opponent, &stop)
+    } else {
+        run_game(&mut stdout, &mut game, &mut pacer, &stop, release_events)
+    };
+    game.shutdown();
 
     // Cleanup
-    execute!(stdout, Show, LeaveAlternateScreen)?;
+    if enhanced {
+        execute!(stdout, PopKeyboardEnhancementFlags)?;
+    }
+    execute!(
+        stdout,
+        Print(bidi::IMPLICIT_MODE),
+        Print(haptics::FLASH_OFF),
+        DisableMouseCapture,
+        Show,
+        LeaveAlternateScreen
+    )?;
     terminal::disable_raw_mode()?;
 
+    if let Some(stats) = stress {
+        print!("{}", stats.report());
+    } else if !screensaver && split.is_none() {
+        let open = game.clock.now().duration_since(started);
+        match &game.config.json_summary {
+            // In place of the line, so stdout is JSON and nothing else
+            Some(Destination::Stdout) => print!("{}", game.json_summary(open, pacer.jitter)),
+            json => {
+                if let Some(Destination::File(path)) = json {
+                    let json = game.json_summary(open, pacer.jitter);
+                    if let Err(err) = storage::write_requested(path, &json) {
+                        eprintln!("tflap: could not write {}: {}", path.display(), err);
+                    }
+                }
+                if !game.config.quiet {
+                    println!("{}", game.exit_summary(open));
+                }
+            }
+        }
+    }
+    result
+}
+
+fn add_rival(name: String, source: RivalSource) -> io::Result<()> {
+    let (score, ticks) = match source {
+        RivalSource::Score(score) => (score, 0),
+        RivalSource::Replay(challenge) => {
+            // Re-simulate the run rather than trusting the score written in the code
+            let mut config = Config::load();
+            challenge.apply(&mut config);
+            let viewer = ReplayViewer::for_challenge(&challenge, &config);
+            (viewer.final_score, viewer.length)
+        }
+    };
+    let mut rivals = rivals::load_rivals();
+    rivals.retain(|r| r.name != name);
+    println!("Added rival {} with a best of {}", name, score);
+    rivals.push(Rival { name, score, ticks });
+    rivals::save_rivals(&rivals)
+}
+
+fn remove_rival(name: &str) -> io::Result<()> {
+    let mut rivals = rivals::load_rivals();
+    let before = rivals.len();
+    rivals.retain(|r| r.name != name);
+    if rivals.len() == before {
+        eprintln!("tflap: no rival named '{}'", name);
+        std::process::exit(1);
+    }
+    rivals::save_rivals(&rivals)
+}
+
+fn list_rivals() {
+    let rivals = rivals::load_rivals();
+    if rivals.is_empty() {
+        println!("No rivals yet. Add one with --add-rival <NAME> <CODE|SCORE>");
+    }
+    for rival in rivals {
+        println!("{:<16} {:>6}", rival.name, rival.score);
+    }
+}
+
+/// A marathon run's progress: the saved marathon, or a new one when nothing is saved.
+fn start_marathon(config: &Config, persist: bool) -> Option<Marathon> {
+    config.marathon.then(|| {
+        if persist {
+            Marathon::resume(config.player.as_deref())
+        } else {
+            Marathon::default()
+        }
+    })
+}
+
+/// Rhythm mode's clock for `bpm`, beating in ticks.
+fn beat_clock(bpm: u16) -> BeatClock {
+    BeatClock::new(bpm, 1000.0 / TICK_RATE.as_millis() as f32)
+}
+
+/// Settings for headless bot runs: the configured difficulty and weather at normal speed,
+/// one life, and a point per pipe.
+fn headless_config(mut config: Config) -> Config {
+    config.scoring = ScoringMode::Classic;
+    config.lives = None;
+    config.banking = false;
+    config.double_or_nothing = false;
+    config.typing = false;
+    config.quiz = false;
+    config.rewind = false;
+    config.rhythm = None;
+    config.marathon = false;
+    config.assist = false;
+    config.milestones = Vec::new();
+    config.speed = 100;
+    config
+}
+
+/// Fly `pilot` through a run on `seed` without drawing it, until it crashes or
+/// `max_ticks` have passed. With `encounters`, each pipe after the first is noted there
+/// with the step to it and whether the bird crashed on the way; a crash the bot then
+/// revives from counts.
+fn fly(
+    config: &Config,
+    pilot: Box<dyn Strategy>,
+    seed: u64,
+    max_ticks: u32,
+    mut encounters: Option<&mut Vec<Encounter>>,
+) -> simulate::Outcome {
+    let mut game = Game::with_seed(HEADLESS_WIDTH, HEADLESS_HEIGHT, config.clone(), seed, false);
+    game.pilot = Some(pilot);
+    let mut passes = 0;
+    let mut last_gap: Option<u16> = None;
+    let mut reviving = false;
+    while game.state != GameState::GameOver && game.tick < max_ticks {
+        game.autopilot();
+        game.update();
+        let Some(encounters) = encounters.as_deref_mut() else {
+            continue;
+        };
+        if game.telemetry.passes.len() > passes {
+            passes = game.telemetry.passes.len();
+            let gap = game.pipes.iter().rev().find(|pipe| pipe.passed);
+            if let Some(gap) = gap.map(|pipe| pipe.gap_y) {
+                if let Some(last) = last_gap {
+                    encounters.push(Encounter {
+                        step: gap as i16 - last as i16,
+                        crashed: false,
+                    });
+                }
+                last_gap = Some(gap);
+            }
+        }
+        let crashed =
+            game.state == GameState::GameOver || (game.state == GameState::Revive && !reviving);
+        reviving = game.state == GameState::Revive;
+        if crashed {
+            let heading = game.pipes.iter().find(|pipe| !pipe.passed);
+            if let (Some(last), Some(pipe)) = (last_gap, heading) {
+                encounters.push(Encounter {
+                    step: pipe.gap_y as i16 - last as i16,
+                    crashed: true,
+                });
+            }
+        }
+    }
+    simulate::Outcome {
+        score: game.score,
+        ticks: game.tick,
+    }
+}
+
+/// Evolve a brain for `tflap train` on headless runs, ranked on pipes passed.
+fn train_brain(config: Config, generations: u32, out: &Path) -> io::Result<()> {
+    let config = headless_config(config);
+    train::run(generations, out, |brain, seed| {
+        let outcome = fly(
+            &config,
+            Box::new(brain.clone()),
+            seed,
+            train::MAX_TICKS,
+            None,
+        );
+        // Surviving longer breaks ties between brains that pass as many pipes
+        outcome.score as f32 + outcome.ticks as f32 / train::MAX_TICKS as f32
+    })
+}
+
+/// Fly a `tflap simulate` batch and print its summary.
+fn simulate_batch(config: Config, batch: &Batch) -> io::Result<()> {
+    let config = headless_config(config);
+    let bot = match batch.brain {
+        Some(_) => "brain",
+        None => config.bot.key(),
+    };
+    eprintln!(
+        "Simulating {} runs on {} thread{}...",
+        batch.runs(),
+        batch.threads,
+        if batch.threads == 1 { "" } else { "s" }
+    );
+    let labels = [
+        ("bot", bot),
+        ("difficulty", config.difficulty.key()),
+        ("weather", config.weather.key()),
+    ];
+    let summary = simulate::run(batch, &labels, |seed| {
+        let pilot: Box<dyn Strategy> = match &batch.brain {
+            Some(brain) => Box::new(brain.clone()),
+            None => config.bot.strategy(),
+        };
+        fly(&config, pilot, seed, batch.max_ticks, None)
+    });
+    print!("{}", summary);
+    Ok(())
+}
+
+/// Run `tflap balance` on a preset at a spread of speeds and print the report.
+fn balance_report(config: Config, batch: &Batch) -> io::Result<()> {
+    let config = headless_config(config);
+    let difficulty = config.difficulty;
+    let speeds: Vec<u8> = (MIN_SPEED..=MAX_SPEED).step_by(25).collect();
+    let bot = match batch.brain {
+        Some(_) => "a trained brain",
+        None => config.bot.key(),
+    };
+    let heading = format!(
+        "Balance report for the {} preset (gap {} rows, pipes every {} columns) in {} weather\n\
+         Reference bot: {}, {} runs per speed on seeds {}..{}, up to {} ticks each",
+        difficulty.key(),
+        difficulty.pipe_gap(),
+        difficulty.pipe_spacing(),
+        config.weather.key(),
+        bot,
+        batch.runs(),
+        batch.seeds.start,
+        batch.seeds.end,
+        batch.max_ticks
+    );
+    eprintln!(
+        "Flying {} runs at each of {} speeds...",
+        batch.runs(),
+        speeds.len()
+    );
+    let report = balance::run(batch, &speeds, &heading, |speed, seed, encounters| {
+        let mut config = config.clone();
+        config.speed = speed;
+        let pilot: Box<dyn Strategy> = match &batch.brain {
+            Some(brain) => Box::new(brain.clone()),
+            None => config.bot.strategy(),
+        };
+        fly(&config, pilot, seed, batch.max_ticks, Some(encounters))
+    });
+    print!("{}", report);
+    Ok(())
+}
+
+/// Play a `tflap autorun` batch, on screen or off, and write a CSV row for each game.
+fn autorun_batch(config: Config, run: &Autorun) -> io::Result<()> {
+    let config = headless_config(config);
+    let batch = &run.batch;
+    // Whichever way `--bot` named it, the row has the bot's name and its tier
+    let (bot, tier) = match batch.brain {
+        Some(_) => ("brain", ""),
+        None => (config.bot.flier(), config.bot.key()),
+    };
+    let pilot = || -> Box<dyn Strategy> {
+        match &batch.brain {
+            Some(brain) => Box::new(brain.clone()),
+            None => config.bot.strategy(),
+        }
+    };
+    let seeds: Vec<u64> = batch.seeds.clone().collect();
+    let games = if run.render {
+        watch_games(&config, &seeds, batch.max_ticks, pilot)?
+    } else {
+        eprintln!(
+            "Playing {} games on {} thread{}...",
+            batch.runs(),
+            batch.threads,
+            if batch.threads == 1 { "" } else { "s" }
+        );
+        simulate::parallel_map(&seeds, batch.threads, &|&seed| {
+            (seed, fly(&config, pilot(), seed, batch.max_ticks, None))
+        })
+    };
+    let labels = [
+        ("bot", bot),
+        ("tier", tier),
+        ("difficulty", config.difficulty.key()),
+        ("weather", config.weather.key()),
+    ];
+    let csv = autorun::to_csv(&labels, batch.max_ticks, &games);
+    match &run.out {
+        Some(path) => storage::write_atomic(path, &csv)?,
+        None => print!("{}", csv),
+    }
+    eprintln!("{}", autorun::summary(batch.max_ticks, &games));
+    Ok(())
+}
+
+/// Play `tflap autorun --render` games one after another on the headless board, at the
+/// usual speed, until they're all done or a key is pressed. Returns those played out.
+fn watch_games(
+    config: &Config,
+    seeds: &[u64],
+    max_ticks: u32,
+    pilot: impl Fn() -> Box<dyn Strategy>,
+) -> io::Result<Vec<(u64, simulate::Outcome)>> {
+    let stop = signals::install()?;
+    let mut stdout = io::stdout();
+    terminal::enable_raw_mode()?;
+    execute!(stdout, EnterAlternateScreen, Hide)?;
+    let result = watch_all(&mut stdout, config, seeds, max_ticks, pilot, &stop);
+    execute!(stdout, Show, LeaveAlternateScreen)?;
+    terminal::disable_raw_mode()?;
     result
 }
 
-fn run_game(stdout: &mut io::Stdout, game: &mut Game, last_tick: &mut Instant) -> io::Result<()> {
+fn watch_all(
+    stdout: &mut io::Stdout,
+    config: &Config,
+    seeds: &[u64],
+    max_ticks: u32,
+    pilot: impl Fn() -> Box<dyn Strategy>,
+    stop: &AtomicBool,
+) -> io::Result<Vec<(u64, simulate::Outcome)>> {
+    let caps = doctor::Capabilities::detect();
+    let display = doctor::choose_display(&caps, config, |glyph| doctor::probe_width(stdout, glyph));
+    let (columns, rows) = terminal::size()?;
+    let (width, height) = display.zoom.logical(columns, rows);
+    if width < HEADLESS_WIDTH || height < HEADLESS_HEIGHT {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!(
+                "--render needs a board of {}x{}, and the terminal only has room for {}x{}",
+                HEADLESS_WIDTH, HEADLESS_HEIGHT, width, height
+            ),
+        ));
+    }
+
+    let mut games = Vec::new();
+    for &seed in seeds {
+        let mut game =
+            Game::with_seed(HEADLESS_WIDTH, HEADLESS_HEIGHT, config.clone(), seed, false);
+        game.pilot = Some(pilot());
+        game.display = display;
+        if !watch_game(stdout, &mut game, max_ticks, stop)? {
+            break;
+        }
+        let outcome = simulate::Outcome {
+            score: game.score,
+            ticks: game.tick,
+        };
+        games.push((seed, outcome));
+    }
+    Ok(games)
+}
+
+/// Draw one autorun game until it's over or capped and the wreck has been up a moment.
+/// False if a key, click or signal stopped it first.
+fn watch_game(
+    stdout: &mut io::Stdout,
+    game: &mut Game,
+    max_ticks: u32,
+    stop: &AtomicBool,
+) -> io::Result<bool> {
+    let clock = Rc::clone(&game.clock);
+    let mut pacer = Pacer::new(clock.now());
+    let mut wreck_ticks = 0;
+    let mut screen = Screen::new(game.config.low_bandwidth);
+
+    while wreck_ticks < SCREENSAVER_WRECK_TICKS {
+        if stop.load(Ordering::Relaxed) {
+            return Ok(false);
+        }
+
+        let mut frame = Frame::new(game.width, game.height);
+        game.render_world(&mut frame);
+        screen.present(frame, stdout, game.display)?;
+
+        while event::poll(Duration::from_millis(0))? {
+            match event::read()? {
+                Event::Key(key) if key.kind != KeyEventKind::Release => return Ok(false),
+                Event::Mouse(mouse) if matches!(mouse.kind, MouseEventKind::Down(_)) => {
+                    return Ok(false)
+                }
+                _ => {}
+            }
+        }
+
+        if pacer.tick(clock.now()) {
+            if game.state == GameState::GameOver || game.tick >= max_ticks {
+                wreck_ticks += 1;
+            } else {
+                game.autopilot();
+                game.update();
+            }
+        }
+
+        clock.sleep(pacer.until_due(clock.now()).min(FRAME_SLEEP));
+    }
+    Ok(true)
+}
+
+/// Let the bot fly until a key or click, starting over a moment after each crash.
+fn run_screensaver(stdout: &mut io::Stdout, game: &mut Game, stop: &AtomicBool) -> io::Result<()> {
+    let clock = Rc::clone(&game.clock);
+    let mut pacer = Pacer::new(clock.now());
+    let mut wreck_ticks = 0;
+    let mut screen = Screen::new(game.config.low_bandwidth);
+
+    loop {
+        if stop.load(Ordering::Relaxed) {
+            return Ok(());
+        }
+
+        let mut frame = Frame::new(game.width, game.height);
+        game.render_world(&mut frame);
+        screen.present(frame, stdout, game.display)?;
+
+        while event::poll(Duration::from_millis(0))? {
+            match event::read()? {
+                Event::Key(key) if key.kind != KeyEventKind::Release => return Ok(()),
+                Event::Mouse(mouse) if matches!(mouse.kind, MouseEventKind::Down(_)) => {
+                    return Ok(())
+                }
+                _ => {}
+            }
+        }
+
+        if pacer.tick(clock.now()) {
+            if game.state == GameState::GameOver {
+                // Leave the wreck up long enough to be seen
+                wreck_ticks += 1;
+                if wreck_ticks >= SCREENSAVER_WRECK_TICKS {
+                    wreck_ticks = 0;
+                    game.retry();
+                }
+            } else {
+                game.autopilot();
+                game.update();
+            }
+        }
+
+        clock.sleep(pacer.until_due(clock.now()).min(FRAME_SLEEP));
+    }
+}
+
+/// Run a stress test until a key or click, timing each tick and frame. The player's bird
+/// starts over straight away when it crashes.
+fn run_stress(
+    stdout: &mut io::Stdout,
+    game: &mut Game,
+    stop: &AtomicBool,
+    stats: &mut Stats,
+) -> io::Result<()> {
+    let clock = Rc::clone(&game.clock);
+    let mut pacer = Pacer::new(clock.now());
+    // With --low-bandwidth this times sending only the changes
+    let mut screen = Screen::new(game.config.low_bandwidth);
+
+    loop {
+        if stop.load(Ordering::Relaxed) {
+            return Ok(());
+        }
+
+        let mut frame = Frame::new(game.width, game.height);
+        stats.render.time(|| game.render(&mut frame));
+        let mut out = stress::Counter::new(stdout);
+        stats
+            .flush
+            .time(|| screen.present(frame, &mut out, game.display))?;
+        stats.bytes += out.bytes;
+
+        while event::poll(Duration::from_millis(0))? {
+            match event::read()? {
+                Event::Key(key) if key.kind != KeyEventKind::Release => return Ok(()),
+                Event::Mouse(mouse) if matches!(mouse.kind, MouseEventKind::Down(_)) => {
+                    return Ok(())
+                }
+                _ => {}
+            }
+        }
+
+        if pacer.tick(clock.now()) {
+            if game.state == GameState::GameOver {
+                game.retry();
+            }
+            stats.update.time(|| {
+                game.autopilot();
+                game.update();
+            });
+            stats.crashes = game.flock_crashes;
+            stats.jitter = pacer.jitter;
+        }
+
+        clock.sleep(pacer.until_due(clock.now()).min(FRAME_SLEEP));
+    }
+}
+
+/// Two runs side by side on the same pipes until Q or Esc: the player's on the left, and
+/// the bot's or a second player's on the right. The keymap's flap key flies the left bird
+/// and Up the right one. Once both have crashed, a flap starts the next pair on a new
+/// seed. Nothing is kept: the boards are half the usual width.
+fn run_split(
+    stdout: &mut io::Stdout,
+    left: &mut Game,
+    opponent: Opponent,
+    stop: &AtomicBool,
+) -> io::Result<()> {
+    let clock = Rc::clone(&left.clock);
+    let mut right = Game::with_seed(
+        left.width,
+        left.height,
+        left.config.clone(),
+        left.seed,
+        false,
+    );
+    right.display = left.display;
+    if opponent == Opponent::Bot {
+        right.pilot = Some(right.config.bot.strategy());
+    }
+    let (columns, rows) = terminal::size()?;
+    let (full_width, _) = left.display.zoom.logical(columns, rows);
+    let cooldown = Duration::from_millis(left.config.flap_cooldown.into());
+    let mut gates = [FlapGate::new(cooldown), FlapGate::new(cooldown)];
+    let mut restart_gate = RestartGate::default();
+    let mut pacer = Pacer::new(clock.now());
+    let mut screen = Screen::new(left.config.low_bandwidth);
+
     loop {
-        game.draw(stdout)?;
+        if stop.load(Ordering::Relaxed) {
+            return Ok(());
+        }
+
+        let mut frame = Frame::new(full_width, left.height);
+        for (game, x) in [(&*left, 0), (&right, i32::from(left.width) + 1)] {
+            let mut half = Frame::new(game.width, game.height);
+            game.render_world(&mut half);
+            frame.blit(&half, x, 0);
+        }
+        for y in 0..left.height {
+            frame.put(i32::from(left.width), i32::from(y), '│', Color::DarkGrey);
+        }
+        let banner = match (left.state, right.state) {
+            (GameState::Title, _) => Some(match opponent {
+                Opponent::Bot => "Flap to race the bot".to_string(),
+                Opponent::Player => {
+                    "Flap to start: left player on the flap key, right on Up".to_string()
+                }
+            }),
+            (GameState::GameOver, GameState::GameOver) => {
+                let result = match left.score.cmp(&right.score) {
+                    std::cmp::Ordering::Greater => "Left wins",
+                    std::cmp::Ordering::Less => "Right wins",
+                    std::cmp::Ordering::Equal => "A draw",
+                };
+                Some(format!(
+                    "{} {}-{}  Flap: go again  Q: quit",
+                    result, left.score, right.score
+                ))
+            }
+            _ => None,
+        };
+        if let Some(banner) = banner {
+            let x = (i32::from(full_width) - bidi::width(&banner) as i32) / 2;
+            frame.put_str(x, i32::from(left.height / 2), &banner, Color::Yellow);
+        }
+        screen.present(frame, stdout, left.display)?;
 
-        // Handle input - process all pending events
         while event::poll(Duration::from_millis(0))? {
-            if let Event::Key(KeyEvent {
-                code, modifiers, ..
+            let Event::Key(KeyEvent {
+                code,
+                modifiers,
+                kind,
+                ..
             }) = event::read()?
+            else {
+                continue;
+            };
+            if kind == KeyEventKind::Release {
+                continue;
+            }
+            if matches!(code, KeyCode::Char('c') | KeyCode::Char('C'))
+                && modifiers.contains(KeyModifiers::CONTROL)
             {
-                match code {
-                    KeyCode::Char('c') | KeyCode::Char('C')
-                        if modifiers.contains(KeyModifiers::CONTROL) =>
-                    {
-                        return Ok(());
+                return Ok(());
+            }
+            let code = keymap::logical(code);
+            let action = left.keymap.action(code);
+            if matches!(action, Some(Action::Quit) | Some(Action::Back)) {
+                return Ok(());
+            }
+            let side = if action == Some(Action::Flap) {
+                0
+            } else if code == KeyCode::Up && opponent == Opponent::Player {
+                1
+            } else {
+                continue;
+            };
+            let now = clock.now();
+            match (left.state, right.state) {
+                (GameState::Title, _) => {
+                    left.start();
+                    right.start();
+                }
+                (GameState::GameOver, GameState::GameOver) if restart_gate.flap(now) => {
+                    let seed = rand::random();
+                    for game in [&mut *left, &mut right] {
+                        game.seed = seed;
+                        game.reset();
+                        game.opening_flap();
                     }
-                    KeyCode::Char(' ') => {
-                        if game.state == GameState::Playing {
-                            game.jump();
-                        }
+                }
+                _ if gates[side].press(now, kind == KeyEventKind::Repeat) => {
+                    let game = if side == 0 { &mut *left } else { &mut right };
+                    if game.state == GameState::Playing {
+                        game.jump();
                     }
-                    KeyCode::Char('r') | KeyCode::Char('R') => {
-                        if game.state == GameState::GameOver {
-                            game.reset();
-                        }
+                }
+                _ => {}
+            }
+        }
+
+        if pacer.tick(clock.now()) {
+            let was_over = left.state == GameState::GameOver && right.state == GameState::GameOver;
+            let crashed = [left.state, right.state].map(|state| state == GameState::GameOver);
+            let lead = left.score.cmp(&right.score);
+            right.autopilot();
+            left.update();
+            right.update();
+            tell_opponents(left, &mut right, crashed, lead);
+            if !was_over && left.state == GameState::GameOver && right.state == GameState::GameOver
+            {
+                restart_gate.crashed(clock.now());
+            }
+        }
+
+        clock.sleep(pacer.until_due(clock.now()).min(FRAME_SLEEP));
+    }
+}
+
+/// Put what just happened on each side of a split game on the other's ticker: a crash,
+/// given whether each side had crashed and who led before the tick, or a lead changing
+/// hands.
+fn tell_opponents(left: &mut Game, right: &mut Game, crashed: [bool; 2], lead: std::cmp::Ordering) {
+    if !crashed[0] && left.state == GameState::GameOver {
+        right
+            .events
+            .emit(GameEvent::OpponentDown { score: left.score });
+    }
+    if !crashed[1] && right.state == GameState::GameOver {
+        left.events
+            .emit(GameEvent::OpponentDown { score: right.score });
+    }
+    let now = left.score.cmp(&right.score);
+    if now != lead && now != std::cmp::Ordering::Equal {
+        let ahead = now == std::cmp::Ordering::Greater;
+        left.events.emit(GameEvent::LeadChanged { ahead });
+        right.events.emit(GameEvent::LeadChanged { ahead: !ahead });
+    }
+}
+
+/// What an event, or the events waiting, asked of the loop, from least to most.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Flow {
+    /// Nothing happened.
+    Idle,
+    /// Something may have changed on screen.
+    Changed,
+    /// The screen was swapped for another, so what's there has to go first.
+    Cleared,
+    Quit,
+}
+
+/// Everything `run_game` keeps between frames besides the game itself: what's showing
+/// over it, and the timing of held and gated keys. Fed events and the time as of each,
+/// so tests can drive it with a script on a clock of their own.
+struct GameLoop {
+    replay: Option<ReplayViewer>,
+    disguise: Option<Disguise>,
+    switch: SwitchInput,
+    hold: Hold,
+    flap_gate: FlapGate,
+    restart_gate: RestartGate,
+    was_over: bool,
+}
+
+impl GameLoop {
+    fn new(game: &Game, release_events: bool) -> Self {
+        Self {
+            replay: None,
+            disguise: None,
+            switch: SwitchInput::new(release_events),
+            hold: Hold::new(release_events),
+            flap_gate: FlapGate::new(Duration::from_millis(game.config.flap_cooldown.into())),
+            restart_gate: RestartGate::default(),
+            was_over: game.state == GameState::GameOver,
+        }
+    }
+
+    /// Handle every event `input` has waiting, as of `now`, stopping at one that quits.
+    fn handle_input(
+        &mut self,
+        game: &mut Game,
+        input: &mut impl InputSource,
+        now: Instant,
+    ) -> io::Result<Flow> {
+        let mut flow = Flow::Idle;
+        while let Some(event) = input.poll()? {
+            flow = flow.max(self.handle_event(game, event, now));
+            if flow == Flow::Quit {
+                break;
+            }
+        }
+        Ok(flow)
+    }
+
+    fn handle_event(&mut self, game: &mut Game, event: Event, now: Instant) -> Flow {
+        if let Event::Mouse(mouse) = event {
+            if self.replay.is_none() && self.disguise.is_none() {
+                game.handle_mouse(game.display.zoom.mouse(mouse));
+            }
+        }
+        let Event::Key(KeyEvent {
+            code,
+            modifiers,
+            kind,
+            ..
+        }) = event
+        else {
+            return Flow::Changed;
+        };
+
+        // Read keys as the layout meant them, whatever the input method typed
+        let code = keymap::logical(code);
+        let action = game.keymap.action(code);
+
+        // Releases only matter for timing the one-switch key and holding rewind
+        if kind == KeyEventKind::Release {
+            match action {
+                Some(Action::Flap) => self.switch.release(),
+                Some(Action::Rewind) => self.hold.release(),
+                _ => {}
+            }
+            return Flow::Changed;
+        }
+
+        if let Some(kiosk) = &mut game.kiosk {
+            if kiosk.is_exit(code, modifiers) {
+                return Flow::Quit;
+            }
+            // Someone's there, so hold off the attract pages and the countdown
+            if matches!(game.state, GameState::Title | GameState::GameOver) {
+                kiosk.restart_clock(now);
+            }
+        } else if matches!(code, KeyCode::Char('c') | KeyCode::Char('C'))
+            && modifiers.contains(KeyModifiers::CONTROL)
+        {
+            return Flow::Quit;
+        }
+
+        if cfg!(feature = "profiling") && code == KeyCode::F(3) {
+            game.show_profile = !game.show_profile;
+            return Flow::Changed;
+        }
+
+        // The boss key works from anywhere, and nothing else does while it's up
+        if action == Some(Action::Boss) && game.kiosk.is_none() {
+            self.disguise = match self.disguise.take() {
+                Some(shown) => {
+                    if shown.paused_run && game.state == GameState::Paused {
+                        game.state = GameState::Playing;
                     }
-                    KeyCode::Char('q') | KeyCode::Char('Q') | KeyCode::Esc => {
-                        return Ok(());
+                    None
+                }
+                None => {
+                    let playing = game.state == GameState::Playing;
+                    game.pause();
+                    if let Some(viewer) = &mut self.replay {
+                        viewer.paused = true;
                     }
-                    _ => {}
+                    Some(Disguise::new(playing))
                 }
+            };
+            return Flow::Cleared;
+        }
+        if self.disguise.is_some() {
+            return Flow::Changed;
+        }
+
+        if let Some(viewer) = &mut self.replay {
+            if matches!(action, Some(Action::Quit) | Some(Action::Back)) {
+                self.replay = None;
+            } else {
+                viewer.handle_key(code);
             }
+            return Flow::Changed;
         }
 
-        // Update game state
-        if last_tick.elapsed() >= TICK_RATE {
-            game.update();
-            *last_tick = Instant::now();
+        // Once the day's time is spent, leaving is all there is to do
+        if game.locked_out() {
+            if matches!(action, Some(Action::Quit) | Some(Action::Back)) {
+                return Flow::Quit;
+            }
+            return Flow::Changed;
+        }
+
+        if game.photo.is_some() {
+            game.handle_photo_key(code);
+            return Flow::Changed;
+        }
+        if game.handle_help_key(action, code) {
+            return Flow::Changed;
+        }
+        // Letters are for typing in typing mode, whatever they're bound to, and a
+        // held key doesn't type again
+        if let KeyCode::Char(c) = code {
+            if c.is_ascii_alphabetic()
+                && game.typing.is_some()
+                && game.state == GameState::Playing
+                && !modifiers.intersects(KeyModifiers::CONTROL | KeyModifiers::ALT)
+            {
+                if kind != KeyEventKind::Repeat {
+                    game.type_letter(c);
+                }
+                return Flow::Changed;
+            }
+        }
+        // Digits answer quiz gates, whatever they're bound to
+        if let KeyCode::Char(c @ '0'..='9') = code {
+            if game.config.quiz && game.state == GameState::Playing {
+                game.answer_quiz(c as u8 - b'0');
+                return Flow::Changed;
+            }
+        }
+        if action == Some(Action::Help)
+            && matches!(
+                game.state,
+                GameState::Title | GameState::Playing | GameState::Paused | GameState::GameOver
+            )
+        {
+            game.open_help();
+            return Flow::Changed;
+        }
+
+        if action == Some(Action::Flap)
+            && game.config.one_switch
+            && matches!(game.state, GameState::Playing | GameState::GameOver)
+        {
+            let tap = self.switch.press(now, kind == KeyEventKind::Repeat);
+            match (tap, game.state) {
+                (Some(Tap::Short), GameState::GameOver) if self.restart_gate.flap(now) => {
+                    game.retry();
+                    game.opening_flap();
+                }
+                (Some(Tap::Short), GameState::GameOver) => {}
+                (Some(Tap::Short), _) => game.flap(Input::SmallFlap),
+                (Some(Tap::Long), GameState::Playing) => game.flap(Input::BigFlap),
+                _ => {}
+            }
+            return Flow::Changed;
+        }
+
+        if let Some(menu_key) = action.and_then(Action::menu_key) {
+            if game.handle_menu_key(menu_key) {
+                return Flow::Changed;
+            }
+        }
+
+        let state = game.state;
+        match action {
+            Some(Action::Quit) => game.request_quit(),
+            Some(Action::Back) if state != GameState::ConfirmQuit => game.request_quit(),
+            Some(Action::QuickRestart)
+                if matches!(
+                    state,
+                    GameState::Playing | GameState::Paused | GameState::Revive | GameState::Wager
+                ) =>
+            {
+                game.quick_restart()
+            }
+            _ if state == GameState::Revive => {
+                if let KeyCode::Char(c) = code {
+                    game.attempt_revive(game.keymap.revive_answer(c));
+                }
+            }
+            _ if state == GameState::Wager => game.answer_wager(action == Some(Action::Wager)),
+            Some(Action::Flap)
+                if state == GameState::Playing
+                    && game.typing.is_none()
+                    && self.flap_gate.press(now, kind == KeyEventKind::Repeat) =>
+            {
+                game.jump()
+            }
+            Some(Action::Flap) if state == GameState::Intermission => game.next_segment(),
+            Some(Action::Flap) if state == GameState::GameOver && self.restart_gate.flap(now) => {
+                game.retry();
+                game.opening_flap();
+            }
+            Some(Action::Pause) if state == GameState::Playing => game.pause(),
+            Some(Action::Bank) if state == GameState::Playing => game.bank(),
+            Some(Action::Rewind) if state == GameState::Playing && game.config.rewind => {
+                self.hold.press(now, kind == KeyEventKind::Repeat)
+            }
+            Some(Action::Detach) if matches!(state, GameState::Playing | GameState::Paused) => {
+                game.detach()
+            }
+            Some(Action::SpeedDown) if state == GameState::Playing => game.change_speed(false),
+            Some(Action::SpeedUp) if state == GameState::Playing => game.change_speed(true),
+            Some(Action::Retry) if state == GameState::GameOver => {
+                game.retry();
+                if self.restart_gate.take_flap(now) {
+                    game.opening_flap();
+                }
+            }
+            Some(Action::RetrySameSeed) if state == GameState::GameOver => {
+                game.retry_same_seed();
+                if self.restart_gate.take_flap(now) {
+                    game.opening_flap();
+                }
+            }
+            Some(Action::Share) if state == GameState::GameOver => game.share(),
+            Some(Action::Replay) if state == GameState::GameOver => {
+                self.replay = Some(ReplayViewer::new(game));
+            }
+            Some(Action::Yes) if state == GameState::ConfirmQuit => game.answer_quit(true),
+            Some(Action::No) | Some(Action::Back) if state == GameState::ConfirmQuit => {
+                game.answer_quit(false)
+            }
+            _ => {}
+        }
+        Flow::Changed
+    }
+
+    /// Everything between reading input and drawing: the one-switch key's long press,
+    /// opening a challenge's replay, and a tick of whatever's showing if one is due by
+    /// `now`. Returns whether it ticked.
+    fn advance(&mut self, game: &mut Game, pacer: &mut Pacer, now: Instant) -> bool {
+        if game.config.one_switch
+            && game.state == GameState::Playing
+            && self.switch.poll(now) == Some(Tap::Long)
+        {
+            game.flap(Input::BigFlap);
+        }
+        if game.watch_requested {
+            game.watch_requested = false;
+            if let Some(challenge) = &game.challenge {
+                let mut viewer = ReplayViewer::for_challenge(challenge, &game.config);
+                viewer.game.display = game.display;
+                self.replay = Some(viewer);
+            }
+        }
+
+        let ticked = pacer.tick(now);
+        if ticked {
+            match (&mut self.disguise, &mut self.replay) {
+                (Some(disguise), _) => disguise.update(),
+                (None, Some(viewer)) => viewer.update(),
+                (None, None) => {
+                    if self.hold.is_held(now) {
+                        game.rewind();
+                    }
+                    game.update()
+                }
+            }
+        }
+        let over = game.state == GameState::GameOver;
+        if over && !self.was_over {
+            self.restart_gate.crashed(now);
+        }
+        self.was_over = over;
+        ticked
+    }
+}
+
+fn run_game(
+    stdout: &mut io::Stdout,
+    game: &mut Game,
+    pacer: &mut Pacer,
+    stop: &AtomicBool,
+    release_events: bool,
+) -> io::Result<()> {
+    let clock = Rc::clone(&game.clock);
+    let mut game_loop = GameLoop::new(game, release_events);
+    let mut input = Terminal;
+    let mut screen = Screen::new(game.config.low_bandwidth);
+    // On a slow link frames are only sent after a tick or an input has changed something
+    let mut dirty = true;
+
+    loop {
+        // SIGTERM/SIGHUP: leave through the same path as a normal quit
+        if stop.load(Ordering::Relaxed) {
+            return Ok(());
+        }
+
+        if dirty || !screen.changes_only {
+            dirty = false;
+            let slow_link = screen.changes_only;
+            match (&game_loop.disguise, &game_loop.replay) {
+                (Some(disguise), _) => {
+                    // Drawn at the terminal's own size, which a challenge board may not match
+                    let (columns, rows) = terminal::size()?;
+                    let (width, height) = game.display.zoom.logical(columns, rows);
+                    let mut frame = Frame::new(width, height);
+                    disguise.render(&mut frame, width, height);
+                    screen.present(frame, stdout, game.display)?;
+                }
+                (None, Some(viewer)) => viewer.draw(stdout, &mut screen)?,
+                (None, None) => {
+                    let started = Instant::now();
+                    game.draw(stdout, &mut screen)?;
+                    game.budget.record(started.elapsed());
+                }
+            }
+            if screen.changes_only && !slow_link {
+                game.budget.announce("Slow link: sending only what changes");
+            }
+        }
+
+        match game_loop.handle_input(game, &mut input, clock.now())? {
+            Flow::Idle => {}
+            Flow::Changed => dirty = true,
+            Flow::Cleared => {
+                // Don't let the old frame's cells survive into the new screen
+                execute!(stdout, terminal::Clear(terminal::ClearType::All))?;
+                screen.invalidate();
+                dirty = true;
+            }
+            Flow::Quit => return Ok(()),
+        }
+        if game.quit_requested {
+            return Ok(());
+        }
+        if game_loop.advance(game, pacer, clock.now()) {
+            dirty = true;
         }
 
         // Small sleep to prevent busy waiting
-        std::thread::sleep(Duration::from_millis(5));
+        clock.sleep(pacer.until_due(clock.now()).min(FRAME_SLEEP));
     }
 }
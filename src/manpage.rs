@@ -0,0 +1,137 @@
+//! The man page, generated from the option table in `config`.
+
+use crate::config::{COMMANDS, FLAGS};
+
+/// Files the game reads and writes, for the FILES section.
+const FILES: &[(&str, &str)] = &[
+    (
+        "~/.tflap_config",
+        "Defaults, kept up to date by the options screen.",
+    ),
+    ("~/.tflap_leaderboard", "Top scores for every category."),
+    (
+        "~/.tflap_history",
+        "Every finished run, for the sparkline and weekly bests.",
+    ),
+    (
+        "~/.tflap_leaderboard.NAME, ~/.tflap_history.NAME",
+        "The same for a player picked with --player.",
+    ),
+    (
+        "~/.tflap_marathon",
+        "A --marathon stopped at a rest, to carry on from.",
+    ),
+    (
+        "~/.tflap_pomodoro",
+        "When the --pomodoro cycle began, to carry on from.",
+    ),
+    ("~/.tflap_rivals", "Registered rivals."),
+    (
+        "~/.tflap_challenges",
+        "Your best on each --challenge-file challenge.",
+    ),
+    (
+        "~/.tflap_scorecard.txt",
+        "The last scorecard saved from a game-over screen.",
+    ),
+    (
+        "~/.tflap_photo.ans, ~/.tflap_photo.png",
+        "The last picture saved from photo mode.",
+    ),
+    (
+        "~/.tflap_detached",
+        "A run put away with the detach key, until tflap attach resumes it.",
+    ),
+    (
+        "~/.tflap_status",
+        "The live score while --status-file is on, removed on exit.",
+    ),
+];
+
+/// Escape text for roff.
+fn escape(text: &str) -> String {
+    let text = text.replace('\\', "\\e").replace('-', "\\-");
+    if text.starts_with('.') || text.starts_with('\'') {
+        format!("\\&{}", text)
+    } else {
+        text
+    }
+}
+
+fn lowercase_first(s: &str) -> String {
+    let mut chars = s.chars();
+    chars
+        .next()
+        .map_or_else(String::new, |c| c.to_lowercase().chain(chars).collect())
+}
+
+/// Italicise placeholders: "<D>" becomes an italic D, "[N]" an optional italic N.
+fn placeholders(value: &str) -> String {
+    value
+        .split(' ')
+        .filter(|word| !word.is_empty())
+        .map(|word| {
+            if let Some(inner) = word.strip_prefix('<').and_then(|w| w.strip_suffix('>')) {
+                format!("\\fI{}\\fR", escape(inner))
+            } else if let Some(inner) = word.strip_prefix('[').and_then(|w| w.strip_suffix(']')) {
+                // Optional flags are bold, optional values italic
+                let font = if inner.starts_with('-') { 'B' } else { 'I' };
+                format!("[\\f{}{}\\fR]", font, escape(inner))
+            } else {
+                escape(word)
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+pub fn render() -> String {
+    let version = env!("CARGO_PKG_VERSION");
+    let mut out = format!(
+        ".TH TFLAP 6 \"\" \"tflap {}\" \"Games\"\n\
+         .SH NAME\n\
+         tflap \\- {}\n\
+         .SH SYNOPSIS\n\
+         .B tflap\n\
+         [\\fIOPTIONS\\fR]\n",
+        version,
+        escape(&lowercase_first(env!("CARGO_PKG_DESCRIPTION")))
+    );
+    for command in COMMANDS {
+        out.push_str(&format!(
+            ".br\n{}\n",
+            format!(".B tflap {}\n{}", command.name, placeholders(command.value)).trim_end()
+        ));
+    }
+    out.push_str(
+        ".SH DESCRIPTION\n\
+         Steer the bird through the gaps between pipes by flapping with Space. \
+         Press ? or F1 in the game for every key binding, the game modes, and the scoring rules.\n\
+         .SH COMMANDS\n",
+    );
+    for command in COMMANDS {
+        out.push_str(&format!(
+            ".TP\n{}\n{}\n",
+            format!("\\fB{}\\fR {}", command.name, placeholders(command.value)).trim_end(),
+            escape(command.help)
+        ));
+    }
+    out.push_str(".SH OPTIONS\n");
+    for flag in FLAGS {
+        let mut names = format!("\\fB\\-\\-{}\\fR", escape(flag.long));
+        if let Some(short) = flag.short {
+            names = format!("\\fB\\-{}\\fR, {}", short, names);
+        }
+        let help: Vec<&str> = flag.help.lines().map(str::trim).collect();
+        out.push_str(&format!(
+            ".TP\n{}\n{}\n",
+            format!("{} {}", names, placeholders(flag.value)).trim_end(),
+            escape(&help.join(" "))
+        ));
+    }
+    out.push_str(".SH FILES\n");
+    for (path, about) in FILES {
+        out.push_str(&format!(".TP\n.I {}\n{}\n", escape(path), escape(about)));
+    }
+    out
+}
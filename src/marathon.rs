@@ -0,0 +1,115 @@
+//! Marathon mode: one long run in segments of `SEGMENT` pipes, with a rest stop between
+//! them.
+//!
+//! Each rest shows how the segment went and a stretch to do, and saves the marathon so
+//! far to `.tflap_marathon` in the data directory: the segments done, the score and the
+//! ticks played. Stopping at a rest and starting a marathon again later carries on from
+//! there, with the score and ticks carried over into the new run. A crash, or leaving
+//! mid-segment, ends the marathon for good and ranks its whole score and length, in a
+//! leaderboard category of marathons only.
+
+use crate::players;
+use crate::storage;
+use std::fs;
+
+/// Pipes in each segment.
+pub const SEGMENT: u32 = 100;
+
+const STRETCHES: &[&str] = &[
+    "Roll your shoulders back a few times.",
+    "Look at something far away for a bit.",
+    "Stand up and reach for the ceiling.",
+    "Shake out your hands and wrists.",
+    "Have a sip of water.",
+    "Turn your head slowly side to side.",
+];
+
+/// Where the run was at a moment, for telling how a segment went.
+#[derive(Clone, Copy, Default)]
+pub struct Mark {
+    pub pipes: u32,
+    pub score: u32,
+    pub tick: u32,
+    pub flaps: u32,
+}
+
+/// What a segment took: the differences between the marks either end of it.
+#[derive(Clone, Copy)]
+pub struct Segment {
+    pub points: u32,
+    pub ticks: u32,
+    pub flaps: u32,
+}
+
+#[derive(Clone, Default)]
+pub struct Marathon {
+    /// Segments finished, counting those of earlier sessions.
+    pub segments: u32,
+    /// Score and ticks from earlier sessions, which this run carries on from.
+    pub carried: u32,
+    pub carried_ticks: u32,
+    /// This run when the segment in progress began.
+    start: Mark,
+    /// The segment just finished, while resting after it.
+    pub last: Option<Segment>,
+}
+
+impl Marathon {
+    /// Carry on with `player`'s saved marathon, or start a new one.
+    pub fn resume(player: Option<&str>) -> Self {
+        let mut marathon = load(player).unwrap_or_default();
+        marathon.start.score = marathon.carried;
+        marathon
+    }
+
+    /// Pipes still to pass before the next rest, with `pipes` passed this run.
+    pub fn to_go(&self, pipes: u32) -> u32 {
+        (self.start.pipes + SEGMENT).saturating_sub(pipes)
+    }
+
+    /// Close the segment at `now`, saving the marathon if `persist`.
+    pub fn finish_segment(&mut self, now: Mark, persist: bool, player: Option<&str>) {
+        self.segments += 1;
+        self.last = Some(Segment {
+            points: now.score.saturating_sub(self.start.score),
+            ticks: now.tick - self.start.tick,
+            flaps: now.flaps - self.start.flaps,
+        });
+        self.start = now;
+        if persist {
+            if let Some(path) = players::home_file("marathon", player) {
+                let content = format!(
+                    "{} {} {}\n",
+                    self.segments,
+                    now.score,
+                    self.carried_ticks + now.tick
+                );
+                let _ = storage::write_atomic(&path, &content);
+            }
+        }
+    }
+
+    /// This rest's stretch.
+    pub fn stretch(&self) -> &'static str {
+        STRETCHES[self.segments as usize % STRETCHES.len()]
+    }
+}
+
+/// Forget `player`'s marathon once it's over.
+pub fn clear(player: Option<&str>) {
+    if let Some(path) = players::home_file("marathon", player) {
+        let _ = storage::remove(&path);
+    }
+}
+
+fn load(player: Option<&str>) -> Option<Marathon> {
+    let path = players::home_file("marathon", player)?;
+    let content = fs::read_to_string(path).ok()?;
+    let mut fields = content.split_whitespace().map(|f| f.parse::<u32>().ok());
+    Some(Marathon {
+        segments: fields.next()??,
+        carried: fields.next()??,
+        carried_ticks: fields.next()??,
+        ..Marathon::default()
+    })
+}
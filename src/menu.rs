@@ -0,0 +1,236 @@
+use crate::render::Frame;
+use crossterm::event::{KeyCode, MouseButton, MouseEvent, MouseEventKind};
+use crossterm::style::Color;
+use std::cell::Cell;
+
+pub enum ItemKind {
+    /// Activated with Enter/Space or a click.
+    Action,
+    Toggle(bool),
+    Slider {
+        value: i32,
+        min: i32,
+        max: i32,
+    },
+    Choice {
+        options: Vec<&'static str>,
+        index: usize,
+    },
+}
+
+pub struct MenuItem<T> {
+    pub id: T,
+    pub label: String,
+    pub kind: ItemKind,
+}
+
+impl<T> MenuItem<T> {
+    pub fn action(id: T, label: impl Into<String>) -> Self {
+        Self {
+            id,
+            label: label.into(),
+            kind: ItemKind::Action,
+        }
+    }
+
+    pub fn toggle(id: T, label: impl Into<String>, on: bool) -> Self {
+        Self {
+            id,
+            label: label.into(),
+            kind: ItemKind::Toggle(on),
+        }
+    }
+
+    pub fn slider(id: T, label: impl Into<String>, value: i32, min: i32, max: i32) -> Self {
+        Self {
+            id,
+            label: label.into(),
+            kind: ItemKind::Slider {
+                value: value.clamp(min, max),
+                min,
+                max,
+            },
+        }
+    }
+
+    pub fn choice(
+        id: T,
+        label: impl Into<String>,
+        options: Vec<&'static str>,
+        index: usize,
+    ) -> Self {
+        Self {
+            id,
+            label: label.into(),
+            kind: ItemKind::Choice { options, index },
+        }
+    }
+
+    fn value_text(&self) -> String {
+        match &self.kind {
+            ItemKind::Action => String::new(),
+            ItemKind::Toggle(on) => if *on { "[x]" } else { "[ ]" }.to_string(),
+            ItemKind::Slider { value, min, max } => {
+                let filled = (value - min) as usize;
+                let empty = (max - value) as usize;
+                format!("{}{} {}", "■".repeat(filled), "·".repeat(empty), value)
+            }
+            ItemKind::Choice { options, index } => format!("< {} >", options[*index]),
+        }
+    }
+
+    /// Step a value item forwards or backwards. Returns true if it changed.
+    fn adjust(&mut self, forward: bool) -> bool {
+        match &mut self.kind {
+            ItemKind::Action => false,
+            ItemKind::Toggle(on) => {
+                *on = !*on;
+                true
+            }
+            ItemKind::Slider { value, min, max } => {
+                let next = if forward { *value + 1 } else { *value - 1 };
+                if next < *min || next > *max {
+                    return false;
+                }
+                *value = next;
+                true
+            }
+            ItemKind::Choice { options, index } => {
+                let len = options.len();
+                *index = if forward {
+                    (*index + 1) % len
+                } else {
+                    (*index + len - 1) % len
+                };
+                true
+            }
+        }
+    }
+}
+
+pub enum MenuEvent<T> {
+    Activated(T),
+    Changed(T),
+    Back,
+}
+
+/// A vertical list of items with a cursor, shared by every menu screen.
+pub struct Menu<T> {
+    items: Vec<MenuItem<T>>,
+    selected: usize,
+    width: usize,
+    /// Where the menu was last drawn, for mouse hit-testing.
+    origin: Cell<(i32, i32)>,
+}
+
+impl<T: Copy + PartialEq> Menu<T> {
+    pub fn new(items: Vec<MenuItem<T>>, width: usize) -> Self {
+        Self {
+            items,
+            selected: 0,
+            width,
+            origin: Cell::new((-1, -1)),
+        }
+    }
+
+    pub fn height(&self) -> usize {
+        self.items.len()
+    }
+
+    fn item(&self, id: T) -> Option<&MenuItem<T>> {
+        self.items.iter().find(|item| item.id == id)
+    }
+
+    pub fn toggle(&self, id: T) -> bool {
+        matches!(self.item(id).map(|i| &i.kind), Some(ItemKind::Toggle(true)))
+    }
+
+    pub fn slider(&self, id: T) -> i32 {
+        match self.item(id).map(|i| &i.kind) {
+            Some(ItemKind::Slider { value, .. }) => *value,
+            _ => 0,
+        }
+    }
+
+    pub fn choice(&self, id: T) -> usize {
+        match self.item(id).map(|i| &i.kind) {
+            Some(ItemKind::Choice { index, .. }) => *index,
+            _ => 0,
+        }
+    }
+
+    fn step(&mut self, down: bool) {
+        let len = self.items.len();
+        self.selected = if down {
+            (self.selected + 1) % len
+        } else {
+            (self.selected + len - 1) % len
+        };
+    }
+
+    fn activate(&mut self) -> Option<MenuEvent<T>> {
+        let item = &mut self.items[self.selected];
+        match item.kind {
+            ItemKind::Action => Some(MenuEvent::Activated(item.id)),
+            _ => item.adjust(true).then_some(MenuEvent::Changed(item.id)),
+        }
+    }
+
+    pub fn handle_key(&mut self, code: KeyCode) -> Option<MenuEvent<T>> {
+        match code {
+            KeyCode::Up => self.step(false),
+            KeyCode::Down | KeyCode::Tab => self.step(true),
+            KeyCode::Enter | KeyCode::Char(' ') => return self.activate(),
+            KeyCode::Left | KeyCode::Right => {
+                let item = &mut self.items[self.selected];
+                if item.adjust(code == KeyCode::Right) {
+                    return Some(MenuEvent::Changed(item.id));
+                }
+            }
+            KeyCode::Esc => return Some(MenuEvent::Back),
+            _ => {}
+        }
+        None
+    }
+
+    /// Hovering selects an item and clicking activates or steps it.
+    pub fn handle_mouse(&mut self, event: MouseEvent) -> Option<MenuEvent<T>> {
+        let (x, y) = self.origin.get();
+        let column = event.column as i32;
+        let row = event.row as i32 - y;
+        if column < x || column >= x + self.width as i32 || row < 0 || row >= self.height() as i32 {
+            return None;
+        }
+
+        self.selected = row as usize;
+        match event.kind {
+            MouseEventKind::Down(MouseButton::Left) => self.activate(),
+            MouseEventKind::ScrollUp => {
+                let item = &mut self.items[self.selected];
+                item.adjust(true).then_some(MenuEvent::Changed(item.id))
+            }
+            MouseEventKind::ScrollDown => {
+                let item = &mut self.items[self.selected];
+                item.adjust(false).then_some(MenuEvent::Changed(item.id))
+            }
+            _ => None,
+        }
+    }
+
+    pub fn render(&self, frame: &mut Frame, x: i32, y: i32) {
+        self.origin.set((x, y));
+        for (i, item) in self.items.iter().enumerate() {
+            let (marker, color) = if i == self.selected {
+                ('>', Color::Yellow)
+            } else {
+                (' ', Color::White)
+            };
+            let value = item.value_text();
+            let pad = self
+                .width
+                .saturating_sub(item.label.chars().count() + value.chars().count() + 2);
+            let line = format!("{} {}{}{}", marker, item.label, " ".repeat(pad), value);
+            frame.put_str(x, y + i as i32, &line, color);
+        }
+    }
+}
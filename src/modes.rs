@@ -0,0 +1,106 @@
+/// An optional way to play, with the flag that turns it on.
+pub struct Mode {
+    pub name: &'static str,
+    pub flag: &'static str,
+    pub about: &'static str,
+}
+
+/// Every mode, in the order the help screen lists them.
+pub const MODES: &[Mode] = &[
+    Mode {
+        name: "Lives",
+        flag: "--lives [N]",
+        about: "Start with hearts; a hit costs one and makes the bird briefly invincible.",
+    },
+    Mode {
+        name: "Difficulty",
+        flag: "--difficulty <D>",
+        about: "Easy, normal, or hard pipe gaps and spacing.",
+    },
+    Mode {
+        name: "Weather",
+        flag: "--weather <W>",
+        about: "Rain brings wind, snow drifts, fog hides distant pipes; random rotates them.",
+    },
+    Mode {
+        name: "Speed ramp",
+        flag: "--ramp <CURVE>",
+        about: "Pipes come faster and closer together the further the run goes.",
+    },
+    Mode {
+        name: "Banking",
+        flag: "--banking",
+        about: "Every 5 pipes, bank the score to keep it, then survive a burst of wide pipes.",
+    },
+    Mode {
+        name: "Double or nothing",
+        flag: "--double-or-nothing",
+        about: "Carry on once after a crash: 5 more pipes double the score, a crash halves it.",
+    },
+    Mode {
+        name: "Typing",
+        flag: "--typing",
+        about: "Each flap is the next letter of a word; the summary gives words per minute.",
+    },
+    Mode {
+        name: "Quiz gates",
+        flag: "--quiz",
+        about: "Some pipes stay shut until the digit key answering their sum is pressed.",
+    },
+    Mode {
+        name: "Rewind",
+        flag: "--rewind",
+        about: "Hold Z to run the game back up to 3 seconds, on a meter pipes refill.",
+    },
+    Mode {
+        name: "Rhythm",
+        flag: "--rhythm <BPM>",
+        about: "Pipes come in on the beat of a tempo, and flaps on the beat score a point.",
+    },
+    Mode {
+        name: "Day/night",
+        flag: "--day-night",
+        about: "The run fades into night, lit only by a lantern around the bird.",
+    },
+    Mode {
+        name: "Memory",
+        flag: "--memory",
+        about: "Pipes fade out as they near the bird; only the gap's edges flash as they go.",
+    },
+    Mode {
+        name: "One-switch",
+        flag: "--one-switch",
+        about: "Space alone: tap for a small flap, hold for a big one.",
+    },
+    Mode {
+        name: "Gap assist",
+        flag: "--assist",
+        about: "An arrow points towards the next gap.",
+    },
+    Mode {
+        name: "Trajectory",
+        flag: "--trajectory",
+        about: "Arcs show where the bird goes with and without a flap.",
+    },
+    Mode {
+        name: "Game speed",
+        flag: "--speed <X>",
+        about: "Run the whole game slower or faster.",
+    },
+    Mode {
+        name: "Mutators",
+        flag: "--mutators <LIST>",
+        about:
+            "Tiny gaps, double speed, invisible pipes or upside-down gravity, each for more points.",
+    },
+    Mode {
+        name: "Practice",
+        flag: "title screen",
+        about: "Drills loop a short segment and restart instantly on a crash.",
+    },
+    Mode {
+        name: "Challenge",
+        flag: "--challenge <CODE>",
+        about: "Play or watch someone else's run from its code.",
+    },
+];
@@ -0,0 +1,517 @@
+//! Background music: a tiny tracker looping a chiptune song while a run is on, a little
+//! quicker for every point scored. It is separate from the fanfare bells, which are the
+//! terminal's own and play whatever the music is doing.
+//!
+//! A song's channels are stems that join in as the run heats up: the bass from the
+//! start, then the lead, then the percussion, as the score and game speed climb. When
+//! the run ends the loop cuts out for a short sting. The music follows the game's
+//! events, so it hears about scores and crashes the same way the celebrations do.
+//!
+//! A song is a text file with one command per line; `#` starts a comment.
+//!
+//! ```text
+//! name <title...>
+//! tempo <rows per minute>
+//! channels <stem> <stem>...
+//! pattern <name>
+//! row <cell> <cell>...
+//! order <pattern> <pattern>...
+//! sting <note> <note>...
+//! ```
+//!
+//! Each `row` belongs to the pattern above it and has one cell per channel: a note such
+//! as `C4` or `F#5`, `x` for a noise hit, `--` to hold the channel's note, or `..` for
+//! silence. `channels` names each column's stem, `bass`, `lead` or `percussion`; without
+//! it every channel plays from the start. `order` lists the patterns to play before
+//! starting over, and `sting` the notes played on a crash.
+//!
+//! Only builds with `cargo build --features audio` make sound, by handing square waves to
+//! `aplay` or `paplay`. Otherwise starting the music does nothing.
+
+/// The loudest music volume setting.
+pub const MAX_VOLUME: u8 = 10;
+
+#[cfg(feature = "audio")]
+mod tracker {
+    use std::fs;
+    use std::io::Write;
+    use std::process::{Child, ChildStdin, Command, Stdio};
+    use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+    use std::sync::Arc;
+    use std::thread;
+    use std::time::{Duration, Instant};
+
+    use super::MAX_VOLUME;
+    use crate::config::Config;
+    use crate::events::GameEvent;
+
+    const DEFAULT_SONG: &str = include_str!("../music/flight.trk");
+
+    const RATE: u32 = 22_050;
+    /// Samples written at a time.
+    const CHUNK: usize = 256;
+    /// How far ahead of the clock the tracker writes, in seconds. More would fill the
+    /// pipe to the player and leave the music seconds behind the game.
+    const LEAD: f32 = 0.1;
+    /// Largest swing either side of silence at full volume, out of 127.
+    const PEAK: f32 = 40.0;
+    /// A note falls from full strength to its sustain level over this many seconds.
+    const DECAY: f32 = 0.25;
+    const SUSTAIN: f32 = 0.4;
+    /// Noise hits die away completely, and much faster.
+    const HIT_DECAY: f32 = 0.06;
+    /// The tempo rises a percent per point, up to this many percent.
+    const MAX_SPEEDUP: u32 = 50;
+    /// Points that take the intensity from nothing to full at normal speed.
+    const FULL_SCORE: f32 = 30.0;
+    /// Speed above normal, in percent, that adds full intensity on its own.
+    const FULL_SPEEDUP: f32 = 50.0;
+    /// Seconds for a stem to fade all the way in or out.
+    const FADE: f32 = 1.0;
+    /// Seconds each note of the sting lasts.
+    const STING_NOTE: f32 = 0.15;
+
+    /// Commands tried in turn to play raw unsigned 8-bit mono sound from stdin.
+    const PLAYERS: [(&str, &[&str]); 2] = [
+        (
+            "aplay",
+            &["-q", "-t", "raw", "-f", "U8", "-r", "22050", "-c", "1", "-"],
+        ),
+        (
+            "paplay",
+            &["--raw", "--format=u8", "--rate=22050", "--channels=1"],
+        ),
+    ];
+
+    #[derive(Clone, Copy, PartialEq)]
+    enum Stem {
+        Bass,
+        Lead,
+        Percussion,
+    }
+
+    impl Stem {
+        fn parse(s: &str) -> Option<Self> {
+            match s {
+                "bass" => Some(Stem::Bass),
+                "lead" => Some(Stem::Lead),
+                "percussion" => Some(Stem::Percussion),
+                _ => None,
+            }
+        }
+
+        /// The intensity, from 0 to 1, at which the stem joins in.
+        fn entry(self) -> f32 {
+            match self {
+                Stem::Bass => 0.0,
+                Stem::Lead => 0.3,
+                Stem::Percussion => 0.6,
+            }
+        }
+
+        /// Gain at the top of a run, before anything has heated up.
+        fn opening_gain(self) -> f32 {
+            if self.entry() == 0.0 {
+                1.0
+            } else {
+                0.0
+            }
+        }
+    }
+
+    #[derive(Clone, Copy)]
+    enum Cell {
+        Hold,
+        Rest,
+        Hit,
+        /// A new note at this frequency in hertz.
+        Note(f32),
+    }
+
+    struct Song {
+        tempo: u32,
+        stems: Vec<Stem>,
+        /// Every row in play order, patterns already laid end to end.
+        rows: Vec<Vec<Cell>>,
+        sting: Vec<Cell>,
+    }
+
+    impl Song {
+        fn parse(text: &str) -> Result<Self, String> {
+            let mut tempo = 0;
+            let mut stems = Vec::new();
+            let mut patterns: Vec<(String, Vec<Vec<Cell>>)> = Vec::new();
+            let mut order = Vec::new();
+            let mut sting = Vec::new();
+            let mut channels = None;
+            for (number, line) in text.lines().enumerate() {
+                let line = line.split('#').next().unwrap_or("").trim();
+                let mut words = line.split_whitespace();
+                let bad = |what: &str| format!("line {}: {}", number + 1, what);
+                let cells = |words: std::str::SplitWhitespace| {
+                    words
+                        .map(|w| cell(w).ok_or_else(|| bad(&format!("unknown note '{}'", w))))
+                        .collect::<Result<Vec<_>, _>>()
+                };
+                match words.next() {
+                    None | Some("name") => {}
+                    Some("tempo") => {
+                        tempo = words
+                            .next()
+                            .and_then(|w| w.parse::<u32>().ok())
+                            .filter(|&t| t > 0)
+                            .ok_or_else(|| bad("tempo needs a positive number"))?;
+                    }
+                    Some("channels") => {
+                        stems = words
+                            .map(|w| {
+                                Stem::parse(w).ok_or_else(|| bad(&format!("unknown stem '{}'", w)))
+                            })
+                            .collect::<Result<Vec<_>, _>>()?;
+                    }
+                    Some("pattern") => {
+                        let name = words.next().ok_or_else(|| bad("pattern needs a name"))?;
+                        patterns.push((name.to_string(), Vec::new()));
+                    }
+                    Some("row") => {
+                        let Some((_, rows)) = patterns.last_mut() else {
+                            return Err(bad("row before any pattern"));
+                        };
+                        let cells = cells(words)?;
+                        if cells.is_empty() || channels.is_some_and(|n| n != cells.len()) {
+                            return Err(bad("every row needs the same number of cells"));
+                        }
+                        channels = Some(cells.len());
+                        rows.push(cells);
+                    }
+                    Some("order") => order.extend(words.map(str::to_string)),
+                    Some("sting") => sting = cells(words)?,
+                    Some(other) => return Err(bad(&format!("unknown command '{}'", other))),
+                }
+            }
+            if tempo == 0 {
+                return Err("song has no tempo".to_string());
+            }
+            let mut rows = Vec::new();
+            for name in &order {
+                let (_, pattern) = patterns
+                    .iter()
+                    .find(|(n, _)| n == name)
+                    .ok_or_else(|| format!("order names unknown pattern '{}'", name))?;
+                rows.extend(pattern.iter().cloned());
+            }
+            let Some(channels) = rows.first().map(Vec::len) else {
+                return Err("song has no rows to play".to_string());
+            };
+            if stems.is_empty() {
+                stems = vec![Stem::Bass; channels];
+            } else if stems.len() != channels {
+                return Err(format!(
+                    "{} channels named for rows of {}",
+                    stems.len(),
+                    channels
+                ));
+            }
+            Ok(Self {
+                tempo,
+                stems,
+                rows,
+                sting,
+            })
+        }
+    }
+
+    fn cell(word: &str) -> Option<Cell> {
+        match word {
+            "--" => return Some(Cell::Hold),
+            ".." => return Some(Cell::Rest),
+            "x" => return Some(Cell::Hit),
+            _ => {}
+        }
+        let mut chars = word.chars();
+        let mut semitone: i32 = match chars.next()? {
+            'C' => 0,
+            'D' => 2,
+            'E' => 4,
+            'F' => 5,
+            'G' => 7,
+            'A' => 9,
+            'B' => 11,
+            _ => return None,
+        };
+        let mut rest = chars.as_str();
+        if let Some(octave) = rest.strip_prefix('#') {
+            semitone += 1;
+            rest = octave;
+        }
+        let octave: i32 = rest.parse().ok().filter(|o| (0..=8).contains(o))?;
+        // MIDI numbering, where A4 is note 69 at 440 Hz
+        let midi = 12 * (octave + 1) + semitone;
+        Some(Cell::Note(440.0 * 2f32.powf((midi - 69) as f32 / 12.0)))
+    }
+
+    #[derive(Clone, Copy)]
+    enum Sound {
+        Tone(f32),
+        Noise,
+    }
+
+    /// What one channel is playing, and for how long.
+    #[derive(Clone, Copy, Default)]
+    struct Voice {
+        sound: Option<Sound>,
+        phase: f32,
+        /// Samples since the sound was struck, for its decay.
+        age: u32,
+    }
+
+    impl Voice {
+        fn strike(&mut self, cell: Cell) {
+            self.sound = match cell {
+                Cell::Hold => return,
+                Cell::Rest => None,
+                Cell::Hit => Some(Sound::Noise),
+                Cell::Note(frequency) => Some(Sound::Tone(frequency)),
+            };
+            self.age = 0;
+        }
+
+        /// The next sample, from -1 to 1. `noise` is the shared noise generator.
+        fn sample(&mut self, noise: &mut u16) -> f32 {
+            let age = self.age as f32 / RATE as f32;
+            self.age = self.age.saturating_add(1);
+            match self.sound {
+                None => 0.0,
+                Some(Sound::Tone(frequency)) => {
+                    self.phase = (self.phase + frequency / RATE as f32) % 1.0;
+                    let square = if self.phase < 0.5 { 1.0 } else { -1.0 };
+                    square * (1.0 - age / DECAY).max(SUSTAIN)
+                }
+                Some(Sound::Noise) => {
+                    // A linear feedback shift register, as the old sound chips had
+                    let bit = (*noise ^ (*noise >> 1)) & 1;
+                    *noise = (*noise >> 1) | (bit << 14);
+                    let level = if *noise & 1 == 0 { 1.0 } else { -1.0 };
+                    level * (1.0 - age / HIT_DECAY).max(0.0)
+                }
+            }
+        }
+    }
+
+    /// What the game tells the tracker thread.
+    struct Control {
+        playing: AtomicBool,
+        score: AtomicU32,
+        /// Game speed as a percentage.
+        speed: AtomicU32,
+        volume: AtomicU32,
+        /// Go back to the top of the song with only the opening stems in.
+        restart: AtomicBool,
+        sting: AtomicBool,
+    }
+
+    impl Control {
+        /// How far the run has heated up, from 0 to 1.
+        fn intensity(&self) -> f32 {
+            let score = self.score.load(Ordering::Relaxed) as f32;
+            let speed = self.speed.load(Ordering::Relaxed) as f32;
+            (score / FULL_SCORE + (speed - 100.0) / FULL_SPEEDUP).clamp(0.0, 1.0)
+        }
+    }
+
+    pub struct Music {
+        control: Arc<Control>,
+        player: Child,
+    }
+
+    impl Music {
+        /// Start the music player, silent until a run is on. Nothing starts with the
+        /// volume at zero.
+        pub fn start(config: &Config) -> Result<Option<Self>, String> {
+            if config.music_volume == 0 {
+                return Ok(None);
+            }
+            let text = match &config.music {
+                Some(path) => fs::read_to_string(path)
+                    .map_err(|e| format!("Can't read {}: {}", path.display(), e))?,
+                None => DEFAULT_SONG.to_string(),
+            };
+            let song = Song::parse(&text).map_err(|e| format!("Music: {}", e))?;
+            let mut player = PLAYERS
+                .iter()
+                .find_map(|(program, args)| {
+                    Command::new(program)
+                        .args(*args)
+                        .stdin(Stdio::piped())
+                        .stdout(Stdio::null())
+                        .stderr(Stdio::null())
+                        .spawn()
+                        .ok()
+                })
+                .ok_or("No aplay or paplay to play music with")?;
+            let out = player.stdin.take().ok_or("Music player has no input")?;
+            let control = Arc::new(Control {
+                playing: AtomicBool::new(false),
+                score: AtomicU32::new(0),
+                speed: AtomicU32::new(config.speed.into()),
+                volume: AtomicU32::new(config.music_volume.into()),
+                restart: AtomicBool::new(false),
+                sting: AtomicBool::new(false),
+            });
+            let shared = Arc::clone(&control);
+            thread::spawn(move || play(&song, &shared, out));
+            Ok(Some(Self { control, player }))
+        }
+
+        pub fn on_event(&self, event: &GameEvent) {
+            let control = &self.control;
+            match *event {
+                GameEvent::Started { speed } => {
+                    control.score.store(0, Ordering::Relaxed);
+                    control.speed.store(speed.into(), Ordering::Relaxed);
+                    control.restart.store(true, Ordering::Relaxed);
+                }
+                GameEvent::Scored { score } => control.score.store(score, Ordering::Relaxed),
+                GameEvent::SpeedChanged { speed } => {
+                    control.speed.store(speed.into(), Ordering::Relaxed)
+                }
+                GameEvent::Died => control.sting.store(true, Ordering::Relaxed),
+                GameEvent::Flapped
+                | GameEvent::NearMiss
+                | GameEvent::Beat
+                | GameEvent::PassedRival { .. }
+                | GameEvent::GhostDown { .. }
+                | GameEvent::OpponentDown { .. }
+                | GameEvent::LeadChanged { .. } => {}
+            }
+        }
+
+        /// Play the loop only while the run is moving.
+        pub fn set_playing(&self, playing: bool) {
+            self.control.playing.store(playing, Ordering::Relaxed);
+        }
+
+        pub fn set_volume(&self, volume: u8) {
+            self.control
+                .volume
+                .store(volume.min(MAX_VOLUME).into(), Ordering::Relaxed);
+        }
+    }
+
+    impl Drop for Music {
+        fn drop(&mut self) {
+            // The tracker thread stops once its writes start failing
+            let _ = self.player.kill();
+            let _ = self.player.wait();
+        }
+    }
+
+    /// Synthesize `song` into the player, over and over, until it goes away.
+    fn play(song: &Song, control: &Control, mut out: ChildStdin) {
+        let channels = song.stems.len();
+        let mut voices = vec![Voice::default(); channels];
+        let mut gains: Vec<f32> = song.stems.iter().map(|s| s.opening_gain()).collect();
+        let mut row = 0;
+        let mut into_row = 0.0f32;
+        for (voice, &cell) in voices.iter_mut().zip(&song.rows[row]) {
+            voice.strike(cell);
+        }
+        let mut sting = Voice::default();
+        // Samples into the sting while it plays
+        let mut sting_at: Option<u32> = None;
+        let sting_samples = (STING_NOTE * RATE as f32) as u32;
+        let fade_step = 1.0 / (FADE * RATE as f32);
+        let mut noise: u16 = 1;
+        let mut buffer = [0u8; CHUNK];
+        let mut started = Instant::now();
+        let mut written = 0u32;
+        loop {
+            if control.restart.swap(false, Ordering::Relaxed) {
+                row = 0;
+                into_row = 0.0;
+                for (voice, &cell) in voices.iter_mut().zip(&song.rows[row]) {
+                    voice.strike(cell);
+                }
+                for (gain, stem) in gains.iter_mut().zip(&song.stems) {
+                    *gain = stem.opening_gain();
+                }
+                sting_at = None;
+            }
+            if control.sting.swap(false, Ordering::Relaxed) && !song.sting.is_empty() {
+                sting_at = Some(0);
+            }
+            let volume = control.volume.load(Ordering::Relaxed) as f32 / MAX_VOLUME as f32;
+            let score = control.score.load(Ordering::Relaxed);
+            let row_samples = RATE as f32 * 60.0
+                / (song.tempo as f32 * (1.0 + score.min(MAX_SPEEDUP) as f32 / 100.0));
+            let intensity = control.intensity();
+            // The sting cuts the loop off until the next run starts
+            let looping = control.playing.load(Ordering::Relaxed) && sting_at.is_none();
+            for sample in &mut buffer {
+                let mut mix = 0.0;
+                if let Some(at) = &mut sting_at {
+                    if *at % sting_samples == 0 {
+                        match song.sting.get((*at / sting_samples) as usize) {
+                            Some(&cell) => sting.strike(cell),
+                            None => sting.sound = None,
+                        }
+                    }
+                    *at = at.saturating_add(1);
+                    mix += sting.sample(&mut noise);
+                }
+                if looping {
+                    into_row += 1.0;
+                    if into_row >= row_samples {
+                        into_row -= row_samples;
+                        row = (row + 1) % song.rows.len();
+                        for (voice, &cell) in voices.iter_mut().zip(&song.rows[row]) {
+                            voice.strike(cell);
+                        }
+                    }
+                    let stems = voices.iter_mut().zip(&mut gains).zip(&song.stems);
+                    for ((voice, gain), stem) in stems {
+                        let target = if intensity >= stem.entry() { 1.0 } else { 0.0 };
+                        *gain += (target - *gain).clamp(-fade_step, fade_step);
+                        mix += voice.sample(&mut noise) * *gain / channels as f32;
+                    }
+                }
+                *sample = (128.0 + mix.clamp(-1.0, 1.0) * PEAK * volume) as u8;
+            }
+            if out.write_all(&buffer).is_err() {
+                return;
+            }
+            written += CHUNK as u32;
+            let ahead = written as f32 / RATE as f32 - started.elapsed().as_secs_f32();
+            if ahead > LEAD {
+                thread::sleep(Duration::from_secs_f32(ahead - LEAD));
+            } else if ahead < -LEAD {
+                // The player fell behind, so start counting again from now
+                started = Instant::now();
+                written = 0;
+            }
+        }
+    }
+}
+
+#[cfg(not(feature = "audio"))]
+mod tracker {
+    use crate::config::Config;
+    use crate::events::GameEvent;
+
+    pub struct Music;
+
+    impl Music {
+        pub fn start(_config: &Config) -> Result<Option<Self>, String> {
+            Ok(None)
+        }
+
+        pub fn on_event(&self, _event: &GameEvent) {}
+
+        pub fn set_playing(&self, _playing: bool) {}
+
+        pub fn set_volume(&self, _volume: u8) {}
+    }
+}
+
+/// The running music, if any.
+pub use tracker::Music;
@@ -0,0 +1,176 @@
+//! Mutators: twists picked before a run that make it harder for a bigger score.
+//!
+//! Each one multiplies the run's points, and they multiply together. A run's mutators
+//! go into its leaderboard category and its challenge code, so a mutated score is only
+//! ever compared with runs under the same ones and replays play out the same.
+
+/// Invisible pipes show for this many ticks out of every `FLASH_PERIOD`.
+const FLASH_TICKS: u32 = 8;
+const FLASH_PERIOD: u32 = 40;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Mutator {
+    TinyGap,
+    DoubleSpeed,
+    Invisible,
+    Inverted,
+}
+
+impl Mutator {
+    pub const ALL: [Mutator; 4] = [
+        Mutator::TinyGap,
+        Mutator::DoubleSpeed,
+        Mutator::Invisible,
+        Mutator::Inverted,
+    ];
+
+    pub fn key(self) -> &'static str {
+        match self {
+            Mutator::TinyGap => "tinygap",
+            Mutator::DoubleSpeed => "double",
+            Mutator::Invisible => "invisible",
+            Mutator::Inverted => "inverted",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|m| m.key() == s)
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Mutator::TinyGap => "Tiny gap",
+            Mutator::DoubleSpeed => "Double speed",
+            Mutator::Invisible => "Invisible pipes",
+            Mutator::Inverted => "Inverted",
+        }
+    }
+
+    pub fn about(self) -> &'static str {
+        match self {
+            Mutator::TinyGap => "Gaps a third narrower.",
+            Mutator::DoubleSpeed => "Everything moves twice as fast.",
+            Mutator::Invisible => "Pipes only show in brief flashes.",
+            Mutator::Inverted => "Gravity pulls up and a flap pushes down.",
+        }
+    }
+
+    /// Points multiplier, as a percentage.
+    pub fn multiplier(self) -> u32 {
+        match self {
+            Mutator::TinyGap => 150,
+            Mutator::DoubleSpeed => 150,
+            Mutator::Invisible => 200,
+            Mutator::Inverted => 125,
+        }
+    }
+
+    pub fn multiplier_label(self) -> String {
+        label(self.multiplier())
+    }
+
+    fn bit(self) -> u8 {
+        1 << Self::ALL.iter().position(|&m| m == self).unwrap_or(0)
+    }
+}
+
+/// A multiplier as written on screen, e.g. "x2.25".
+fn label(percent: u32) -> String {
+    format!("x{}.{:02}", percent / 100, percent % 100)
+}
+
+/// The mutators turned on for a run.
+#[derive(Clone, Copy, PartialEq, Default)]
+pub struct Mutators {
+    bits: u8,
+}
+
+impl Mutators {
+    /// A comma-separated list of mutator keys, or "none".
+    pub fn parse(s: &str) -> Option<Self> {
+        let mut mutators = Self::default();
+        if s == "none" {
+            return Some(mutators);
+        }
+        for key in s.split(',') {
+            mutators.set(Mutator::parse(key.trim())?, true);
+        }
+        Some(mutators)
+    }
+
+    /// The packed form challenge codes carry. Unknown bits are an error.
+    pub fn from_bits(bits: u8) -> Option<Self> {
+        let all = Mutator::ALL.iter().fold(0, |all, m| all | m.bit());
+        (bits & !all == 0).then_some(Self { bits })
+    }
+
+    pub fn bits(self) -> u8 {
+        self.bits
+    }
+
+    pub fn has(self, mutator: Mutator) -> bool {
+        self.bits & mutator.bit() != 0
+    }
+
+    pub fn set(&mut self, mutator: Mutator, on: bool) {
+        if on {
+            self.bits |= mutator.bit();
+        } else {
+            self.bits &= !mutator.bit();
+        }
+    }
+
+    pub fn is_empty(self) -> bool {
+        self.bits == 0
+    }
+
+    pub fn iter(self) -> impl Iterator<Item = Mutator> {
+        Mutator::ALL.into_iter().filter(move |&m| self.has(m))
+    }
+
+    /// The list `parse` reads back.
+    pub fn key(self) -> String {
+        if self.is_empty() {
+            return "none".to_string();
+        }
+        self.iter().map(Mutator::key).collect::<Vec<_>>().join(",")
+    }
+
+    /// All the multipliers together, as a percentage.
+    pub fn multiplier(self) -> u32 {
+        self.iter()
+            .fold(100, |total, m| total * m.multiplier() / 100)
+    }
+
+    /// `points` with the multiplier applied.
+    pub fn apply(self, points: u32) -> u32 {
+        (u64::from(points) * u64::from(self.multiplier()) / 100) as u32
+    }
+
+    pub fn multiplier_label(self) -> String {
+        label(self.multiplier())
+    }
+
+    /// Rows of open space in a pipe that would otherwise have `gap`.
+    pub fn pipe_gap(self, gap: u16) -> u16 {
+        if self.has(Mutator::TinyGap) {
+            gap - gap / 3
+        } else {
+            gap
+        }
+    }
+
+    /// How many times faster than its speed setting the run goes.
+    pub fn speed_factor(self) -> f32 {
+        if self.has(Mutator::DoubleSpeed) {
+            2.0
+        } else {
+            1.0
+        }
+    }
+
+    /// Whether pipes are drawn on `tick`.
+    pub fn pipes_shown(self, tick: u32) -> bool {
+        !self.has(Mutator::Invisible) || tick % FLASH_PERIOD < FLASH_TICKS
+    }
+}
@@ -0,0 +1,98 @@
+//! When the game loops tick. Each tick falls due a `TICK_RATE` after the last one fell
+//! due, not after it ran, so the few milliseconds a tick runs late are made up on the
+//! next instead of piling up: a 10 minute session is 12000 ticks, which keeps speedrun
+//! times and rhythm mode's beat in step with the wall clock.
+//!
+//! How late each tick ran is kept as it goes, for the stress test and `--json-summary`.
+
+use crate::TICK_RATE;
+use std::time::{Duration, Instant};
+
+/// Furthest behind the loop may fall before it gives up catching up and starts counting
+/// afresh, as after the machine was suspended, rather than racing through the backlog.
+const MAX_BEHIND: Duration = TICK_RATE.saturating_mul(4);
+
+/// How late the ticks have run.
+#[derive(Clone, Copy, Default)]
+pub struct Jitter {
+    pub ticks: u32,
+    total: Duration,
+    pub max: Duration,
+    /// Times the loop fell too far behind and started counting afresh.
+    pub resyncs: u32,
+}
+
+impl Jitter {
+    pub fn mean(&self) -> Duration {
+        self.total.checked_div(self.ticks).unwrap_or_default()
+    }
+
+    fn record(&mut self, late: Duration) {
+        self.ticks += 1;
+        self.total += late;
+        self.max = self.max.max(late);
+    }
+}
+
+pub struct Pacer {
+    due: Instant,
+    pub jitter: Jitter,
+}
+
+impl Pacer {
+    /// The first tick due a tick from `now`.
+    pub fn new(now: Instant) -> Self {
+        Self {
+            due: now + TICK_RATE,
+            jitter: Jitter::default(),
+        }
+    }
+
+    /// Whether a tick is due by `now`. If it is, it's counted and the next one is set.
+    pub fn tick(&mut self, now: Instant) -> bool {
+        let Some(late) = now.checked_duration_since(self.due) else {
+            return false;
+        };
+        self.jitter.record(late);
+        if late > MAX_BEHIND {
+            self.jitter.resyncs += 1;
+            self.due = now + TICK_RATE;
+        } else {
+            self.due += TICK_RATE;
+        }
+        true
+    }
+
+    /// How long from `now` until the next tick is due, or zero if it already is.
+    pub fn until_due(&self, now: Instant) -> Duration {
+        self.due.saturating_duration_since(now)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ticks_run_late_without_the_lateness_adding_up() {
+        let start = Instant::now();
+        let mut pacer = Pacer::new(start);
+        // Seen every 7ms, as a loop that sleeps a little between frames would
+        let ticks = (1..=600 * 1000 / 7 + 1)
+            .filter(|&frame| pacer.tick(start + Duration::from_millis(frame * 7)))
+            .count();
+        assert_eq!(ticks, 600 * 1000 / TICK_RATE.as_millis() as usize);
+        assert!(pacer.jitter.max < Duration::from_millis(7));
+        assert_eq!(pacer.jitter.resyncs, 0);
+    }
+
+    #[test]
+    fn a_long_stall_starts_the_count_afresh() {
+        let start = Instant::now();
+        let mut pacer = Pacer::new(start);
+        assert!(pacer.tick(start + Duration::from_secs(5)));
+        assert_eq!(pacer.jitter.resyncs, 1);
+        assert!(!pacer.tick(start + Duration::from_secs(5) + TICK_RATE / 2));
+        assert!(pacer.tick(start + Duration::from_secs(5) + TICK_RATE));
+    }
+}
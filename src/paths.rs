@@ -0,0 +1,54 @@
+//! Where tflap keeps its files. They all sit side by side as `.tflap_*` in one
+//! directory: `$HOME`, or the one `--data-dir` or `TFLAP_DATA_DIR` names, for sandboxes,
+//! tests, and a leaderboard of its own for each project.
+
+use std::env;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+/// Set once at startup, before the config file is read from it.
+static DATA_DIR: OnceLock<PathBuf> = OnceLock::new();
+
+pub struct Paths {
+    dir: PathBuf,
+}
+
+impl Paths {
+    /// Keep every file in `dir` for the rest of the session. Only the first call counts.
+    pub fn set_data_dir(dir: PathBuf) {
+        let _ = DATA_DIR.set(dir);
+    }
+
+    /// The directory in use: the data directory if one was given, or `$HOME`. None if
+    /// there's neither.
+    pub fn get() -> Option<Self> {
+        DATA_DIR
+            .get()
+            .cloned()
+            .or_else(|| {
+                env::var_os("HOME")
+                    .filter(|home| !home.is_empty())
+                    .map(PathBuf::from)
+            })
+            .map(|dir| Self { dir })
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// `.tflap_<name>` in the directory.
+    pub fn file(&self, name: &str) -> PathBuf {
+        self.dir.join(format!(".tflap_{}", name))
+    }
+}
+
+/// Whether a data directory was given, rather than falling back on `$HOME`.
+pub fn has_data_dir() -> bool {
+    DATA_DIR.get().is_some()
+}
+
+/// `.tflap_<name>` in the directory in use, if there is one.
+pub fn file(name: &str) -> Option<PathBuf> {
+    Paths::get().map(|paths| paths.file(name))
+}
@@ -0,0 +1,143 @@
+//! Photo mode, from the pause menu: the run stays frozen while a camera moves over the
+//! board, the HUD comes and goes, and a color filter goes over it all, and the picture
+//! can be saved as ANSI text to `cat` or as a PNG to share.
+
+use crate::export::{self, Export, Stamp};
+use crate::paths::Paths;
+use crate::render::{to_rgb, Frame, Zoom};
+use crossterm::style::Color;
+use std::io;
+use std::path::PathBuf;
+
+/// Camera steps per arrow press, in board cells.
+const PAN_STEP: i32 = 2;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Filter {
+    None,
+    Sepia,
+    Noir,
+    Negative,
+    Sunset,
+}
+
+impl Filter {
+    const ALL: [Filter; 5] = [
+        Filter::None,
+        Filter::Sepia,
+        Filter::Noir,
+        Filter::Negative,
+        Filter::Sunset,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Filter::None => "No filter",
+            Filter::Sepia => "Sepia",
+            Filter::Noir => "Noir",
+            Filter::Negative => "Negative",
+            Filter::Sunset => "Sunset",
+        }
+    }
+
+    pub fn next(self) -> Self {
+        let i = Self::ALL.iter().position(|&f| f == self).unwrap_or(0);
+        Self::ALL[(i + 1) % Self::ALL.len()]
+    }
+
+    fn apply(self, color: Color) -> Color {
+        if self == Filter::None || color == Color::Reset {
+            return color;
+        }
+        let (r, g, b) = to_rgb(color);
+        let (r, g, b) = (r as f32, g as f32, b as f32);
+        let grey = 0.299 * r + 0.587 * g + 0.114 * b;
+        let (r, g, b) = match self {
+            Filter::None => (r, g, b),
+            Filter::Sepia => (grey * 1.07, grey * 0.74, grey * 0.43),
+            // Pushed apart around the middle, for harder shadows
+            Filter::Noir => {
+                let grey = (grey - 128.0) * 1.4 + 128.0;
+                (grey, grey, grey)
+            }
+            Filter::Negative => (255.0 - r, 255.0 - g, 255.0 - b),
+            Filter::Sunset => (r * 0.6 + 110.0, g * 0.75 + 30.0, b * 0.55 + 20.0),
+        };
+        let channel = |c: f32| c.clamp(0.0, 255.0) as u8;
+        Color::Rgb {
+            r: channel(r),
+            g: channel(g),
+            b: channel(b),
+        }
+    }
+}
+
+pub struct Photo {
+    /// Whether the camera is in close, at twice the size.
+    pub zoomed: bool,
+    /// The board cell at the top left of the camera when it's in close.
+    x: i32,
+    y: i32,
+    pub hud: bool,
+    pub filter: Filter,
+    /// Where the last picture went, or why it didn't.
+    pub status: Option<String>,
+}
+
+impl Photo {
+    /// The camera pulled back with the HUD showing, centred on the bird for when it zooms.
+    pub fn new(bird: (i32, i32), width: u16, height: u16) -> Self {
+        let mut photo = Self {
+            zoomed: false,
+            x: bird.0 - width as i32 / 4,
+            y: bird.1 - height as i32 / 4,
+            hud: true,
+            filter: Filter::None,
+            status: None,
+        };
+        photo.pan(0, 0, width, height);
+        photo
+    }
+
+    /// Move the camera by `dx`, `dy` steps, keeping it over the board.
+    pub fn pan(&mut self, dx: i32, dy: i32, width: u16, height: u16) {
+        self.x = (self.x + dx * PAN_STEP).clamp(0, (width - width / 2) as i32);
+        self.y = (self.y + dy * PAN_STEP).clamp(0, (height - height / 2) as i32);
+    }
+
+    /// What the camera sees of `world`, at the board's size.
+    pub fn frame(&self, world: Frame) -> Frame {
+        if !self.zoomed {
+            return world;
+        }
+        let (width, height) = (world.width(), world.height());
+        let seen = world
+            .crop(self.x as u16, self.y as u16, width / 2, height / 2)
+            .grow(Zoom::Double);
+        let mut frame = Frame::new(width, height);
+        frame.blit(&seen, 0, 0);
+        frame
+    }
+
+    /// Put the filter over a composed picture.
+    pub fn develop(&self, frame: &mut Frame) {
+        frame.recolor(|color| self.filter.apply(color));
+    }
+}
+
+/// Save `frame` as ANSI text or as a PNG next to the other save files, returning where
+/// it went.
+pub fn save(frame: &Frame, png: bool, stamp: &Stamp) -> io::Result<PathBuf> {
+    let paths =
+        Paths::get().ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "$HOME is not set"))?;
+    let path = if png {
+        let path = paths.file("photo.png");
+        export::save(&path, Export::Png(frame), stamp)?;
+        path
+    } else {
+        let path = paths.file("photo.ans");
+        export::save(&path, Export::Ansi(frame), stamp)?;
+        path
+    };
+    Ok(path)
+}
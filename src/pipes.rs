@@ -0,0 +1,101 @@
+//! Pipe styles: the glyphs a pipe's body and cap rows are drawn with.
+//!
+//! Every style fills the pipe's whole width on every row it blocks, so what's drawn
+//! always matches what the bird collides with.
+
+#[derive(Clone, Copy, PartialEq, Default)]
+pub enum PipeStyle {
+    #[default]
+    Solid,
+    /// A narrower body under a full-width lip at the gap, like the original game.
+    Capped,
+    /// Hollow double-line walls.
+    Dashed,
+    /// Shaded from a lit edge to a dark one, like a round pipe.
+    Gradient,
+}
+
+/// A pipe row as a pattern stretched to the pipe's width: the first and last glyphs
+/// are its edges and the ones between are spread across its middle.
+struct Glyphs {
+    /// Body rows, taken in turn down the pipe.
+    body: &'static [&'static str],
+    /// The row beside the gap.
+    cap: &'static str,
+}
+
+impl PipeStyle {
+    pub const ALL: [PipeStyle; 4] = [
+        PipeStyle::Solid,
+        PipeStyle::Capped,
+        PipeStyle::Dashed,
+        PipeStyle::Gradient,
+    ];
+
+    pub fn key(self) -> &'static str {
+        match self {
+            PipeStyle::Solid => "solid",
+            PipeStyle::Capped => "capped",
+            PipeStyle::Dashed => "dashed",
+            PipeStyle::Gradient => "gradient",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|style| style.key() == s)
+    }
+
+    fn glyphs(self) -> Glyphs {
+        match self {
+            PipeStyle::Solid => Glyphs {
+                body: &["█"],
+                cap: "█",
+            },
+            PipeStyle::Capped => Glyphs {
+                body: &["▐█▌"],
+                cap: "█",
+            },
+            PipeStyle::Dashed => Glyphs {
+                body: &["╠═╣", "║ ║"],
+                cap: "╬═╬",
+            },
+            PipeStyle::Gradient => Glyphs {
+                body: &["░▒▓█▓▒░"],
+                cap: "▒▓██▓▒",
+            },
+        }
+    }
+
+    /// Row `y` of a pipe `width` columns wide, `cap` if it's the row beside the gap.
+    /// `solid` is the display's own pipe glyph, which the solid style keeps.
+    pub fn row(self, y: i32, width: u16, cap: bool, solid: char) -> String {
+        let glyphs = self.glyphs();
+        let pattern = if self == PipeStyle::Solid {
+            solid.to_string()
+        } else if cap {
+            glyphs.cap.to_string()
+        } else {
+            glyphs.body[y.rem_euclid(glyphs.body.len() as i32) as usize].to_string()
+        };
+        (0..width)
+            .map(|column| stretch(&pattern, column, width))
+            .collect()
+    }
+}
+
+/// The glyph of `pattern` at `column` of a row `width` wide.
+fn stretch(pattern: &str, column: u16, width: u16) -> char {
+    let chars: Vec<char> = pattern.chars().collect();
+    let last = chars.len() - 1;
+    if chars.len() < 3 || width < 3 {
+        return chars[(column as usize).min(last)];
+    }
+    if column == 0 {
+        chars[0]
+    } else if column == width - 1 {
+        chars[last]
+    } else {
+        let middle = last - 1;
+        chars[1 + (column - 1) as usize * middle / (width - 2) as usize]
+    }
+}
@@ -0,0 +1,70 @@
+//! Players sharing one machine, each with their own leaderboard and history.
+//!
+//! Without `--player` the files are the ones tflap has always used. A named player's
+//! sit beside them with the name on the end, which is how the family leaderboard finds
+//! everyone who has played.
+
+use crate::paths::Paths;
+use crossterm::style::Color;
+use std::fs;
+use std::path::PathBuf;
+
+const MAX_NAME: usize = 16;
+/// What the family leaderboard calls whoever plays without a name.
+const DEFAULT_NAME: &str = "default";
+/// Colors players are told apart by, picked from their names so they stay put as others
+/// join.
+const COLORS: [Color; 6] = [
+    Color::Cyan,
+    Color::Yellow,
+    Color::Green,
+    Color::Magenta,
+    Color::Blue,
+    Color::Red,
+];
+
+/// Names are short and safe to put in a file name.
+pub fn valid_name(name: &str) -> bool {
+    (1..=MAX_NAME).contains(&name.len())
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+/// `~/.tflap_<file>` (or in the data directory), with `.<player>` on the end for a named player.
+pub fn home_file(file: &str, player: Option<&str>) -> Option<PathBuf> {
+    let paths = Paths::get()?;
+    Some(match player {
+        Some(player) => paths.file(&format!("{}.{}", file, player)),
+        None => paths.file(file),
+    })
+}
+
+/// Everyone with a leaderboard: the unnamed player first, then the rest by name.
+pub fn all() -> Vec<Option<String>> {
+    let mut names: Vec<String> = Paths::get()
+        .and_then(|paths| fs::read_dir(paths.dir()).ok())
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| {
+            let file = entry.ok()?.file_name().into_string().ok()?;
+            let name = file.strip_prefix(".tflap_leaderboard.")?;
+            valid_name(name).then(|| name.to_string())
+        })
+        .collect();
+    names.sort();
+    let mut players = vec![None];
+    players.extend(names.into_iter().map(Some));
+    players
+}
+
+pub fn label(player: Option<&str>) -> &str {
+    player.unwrap_or(DEFAULT_NAME)
+}
+
+pub fn color(player: Option<&str>) -> Color {
+    let sum = label(player).bytes().fold(0usize, |sum, b| {
+        sum.wrapping_mul(31).wrapping_add(b as usize)
+    });
+    COLORS[sum % COLORS.len()]
+}
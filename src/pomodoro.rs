@@ -0,0 +1,105 @@
+//! Pomodoro mode: the game as a break timer, playable only between focus periods.
+//!
+//! A cycle is `ROUNDS` focus periods of the chosen length, each followed by a
+//! `SHORT_BREAK`, except the last which gets a `LONG_BREAK`, and then it starts over.
+//! The cycle's start is kept in `.tflap_pomodoro` in the data directory, so quitting to
+//! work and starting tflap again at the next break doesn't reset the clock. A cycle
+//! begun `STALE` or more ago, or one with other focus periods, starts afresh.
+
+use crate::players;
+use crate::storage;
+use std::fs;
+
+pub const ROUNDS: u64 = 4;
+const SHORT_BREAK: u64 = 5 * 60;
+const LONG_BREAK: u64 = 15 * 60;
+/// A cycle this old is from another day's work.
+const STALE: u64 = 8 * 60 * 60;
+pub const MAX_MINUTES: u32 = 120;
+
+/// Where the cycle is at a moment.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Period {
+    Focus,
+    Break,
+}
+
+pub struct Pomodoro {
+    /// Seconds in each focus period.
+    focus: u64,
+    /// When the cycle began, in seconds since the Unix epoch.
+    started: u64,
+}
+
+impl Pomodoro {
+    /// Carry on with the saved cycle if it's recent and has the same focus periods,
+    /// otherwise start one at `now` and save it.
+    pub fn resume(minutes: u32, now: u64) -> Self {
+        let focus = minutes as u64 * 60;
+        let saved = load().filter(|&(started, saved_focus)| {
+            saved_focus == focus && started <= now && now - started < STALE
+        });
+        match saved {
+            Some((started, _)) => Self { focus, started },
+            None => {
+                let pomodoro = Self {
+                    focus,
+                    started: now,
+                };
+                pomodoro.save();
+                pomodoro
+            }
+        }
+    }
+
+    fn save(&self) {
+        if let Some(path) = players::home_file("pomodoro", None) {
+            let _ = storage::write_atomic(&path, &format!("{} {}\n", self.started, self.focus));
+        }
+    }
+
+    /// The period at `now`, which round of the cycle it's in counting from 1, and the
+    /// seconds left in it.
+    pub fn period(&self, now: u64) -> (Period, u64, u64) {
+        let cycle = (1..=ROUNDS)
+            .map(|round| self.focus + break_after(round))
+            .sum::<u64>();
+        let mut at = now.saturating_sub(self.started) % cycle;
+        for round in 1..=ROUNDS {
+            if at < self.focus {
+                return (Period::Focus, round, self.focus - at);
+            }
+            at -= self.focus;
+            let rest = break_after(round);
+            if at < rest {
+                return (Period::Break, round, rest - at);
+            }
+            at -= rest;
+        }
+        unreachable!("the cycle is exactly the rounds' length")
+    }
+}
+
+/// Seconds of break after focus round `round`.
+pub fn break_after(round: u64) -> u64 {
+    if round == ROUNDS {
+        LONG_BREAK
+    } else {
+        SHORT_BREAK
+    }
+}
+
+/// The saved cycle's start and focus period length, in seconds.
+fn load() -> Option<(u64, u64)> {
+    let path = players::home_file("pomodoro", None)?;
+    let content = fs::read_to_string(path).ok()?;
+    let mut fields = content.split_whitespace();
+    let started = fields.next()?.parse().ok()?;
+    let focus = fields.next()?.parse().ok()?;
+    Some((started, focus))
+}
+
+/// Seconds as "m:ss".
+pub fn clock(seconds: u64) -> String {
+    format!("{}:{:02}", seconds / 60, seconds % 60)
+}
@@ -0,0 +1,135 @@
+//! Timed spans around the phases of a tick and a frame, for finding where the time goes
+//! on a slow terminal. Built only with `cargo build --features profiling`; otherwise a
+//! span is an empty value and the summary is always empty, so nothing is measured.
+//!
+//! Spans nest by when they are opened: one opened while another is still alive is
+//! counted as part of it. The summary covers the last whole second.
+
+#[cfg(feature = "profiling")]
+mod spans {
+    use std::cell::RefCell;
+    use std::time::{Duration, Instant};
+
+    const WINDOW: Duration = Duration::from_secs(1);
+    const BAR_WIDTH: usize = 20;
+
+    struct Total {
+        /// Names from the outermost span down, joined with '/'.
+        path: String,
+        depth: usize,
+        time: Duration,
+        calls: u32,
+    }
+
+    struct Profiler {
+        open: Vec<&'static str>,
+        window_start: Instant,
+        /// Kept in the order the spans were first seen, so a parent comes before its children.
+        current: Vec<Total>,
+        shown: Vec<String>,
+    }
+
+    impl Profiler {
+        fn open(&mut self, name: &'static str) {
+            self.open.push(name);
+            let path = self.open.join("/");
+            if !self.current.iter().any(|total| total.path == path) {
+                self.current.push(Total {
+                    path,
+                    depth: self.open.len() - 1,
+                    time: Duration::ZERO,
+                    calls: 0,
+                });
+            }
+        }
+
+        fn close(&mut self, elapsed: Duration) {
+            let path = self.open.join("/");
+            self.open.pop();
+            if let Some(total) = self.current.iter_mut().find(|total| total.path == path) {
+                total.time += elapsed;
+                total.calls += 1;
+            }
+            if self.open.is_empty() && self.window_start.elapsed() >= WINDOW {
+                self.roll();
+            }
+        }
+
+        fn roll(&mut self) {
+            let window = self.window_start.elapsed().as_secs_f64();
+            self.shown = self
+                .current
+                .iter()
+                .map(|total| {
+                    let name = total.path.rsplit('/').next().unwrap_or("");
+                    let share = total.time.as_secs_f64() / window;
+                    let bar =
+                        "█".repeat(((share * BAR_WIDTH as f64).round() as usize).min(BAR_WIDTH));
+                    format!(
+                        "{:indent$}{:<w$} {:>6.2} ms {:>4}/s {}",
+                        "",
+                        name,
+                        total.time.as_secs_f64() * 1000.0 / total.calls.max(1) as f64,
+                        total.calls,
+                        bar,
+                        indent = total.depth * 2,
+                        w = 12 - total.depth * 2,
+                    )
+                })
+                .collect();
+            self.current.clear();
+            self.window_start = Instant::now();
+        }
+    }
+
+    thread_local! {
+        static PROFILER: RefCell<Profiler> = RefCell::new(Profiler {
+            open: Vec::new(),
+            window_start: Instant::now(),
+            current: Vec::new(),
+            shown: Vec::new(),
+        });
+    }
+
+    pub struct Span {
+        start: Instant,
+    }
+
+    pub fn span(name: &'static str) -> Span {
+        PROFILER.with(|profiler| profiler.borrow_mut().open(name));
+        Span {
+            start: Instant::now(),
+        }
+    }
+
+    impl Drop for Span {
+        fn drop(&mut self) {
+            let elapsed = self.start.elapsed();
+            PROFILER.with(|profiler| profiler.borrow_mut().close(elapsed));
+        }
+    }
+
+    pub fn summary() -> Vec<String> {
+        PROFILER.with(|profiler| profiler.borrow().shown.clone())
+    }
+}
+
+#[cfg(not(feature = "profiling"))]
+mod spans {
+    pub struct Span;
+
+    #[inline(always)]
+    pub fn span(_name: &'static str) -> Span {
+        Span
+    }
+
+    pub fn summary() -> Vec<String> {
+        Vec::new()
+    }
+}
+
+/// Time from now until the returned value is dropped.
+pub use spans::span;
+/// One line per span seen in the last second: average time per call, calls per second,
+/// and a bar for the share of the second it took. Children are indented under parents.
+pub use spans::summary;
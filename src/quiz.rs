@@ -0,0 +1,75 @@
+//! Quiz gates: now and then a pipe comes with a sum on it, and its gap stays shut until
+//! the answer's digit key is pressed.
+//!
+//! Answers are always a single digit. Easy gates add, normal ones subtract from a
+//! two-digit number too, and hard ones divide as well. Each gate takes one answer: a
+//! wrong one leaves it shut for good, so a guess is as good as a crash.
+
+use crate::difficulty::Difficulty;
+use rand::Rng;
+
+/// One spawned pipe in this many is a gate, on average.
+pub const ONE_IN: u32 = 4;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Gate {
+    Shut,
+    Open,
+    /// Answered wrong, and shut for good.
+    Wrong,
+}
+
+#[derive(Clone)]
+pub struct Quiz {
+    pub question: String,
+    answer: u8,
+    pub gate: Gate,
+}
+
+impl Quiz {
+    pub fn new(rng: &mut impl Rng, difficulty: Difficulty) -> Self {
+        let kinds = match difficulty {
+            Difficulty::Easy => 1,
+            Difficulty::Normal => 2,
+            Difficulty::Hard => 3,
+        };
+        let answer = rng.gen_range(0..=9);
+        let question = match rng.gen_range(0..kinds) {
+            0 => {
+                let a = rng.gen_range(0..=answer);
+                format!("{}+{}", a, answer - a)
+            }
+            1 => {
+                let b = rng.gen_range(1..=9);
+                format!("{}-{}", answer + b, b)
+            }
+            _ => {
+                let b = rng.gen_range(2..=9);
+                format!("{}/{}", answer * b, b)
+            }
+        };
+        Self {
+            question,
+            answer,
+            gate: Gate::Shut,
+        }
+    }
+
+    /// Answer with `digit`, opening the gate if it's right. Returns whether it was.
+    pub fn answer(&mut self, digit: u8) -> bool {
+        let right = digit == self.answer;
+        self.gate = if right { Gate::Open } else { Gate::Wrong };
+        right
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.gate == Gate::Open
+    }
+}
+
+/// How the run's gates went.
+#[derive(Clone, Copy, Default)]
+pub struct Tally {
+    pub right: u32,
+    pub wrong: u32,
+}
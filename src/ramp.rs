@@ -0,0 +1,93 @@
+//! The speed ramp: pipes that come faster and closer together as a run goes on.
+//!
+//! How quickly it builds follows a curve over the pipes passed, reaching full speed at
+//! `FULL_PIPES`. Only the pipes speed up; the bird falls and flaps the same at any
+//! point of the ramp. A run without it keeps one column per tick the whole way.
+
+/// Pipes passed before the ramp is at full speed.
+const FULL_PIPES: f32 = 60.0;
+/// Pipe speed at the top of the ramp, in columns per tick.
+pub const MAX_PIPE_SPEED: f32 = 1.6;
+/// Share of the usual spacing between pipes left at the top of the ramp.
+pub const MIN_SPACING: f32 = 0.8;
+
+#[derive(Clone, Copy, PartialEq, Default)]
+pub enum Curve {
+    #[default]
+    Off,
+    Linear,
+    /// Slow to start, steep at the end.
+    EaseIn,
+    /// Quick to start, levelling off.
+    EaseOut,
+}
+
+impl Curve {
+    pub const ALL: [Curve; 4] = [Curve::Off, Curve::Linear, Curve::EaseIn, Curve::EaseOut];
+
+    pub fn key(self) -> &'static str {
+        match self {
+            Curve::Off => "off",
+            Curve::Linear => "linear",
+            Curve::EaseIn => "ease-in",
+            Curve::EaseOut => "ease-out",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|curve| curve.key() == s)
+    }
+
+    /// How far up the ramp `t` of the way along it is, both from 0 to 1.
+    fn shape(self, t: f32) -> f32 {
+        match self {
+            Curve::Off => 0.0,
+            Curve::Linear => t,
+            Curve::EaseIn => t * t,
+            Curve::EaseOut => 1.0 - (1.0 - t) * (1.0 - t),
+        }
+    }
+}
+
+/// Pipe speed and spacing for one run, following the curve as pipes are passed.
+#[derive(Clone, Copy)]
+pub struct SpeedRamp {
+    curve: Curve,
+    pipes: u32,
+}
+
+impl SpeedRamp {
+    pub fn new(curve: Curve) -> Self {
+        Self { curve, pipes: 0 }
+    }
+
+    pub fn passed(&mut self) {
+        self.pipes += 1;
+    }
+
+    /// How far up the ramp the run is, from 0 to 1.
+    pub fn level(&self) -> f32 {
+        self.level_after(0)
+    }
+
+    fn level_after(&self, pipes: u32) -> f32 {
+        let t = ((self.pipes + pipes) as f32 / FULL_PIPES).min(1.0);
+        self.curve.shape(t)
+    }
+
+    /// Columns the pipes scroll per tick.
+    pub fn pipe_speed(&self) -> f32 {
+        self.pipe_speed_after(0)
+    }
+
+    /// Columns per tick once `pipes` more have been passed.
+    pub fn pipe_speed_after(&self, pipes: u32) -> f32 {
+        1.0 + (MAX_PIPE_SPEED - 1.0) * self.level_after(pipes)
+    }
+
+    /// Columns between pipes that would otherwise have `spacing`.
+    pub fn spacing(&self, spacing: i32) -> i32 {
+        let share = 1.0 - (1.0 - MIN_SPACING) * self.level();
+        (spacing as f32 * share).round() as i32
+    }
+}
@@ -0,0 +1,850 @@
+use crate::bidi;
+use crate::entity::Sprite;
+use crossterm::{
+    cursor::MoveTo,
+    event::MouseEvent,
+    queue,
+    style::{Color, Print, ResetColor, SetForegroundColor},
+};
+use std::io::{self, Write};
+use std::time::{Duration, Instant};
+
+/// Below this brightness a cell is drawn as empty space.
+const MIN_VISIBLE: f32 = 0.05;
+/// A full redraw taking longer than this to write out points to a slow link...
+const SLOW_FLUSH: Duration = Duration::from_millis(12);
+/// ...when most of this many in a row do.
+const LINK_SAMPLES: u32 = 20;
+
+/// How many colors the terminal can show.
+#[derive(Clone, Copy, PartialEq)]
+pub enum ColorDepth {
+    TrueColor,
+    Ansi256,
+    Ansi16,
+}
+
+/// What a frame may use when it is written out. Frames are always drawn with the full
+/// set of glyphs and colors and brought down to this at flush time.
+#[derive(Clone, Copy)]
+pub struct Display {
+    /// Replace block, box-drawing, and other symbols with plain ASCII.
+    pub ascii: bool,
+    /// Draw the game's sprites as two-column emoji.
+    pub emoji: bool,
+    pub colors: ColorDepth,
+    pub zoom: Zoom,
+}
+
+/// How many terminal cells each of the game's cells is drawn across. The game plays on
+/// the smaller board the zoom leaves, so only drawing and mouse positions change.
+#[derive(Clone, Copy, PartialEq, Default)]
+pub enum Zoom {
+    #[default]
+    One,
+    /// Two columns by one row, which about squares up a terminal cell.
+    Wide,
+    /// Two columns by two rows.
+    Double,
+}
+
+impl Zoom {
+    pub const ALL: [Zoom; 3] = [Zoom::One, Zoom::Wide, Zoom::Double];
+
+    pub fn key(self) -> &'static str {
+        match self {
+            Zoom::One => "1x",
+            Zoom::Wide => "2x1",
+            Zoom::Double => "2x2",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "1" => Some(Zoom::One),
+            "2" => Some(Zoom::Double),
+            _ => Self::ALL.into_iter().find(|zoom| zoom.key() == s),
+        }
+    }
+
+    /// Terminal columns and rows per game cell.
+    pub fn cells(self) -> (u16, u16) {
+        match self {
+            Zoom::One => (1, 1),
+            Zoom::Wide => (2, 1),
+            Zoom::Double => (2, 2),
+        }
+    }
+
+    /// The board that fits a terminal `width` by `height`.
+    pub fn logical(self, width: u16, height: u16) -> (u16, u16) {
+        let (columns, rows) = self.cells();
+        (width / columns, height / rows)
+    }
+
+    /// A mouse event with its position in game cells.
+    pub fn mouse(self, mut event: MouseEvent) -> MouseEvent {
+        let (columns, rows) = self.cells();
+        event.column /= columns;
+        event.row /= rows;
+        event
+    }
+}
+
+/// Characters for the game's own sprites. The ASCII set is drawn for ASCII displays
+/// directly; anything else on screen is mapped glyph by glyph at flush time.
+pub struct Sprites {
+    pub bird: char,
+    pub pipe: char,
+    /// The bird once a run has ended.
+    pub crash: char,
+    pub rival: char,
+    /// Trajectory preview dots without and with a flap.
+    pub coast: char,
+    pub flap: char,
+    /// The bird, pipe, and crash glyphs take two columns each.
+    pub wide: bool,
+    /// Repeating strips along the ground and the ceiling, scrolled with the pipes. Only a
+    /// few cells in each repeat change as they scroll, to keep slow links quiet.
+    pub ground: &'static str,
+    pub ceiling: &'static str,
+}
+
+impl Sprites {
+    /// One of the bird, pipe, or crash glyphs as a sprite, as wide as the set draws them.
+    pub fn sprite(&self, glyph: char, color: Color) -> Sprite {
+        Sprite {
+            glyph,
+            color,
+            wide: self.wide,
+        }
+    }
+}
+
+impl Display {
+    pub fn sprites(self) -> Sprites {
+        if self.ascii {
+            Sprites {
+                bird: '>',
+                pipe: '#',
+                crash: '>',
+                rival: ':',
+                coast: '.',
+                flap: 'o',
+                wide: false,
+                ground: "=========,==",
+                ceiling: "------------",
+            }
+        } else if self.emoji {
+            Sprites {
+                bird: '🐤',
+                pipe: '🌵',
+                crash: '💥',
+                rival: '┊',
+                coast: '·',
+                flap: '∘',
+                wide: true,
+                ground: "▀▀▀▀▀▀▀▀▀▄▀▀",
+                ceiling: "▄▄▄▄▄▄▄▄▄▄▄▄",
+            }
+        } else {
+            Sprites {
+                bird: '@',
+                pipe: '█',
+                crash: '@',
+                rival: '┊',
+                coast: '·',
+                flap: '∘',
+                wide: false,
+                ground: "▀▀▀▀▀▀▀▀▀▄▀▀",
+                ceiling: "▄▄▄▄▄▄▄▄▄▄▄▄",
+            }
+        }
+    }
+}
+
+impl Default for Display {
+    fn default() -> Self {
+        Self {
+            ascii: false,
+            emoji: false,
+            colors: ColorDepth::TrueColor,
+            zoom: Zoom::One,
+        }
+    }
+}
+
+/// Whether a glyph is a solid shape that should fill all of a zoomed cell, rather than a
+/// letter or symbol drawn once in its corner to stay readable.
+fn fills_cell(ch: char) -> bool {
+    matches!(ch, '\u{2500}'..='\u{259f}' | '#' | '=' | '-' | '_')
+}
+
+/// The closest plain ASCII character to a glyph the game draws.
+fn ascii_glyph(ch: char) -> char {
+    match ch {
+        _ if ch.is_ascii() => ch,
+        '█' | '▀' | '▄' | '■' | '▇' | '▐' | '▌' | '▓' => '#',
+        '▅' | '▆' => '=',
+        '═' | '━' | '─' | '▃' => '-',
+        '▁' | '▂' => '_',
+        '║' | '│' => '|',
+        '╔' | '╗' | '╚' | '╝' | '┌' | '┐' | '└' | '┘' | '┼' | '╠' | '╣' | '╬' => {
+            '+'
+        }
+        '▒' => '%',
+        '┊' | '░' => ':',
+        '·' => '.',
+        '∘' | '●' | '♡' => 'o',
+        '★' | '♥' => '*',
+        '→' | '►' => '>',
+        '←' => '<',
+        '↑' | '▲' => '^',
+        '↓' | '▼' => 'v',
+        _ => '?',
+    }
+}
+
+/// Bring a color down to what the terminal can show. Named colors are left to the
+/// terminal's own palette.
+fn downsample(color: Color, depth: ColorDepth) -> Color {
+    let Color::Rgb { r, g, b } = color else {
+        return color;
+    };
+    match depth {
+        ColorDepth::TrueColor => color,
+        ColorDepth::Ansi256 => {
+            // The 6x6x6 color cube starts at index 16
+            let level = |c: u8| (c as u16 * 5 / 255) as u8;
+            Color::AnsiValue(16 + 36 * level(r) + 6 * level(g) + level(b))
+        }
+        ColorDepth::Ansi16 => {
+            const PALETTE: [Color; 16] = [
+                Color::Black,
+                Color::DarkGrey,
+                Color::Red,
+                Color::DarkRed,
+                Color::Green,
+                Color::DarkGreen,
+                Color::Yellow,
+                Color::DarkYellow,
+                Color::Blue,
+                Color::DarkBlue,
+                Color::Magenta,
+                Color::DarkMagenta,
+                Color::Cyan,
+                Color::DarkCyan,
+                Color::Grey,
+                Color::White,
+            ];
+            let distance = |c: &Color| {
+                let (pr, pg, pb) = to_rgb(*c);
+                let d = |a: u8, b: u8| (a as i32 - b as i32).pow(2);
+                d(r, pr) + d(g, pg) + d(b, pb)
+            };
+            PALETTE
+                .into_iter()
+                .min_by_key(distance)
+                .unwrap_or(Color::White)
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub struct Cell {
+    pub ch: char,
+    pub fg: Color,
+    /// Light level from 0.0 (black) to 1.0 (the color as-is).
+    pub brightness: f32,
+}
+
+impl Cell {
+    const BLANK: Cell = Cell {
+        ch: ' ',
+        fg: Color::Reset,
+        brightness: 1.0,
+    };
+}
+
+/// Fills the right half of a two-column glyph. Nothing is printed for it, since the
+/// terminal has already moved past it.
+const WIDE_TAIL: char = '\0';
+
+/// An off-screen grid of cells that a whole frame is drawn into before it is
+/// written to the terminal in one pass.
+#[derive(Clone)]
+pub struct Frame {
+    width: u16,
+    height: u16,
+    cells: Vec<Cell>,
+}
+
+impl Frame {
+    pub fn new(width: u16, height: u16) -> Self {
+        Self {
+            width,
+            height,
+            cells: vec![Cell::BLANK; width as usize * height as usize],
+        }
+    }
+
+    pub fn width(&self) -> u16 {
+        self.width
+    }
+
+    pub fn height(&self) -> u16 {
+        self.height
+    }
+
+    fn index(&self, x: i32, y: i32) -> Option<usize> {
+        if x < 0 || y < 0 || x >= self.width as i32 || y >= self.height as i32 {
+            return None;
+        }
+        Some(y as usize * self.width as usize + x as usize)
+    }
+
+    /// Set a single cell, silently clipping anything outside the frame.
+    pub fn put(&mut self, x: i32, y: i32, ch: char, fg: Color) {
+        if let Some(i) = self.index(x, y) {
+            self.split_wide(x, y);
+            self.cells[i] = Cell {
+                ch,
+                fg,
+                brightness: 1.0,
+            };
+        }
+    }
+
+    /// Set a glyph that takes this cell and the next. Half of one clipped by an edge of
+    /// the frame is left blank.
+    pub fn put_wide(&mut self, x: i32, y: i32, ch: char, fg: Color) {
+        match (self.index(x, y), self.index(x + 1, y)) {
+            (Some(_), Some(tail)) => {
+                self.put(x, y, ch, fg);
+                self.split_wide(x + 1, y);
+                self.cells[tail] = Cell {
+                    ch: WIDE_TAIL,
+                    fg,
+                    brightness: 1.0,
+                };
+            }
+            (Some(_), None) => self.put(x, y, ' ', fg),
+            (None, Some(_)) => self.put(x + 1, y, ' ', fg),
+            (None, None) => {}
+        }
+    }
+
+    /// Blank whichever half of a two-column glyph is left behind when the other half of it
+    /// at (x, y) is drawn over.
+    fn split_wide(&mut self, x: i32, y: i32) {
+        let Some(i) = self.index(x, y) else {
+            return;
+        };
+        let partner = if self.cells[i].ch == WIDE_TAIL {
+            self.index(x - 1, y)
+        } else {
+            self.index(x + 1, y)
+                .filter(|&next| self.cells[next].ch == WIDE_TAIL)
+        };
+        if let Some(partner) = partner {
+            self.cells[partner].ch = ' ';
+        }
+    }
+
+    /// Write `s` from `x` rightwards, with any right-to-left text put in visual order
+    /// and wide letters taking two cells.
+    pub fn put_str(&mut self, mut x: i32, y: i32, s: &str, fg: Color) {
+        for ch in bidi::visual(s).chars() {
+            match bidi::char_width(ch) {
+                0 => {}
+                1 => self.put(x, y, ch, fg),
+                _ => self.put_wide(x, y, ch, fg),
+            }
+            x += bidi::char_width(ch) as i32;
+        }
+    }
+
+    /// Scale the brightness of `width` cells from (x, y) rightwards by `light`, clipping
+    /// anything outside the frame.
+    pub fn dim(&mut self, x: i32, y: i32, width: i32, light: f32) {
+        for x in x..x + width {
+            if let Some(i) = self.index(x, y) {
+                self.cells[i].brightness *= light.clamp(0.0, 1.0);
+            }
+        }
+    }
+
+    /// Copy all of `other` in with its top left corner at (x, y), clipping anything
+    /// outside this frame.
+    pub fn blit(&mut self, other: &Frame, x: i32, y: i32) {
+        for row in 0..other.height as i32 {
+            for column in 0..other.width as i32 {
+                if let Some(i) = self.index(x + column, y + row) {
+                    self.split_wide(x + column, y + row);
+                    self.cells[i] =
+                        other.cells[row as usize * other.width as usize + column as usize];
+                }
+            }
+        }
+    }
+
+    /// The `width` by `height` block of the frame with its top left at (`x`, `y`),
+    /// blank where it runs off the edge. A two-column glyph cut in half by the left edge is
+    /// dropped with its tail.
+    pub fn crop(&self, x: u16, y: u16, width: u16, height: u16) -> Frame {
+        let mut part = Frame::new(width, height);
+        for row in 0..height {
+            for column in 0..width {
+                if let Some(i) = self.index(i32::from(x + column), i32::from(y + row)) {
+                    let cell = self.cells[i];
+                    if column == 0 && cell.ch == WIDE_TAIL {
+                        continue;
+                    }
+                    part.cells[row as usize * width as usize + column as usize] = cell;
+                }
+            }
+        }
+        part
+    }
+
+    /// The frame shrunk `factor` times each way, each cell standing for the first thing
+    /// drawn in its block. Two-column glyphs become one-column blocks so the small frame
+    /// keeps its layout.
+    pub fn shrink(&self, factor: u16) -> Frame {
+        let factor = factor.max(1);
+        let mut small = Frame::new(self.width / factor, self.height / factor);
+        for y in 0..small.height {
+            for x in 0..small.width {
+                let block = (0..factor)
+                    .flat_map(|dy| (0..factor).map(move |dx| (x * factor + dx, y * factor + dy)));
+                let drawn = block
+                    .map(|(bx, by)| {
+                        (
+                            bx,
+                            by,
+                            self.cells[by as usize * self.width as usize + bx as usize],
+                        )
+                    })
+                    .find(|(_, _, cell)| cell.ch != ' ' && cell.ch != WIDE_TAIL);
+                if let Some((bx, by, mut cell)) = drawn {
+                    let next = self.index(i32::from(bx) + 1, i32::from(by));
+                    if next.is_some_and(|next| self.cells[next].ch == WIDE_TAIL) {
+                        cell.ch = '■';
+                    }
+                    small.cells[y as usize * small.width as usize + x as usize] = cell;
+                }
+            }
+        }
+        small
+    }
+
+    /// The frame drawn at `zoom`. Solid glyphs fill their whole block, two-column glyphs
+    /// repeat in pairs, and anything else sits in the block's top left cell.
+    pub fn grow(&self, zoom: Zoom) -> Frame {
+        let (columns, rows) = zoom.cells();
+        let mut big = Frame::new(self.width * columns, self.height * rows);
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let cell = self.cells[y as usize * self.width as usize + x as usize];
+                if cell.ch == WIDE_TAIL {
+                    continue;
+                }
+                let wide = self
+                    .index(i32::from(x) + 1, i32::from(y))
+                    .is_some_and(|next| self.cells[next].ch == WIDE_TAIL);
+                let span = if wide { columns * 2 } else { columns };
+                for dy in 0..rows {
+                    for dx in 0..span {
+                        let mut out = cell;
+                        if wide && dx % 2 == 1 {
+                            out.ch = WIDE_TAIL;
+                        } else if !wide && !fills_cell(cell.ch) && (dx > 0 || dy > 0) {
+                            out.ch = ' ';
+                        }
+                        let (bx, by) = (x * columns + dx, y * rows + dy);
+                        big.cells[by as usize * big.width as usize + bx as usize] = out;
+                    }
+                }
+            }
+        }
+        big
+    }
+
+    /// Scale every cell's brightness by `light(x, y)`.
+    pub fn apply_lighting(&mut self, light: impl Fn(i32, i32) -> f32) {
+        for y in 0..self.height as i32 {
+            for x in 0..self.width as i32 {
+                let i = y as usize * self.width as usize + x as usize;
+                self.cells[i].brightness *= light(x, y).clamp(0.0, 1.0);
+            }
+        }
+    }
+
+    /// Run every cell's color through `change`.
+    pub fn recolor(&mut self, change: impl Fn(Color) -> Color) {
+        for cell in &mut self.cells {
+            cell.fg = change(cell.fg);
+        }
+    }
+
+    /// The frame as plain lines of text, without colors or trailing spaces, for tests to
+    /// read the screen.
+    #[cfg(test)]
+    pub fn to_text(&self) -> String {
+        let mut out = String::new();
+        for y in 0..self.height {
+            let start = out.len();
+            for x in 0..self.width {
+                let cell = self.cells[y as usize * self.width as usize + x as usize];
+                if cell.ch != WIDE_TAIL {
+                    out.push(shown(cell).0);
+                }
+            }
+            let trimmed = start + out[start..].trim_end_matches(' ').len();
+            out.truncate(trimmed);
+            out.push('\n');
+        }
+        out
+    }
+
+    /// The frame as lines of text with true-color escapes, for a file to `cat` later
+    /// rather than this terminal, so nothing moves the cursor.
+    pub fn to_ansi(&self) -> String {
+        let mut out = String::new();
+        for y in 0..self.height {
+            let mut current = None;
+            // Whether the glyph just written was a wide one, covering its tail
+            let mut covered = false;
+            for x in 0..self.width {
+                let cell = self.cells[y as usize * self.width as usize + x as usize];
+                if cell.ch == WIDE_TAIL {
+                    if !covered {
+                        out.push(' ');
+                    }
+                    covered = false;
+                    continue;
+                }
+                let (ch, fg) = shown(cell);
+                covered = ch == cell.ch;
+                if ch != ' ' && current != Some(fg) {
+                    let (r, g, b) = to_rgb(fg);
+                    out.push_str(&format!("\x1b[38;2;{};{};{}m", r, g, b));
+                    current = Some(fg);
+                }
+                out.push(ch);
+            }
+            let trimmed = out.trim_end_matches(' ').len();
+            out.truncate(trimmed);
+            out.push_str("\x1b[0m\n");
+        }
+        out
+    }
+
+    /// The frame as RGB pixels on black, `cell_width` by `cell_height` for each cell:
+    /// blocks fill the part of the cell they cover, shades fill it dimmer, and anything
+    /// else is a dot of its color in the middle. Returns the width, the height and the
+    /// pixels row by row.
+    pub fn to_pixels(&self, cell_width: u32, cell_height: u32) -> (u32, u32, Vec<u8>) {
+        let (width, height) = (
+            self.width as u32 * cell_width,
+            self.height as u32 * cell_height,
+        );
+        let mut pixels = vec![0; width as usize * height as usize * 3];
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let mut cell = self.cells[y as usize * self.width as usize + x as usize];
+                // A wide glyph is drawn across both its cells, the tail taking the right half
+                let (start, span) = if cell.ch == WIDE_TAIL {
+                    match self.index(i32::from(x) - 1, i32::from(y)) {
+                        Some(head) => cell = self.cells[head],
+                        None => continue,
+                    }
+                    (0.5, 0.5)
+                } else if self
+                    .index(i32::from(x) + 1, i32::from(y))
+                    .is_some_and(|next| self.cells[next].ch == WIDE_TAIL)
+                {
+                    (0.0, 0.5)
+                } else {
+                    (0.0, 1.0)
+                };
+                let (ch, fg) = shown(cell);
+                if ch == ' ' {
+                    continue;
+                }
+                let (r, g, b) = to_rgb(fg);
+                for py in 0..cell_height {
+                    for px in 0..cell_width {
+                        let fx = start + span * (px as f32 + 0.5) / cell_width as f32;
+                        let fy = (py as f32 + 0.5) / cell_height as f32;
+                        let level = coverage(ch, fx, fy);
+                        if level <= 0.0 {
+                            continue;
+                        }
+                        let (ix, iy) = (x as u32 * cell_width + px, y as u32 * cell_height + py);
+                        let i = (iy as usize * width as usize + ix as usize) * 3;
+                        let scale = |c: u8| (c as f32 * level) as u8;
+                        pixels[i..i + 3].copy_from_slice(&[scale(r), scale(g), scale(b)]);
+                    }
+                }
+            }
+        }
+        (width, height, pixels)
+    }
+
+    pub fn flush(&self, out: &mut impl Write, display: Display) -> io::Result<()> {
+        self.flush_since(out, display, None)
+    }
+
+    /// Write the cells that differ from `previous`, the frame last written, or all of
+    /// them if there is none or it was another size.
+    pub fn flush_since(
+        &self,
+        out: &mut impl Write,
+        display: Display,
+        previous: Option<&Frame>,
+    ) -> io::Result<()> {
+        let previous = previous.filter(|p| p.width == self.width && p.height == self.height);
+        let changed = |i: usize| match previous {
+            Some(previous) => previous.cells[i] != self.cells[i],
+            None => true,
+        };
+        let mut current = None;
+        for y in 0..self.height {
+            let row = y as usize * self.width as usize;
+            let mut x = 0;
+            while x < self.width {
+                if !changed(row + x as usize) {
+                    x += 1;
+                    continue;
+                }
+                let mut end = x;
+                while end < self.width && changed(row + end as usize) {
+                    end += 1;
+                }
+                // A changed tail is printed by the glyph it belongs to
+                let start = if x > 0 && self.cells[row + x as usize].ch == WIDE_TAIL {
+                    x - 1
+                } else {
+                    x
+                };
+                self.print_run(out, display, y, start..end, &mut current)?;
+                x = end;
+            }
+        }
+        if previous.is_none() || current.is_some() {
+            queue!(out, ResetColor)?;
+        }
+        out.flush()
+    }
+
+    fn print_run(
+        &self,
+        out: &mut impl Write,
+        display: Display,
+        y: u16,
+        columns: std::ops::Range<u16>,
+        current: &mut Option<Color>,
+    ) -> io::Result<()> {
+        queue!(out, MoveTo(columns.start, y))?;
+        // Whether the glyph just printed went out as is, so a wide one covers its tail
+        let mut covered = false;
+        for x in columns {
+            let cell = self.cells[y as usize * self.width as usize + x as usize];
+            let (ch, fg) = shown(cell);
+            let fg = downsample(fg, display.colors);
+            let ch = if cell.ch == WIDE_TAIL {
+                if covered {
+                    covered = false;
+                    continue;
+                }
+                ' '
+            } else if display.ascii {
+                ascii_glyph(ch)
+            } else {
+                ch
+            };
+            covered = ch == cell.ch;
+            if *current != Some(fg) {
+                queue!(out, SetForegroundColor(fg))?;
+                *current = Some(fg);
+            }
+            queue!(out, Print(ch))?;
+        }
+        Ok(())
+    }
+}
+
+/// What the terminal is showing, so that on a slow link only the cells that changed are
+/// sent: the bird's column, the leading and trailing edges of the pipes, and the score.
+pub struct Screen {
+    shown: Option<Frame>,
+    /// Set from the start with `--low-bandwidth`, or once full redraws are seen to be slow.
+    pub changes_only: bool,
+    samples: u32,
+    slow: u32,
+}
+
+impl Screen {
+    pub fn new(changes_only: bool) -> Self {
+        Self {
+            shown: None,
+            changes_only,
+            samples: 0,
+            slow: 0,
+        }
+    }
+
+    pub fn present(
+        &mut self,
+        frame: Frame,
+        out: &mut impl Write,
+        display: Display,
+    ) -> io::Result<()> {
+        let frame = match display.zoom {
+            Zoom::One => frame,
+            zoom => frame.grow(zoom),
+        };
+        if self.changes_only {
+            frame.flush_since(out, display, self.shown.as_ref())?;
+        } else {
+            let started = Instant::now();
+            frame.flush(out, display)?;
+            self.samples += 1;
+            if started.elapsed() > SLOW_FLUSH {
+                self.slow += 1;
+            }
+            if self.samples == LINK_SAMPLES {
+                self.changes_only = self.slow * 2 > LINK_SAMPLES;
+                self.samples = 0;
+                self.slow = 0;
+            }
+        }
+        self.shown = Some(frame);
+        Ok(())
+    }
+
+    /// Forget what is on screen after it has been cleared, so the next frame is sent whole.
+    pub fn invalidate(&mut self) {
+        self.shown = None;
+    }
+}
+
+/// What a cell shows once its brightness is taken in: empty space if it's too dark to
+/// see, or its glyph in a dimmed color.
+fn shown(cell: Cell) -> (char, Color) {
+    if cell.brightness < MIN_VISIBLE {
+        (' ', Color::Reset)
+    } else if cell.brightness < 1.0 {
+        (cell.ch, dim(cell.fg, cell.brightness))
+    } else {
+        (cell.ch, cell.fg)
+    }
+}
+
+/// How much of its color a glyph puts at (`x`, `y`) across its cell, each from 0.0 at
+/// the top left to 1.0 at the bottom right.
+fn coverage(ch: char, x: f32, y: f32) -> f32 {
+    let lit = |on: bool| if on { 1.0 } else { 0.0 };
+    match ch {
+        '█' | '■' => 1.0,
+        '▀' => lit(y < 0.5),
+        '▌' => lit(x < 0.5),
+        '▐' => lit(x >= 0.5),
+        // Eighths of a block, up from the bottom
+        '▁'..='▇' => lit(y >= 1.0 - (ch as u32 - '▁' as u32 + 1) as f32 / 8.0),
+        '░' => 0.25,
+        '▒' => 0.5,
+        '▓' => 0.75,
+        '─' | '━' | '═' | '-' | '=' => lit((0.4..0.6).contains(&y)),
+        '│' | '║' | '┊' | '|' => lit((0.4..0.6).contains(&x)),
+        '_' => lit(y >= 0.85),
+        _ => lit((0.25..0.75).contains(&x) && (0.25..0.75).contains(&y)),
+    }
+}
+
+/// Darken a color towards black as a true-color value.
+fn dim(color: Color, brightness: f32) -> Color {
+    let (r, g, b) = to_rgb(color);
+    let scale = |c: u8| (c as f32 * brightness) as u8;
+    Color::Rgb {
+        r: scale(r),
+        g: scale(g),
+        b: scale(b),
+    }
+}
+
+/// Turn a color's hue around the color wheel by `degrees`, as a true-color value.
+pub fn shift_hue(color: Color, degrees: f32) -> Color {
+    let (r, g, b) = to_rgb(color);
+    let (sin, cos) = degrees.to_radians().sin_cos();
+    // Rotation about the grey axis of the RGB cube
+    let third = (1.0 - cos) / 3.0;
+    let root = (1.0f32 / 3.0).sqrt() * sin;
+    let (same, ahead, behind) = (cos + third, third - root, third + root);
+    let mix = |a: u8, b: u8, c: u8| {
+        (a as f32 * same + b as f32 * ahead + c as f32 * behind).clamp(0.0, 255.0) as u8
+    };
+    Color::Rgb {
+        r: mix(r, g, b),
+        g: mix(g, b, r),
+        b: mix(b, r, g),
+    }
+}
+
+pub fn to_rgb(color: Color) -> (u8, u8, u8) {
+    match color {
+        Color::Rgb { r, g, b } => (r, g, b),
+        Color::Black => (0, 0, 0),
+        Color::DarkGrey => (128, 128, 128),
+        Color::Red => (255, 85, 85),
+        Color::DarkRed => (170, 0, 0),
+        Color::Green => (85, 255, 85),
+        Color::DarkGreen => (0, 170, 0),
+        Color::Yellow => (255, 255, 85),
+        Color::DarkYellow => (170, 170, 0),
+        Color::Blue => (85, 85, 255),
+        Color::DarkBlue => (0, 0, 170),
+        Color::Magenta => (255, 85, 255),
+        Color::DarkMagenta => (170, 0, 170),
+        Color::Cyan => (85, 255, 255),
+        Color::DarkCyan => (0, 170, 170),
+        Color::Grey => (192, 192, 192),
+        _ => (255, 255, 255),
+    }
+}
+
+/// Downsample `values` into a one-line bar chart `width` cells wide.
+pub fn sparkline(values: &[u32], width: usize) -> String {
+    const BARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+    if values.is_empty() || width == 0 {
+        return String::new();
+    }
+
+    let max = values.iter().copied().max().unwrap_or(0).max(1);
+    let columns = width.min(values.len());
+    (0..columns)
+        .map(|i| {
+            // Take the last value in each bucket so the line ends on the final score
+            let end = (i + 1) * values.len() / columns;
+            let value = values[end - 1];
+            BARS[(value as usize * (BARS.len() - 1)) / max as usize]
+        })
+        .collect()
+}
+
+/// Frame `lines` in a double-line box, padding each to `inner` columns.
+pub fn boxed(lines: &[String], inner: usize) -> Vec<String> {
+    let mut out = Vec::with_capacity(lines.len() + 2);
+    out.push(format!("╔{}╗", "═".repeat(inner)));
+    for line in lines {
+        let len = bidi::width(line);
+        out.push(format!(
+            "║{}{}║",
+            line,
+            " ".repeat(inner.saturating_sub(len))
+        ));
+    }
+    out.push(format!("╚{}╝", "═".repeat(inner)));
+    out
+}
@@ -0,0 +1,63 @@
+//! Rewind mode: holding the rewind key runs the game back, a tick for every tick it's
+//! held, through a ring buffer of the run's last `TICKS` states.
+//!
+//! Rewinding drains a meter that starts full and holds `TICKS` of rewinding; each pipe
+//! passed puts `REFILL` back. The tick count carries on forward while rewinding, so a
+//! run's inputs stay in order and a replay rewinds at the same moments to the same
+//! states.
+
+use std::collections::VecDeque;
+
+/// States kept, and so the most the meter holds: 3 seconds of play.
+pub const TICKS: usize = 60;
+/// Ticks of rewinding each pipe passed earns back.
+const REFILL: u32 = 6;
+
+#[derive(Clone)]
+pub struct Rewind<T> {
+    states: VecDeque<T>,
+    meter: u32,
+}
+
+impl<T> Default for Rewind<T> {
+    fn default() -> Self {
+        Self {
+            states: VecDeque::new(),
+            meter: TICKS as u32,
+        }
+    }
+}
+
+impl<T> Rewind<T> {
+    /// Keep `state` as the latest to go back to, forgetting the oldest past `TICKS`.
+    pub fn record(&mut self, state: T) {
+        if self.states.len() == TICKS {
+            self.states.pop_front();
+        }
+        self.states.push_back(state);
+    }
+
+    /// Whether there's both meter and a state left to go back to.
+    pub fn can_rewind(&self) -> bool {
+        self.meter > 0 && !self.states.is_empty()
+    }
+
+    /// Go back a tick, using up a tick of the meter.
+    pub fn step_back(&mut self) -> Option<T> {
+        if !self.can_rewind() {
+            return None;
+        }
+        self.meter -= 1;
+        self.states.pop_back()
+    }
+
+    /// Earn some rewinding back for passing a pipe.
+    pub fn refill(&mut self) {
+        self.meter = (self.meter + REFILL).min(TICKS as u32);
+    }
+
+    /// How much of the meter is left, from 0.0 to 1.0.
+    pub fn fraction(&self) -> f32 {
+        self.meter as f32 / TICKS as f32
+    }
+}
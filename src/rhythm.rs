@@ -0,0 +1,91 @@
+//! Rhythm mode: pipes come in on the beats of a set tempo, and flaps on the beat score.
+//!
+//! The beat clock runs on game time, so slow motion slows the tempo with everything
+//! else and a replay hears the same beats. Pipes spawn every few beats, as many as keep
+//! them at least their usual spacing apart, and at a distance that brings each one to
+//! the bird on a beat. A flap within `WINDOW` of a beat earns `BEAT_BONUS`, once a beat.
+
+pub const MIN_BPM: u16 = 40;
+pub const MAX_BPM: u16 = 200;
+/// How near a beat a flap has to be, in ticks either side.
+const WINDOW: f32 = 1.0;
+pub const BEAT_BONUS: u32 = 1;
+
+#[derive(Clone)]
+pub struct BeatClock {
+    ticks_per_beat: f32,
+    /// Ticks since the last beat.
+    at: f32,
+    /// Beats since the run began.
+    beat: u32,
+    /// Beats since the last pipe, and how many there are between pipes.
+    since_pipe: u32,
+    per_pipe: u32,
+    /// The last beat a flap scored on.
+    scored: u32,
+}
+
+impl BeatClock {
+    pub fn new(bpm: u16, ticks_per_second: f32) -> Self {
+        Self {
+            ticks_per_beat: ticks_per_second * 60.0 / bpm as f32,
+            at: 0.0,
+            beat: 0,
+            since_pipe: 0,
+            per_pipe: 1,
+            // Not the run's first frame
+            scored: 0,
+        }
+    }
+
+    pub fn ticks_per_beat(&self) -> f32 {
+        self.ticks_per_beat
+    }
+
+    /// Move on `dt` ticks of game time. Returns whether a beat fell in them.
+    pub fn advance(&mut self, dt: f32) -> bool {
+        self.at += dt;
+        if self.at < self.ticks_per_beat {
+            return false;
+        }
+        self.at -= self.ticks_per_beat;
+        self.beat += 1;
+        self.since_pipe += 1;
+        true
+    }
+
+    /// On a beat, whether it's time for a pipe when they come every `per_pipe` beats.
+    pub fn pipe_due(&mut self, per_pipe: u32) -> bool {
+        self.per_pipe = per_pipe.max(1);
+        if self.since_pipe < self.per_pipe {
+            return false;
+        }
+        self.since_pipe = 0;
+        true
+    }
+
+    /// Whether a flap now is on a beat that hasn't been scored yet, marking it scored.
+    pub fn on_beat(&mut self) -> bool {
+        let (off, beat) = if self.at <= self.ticks_per_beat / 2.0 {
+            (self.at, self.beat)
+        } else {
+            (self.ticks_per_beat - self.at, self.beat + 1)
+        };
+        if off > WINDOW || beat == self.scored {
+            return false;
+        }
+        self.scored = beat;
+        true
+    }
+
+    /// Whether the moment is close enough to a beat to count.
+    pub fn is_near_beat(&self) -> bool {
+        self.at.min(self.ticks_per_beat - self.at) <= WINDOW
+    }
+
+    /// Which beat of the stretch between pipes it is, counting from 0, and how many
+    /// beats the stretch has.
+    pub fn bar(&self) -> (u32, u32) {
+        (self.since_pipe.min(self.per_pipe - 1), self.per_pipe)
+    }
+}
@@ -0,0 +1,68 @@
+use crate::challenge::Challenge;
+use crate::paths;
+use crate::storage;
+use std::fs;
+use std::path::PathBuf;
+
+/// Another player's best run to measure against.
+pub struct Rival {
+    pub name: String,
+    pub score: u32,
+    /// Ticks the rival's run lasted, which is also how far it got, or 0 if only the
+    /// score is known.
+    pub ticks: u32,
+}
+
+/// What a rival was registered from on the command line.
+pub enum RivalSource {
+    Score(u32),
+    Replay(Challenge),
+}
+
+impl RivalSource {
+    pub fn parse(value: &str) -> Result<Self, String> {
+        match value.parse() {
+            Ok(score) => Ok(RivalSource::Score(score)),
+            Err(_) => Challenge::decode(value).map(RivalSource::Replay),
+        }
+    }
+}
+
+fn get_rivals_path() -> Option<PathBuf> {
+    paths::file("rivals")
+}
+
+/// Rival names are one word so the file stays one rival per line.
+pub fn valid_name(name: &str) -> bool {
+    !name.is_empty() && name.len() <= 16 && !name.chars().any(char::is_whitespace)
+}
+
+pub fn load_rivals() -> Vec<Rival> {
+    let Some(content) = get_rivals_path().and_then(|path| fs::read_to_string(path).ok()) else {
+        return Vec::new();
+    };
+    content
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let name = fields.next()?.to_string();
+            let score = fields.next()?.parse().ok()?;
+            let ticks = fields.next()?.parse().ok()?;
+            Some(Rival { name, score, ticks })
+        })
+        .collect()
+}
+
+pub fn save_rivals(rivals: &[Rival]) -> std::io::Result<()> {
+    let content: String = rivals
+        .iter()
+        .map(|r| format!("{} {} {}\n", r.name, r.score, r.ticks))
+        .collect();
+    match get_rivals_path() {
+        Some(path) => storage::write_atomic(&path, &content),
+        None => Err(std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            "$HOME is not set",
+        )),
+    }
+}
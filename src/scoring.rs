@@ -0,0 +1,124 @@
+use crate::rhythm;
+
+/// Bonus for staying near the middle of the gap the whole way through a pipe.
+const CENTER_BONUS: u32 = 1;
+/// Bonus for passing a pipe without flapping inside it.
+const GLIDE_BONUS: u32 = 1;
+/// Bonus for brushing the very edge of the gap.
+const GRAZE_BONUS: u32 = 2;
+/// Pipes in a row before each further pipe earns a streak bonus.
+const STREAK_START: u32 = 5;
+const STREAK_BONUS: u32 = 1;
+/// Most points a single pipe can earn, with every bonus at once.
+const MAX_PIPE_POINTS: u32 = 1 + CENTER_BONUS + GLIDE_BONUS + GRAZE_BONUS + STREAK_BONUS;
+
+#[derive(Clone, Copy, PartialEq, Default)]
+pub enum ScoringMode {
+    Classic,
+    #[default]
+    Style,
+}
+
+impl ScoringMode {
+    pub fn key(self) -> &'static str {
+        match self {
+            ScoringMode::Classic => "classic",
+            ScoringMode::Style => "style",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "classic" => Some(ScoringMode::Classic),
+            "style" => Some(ScoringMode::Style),
+            _ => None,
+        }
+    }
+
+    /// Most points a single pipe can earn.
+    pub fn max_pipe_points(self) -> u32 {
+        match self {
+            ScoringMode::Classic => 1,
+            ScoringMode::Style => MAX_PIPE_POINTS,
+        }
+    }
+}
+
+/// The scoring rules in words, for the help screen.
+pub fn rules() -> Vec<String> {
+    vec![
+        "Every pipe passed: +1".to_string(),
+        format!("Center: +{} for staying near the middle", CENTER_BONUS),
+        format!("Glide: +{} for not flapping inside a pipe", GLIDE_BONUS),
+        format!("Graze: +{} for brushing the edge of the gap", GRAZE_BONUS),
+        format!(
+            "Streak: +{} per pipe after the {}th in a row",
+            STREAK_BONUS, STREAK_START
+        ),
+        format!(
+            "Beat: +{} for flapping on the beat in rhythm mode",
+            rhythm::BEAT_BONUS
+        ),
+        "Classic scoring counts pipes only".to_string(),
+    ]
+}
+
+/// How a single pipe was passed, collected while the bird was inside it.
+pub struct PipePass {
+    /// Closest the bird came to either gap edge, in rows.
+    pub min_clearance: u16,
+    /// Clearance from either edge when the bird sits exactly mid-gap.
+    pub center_clearance: u16,
+    pub flapped: bool,
+    /// The bird went through the pipe wall itself (while invincible).
+    pub clipped: bool,
+    /// Pipes passed in a row, including this one.
+    pub streak: u32,
+}
+
+/// Where a run's points came from.
+#[derive(Clone, Copy, Default)]
+pub struct ScoreBreakdown {
+    pub pipes: u32,
+    pub center: u32,
+    pub glide: u32,
+    pub graze: u32,
+    pub streak: u32,
+    /// Flaps on the beat in rhythm mode.
+    pub beat: u32,
+    pub penalty: u32,
+}
+
+impl ScoreBreakdown {
+    pub fn total(&self) -> u32 {
+        (self.pipes + self.center + self.glide + self.graze + self.streak + self.beat)
+            .saturating_sub(self.penalty)
+    }
+
+    /// Score a passed pipe and return the name of the flashiest bonus earned, if any.
+    pub fn record_pass(&mut self, mode: ScoringMode, pass: &PipePass) -> Option<&'static str> {
+        self.pipes += 1;
+        if mode == ScoringMode::Classic || pass.clipped {
+            return None;
+        }
+
+        let mut best = None;
+        if pass.streak > STREAK_START {
+            self.streak += STREAK_BONUS;
+            best = Some("STREAK");
+        }
+        if !pass.flapped {
+            self.glide += GLIDE_BONUS;
+            best = Some("GLIDE");
+        }
+        if pass.min_clearance + 1 >= pass.center_clearance {
+            self.center += CENTER_BONUS;
+            best = Some("CENTER");
+        }
+        if pass.min_clearance == 0 {
+            self.graze += GRAZE_BONUS;
+            best = Some("GRAZE");
+        }
+        best
+    }
+}
@@ -0,0 +1,14 @@
+use signal_hook::consts::SIGTERM;
+use std::io;
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+
+/// Set a flag on SIGTERM (and SIGHUP on Unix, sent when the terminal closes) so the
+/// main loop can leave through the normal shutdown path instead of dying mid-write.
+pub fn install() -> io::Result<Arc<AtomicBool>> {
+    let stop = Arc::new(AtomicBool::new(false));
+    signal_hook::flag::register(SIGTERM, Arc::clone(&stop))?;
+    #[cfg(unix)]
+    signal_hook::flag::register(signal_hook::consts::SIGHUP, Arc::clone(&stop))?;
+    Ok(stop)
+}
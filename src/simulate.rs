@@ -0,0 +1,144 @@
+//! `tflap simulate`: large batches of headless bot runs, summarised as JSON for tuning
+//! difficulty presets.
+//!
+//! Every run is seeded from the batch's seed range, so the same batch on the same build
+//! flies the same pipes. The easy bot's fumbles are the one thing left to chance.
+
+use crate::bot::Brain;
+use std::fmt::Write as _;
+use std::ops::Range;
+use std::thread;
+
+/// Longest a run may last by default, five minutes. The better bots can fly the gentler
+/// presets for ever, so runs that get this far are counted as capped.
+pub const DEFAULT_MAX_TICKS: u32 = 6000;
+pub const DEFAULT_RUNS: usize = 1000;
+
+/// What `tflap simulate` was asked to run.
+pub struct Batch {
+    pub seeds: Range<u64>,
+    pub threads: usize,
+    pub max_ticks: u32,
+    /// Flies every run instead of the `--bot` tier.
+    pub brain: Option<Brain>,
+}
+
+impl Batch {
+    pub fn runs(&self) -> usize {
+        (self.seeds.end - self.seeds.start) as usize
+    }
+}
+
+/// All threads going, for when none were asked for.
+pub fn default_threads() -> usize {
+    thread::available_parallelism().map_or(1, |n| n.get())
+}
+
+/// `f` of each item, in order, with the items split between `threads`.
+pub fn parallel_map<I: Sync, T: Send>(
+    items: &[I],
+    threads: usize,
+    f: &(impl Fn(&I) -> T + Sync),
+) -> Vec<T> {
+    let chunk = items.len() / threads.max(1) + 1;
+    thread::scope(|scope| {
+        let workers: Vec<_> = items
+            .chunks(chunk)
+            .map(|items| scope.spawn(move || items.iter().map(f).collect::<Vec<_>>()))
+            .collect();
+        workers
+            .into_iter()
+            .flat_map(|worker| worker.join().expect("simulation thread panicked"))
+            .collect()
+    })
+}
+
+/// How one run went.
+#[derive(Clone, Copy)]
+pub struct Outcome {
+    pub score: u32,
+    pub ticks: u32,
+}
+
+/// Fly every run in `batch` with `fly` and summarise them as JSON. `labels` go in first,
+/// to say what was run.
+pub fn run(batch: &Batch, labels: &[(&str, &str)], fly: impl Fn(u64) -> Outcome + Sync) -> String {
+    let seeds: Vec<u64> = batch.seeds.clone().collect();
+    let outcomes = parallel_map(&seeds, batch.threads, &|&seed| fly(seed));
+    summarise(batch, labels, &outcomes)
+}
+
+/// The score `p` percent of the way up `sorted`.
+pub fn percentile(sorted: &[u32], p: usize) -> u32 {
+    let index = (sorted.len() * p / 100).min(sorted.len().saturating_sub(1));
+    sorted.get(index).copied().unwrap_or(0)
+}
+
+fn summarise(batch: &Batch, labels: &[(&str, &str)], outcomes: &[Outcome]) -> String {
+    let mut scores: Vec<u32> = outcomes.iter().map(|outcome| outcome.score).collect();
+    scores.sort_unstable();
+    let runs = scores.len().max(1);
+    let mean =
+        |values: &mut dyn Iterator<Item = u32>| values.map(f64::from).sum::<f64>() / runs as f64;
+    let percentile = |p| percentile(&scores, p);
+    let best = scores.last().copied().unwrap_or(0);
+    let capped = outcomes
+        .iter()
+        .filter(|outcome| outcome.ticks >= batch.max_ticks)
+        .count();
+
+    let mut out = String::from("{\n");
+    for (key, value) in labels {
+        let _ = writeln!(out, "  \"{}\": \"{}\",", key, value);
+    }
+    let _ = writeln!(out, "  \"runs\": {},", scores.len());
+    let _ = writeln!(
+        out,
+        "  \"seeds\": [{}, {}],",
+        batch.seeds.start, batch.seeds.end
+    );
+    let _ = writeln!(out, "  \"max_ticks\": {},", batch.max_ticks);
+    let _ = writeln!(out, "  \"capped\": {},", capped);
+    let _ = writeln!(
+        out,
+        "  \"score\": {{\"mean\": {:.3}, \"min\": {}, \"p10\": {}, \"p25\": {}, \"median\": {}, \"p75\": {}, \"p90\": {}, \"max\": {}}},",
+        mean(&mut scores.iter().copied()),
+        scores.first().copied().unwrap_or(0),
+        percentile(10),
+        percentile(25),
+        percentile(50),
+        percentile(75),
+        percentile(90),
+        best
+    );
+    let _ = writeln!(
+        out,
+        "  \"mean_ticks\": {:.1},",
+        mean(&mut outcomes.iter().map(|outcome| outcome.ticks))
+    );
+
+    // Runs ending on each score, and the share still flying as each pipe comes up
+    let mut counts = vec![0usize; best as usize + 1];
+    for &score in &scores {
+        counts[score as usize] += 1;
+    }
+    let distribution: Vec<String> = counts
+        .iter()
+        .enumerate()
+        .filter(|(_, &count)| count > 0)
+        .map(|(score, count)| format!("[{}, {}]", score, count))
+        .collect();
+    let _ = writeln!(out, "  \"distribution\": [{}],", distribution.join(", "));
+    let mut alive = scores.len();
+    let survival: Vec<String> = counts
+        .iter()
+        .map(|count| {
+            let share = alive as f64 / runs as f64;
+            alive -= count;
+            format!("{:.4}", share)
+        })
+        .collect();
+    let _ = writeln!(out, "  \"survival\": [{}]", survival.join(", "));
+    out.push_str("}\n");
+    out
+}
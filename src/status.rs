@@ -0,0 +1,53 @@
+//! A one-line status file for a terminal multiplexer's status bar.
+//!
+//! The file holds the current score and state, e.g. `tflap 12 (best 30)`, and is removed
+//! when the game exits so the status bar clears. A tmux status line can show it with
+//! `set -g status-right '#(cat ~/.tflap_status 2>/dev/null)'` and a short
+//! `status-interval`. Writes happen on a background thread, so a slow disk never
+//! holds up a frame.
+
+use crate::storage;
+use std::path::PathBuf;
+use std::sync::mpsc::{self, Sender};
+use std::thread::{self, JoinHandle};
+
+pub struct StatusExporter {
+    tx: Sender<String>,
+    writer: JoinHandle<()>,
+    /// The line last handed to the writer, so unchanged ticks cost nothing.
+    last: String,
+}
+
+impl StatusExporter {
+    pub fn start(path: PathBuf) -> Self {
+        let (tx, rx) = mpsc::channel::<String>();
+        let writer = thread::spawn(move || {
+            while let Ok(mut line) = rx.recv() {
+                // Only the newest line matters when several queue up
+                while let Ok(newer) = rx.try_recv() {
+                    line = newer;
+                }
+                let _ = storage::write_atomic(&path, &format!("{}\n", line));
+            }
+            let _ = storage::remove(&path);
+        });
+        Self {
+            tx,
+            writer,
+            last: String::new(),
+        }
+    }
+
+    pub fn publish(&mut self, line: String) {
+        if line != self.last {
+            let _ = self.tx.send(line.clone());
+            self.last = line;
+        }
+    }
+
+    /// Stop the writer and remove the file.
+    pub fn finish(self) {
+        drop(self.tx);
+        let _ = self.writer.join();
+    }
+}
@@ -0,0 +1,193 @@
+//! Writing save files safely, and the versions of the ones that hold scores and stats.
+//! Everything the game writes to disk goes through here, so `--no-save`, or a data
+//! directory that can't be written to, can turn it all off in one place.
+//!
+//! A versioned file starts with a `# tflap <name> v<N>` line; files from before there
+//! were versions have none and count as version 0. Reading an older file takes it
+//! through each migration step up to the current version and writes it back, once a
+//! copy of the old one is safe at `<file>.v<N>.bak`. A file from a newer tflap is read
+//! as far as this one understands it but never written over, so going back a version
+//! doesn't lose anything the newer one kept.
+
+use crate::paths::{self, Paths};
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Why saving is off, set once at startup, after which every write fails with it
+/// without touching the disk.
+static WRITES_OFF: OnceLock<String> = OnceLock::new();
+
+/// Turn off every write for the rest of the session, saying `why` to anything that
+/// tries one.
+pub fn disable_writes(why: String) {
+    let _ = WRITES_OFF.set(why);
+}
+
+fn check_writable() -> io::Result<()> {
+    match WRITES_OFF.get() {
+        Some(why) => Err(io::Error::new(io::ErrorKind::PermissionDenied, why.clone())),
+        None => Ok(()),
+    }
+}
+
+/// Check there's a data directory and a file can be made in it, by making and removing
+/// one. Returns why not if not, in a few words.
+pub fn probe() -> Result<(), String> {
+    let paths = Paths::get().ok_or("$HOME is not set")?;
+    let probe = paths.file("probe");
+    fs::write(&probe, "")
+        .and_then(|()| fs::remove_file(&probe))
+        .map_err(|_| {
+            let dir = if paths::has_data_dir() {
+                "--data-dir"
+            } else {
+                "$HOME"
+            };
+            format!("{} is read-only", dir)
+        })
+}
+
+/// Write `content` to `path` as it is, for files that are only ever written whole by
+/// someone asking for them.
+pub fn write(path: &Path, content: impl AsRef<[u8]>) -> io::Result<()> {
+    check_writable()?;
+    fs::write(path, content)
+}
+
+pub fn remove(path: &Path) -> io::Result<()> {
+    check_writable()?;
+    fs::remove_file(path)
+}
+
+/// A kind of versioned save file.
+pub struct Format {
+    pub name: &'static str,
+    /// `migrations[n]` turns the contents of a version `n` file into version `n + 1`,
+    /// so the current version is how many steps there are.
+    pub migrations: &'static [fn(&str) -> String],
+}
+
+impl Format {
+    pub fn version(&self) -> usize {
+        self.migrations.len()
+    }
+
+    fn header(&self) -> String {
+        format!("# tflap {} v{}\n", self.name, self.version())
+    }
+
+    /// The version a file's `content` is in, and what's under its header brought up to
+    /// the current version.
+    pub fn migrate(&self, content: &str) -> (usize, String) {
+        let (version, body) = self.split(content);
+        let steps = self.migrations.get(version..).unwrap_or_default();
+        let body = steps
+            .iter()
+            .fold(body.to_string(), |body, step| step(&body));
+        (version, body)
+    }
+
+    /// The version a file's contents are in, and the contents under the header.
+    fn split<'a>(&self, content: &'a str) -> (usize, &'a str) {
+        let prefix = format!("# tflap {} v", self.name);
+        let Some(rest) = content.strip_prefix(&prefix) else {
+            return (0, content);
+        };
+        let (version, body) = rest.split_once('\n').unwrap_or((rest, ""));
+        match version.trim().parse() {
+            Ok(version) => (version, body),
+            Err(_) => (0, content),
+        }
+    }
+}
+
+/// A versioned file's contents, in the current version's format as far as they go.
+pub struct Loaded {
+    pub body: String,
+    /// False for a file from a newer tflap, which mustn't be written over.
+    pub writable: bool,
+}
+
+/// Read the versioned file at `path`, migrating it on disk if it's older than `format`.
+/// None if it isn't there or can't be read.
+pub fn read_versioned(path: &Path, format: &Format) -> Option<Loaded> {
+    // A stray byte that isn't UTF-8 costs its line, not the whole file
+    let content = fs::read(path).ok()?;
+    let (version, body) = format.migrate(&String::from_utf8_lossy(&content));
+    if version >= format.version() {
+        return Some(Loaded {
+            body,
+            writable: version == format.version(),
+        });
+    }
+    // Only a file with its old version safely kept is written over
+    let writable = backup(path, version).is_ok() && write_versioned(path, format, &body).is_ok();
+    Some(Loaded { body, writable })
+}
+
+/// Copy the version `version` file at `path` aside before it's migrated. An earlier
+/// backup of the same version is left as it is.
+fn backup(path: &Path, version: usize) -> io::Result<PathBuf> {
+    let mut name = path.as_os_str().to_owned();
+    name.push(format!(".v{}.bak", version));
+    let backup = PathBuf::from(name);
+    check_writable()?;
+    if !backup.exists() {
+        fs::copy(path, &backup)?;
+    }
+    Ok(backup)
+}
+
+/// Write `body` to `path` under the current version's header.
+pub fn write_versioned(path: &Path, format: &Format, body: &str) -> io::Result<()> {
+    write_atomic(path, &(format.header() + body))
+}
+
+/// Add `line` to the end of the versioned file at `path`, starting it if it isn't there
+/// and migrating it first if it's older. A file from a newer tflap is left alone.
+pub fn append_versioned(path: &Path, format: &Format, line: &str) -> io::Result<()> {
+    check_writable()?;
+    if let Some(loaded) = read_versioned(path, format) {
+        if !loaded.writable {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{} is from a newer tflap", path.display()),
+            ));
+        }
+    } else if !path.exists() {
+        fs::write(path, format.header())?;
+    }
+    let mut file = OpenOptions::new().append(true).open(path)?;
+    writeln!(file, "{}", line)
+}
+
+/// Write `content` to a temporary file next to `path` and rename it into place, so the
+/// old file survives intact if the game is killed part way through.
+pub fn write_atomic(path: &Path, content: &str) -> io::Result<()> {
+    check_writable()?;
+    replace(path, content)
+}
+
+/// `write_atomic` for a file named on the command line, which is written even with
+/// saving off: asking for it by name is asking for it to be written.
+pub fn write_requested(path: &Path, content: &str) -> io::Result<()> {
+    replace(path, content)
+}
+
+fn replace(path: &Path, content: &str) -> io::Result<()> {
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".tmp");
+    fs::write(&tmp, content)?;
+    fs::rename(&tmp, path)
+}
+
+/// Seconds since the Unix epoch, or 0 if the clock is before it.
+pub fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
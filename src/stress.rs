@@ -0,0 +1,124 @@
+//! Timings for `tflap --stress`, which fills the screen with bot-flown birds and close-set
+//! pipes to load the update loop and renderer, then reports how each held up.
+
+use crate::pacer::Jitter;
+use std::fmt::Write as _;
+use std::io::{self, Write};
+use std::time::{Duration, Instant};
+
+#[derive(Default)]
+pub struct Timing {
+    count: u32,
+    total: Duration,
+    max: Duration,
+}
+
+impl Timing {
+    /// Run `f` and count how long it took.
+    pub fn time<T>(&mut self, f: impl FnOnce() -> T) -> T {
+        let start = Instant::now();
+        let result = f();
+        let elapsed = start.elapsed();
+        self.count += 1;
+        self.total += elapsed;
+        self.max = self.max.max(elapsed);
+        result
+    }
+
+    fn average(&self) -> Duration {
+        self.total.checked_div(self.count).unwrap_or_default()
+    }
+}
+
+pub struct Stats {
+    birds: usize,
+    started: Instant,
+    /// One per tick.
+    pub update: Timing,
+    /// Building each frame, and writing it out.
+    pub render: Timing,
+    pub flush: Timing,
+    pub bytes: u64,
+    pub crashes: u32,
+    /// How late the ticks ran.
+    pub jitter: Jitter,
+}
+
+impl Stats {
+    pub fn new(birds: usize) -> Self {
+        Self {
+            birds,
+            started: Instant::now(),
+            update: Timing::default(),
+            render: Timing::default(),
+            flush: Timing::default(),
+            bytes: 0,
+            crashes: 0,
+            jitter: Jitter::default(),
+        }
+    }
+
+    pub fn report(&self) -> String {
+        let seconds = self.started.elapsed().as_secs_f64();
+        let frames = self.flush.count;
+        let mut out = format!(
+            "Stress test: {} birds for {:.1}s, {} ticks, {} frames ({:.1} fps)\n",
+            self.birds,
+            seconds,
+            self.update.count,
+            frames,
+            frames as f64 / seconds.max(0.001)
+        );
+        for (name, timing) in [
+            ("update", &self.update),
+            ("render", &self.render),
+            ("flush", &self.flush),
+        ] {
+            let _ = writeln!(
+                out,
+                "  {:<7} avg {:>8.3} ms   max {:>8.3} ms",
+                name,
+                timing.average().as_secs_f64() * 1000.0,
+                timing.max.as_secs_f64() * 1000.0
+            );
+        }
+        let _ = writeln!(
+            out,
+            "  late    avg {:>8.3} ms   max {:>8.3} ms   {} resyncs",
+            self.jitter.mean().as_secs_f64() * 1000.0,
+            self.jitter.max.as_secs_f64() * 1000.0,
+            self.jitter.resyncs
+        );
+        let _ = writeln!(
+            out,
+            "  output  {:.1} KB per frame, {} crashes",
+            self.bytes as f64 / frames.max(1) as f64 / 1024.0,
+            self.crashes
+        );
+        out
+    }
+}
+
+/// Passes writes through while counting the bytes.
+pub struct Counter<'a, W: Write> {
+    inner: &'a mut W,
+    pub bytes: u64,
+}
+
+impl<'a, W: Write> Counter<'a, W> {
+    pub fn new(inner: &'a mut W) -> Self {
+        Self { inner, bytes: 0 }
+    }
+}
+
+impl<W: Write> Write for Counter<'_, W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        self.bytes += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
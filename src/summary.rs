@@ -0,0 +1,162 @@
+//! `--json-summary`: the session as JSON on the way out, for tournaments and statistics
+//! scripts run around the game: every run finished or abandoned, with its score, seed
+//! and category, and the settings it was all played with.
+
+use crate::history::Outcome;
+use crate::pacer::Jitter;
+use std::fmt::Write;
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// Where the summary goes.
+#[derive(Clone)]
+pub enum Destination {
+    Stdout,
+    File(PathBuf),
+}
+
+/// One run of the session.
+pub struct Run {
+    pub score: u32,
+    pub seed: u64,
+    pub ticks: u32,
+    pub outcome: Outcome,
+    /// The leaderboard category key it counted towards.
+    pub category: String,
+}
+
+/// The session so far.
+pub struct Summary<'a> {
+    pub player: Option<&'a str>,
+    /// How long the game was open.
+    pub open: Duration,
+    /// How late its ticks ran.
+    pub jitter: Jitter,
+    /// The best score in the last run's category, from before the session too.
+    pub best: u32,
+    pub runs: &'a [Run],
+    /// The config file's `key = value` lines in effect.
+    pub settings: String,
+}
+
+impl Summary<'_> {
+    pub fn to_json(&self) -> String {
+        let mut out = String::from("{\n");
+        let _ = writeln!(out, "  \"version\": {},", string(env!("CARGO_PKG_VERSION")));
+        let player = self.player.map_or_else(|| "null".to_string(), string);
+        let _ = writeln!(out, "  \"player\": {},", player);
+        let _ = writeln!(out, "  \"seconds\": {},", self.open.as_secs());
+        let _ = writeln!(
+            out,
+            "  \"ticks\": {{\"count\": {}, \"late_ms\": {{\"mean\": {:.3}, \"max\": {:.3}}}, \"resyncs\": {}}},",
+            self.jitter.ticks,
+            self.jitter.mean().as_secs_f64() * 1000.0,
+            self.jitter.max.as_secs_f64() * 1000.0,
+            self.jitter.resyncs
+        );
+        let _ = writeln!(out, "  \"runs_played\": {},", self.runs.len());
+        let _ = writeln!(out, "  \"best\": {},", self.best);
+        let settings: Vec<String> = self
+            .settings
+            .lines()
+            .filter_map(|line| line.split_once('='))
+            .map(|(key, value)| format!("{}: {}", string(key.trim()), string(value.trim())))
+            .collect();
+        let _ = writeln!(out, "  \"settings\": {{{}}},", settings.join(", "));
+        let runs: Vec<String> = self
+            .runs
+            .iter()
+            .map(|run| {
+                format!(
+                    "    {{\"score\": {}, \"seed\": {}, \"ticks\": {}, \"outcome\": {}, \"category\": {}}}",
+                    run.score,
+                    run.seed,
+                    run.ticks,
+                    string(run.outcome.key()),
+                    string(&run.category)
+                )
+            })
+            .collect();
+        if runs.is_empty() {
+            out.push_str("  \"runs\": []\n");
+        } else {
+            let _ = writeln!(out, "  \"runs\": [\n{}\n  ]", runs.join(",\n"));
+        }
+        out.push_str("}\n");
+        out
+    }
+}
+
+/// `text` as a JSON string.
+fn string(text: &str) -> String {
+    let mut out = String::from("\"");
+    for c in text.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            c if c < ' ' => {
+                let _ = write!(out, "\\u{:04x}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn summary<'a>(player: Option<&'a str>, runs: &'a [Run]) -> Summary<'a> {
+        Summary {
+            player,
+            open: Duration::from_secs(90),
+            jitter: Jitter::default(),
+            best: 12,
+            runs,
+            settings: "# tflap settings\nlives = off\nmusic = C:\\songs\\\"one\".ogg\n".to_string(),
+        }
+    }
+
+    #[test]
+    fn text_from_outside_is_escaped() {
+        let json = summary(Some("a\"b\\c\td"), &[]).to_json();
+        assert!(
+            json.contains("\"player\": \"a\\\"b\\\\c\\u0009d\","),
+            "{}",
+            json
+        );
+        assert!(
+            json.contains(
+                "\"settings\": {\"lives\": \"off\", \"music\": \"C:\\\\songs\\\\\\\"one\\\".ogg\"},"
+            ),
+            "{}",
+            json
+        );
+    }
+
+    #[test]
+    fn a_session_without_runs_has_an_empty_list() {
+        let json = summary(None, &[]).to_json();
+        assert!(json.contains("\"player\": null,"), "{}", json);
+        assert!(json.contains("\"runs_played\": 0,"), "{}", json);
+        assert!(json.ends_with("  \"runs\": []\n}\n"), "{}", json);
+
+        let runs = [Run {
+            score: 7,
+            seed: 42,
+            ticks: 300,
+            outcome: Outcome::Died,
+            category: "normal".to_string(),
+        }];
+        let json = summary(None, &runs).to_json();
+        assert!(
+            json.ends_with(
+                "  \"runs\": [\n    {\"score\": 7, \"seed\": 42, \"ticks\": 300, \"outcome\": \"died\", \"category\": \"normal\"}\n  ]\n}\n"
+            ),
+            "{}",
+            json
+        );
+    }
+}
@@ -0,0 +1,129 @@
+/// The run's state at the start of one tick.
+#[derive(Clone, PartialEq)]
+pub struct Sample {
+    pub score: u32,
+    pub y: f32,
+    pub velocity: f32,
+    /// Whether a flap came in just before this tick.
+    pub flapped: bool,
+    /// First and last open rows of the next gap the bird hasn't got past.
+    pub gap: Option<(u16, u16)>,
+}
+
+/// A player input that affects the simulation, kept so a run can be replayed.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Input {
+    Flap,
+    /// One-switch mode: a tap, and a press held long enough for a big flap.
+    SmallFlap,
+    BigFlap,
+    Revive(char),
+    /// Slow motion changed to this percentage of normal speed.
+    Speed(u8),
+    /// The score was banked, in banking mode.
+    Bank,
+    /// Double or nothing was taken, or turned down.
+    Wager(bool),
+    /// A quiz gate was answered with this digit.
+    Answer(u8),
+    /// The run went back a tick, in rewind mode.
+    Rewind,
+}
+
+/// What the bird crashed into.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Hit {
+    Pipe,
+    Ground,
+    Ceiling,
+}
+
+impl Hit {
+    pub fn label(self) -> &'static str {
+        match self {
+            Hit::Pipe => "a pipe",
+            Hit::Ground => "the ground",
+            Hit::Ceiling => "the ceiling",
+        }
+    }
+}
+
+/// How the bird went through one pipe.
+pub struct PassRecord {
+    pub min_clearance: u16,
+    pub center_clearance: u16,
+}
+
+/// Everything recorded about the current run, tick by tick.
+#[derive(Default)]
+pub struct RunTelemetry {
+    /// One for every tick simulated, in order.
+    pub samples: Vec<Sample>,
+    pub passes: Vec<PassRecord>,
+    /// Inputs keyed by the number of ticks simulated before they arrived.
+    pub inputs: Vec<(u32, Input)>,
+    /// Every crash that cost a heart or ended the run, in order.
+    pub hits: Vec<Hit>,
+}
+
+impl RunTelemetry {
+    pub fn ticks(&self) -> u32 {
+        self.samples.len() as u32
+    }
+
+    /// Score at the start of each tick.
+    pub fn scores(&self) -> Vec<u32> {
+        self.samples.iter().map(|sample| sample.score).collect()
+    }
+
+    /// The samples as CSV, one row per tick.
+    pub fn to_csv(&self) -> String {
+        let mut out = String::from("tick,score,y,velocity,flap,gap_top,gap_bottom\n");
+        for (tick, sample) in self.samples.iter().enumerate() {
+            let (top, bottom) = sample
+                .gap
+                .map_or((String::new(), String::new()), |(top, bottom)| {
+                    (top.to_string(), bottom.to_string())
+                });
+            out.push_str(&format!(
+                "{},{},{:.3},{:.3},{},{},{}\n",
+                tick,
+                sample.score,
+                sample.y,
+                sample.velocity,
+                u8::from(sample.flapped),
+                top,
+                bottom
+            ));
+        }
+        out
+    }
+
+    pub fn flaps(&self) -> usize {
+        self.inputs
+            .iter()
+            .filter(|(_, input)| matches!(input, Input::Flap | Input::SmallFlap))
+            .count()
+    }
+
+    pub fn hits_on(&self, hit: Hit) -> usize {
+        self.hits.iter().filter(|&&h| h == hit).count()
+    }
+
+    pub fn grazes(&self) -> usize {
+        self.passes.iter().filter(|p| p.min_clearance == 0).count()
+    }
+
+    /// Share of pipes passed near the middle of the gap, as a percentage.
+    pub fn accuracy(&self) -> u32 {
+        if self.passes.is_empty() {
+            return 0;
+        }
+        let centered = self
+            .passes
+            .iter()
+            .filter(|p| p.min_clearance + 1 >= p.center_clearance)
+            .count();
+        (centered * 100 / self.passes.len()) as u32
+    }
+}
@@ -0,0 +1,190 @@
+//! The ticker along the top of the HUD: what just happened in the run, from the event
+//! bus, scrolling in from the right a few columns a tick. A new item comes on at most
+//! every `RELEASE_TICKS`, and only the latest few wait their turn, so a burst of grazes
+//! can't push it far behind the run or scroll past faster than it can be read.
+
+use crate::bidi;
+use crate::events::GameEvent;
+use std::collections::VecDeque;
+
+/// Least time between items coming on, about three quarters of a second.
+const RELEASE_TICKS: u32 = 15;
+/// How long an item stays on the line.
+const SHOW_TICKS: u32 = 100;
+/// Items waiting their turn beyond this drop the oldest.
+const MAX_QUEUED: usize = 3;
+/// Columns the line scrolls each tick.
+const SCROLL_STEP: usize = 3;
+const SEPARATOR: &str = " · ";
+
+enum Item {
+    /// Grazes close together, counted as one.
+    Grazes(u32),
+    Text(String),
+}
+
+impl Item {
+    fn text(&self) -> String {
+        match self {
+            Item::Grazes(1) => "Graze".to_string(),
+            Item::Grazes(count) => format!("Graze x{}", count),
+            Item::Text(text) => text.clone(),
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct Ticker {
+    /// Scores that count as milestones.
+    milestones: Vec<u32>,
+    /// Waiting to come on, oldest first.
+    queue: VecDeque<Item>,
+    /// On the line, oldest first, with the ticks each has been on it.
+    shown: VecDeque<(String, u32)>,
+    /// Columns left to scroll before the newest item is all the way in.
+    offset: usize,
+    /// Ticks until the next item may come on.
+    wait: u32,
+}
+
+impl Ticker {
+    pub fn new(milestones: Vec<u32>) -> Self {
+        Self {
+            milestones,
+            ..Self::default()
+        }
+    }
+
+    pub fn on_event(&mut self, event: &GameEvent) {
+        let text = match event {
+            GameEvent::Scored { score } if self.milestones.contains(score) => {
+                format!("{} points!", score)
+            }
+            GameEvent::NearMiss => {
+                match self.queue.back_mut() {
+                    Some(Item::Grazes(count)) => *count += 1,
+                    _ => self.push(Item::Grazes(1)),
+                }
+                return;
+            }
+            GameEvent::PassedRival { name } => format!("Passed {}", bidi::isolate(name)),
+            GameEvent::GhostDown { score } => format!("Ghost down at {}", score),
+            GameEvent::OpponentDown { score } => format!("Opponent out at {}", score),
+            GameEvent::LeadChanged { ahead: true } => "You take the lead".to_string(),
+            GameEvent::LeadChanged { ahead: false } => "Opponent takes the lead".to_string(),
+            _ => return,
+        };
+        self.push(Item::Text(text));
+    }
+
+    fn push(&mut self, item: Item) {
+        if self.queue.len() == MAX_QUEUED {
+            self.queue.pop_front();
+        }
+        self.queue.push_back(item);
+    }
+
+    /// Scroll on a tick, age what's showing, and bring the next item on if it's time.
+    pub fn update(&mut self) {
+        self.offset = self.offset.saturating_sub(SCROLL_STEP);
+        self.wait = self.wait.saturating_sub(1);
+        for (_, ticks) in &mut self.shown {
+            *ticks += 1;
+        }
+        self.shown.retain(|&(_, ticks)| ticks < SHOW_TICKS);
+        if self.wait > 0 {
+            return;
+        }
+        if let Some(item) = self.queue.pop_front() {
+            let text = item.text();
+            if !self.shown.is_empty() {
+                self.offset += bidi::width(SEPARATOR);
+            }
+            self.offset += bidi::width(&text);
+            self.shown.push_back((text, 0));
+            self.wait = RELEASE_TICKS;
+        }
+    }
+
+    /// What fits of the line in `room` columns, to draw against the right of its space:
+    /// the end still to scroll in is cut off there, and the oldest items run off the left.
+    /// A wide letter half over either edge is left out, and a name cut short is still
+    /// isolated, so a cut can't leave half of an isolate pair to upset the rest.
+    pub fn line(&self, room: usize) -> String {
+        let texts: Vec<&str> = self.shown.iter().map(|(text, _)| text.as_str()).collect();
+        let letters = bidi::letters(&texts.join(SEPARATOR));
+
+        let mut end = letters.len();
+        let mut cut = 0;
+        while cut < self.offset && end > 0 {
+            end -= 1;
+            cut += bidi::char_width(letters[end].0);
+        }
+        // What's left of a wide letter cut in half keeps its column, so the line doesn't
+        // jump a column to the right
+        let gap = cut - cut.min(self.offset);
+        let mut start = end;
+        let mut used = gap.min(room);
+        while start > 0 && used + bidi::char_width(letters[start - 1].0) <= room {
+            start -= 1;
+            used += bidi::char_width(letters[start].0);
+        }
+
+        let mut line = bidi::rejoin(&letters[start..end]);
+        line.push_str(&" ".repeat(gap.min(room)));
+        line
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The line in `room` columns at every step of `name` scrolling in after an item
+    /// already on it.
+    fn scroll_in(name: &str, room: usize) -> Vec<String> {
+        let mut ticker = Ticker::new(vec![10]);
+        ticker.on_event(&GameEvent::Scored { score: 10 });
+        ticker.update();
+        ticker.wait = 0;
+        ticker.on_event(&GameEvent::PassedRival {
+            name: name.to_string(),
+        });
+        ticker.update();
+        let mut lines = Vec::new();
+        while ticker.offset > 0 {
+            lines.push(ticker.line(room));
+            ticker.offset -= 1;
+        }
+        lines.push(ticker.line(room));
+        lines
+    }
+
+    #[test]
+    fn a_wide_name_never_runs_past_its_room() {
+        for room in 0..30 {
+            for line in scroll_in("山田太郎", room) {
+                assert!(bidi::width(&line) <= room, "{:?} in {}", line, room);
+            }
+        }
+        assert_eq!(scroll_in("山田太郎", 12).last().unwrap(), "sed 山田太郎");
+        // Cut through the middle of a letter, the line keeps its place
+        let lines = scroll_in("山田太郎", 12);
+        assert_eq!(lines[lines.len() - 2], "ssed 山田太 ");
+    }
+
+    #[test]
+    fn a_name_cut_short_stays_isolated() {
+        for room in 0..30 {
+            for line in scroll_in("שלום", room) {
+                let marks: String = line
+                    .chars()
+                    .filter(|&ch| ch == bidi::ISOLATE || ch == bidi::POP)
+                    .collect();
+                let pairs = marks.chars().count() / 2;
+                let whole = format!("{}{}", bidi::ISOLATE, bidi::POP).repeat(pairs);
+                assert_eq!(marks, whole, "{:?} in {}", line, room);
+            }
+        }
+    }
+}
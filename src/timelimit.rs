@@ -0,0 +1,69 @@
+//! Daily time limits, for parents who want a game to stop at a set time each day.
+//!
+//! Time is counted in ticks spent in runs, so menus and pauses are free, and each player
+//! has their own allowance. A day runs from midnight to midnight UTC, like the dates on
+//! the leaderboard: the runs the history has from today count toward it, with the one
+//! in progress on top. Warnings come up in the last `WARN_MINUTES`, and once the time is
+//! spent the game locks until tomorrow.
+
+use crate::history::RunRecord;
+
+/// How long before the end the time left is shown.
+const WARN_MINUTES: u32 = 5;
+const DAY: u64 = 86_400;
+
+pub struct TimeLimit {
+    /// Ticks of play a day, and ticks already played today in finished runs.
+    allowed: u32,
+    used: u32,
+    ticks_per_minute: u32,
+    /// Days since the Unix epoch that `used` is for.
+    day: u64,
+}
+
+impl TimeLimit {
+    /// `minutes` a day, with what `runs` from the day of `now` have already used.
+    pub fn new(minutes: u32, ticks_per_minute: u32, runs: &[RunRecord], now: u64) -> Self {
+        let day = now / DAY;
+        let used = runs
+            .iter()
+            .filter(|run| run.timestamp / DAY == day)
+            .map(|run| run.ticks)
+            .sum();
+        Self {
+            allowed: minutes * ticks_per_minute,
+            used,
+            ticks_per_minute,
+            day,
+        }
+    }
+
+    /// Start the count again when `now` is a new day.
+    pub fn refresh(&mut self, now: u64) {
+        if now / DAY != self.day {
+            self.day = now / DAY;
+            self.used = 0;
+        }
+    }
+
+    /// Count a finished run of `ticks`.
+    pub fn ran(&mut self, ticks: u32) {
+        self.used = self.used.saturating_add(ticks);
+    }
+
+    /// Whether today's time is spent, with `ticks` of a run in progress.
+    pub fn is_over(&self, ticks: u32) -> bool {
+        self.used.saturating_add(ticks) >= self.allowed
+    }
+
+    /// Minutes left today, rounded up, once few enough to warn about.
+    pub fn warning(&self, ticks: u32) -> Option<u32> {
+        let left = self.allowed.checked_sub(self.used.saturating_add(ticks))?;
+        let minutes = left.checked_sub(1)? / self.ticks_per_minute + 1;
+        (minutes <= WARN_MINUTES).then_some(minutes)
+    }
+
+    pub fn minutes(&self) -> u32 {
+        self.allowed / self.ticks_per_minute
+    }
+}
@@ -0,0 +1,121 @@
+//! `tflap train`: evolving a `Brain` for the bot with a genetic algorithm.
+//!
+//! Each generation every brain flies the same few headless runs, spread over all the
+//! cores, and is scored on pipes passed. The best few go through unchanged and the rest
+//! are bred from tournament winners with a little noise. The best brain so far is saved
+//! whenever it improves, so stopping early still leaves something to watch.
+
+use crate::bot::{Brain, WEIGHTS};
+use crate::simulate;
+use crate::storage;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::io;
+use std::path::Path;
+
+/// Longest a training run may last, so a brain that has learned to fly forever still
+/// finishes. Two thousand ticks is about fifty pipes.
+pub const MAX_TICKS: u32 = 2000;
+pub const DEFAULT_GENERATIONS: u32 = 200;
+const POPULATION: usize = 40;
+/// Carried into the next generation as they are.
+const ELITES: usize = 4;
+/// Runs each brain flies per generation, on seeds shared by the whole generation.
+const RUNS: usize = 3;
+const TOURNAMENT: usize = 3;
+const MUTATION_CHANCE: f64 = 0.1;
+const MUTATION_SIZE: f32 = 0.3;
+
+/// Evolve brains for `generations`, scoring one on a seed with `fitness`, and save the
+/// best to `out`.
+pub fn run(
+    generations: u32,
+    out: &Path,
+    fitness: impl Fn(&Brain, u64) -> f32 + Sync,
+) -> io::Result<()> {
+    let mut rng = StdRng::from_entropy();
+    let mut population: Vec<Brain> = (0..POPULATION)
+        .map(|_| Brain {
+            weights: (0..WEIGHTS).map(|_| rng.gen_range(-1.0..1.0)).collect(),
+        })
+        .collect();
+    let mut best = f32::MIN;
+
+    for generation in 1..=generations {
+        let seeds: Vec<u64> = (0..RUNS).map(|_| rng.gen()).collect();
+        let scores = evaluate(&population, &seeds, &fitness);
+        let mut ranked: Vec<(f32, Brain)> = scores.into_iter().zip(population).collect();
+        ranked.sort_by(|a, b| b.0.total_cmp(&a.0));
+
+        let mean = ranked.iter().map(|(score, _)| score).sum::<f32>() / POPULATION as f32;
+        println!(
+            "generation {:>4}/{}  best {:>7.2}  mean {:>7.2}",
+            generation, generations, ranked[0].0, mean
+        );
+        if ranked[0].0 > best {
+            best = ranked[0].0;
+            storage::write_atomic(out, &ranked[0].1.to_json(generation, best))?;
+        }
+
+        population = ranked
+            .iter()
+            .take(ELITES)
+            .map(|(_, brain)| brain.clone())
+            .collect();
+        while population.len() < POPULATION {
+            let mother = select(&ranked, &mut rng);
+            let father = select(&ranked, &mut rng);
+            population.push(breed(mother, father, &mut rng));
+        }
+    }
+
+    println!("Best brain saved to {}", out.display());
+    Ok(())
+}
+
+/// Average score of each brain over `seeds`, with the population split across threads.
+fn evaluate(
+    population: &[Brain],
+    seeds: &[u64],
+    fitness: &(impl Fn(&Brain, u64) -> f32 + Sync),
+) -> Vec<f32> {
+    simulate::parallel_map(population, simulate::default_threads(), &|brain| {
+        let total: f32 = seeds.iter().map(|&seed| fitness(brain, seed)).sum();
+        total / seeds.len() as f32
+    })
+}
+
+/// The best of a few brains picked at random.
+fn select<'a>(ranked: &'a [(f32, Brain)], rng: &mut StdRng) -> &'a Brain {
+    // Ranked best first, so the lowest index drawn wins
+    let winner = (0..TOURNAMENT)
+        .map(|_| rng.gen_range(0..ranked.len()))
+        .min()
+        .unwrap_or(0);
+    &ranked[winner].1
+}
+
+/// Each weight from either parent, some of them nudged.
+fn breed(mother: &Brain, father: &Brain, rng: &mut StdRng) -> Brain {
+    let weights = mother
+        .weights
+        .iter()
+        .zip(&father.weights)
+        .map(|(&a, &b)| {
+            let weight = if rng.gen_bool(0.5) { a } else { b };
+            if rng.gen_bool(MUTATION_CHANCE) {
+                weight + gaussian(rng) * MUTATION_SIZE
+            } else {
+                weight
+            }
+        })
+        .collect();
+    Brain { weights }
+}
+
+/// A standard normal sample, by the Box-Muller transform.
+fn gaussian(rng: &mut StdRng) -> f32 {
+    let u: f32 = rng.gen_range(f32::EPSILON..1.0);
+    let v: f32 = rng.gen_range(0.0..1.0);
+    (-2.0 * u.ln()).sqrt() * (std::f32::consts::TAU * v).cos()
+}
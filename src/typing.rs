@@ -0,0 +1,85 @@
+//! Typing mode: each flap is the next letter of a word shown over the bird.
+//!
+//! Words come from `words/common.txt` in an order set by the run's seed, so a challenge
+//! types the same ones. A wrong letter is a miss and doesn't flap. The summary gives
+//! the typing speed in words per minute, counting five letters as a word the way typing
+//! tutors do, and the share of letters typed right.
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+const WORDS: &str = include_str!("../words/common.txt");
+const LETTERS_PER_WORD: f32 = 5.0;
+
+#[derive(Clone)]
+pub struct Typing {
+    words: Vec<&'static str>,
+    rng: StdRng,
+    word: &'static str,
+    /// Letters of the word typed so far.
+    typed: usize,
+    hits: u32,
+    misses: u32,
+}
+
+impl Typing {
+    pub fn new(seed: u64) -> Self {
+        let words: Vec<&str> = WORDS
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .collect();
+        let mut typing = Self {
+            words,
+            rng: StdRng::seed_from_u64(seed),
+            word: "",
+            typed: 0,
+            hits: 0,
+            misses: 0,
+        };
+        typing.next_word();
+        typing
+    }
+
+    fn next_word(&mut self) {
+        self.word = self.words[self.rng.gen_range(0..self.words.len())];
+        self.typed = 0;
+    }
+
+    /// Check a typed letter against the next one. Returns whether it was right, and so
+    /// should flap.
+    pub fn press(&mut self, c: char) -> bool {
+        let Some(next) = self.word[self.typed..].chars().next() else {
+            return false;
+        };
+        if c.to_ascii_lowercase() != next {
+            self.misses += 1;
+            return false;
+        }
+        self.hits += 1;
+        self.typed += next.len_utf8();
+        if self.typed == self.word.len() {
+            self.next_word();
+        }
+        true
+    }
+
+    /// The word split into the letters typed and those still to go.
+    pub fn word(&self) -> (&str, &str) {
+        self.word.split_at(self.typed)
+    }
+
+    /// Words per minute over a run of `seconds`.
+    pub fn wpm(&self, seconds: f32) -> f32 {
+        if seconds <= 0.0 {
+            return 0.0;
+        }
+        self.hits as f32 / LETTERS_PER_WORD * 60.0 / seconds
+    }
+
+    /// Percentage of letters typed right, or `None` before any were typed.
+    pub fn accuracy(&self) -> Option<f32> {
+        let total = self.hits + self.misses;
+        (total > 0).then(|| self.hits as f32 * 100.0 / total as f32)
+    }
+}
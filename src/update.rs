@@ -0,0 +1,162 @@
+//! `tflap update`: swap a standalone binary for the latest GitHub release.
+//!
+//! Downloads go through `curl` and archives are unpacked with `tar`, which ship with every
+//! platform a binary is published for (Windows 10 and later included). Each release asset
+//! comes with a `.sha256` file, checked before anything is replaced.
+
+use crate::sha256;
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+const LATEST_RELEASE_URL: &str = "https://api.github.com/repos/Justhiro55/tflap/releases/latest";
+const DOWNLOAD_URL: &str = "https://github.com/Justhiro55/tflap/releases/download";
+
+// `io::Error::other` needs Rust 1.74, newer than the supported minimum
+#[allow(clippy::io_other_error)]
+fn error(message: impl Into<String>) -> io::Error {
+    io::Error::new(io::ErrorKind::Other, message.into())
+}
+
+/// The release asset built for this platform, matching `.github/workflows/release.yml`.
+fn asset_name() -> Option<&'static str> {
+    if cfg!(all(
+        target_os = "linux",
+        target_arch = "x86_64",
+        target_env = "musl"
+    )) {
+        Some("tflap-linux-x86_64-musl")
+    } else if cfg!(all(target_os = "linux", target_arch = "x86_64")) {
+        Some("tflap-linux-x86_64")
+    } else if cfg!(all(target_os = "macos", target_arch = "x86_64")) {
+        Some("tflap-macos-x86_64")
+    } else if cfg!(all(target_os = "macos", target_arch = "aarch64")) {
+        Some("tflap-macos-arm64")
+    } else if cfg!(all(target_os = "windows", target_arch = "x86_64")) {
+        Some("tflap-windows-x86_64.exe")
+    } else {
+        None
+    }
+}
+
+fn archive_name(asset: &str) -> String {
+    if cfg!(windows) {
+        format!("{}.zip", asset)
+    } else {
+        format!("{}.tar.gz", asset)
+    }
+}
+
+fn fetch(url: &str) -> io::Result<Vec<u8>> {
+    let output = Command::new("curl")
+        .args(["-fsSL", "--proto", "=https", "--user-agent", "tflap", url])
+        .output()
+        .map_err(|err| error(format!("could not run curl: {}", err)))?;
+    if !output.status.success() {
+        return Err(error(format!(
+            "download failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    Ok(output.stdout)
+}
+
+/// Pull `"tag_name": "v1.2.3"` out of the release JSON.
+fn tag_name(json: &str) -> Option<&str> {
+    let rest = &json[json.find("\"tag_name\"")? + "\"tag_name\"".len()..];
+    let rest = rest.trim_start().strip_prefix(':')?.trim_start();
+    let rest = rest.strip_prefix('"')?;
+    Some(&rest[..rest.find('"')?])
+}
+
+fn parse_version(s: &str) -> Option<(u32, u32, u32)> {
+    let mut parts = s.trim_start_matches('v').split('.');
+    let version = (
+        parts.next()?.parse().ok()?,
+        parts.next()?.parse().ok()?,
+        parts.next()?.parse().ok()?,
+    );
+    parts.next().is_none().then_some(version)
+}
+
+/// Binaries installed by `cargo install` should be updated the same way.
+fn installed_by_cargo(exe: &Path) -> bool {
+    exe.components().any(|c| c.as_os_str() == ".cargo")
+}
+
+/// Put `new` where `exe` is. A running Windows executable can be renamed but not
+/// overwritten, so the old one is moved aside first there.
+fn swap_in(new: &Path, exe: &Path) -> io::Result<()> {
+    // Copy next to the target first so the final rename stays on one filesystem
+    let staged = exe.with_extension("new");
+    fs::copy(new, &staged)?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(&staged, fs::Permissions::from_mode(0o755))?;
+    }
+    if cfg!(windows) {
+        let old = exe.with_extension("old");
+        let _ = fs::remove_file(&old);
+        fs::rename(exe, &old)?;
+    }
+    fs::rename(&staged, exe)
+}
+
+pub fn run(check_only: bool) -> io::Result<()> {
+    let current = env!("CARGO_PKG_VERSION");
+    let json = String::from_utf8_lossy(&fetch(LATEST_RELEASE_URL)?).into_owned();
+    let tag = tag_name(&json).ok_or_else(|| error("no release found"))?;
+    let latest = tag.trim_start_matches('v');
+    if parse_version(latest) <= parse_version(current) {
+        println!("tflap {} is up to date", current);
+        return Ok(());
+    }
+    println!("tflap {} is available (you have {})", latest, current);
+    if check_only {
+        return Ok(());
+    }
+
+    let exe = env::current_exe()?;
+    if installed_by_cargo(&exe) {
+        return Err(error(
+            "installed with cargo; run `cargo install tflap` to update",
+        ));
+    }
+    let asset = asset_name().ok_or_else(|| error("no prebuilt binary for this platform"))?;
+    let archive = archive_name(asset);
+    let url = format!("{}/{}/{}", DOWNLOAD_URL, tag, archive);
+
+    let bytes = fetch(&url)?;
+    let checksum = String::from_utf8_lossy(&fetch(&format!("{}.sha256", url))?).into_owned();
+    let expected = checksum.split_whitespace().next().unwrap_or("");
+    if !expected.eq_ignore_ascii_case(&sha256::hex_digest(&bytes)) {
+        return Err(error(format!("checksum mismatch for {}", archive)));
+    }
+
+    let dir: PathBuf = env::temp_dir().join(format!("tflap-update-{}", std::process::id()));
+    fs::create_dir_all(&dir)?;
+    let result = (|| {
+        let path = dir.join(&archive);
+        fs::write(&path, &bytes)?;
+        let status = Command::new("tar")
+            .arg("-xf")
+            .arg(&path)
+            .arg("-C")
+            .arg(&dir)
+            .status()
+            .map_err(|err| error(format!("could not run tar: {}", err)))?;
+        if !status.success() {
+            return Err(error(format!("could not unpack {}", archive)));
+        }
+        let binary = if cfg!(windows) { "tflap.exe" } else { "tflap" };
+        swap_in(&dir.join(binary), &exe)
+    })();
+    let _ = fs::remove_dir_all(&dir);
+    result?;
+
+    println!("Updated {} to tflap {}", exe.display(), latest);
+    Ok(())
+}
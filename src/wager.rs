@@ -0,0 +1,95 @@
+//! Double or nothing: once a run, a crash can be bet on carrying on.
+//!
+//! The offer comes up over the crash for `OFFER_TICKS`. Taking it puts the bird back
+//! where it fell with the score as the stake: passing `PIPES` more pipes doubles the
+//! stake, and crashing first leaves half of it, whatever was scored meanwhile. Turning
+//! the offer down, or letting it run out, ends the run as it stood.
+
+/// Pipes to pass after taking the bet to win it.
+pub const PIPES: u32 = 5;
+/// How long the offer stays up, about four seconds.
+const OFFER_TICKS: u32 = 80;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Wager {
+    /// Waiting for an answer, with ticks left to give one.
+    Offered {
+        remaining: u32,
+    },
+    Riding {
+        stake: u32,
+        pipes_left: u32,
+    },
+    Won {
+        stake: u32,
+    },
+    Lost {
+        stake: u32,
+    },
+    Declined,
+}
+
+impl Wager {
+    pub fn offer() -> Self {
+        Wager::Offered {
+            remaining: OFFER_TICKS,
+        }
+    }
+
+    /// Bet `score` on the next `PIPES` pipes.
+    pub fn take(score: u32) -> Self {
+        Wager::Riding {
+            stake: score,
+            pipes_left: PIPES,
+        }
+    }
+
+    /// Advance the offer one tick. Returns true once it has run out.
+    pub fn tick(&mut self) -> bool {
+        let Wager::Offered { remaining } = self else {
+            return false;
+        };
+        *remaining = remaining.saturating_sub(1);
+        *remaining == 0
+    }
+
+    /// Fraction of the offer's time still left, from 1.0 down to 0.0.
+    pub fn fraction_left(&self) -> f32 {
+        match self {
+            Wager::Offered { remaining } => *remaining as f32 / OFFER_TICKS as f32,
+            _ => 0.0,
+        }
+    }
+
+    /// Note a pipe passed. Returns true when it won the bet.
+    pub fn passed(&mut self) -> bool {
+        let Wager::Riding { stake, pipes_left } = self else {
+            return false;
+        };
+        *pipes_left -= 1;
+        if *pipes_left > 0 {
+            return false;
+        }
+        *self = Wager::Won { stake: *stake };
+        true
+    }
+
+    /// Points on top of the run's own: the stake again, once the bet is won.
+    pub fn bonus(&self) -> u32 {
+        match self {
+            Wager::Won { stake } => *stake,
+            _ => 0,
+        }
+    }
+
+    /// What a run ending on `score` keeps: half the stake if the bet was still riding.
+    pub fn settle(&mut self, score: u32) -> u32 {
+        match *self {
+            Wager::Riding { stake, .. } => {
+                *self = Wager::Lost { stake };
+                stake / 2
+            }
+            _ => score,
+        }
+    }
+}
@@ -0,0 +1,204 @@
+use crate::collision::Aabb;
+use crate::entity::{Body, Entities, Entity, Sprite};
+use crate::world::Pos;
+use crossterm::style::Color;
+use rand::Rng;
+
+/// How long a weather state lasts before rotating, in ticks (~30s).
+const WEATHER_DURATION: u32 = 600;
+const WIND_STRENGTH: f32 = 0.12;
+/// How far the gusts move through their cycle each tick.
+const WIND_STEP: f32 = 0.05;
+const FOG_VISIBILITY: i32 = 24;
+const RAIN_DENSITY: usize = 40;
+const SNOW_DENSITY: usize = 25;
+const FOG_DENSITY: usize = 30;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Weather {
+    Clear,
+    Rain,
+    Snow,
+    Fog,
+}
+
+impl Weather {
+    const ALL: [Weather; 4] = [Weather::Clear, Weather::Rain, Weather::Snow, Weather::Fog];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Weather::Clear => "Clear",
+            Weather::Rain => "Rain",
+            Weather::Snow => "Snow",
+            Weather::Fog => "Fog",
+        }
+    }
+}
+
+/// Weather chosen on the command line: a fixed state or random rotation.
+#[derive(Clone, Copy, PartialEq)]
+pub enum WeatherSetting {
+    Fixed(Weather),
+    Random,
+}
+
+impl WeatherSetting {
+    /// Every setting in the order menus cycle through them.
+    pub const ALL: [WeatherSetting; 5] = [
+        WeatherSetting::Fixed(Weather::Clear),
+        WeatherSetting::Fixed(Weather::Rain),
+        WeatherSetting::Fixed(Weather::Snow),
+        WeatherSetting::Fixed(Weather::Fog),
+        WeatherSetting::Random,
+    ];
+
+    pub fn parse(s: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|setting| setting.key() == s)
+    }
+
+    pub fn key(self) -> &'static str {
+        match self {
+            WeatherSetting::Fixed(Weather::Clear) => "clear",
+            WeatherSetting::Fixed(Weather::Rain) => "rain",
+            WeatherSetting::Fixed(Weather::Snow) => "snow",
+            WeatherSetting::Fixed(Weather::Fog) => "fog",
+            WeatherSetting::Random => "random",
+        }
+    }
+}
+
+impl Default for WeatherSetting {
+    fn default() -> Self {
+        WeatherSetting::Fixed(Weather::Clear)
+    }
+}
+
+/// Current weather plus its particles. Update and draw consult it for modifiers
+/// rather than special-casing each weather type themselves.
+#[derive(Clone)]
+pub struct WeatherSystem {
+    setting: WeatherSetting,
+    current: Weather,
+    ticks_left: u32,
+    wind_phase: f32,
+    particles: Entities,
+}
+
+impl WeatherSystem {
+    pub fn new(setting: WeatherSetting, rng: &mut impl Rng) -> Self {
+        let mut system = Self {
+            setting,
+            current: Weather::Clear,
+            ticks_left: 0,
+            wind_phase: 0.0,
+            particles: Entities::new(board(0, 0)),
+        };
+        system.reset(rng);
+        system
+    }
+
+    pub fn reset(&mut self, rng: &mut impl Rng) {
+        self.current = match self.setting {
+            WeatherSetting::Fixed(weather) => weather,
+            WeatherSetting::Random => Weather::ALL[rng.gen_range(0..Weather::ALL.len())],
+        };
+        self.ticks_left = WEATHER_DURATION;
+        self.wind_phase = 0.0;
+        self.particles.clear();
+    }
+
+    pub fn current(&self) -> Weather {
+        self.current
+    }
+
+    /// Vertical force added to the bird each tick. Rain brings gusting wind.
+    pub fn wind(&self) -> f32 {
+        match self.current {
+            Weather::Rain => WIND_STRENGTH * self.wind_phase.sin(),
+            _ => 0.0,
+        }
+    }
+
+    /// The wind `ticks` updates from now, if the weather holds.
+    pub fn wind_after(&self, ticks: u32) -> f32 {
+        match self.current {
+            Weather::Rain => WIND_STRENGTH * (self.wind_phase + WIND_STEP * ticks as f32).sin(),
+            _ => 0.0,
+        }
+    }
+
+    /// How far ahead of the bird pipes stay visible, `None` when unlimited.
+    pub fn visibility(&self) -> Option<i32> {
+        match self.current {
+            Weather::Fog => Some(FOG_VISIBILITY),
+            _ => None,
+        }
+    }
+
+    pub fn particles(&self) -> &Entities {
+        &self.particles
+    }
+
+    /// Glyph used to draw this weather's particles.
+    pub fn particle_glyph(&self) -> char {
+        match self.current {
+            Weather::Clear => ' ',
+            Weather::Rain => {
+                if self.wind() < 0.0 {
+                    '/'
+                } else {
+                    '|'
+                }
+            }
+            Weather::Snow => '*',
+            Weather::Fog => '░',
+        }
+    }
+
+    pub fn update(&mut self, rng: &mut impl Rng, width: u16, height: u16) {
+        if self.setting == WeatherSetting::Random {
+            self.ticks_left = self.ticks_left.saturating_sub(1);
+            if self.ticks_left == 0 {
+                self.reset(rng);
+            }
+        }
+        self.wind_phase += WIND_STEP;
+
+        let (density, fall_speed, drift) = match self.current {
+            Weather::Clear => (0, 0.0, 0.0),
+            Weather::Rain => (RAIN_DENSITY, 1.5, -1.0),
+            Weather::Snow => (SNOW_DENSITY, 0.3, -0.6),
+            Weather::Fog => (FOG_DENSITY, 0.0, -0.5),
+        };
+
+        let velocity = Pos::new(drift, fall_speed);
+        let sprite = Sprite {
+            glyph: self.particle_glyph(),
+            color: Color::DarkGrey,
+            wide: false,
+        };
+        for particle in self.particles.iter_mut() {
+            particle.body.velocity = velocity;
+            particle.sprite = sprite;
+        }
+        self.particles.set_bounds(board(width, height));
+        self.particles.physics();
+
+        while self.particles.len() < density {
+            let pos = Pos::new(
+                rng.gen_range(0.0..width as f32),
+                rng.gen_range(0.0..height as f32),
+            );
+            self.particles.spawn(Entity {
+                body: Body::drifting(pos, velocity),
+                sprite,
+            });
+        }
+    }
+}
+
+/// Where particles live: across the board and anywhere above it, drifting in from the
+/// top.
+fn board(width: u16, height: u16) -> Aabb {
+    Aabb::new(0.0, f32::NEG_INFINITY, width as f32, height as f32)
+}
@@ -0,0 +1,55 @@
+//! World coordinates, and the transform from them to screen cells.
+//!
+//! Everything in the game sits at an `f32` position on the board: x counts columns to
+//! the right and y rows down from the top, so something at (3.7, 5.2) is in column 3,
+//! row 5. The simulation works in these and only asks which cell a position falls in
+//! through `Pos::cell`, and drawing only places things on screen through a `View`, so
+//! the rounding is the same everywhere instead of down to whichever cast was nearest.
+
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+pub struct Pos {
+    pub x: f32,
+    pub y: f32,
+}
+
+impl Pos {
+    pub const fn new(x: f32, y: f32) -> Self {
+        Self { x, y }
+    }
+
+    /// The board cell the position falls in, as (column, row).
+    pub fn cell(self) -> (i32, i32) {
+        (self.x.floor() as i32, self.y.floor() as i32)
+    }
+
+    pub fn row(self) -> i32 {
+        self.cell().1
+    }
+}
+
+/// How the world is laid out on a screen of cells the size of the board. Up the speed
+/// ramp the view widens ahead of the anchor column, so things further away are drawn
+/// further out; at the anchor itself nothing moves.
+#[derive(Clone, Copy)]
+pub struct View {
+    anchor: f32,
+    /// Screen columns for each world column away from the anchor.
+    stretch: f32,
+}
+
+impl View {
+    /// A view stretched `stretch` times either side of column `anchor`.
+    pub fn new(anchor: f32, stretch: f32) -> Self {
+        Self { anchor, stretch }
+    }
+
+    /// The screen cell `pos` is drawn in, as (column, row).
+    pub fn to_screen(self, pos: Pos) -> (i32, i32) {
+        let x = self.anchor + ((pos.x - self.anchor) * self.stretch).round();
+        Pos::new(x, pos.y).cell()
+    }
+
+    pub fn column(self, x: f32) -> i32 {
+        self.to_screen(Pos::new(x, 0.0)).0
+    }
+}